@@ -0,0 +1,299 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use walkdir::WalkDir;
+use crate::frame_similarity::{SimilarityAlgorithm, SimilarityTuning};
+use crate::sessions::content_hash;
+use crate::video_frame_extractor::AnalyzeRange;
+
+/// 文件头与格式版本，格式变化时递增版本，旧缓存自动作废
+const MAGIC: &[u8; 4] = b"MPFA";
+const VERSION: u8 = 2;
+
+/// 一个视频的逐帧分析结果：调整阈值、最短时长或掐头去尾后重新拆解时直接复用，
+/// 不再解码帧画面、计算哈希与相似度
+#[derive(Debug, Clone, Default)]
+pub struct FrameAnalysis {
+    /// 第一帧在整个视频中的序号（限定分析范围时不为 0）
+    pub first_frame: u32,
+    /// 每帧的时间戳（秒）
+    pub timestamps: Vec<f64>,
+    /// 每帧的 dHash（启用预筛选时才会计算）
+    pub hashes: Option<Vec<Option<u64>>>,
+    /// 相邻帧相似度，按算法与参数区分，第 i 项为第 i 帧与第 i+1 帧
+    series: Vec<(String, Vec<f32>)>,
+}
+
+impl FrameAnalysis {
+    pub fn similarities(&self, key: &str) -> Option<Vec<f64>> {
+        self.series
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, values)| values.iter().map(|v| *v as f64).collect())
+    }
+
+    /// 帧序列与已有结果不一致（视频帧重新提取过）时丢弃旧结果
+    ///
+    /// 相似度数量必须比帧数少一（解码时按此读取每组序列），否则不保存并返回 false
+    fn merge(&mut self, entry: &SeriesEntry) -> bool {
+        let SeriesEntry { first_frame, timestamps, hashes, key, similarities } = *entry;
+        if similarities.len() != timestamps.len().saturating_sub(1) {
+            return false;
+        }
+        if self.first_frame != first_frame || self.timestamps != timestamps {
+            *self = Self {
+                first_frame,
+                timestamps: timestamps.to_vec(),
+                ..Self::default()
+            };
+        }
+        if let Some(hashes) = hashes.filter(|h| h.len() == timestamps.len()) {
+            self.hashes = Some(hashes.to_vec());
+        }
+        let values = similarities.iter().map(|v| *v as f32).collect();
+        match self.series.iter_mut().find(|(k, _)| k == key) {
+            Some(entry) => entry.1 = values,
+            None => self.series.push((key.to_string(), values)),
+        }
+        true
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let count = self.timestamps.len();
+        let mut buf = Vec::with_capacity(20 + count * 17 + self.series.len() * count * 4);
+        buf.extend_from_slice(MAGIC);
+        buf.push(VERSION);
+        buf.extend_from_slice(&self.first_frame.to_le_bytes());
+        buf.extend_from_slice(&(count as u32).to_le_bytes());
+        for timestamp in &self.timestamps {
+            buf.extend_from_slice(&timestamp.to_le_bytes());
+        }
+        match &self.hashes {
+            Some(hashes) => {
+                buf.push(1);
+                for hash in hashes {
+                    buf.push(hash.is_some() as u8);
+                    buf.extend_from_slice(&hash.unwrap_or(0).to_le_bytes());
+                }
+            }
+            None => buf.push(0),
+        }
+        buf.extend_from_slice(&(self.series.len() as u32).to_le_bytes());
+        for (key, values) in &self.series {
+            buf.extend_from_slice(&(key.len() as u32).to_le_bytes());
+            buf.extend_from_slice(key.as_bytes());
+            for value in values {
+                buf.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        buf
+    }
+
+    /// 解析缓存文件，格式不符或数据截断时返回 None
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut reader = Reader { bytes, pos: 0 };
+        if reader.take(4)? != MAGIC || reader.take(1)?[0] != VERSION {
+            return None;
+        }
+        let first_frame = reader.u32()?;
+        let count = reader.u32()? as usize;
+        let timestamps = (0..count).map(|_| reader.f64()).collect::<Option<Vec<_>>>()?;
+        let hashes = match reader.take(1)?[0] {
+            0 => None,
+            _ => Some(
+                (0..count)
+                    .map(|_| {
+                        let valid = reader.take(1)?[0] != 0;
+                        let hash = reader.u64()?;
+                        Some(valid.then_some(hash))
+                    })
+                    .collect::<Option<Vec<_>>>()?,
+            ),
+        };
+        let series_count = reader.u32()? as usize;
+        // 序列数来自文件，损坏时可能很大，不按它预先分配
+        let mut series = Vec::new();
+        for _ in 0..series_count {
+            let key_len = reader.u32()? as usize;
+            let key = String::from_utf8(reader.take(key_len)?.to_vec()).ok()?;
+            let values = (0..count.saturating_sub(1))
+                .map(|_| reader.f32())
+                .collect::<Option<Vec<_>>>()?;
+            series.push((key, values));
+        }
+        Some(Self {
+            first_frame,
+            timestamps,
+            hashes,
+            series,
+        })
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let slice = self.bytes.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(slice)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        Some(f32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn f64(&mut self) -> Option<f64> {
+        Some(f64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+}
+
+/// 相似度序列的键：算法与全部相似度参数（含忽略区域与预筛选），任一变化都需重新计算
+pub fn series_key(algo: SimilarityAlgorithm, tuning: &SimilarityTuning) -> String {
+    format!("{:?}|{:?}", algo, tuning)
+}
+
+/// 分析缓存目录（应用缓存目录下）
+fn cache_root(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_cache_dir()
+        .map(|dir| dir.join("frame_analysis"))
+        .map_err(|e| format!("无法获取缓存目录: {}", e))
+}
+
+/// 按视频内容指纹定位缓存文件，视频移动或改名后仍能命中；限定分析范围时每个范围单独一个文件
+fn cache_file(app: &AppHandle, video_path: &str, range: Option<AnalyzeRange>) -> Option<PathBuf> {
+    let hash = content_hash(Path::new(video_path)).ok()?;
+    let name = match range {
+        Some(range) => format!("{}_{}.bin", hash, range.tag()),
+        None => format!("{}.bin", hash),
+    };
+    Some(cache_root(app).ok()?.join(name))
+}
+
+/// 读取视频（或分析范围）的逐帧分析结果，没有缓存或已损坏时返回 None
+pub fn load(app: &AppHandle, video_path: &str, range: Option<AnalyzeRange>) -> Option<FrameAnalysis> {
+    let bytes = fs::read(cache_file(app, video_path, range)?).ok()?;
+    FrameAnalysis::decode(&bytes)
+}
+
+/// 一组待保存的逐帧分析结果
+#[derive(Clone, Copy)]
+pub struct SeriesEntry<'a> {
+    pub first_frame: u32,
+    pub timestamps: &'a [f64],
+    /// 预筛选用的每帧哈希
+    pub hashes: Option<&'a [Option<u64>]>,
+    /// 相似度序列的键（见 `series_key`）
+    pub key: &'a str,
+    pub similarities: &'a [f64],
+}
+
+/// 保存一组相邻帧相似度（同一视频的其他算法结果一并保留），写入失败不影响业务
+pub fn store(app: &AppHandle, video_path: &str, range: Option<AnalyzeRange>, entry: SeriesEntry) {
+    let Some(path) = cache_file(app, video_path, range) else {
+        return;
+    };
+    let mut analysis = fs::read(&path)
+        .ok()
+        .and_then(|bytes| FrameAnalysis::decode(&bytes))
+        .unwrap_or_default();
+    if !analysis.merge(&entry) {
+        return;
+    }
+
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    // 先写临时文件再改名，避免并发拆解读到写了一半的缓存
+    let temp = path.with_extension("tmp");
+    if fs::write(&temp, analysis.encode()).is_ok() {
+        let _ = fs::rename(&temp, &path);
+    }
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum()
+}
+
+// 获取逐帧分析缓存占用的空间（字节）
+#[tauri::command]
+pub fn get_analysis_cache_size(app: AppHandle) -> Result<u64, String> {
+    Ok(dir_size(&cache_root(&app)?))
+}
+
+// 清空逐帧分析缓存，返回释放的空间（字节）
+#[tauri::command]
+pub fn clear_analysis_cache(app: AppHandle) -> Result<u64, String> {
+    let root = cache_root(&app)?;
+    let size = dir_size(&root);
+    if root.exists() {
+        fs::remove_dir_all(&root).map_err(|e| format!("清理缓存目录失败: {}", e))?;
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry<'a>(timestamps: &'a [f64], key: &'a str, similarities: &'a [f64]) -> SeriesEntry<'a> {
+        SeriesEntry {
+            first_frame: 0,
+            timestamps,
+            hashes: None,
+            key,
+            similarities,
+        }
+    }
+
+    #[test]
+    fn series_round_trip() {
+        let timestamps = [0.0, 0.04, 0.08];
+        let mut analysis = FrameAnalysis::default();
+        assert!(analysis.merge(&entry(&timestamps, "ssim", &[0.5, 0.25])));
+        assert!(analysis.merge(&entry(&timestamps, "edge", &[1.0, 0.75])));
+
+        let decoded = FrameAnalysis::decode(&analysis.encode()).unwrap();
+        assert_eq!(decoded.timestamps, timestamps.to_vec());
+        assert_eq!(decoded.similarities("ssim"), Some(vec![0.5, 0.25]));
+        assert_eq!(decoded.similarities("edge"), Some(vec![1.0, 0.75]));
+    }
+
+    #[test]
+    fn mismatched_series_is_rejected() {
+        let timestamps = [0.0, 0.04, 0.08];
+        let mut analysis = FrameAnalysis::default();
+        assert!(!analysis.merge(&entry(&timestamps, "ssim", &[0.5])));
+        assert!(analysis.similarities("ssim").is_none());
+    }
+
+    #[test]
+    fn corrupt_series_count_is_none() {
+        let mut analysis = FrameAnalysis::default();
+        analysis.merge(&entry(&[0.0, 0.04], "ssim", &[0.5]));
+        let mut bytes = analysis.encode();
+        // 序列数紧跟在时间戳与哈希标记之后
+        let at = 4 + 1 + 4 + 4 + 2 * 8 + 1;
+        bytes[at..at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(FrameAnalysis::decode(&bytes).is_none());
+        assert!(FrameAnalysis::decode(&bytes[..bytes.len() - 1]).is_none());
+    }
+}
@@ -0,0 +1,198 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_shell::ShellExt;
+use crate::events::AUDIO_ANALYSIS_PROGRESS;
+use crate::i18n::progress;
+use crate::path_scope::check_input;
+use crate::video_processor::{collect_videos, get_video_info};
+
+/// 静音检测阈值（dB）
+const SILENCE_NOISE_DB: f64 = -50.0;
+/// 静音最短持续时间（秒）
+const SILENCE_MIN_DURATION: f64 = 0.5;
+/// 静音占比超过该值视为静音片段
+const SILENT_CLIP_PERCENT: f64 = 95.0;
+/// 真峰值超过该值视为削波
+const CLIPPING_PEAK_DBFS: f64 = -0.1;
+
+/// 单个视频的音频分析结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClipAudioReport {
+    pub path: String,
+    pub name: String,
+    pub duration: f64,
+    pub has_audio: bool,
+    /// 综合响度（LUFS）
+    pub integrated_lufs: Option<f64>,
+    /// 真峰值（dBFS）
+    pub true_peak_dbfs: Option<f64>,
+    /// 静音时长占比（0-100）
+    pub silence_percent: f64,
+    pub is_silent: bool,
+    pub is_clipping: bool,
+}
+
+/// ebur128 + silencedetect 的解析结果
+#[derive(Debug, Clone, Default)]
+pub struct AudioStats {
+    pub integrated_lufs: Option<f64>,
+    pub true_peak_dbfs: Option<f64>,
+    pub silence_duration: f64,
+}
+
+/// 从 "I:  -19.4 LUFS" 这类行中取出数值
+fn parse_summary_value(line: &str, key: &str) -> Option<f64> {
+    let rest = line.trim().strip_prefix(key)?;
+    rest.split_whitespace().next()?.parse::<f64>().ok()
+}
+
+/// 解析 FFmpeg stderr 中的 ebur128 汇总与 silencedetect 输出
+pub fn parse_audio_stats(stderr: &str) -> AudioStats {
+    let mut stats = AudioStats::default();
+
+    // ebur128 每 100ms 也会输出 "I:" 字段，只解析最后的 Summary 段
+    if let Some(pos) = stderr.rfind("Summary:") {
+        for line in stderr[pos..].lines() {
+            if let Some(value) = parse_summary_value(line, "I:") {
+                stats.integrated_lufs = Some(value);
+            } else if let Some(value) = parse_summary_value(line, "Peak:") {
+                stats.true_peak_dbfs = Some(value);
+            }
+        }
+    }
+
+    for line in stderr.lines() {
+        if let Some(pos) = line.find("silence_duration:") {
+            if let Some(value) = line[pos + "silence_duration:".len()..]
+                .split_whitespace()
+                .next()
+                .and_then(|v| v.parse::<f64>().ok())
+            {
+                stats.silence_duration += value;
+            }
+        }
+    }
+
+    stats
+}
+
+/// 调用 FFmpeg 统计单个文件的响度、峰值与静音时长
+pub async fn measure_audio(app: &AppHandle, video_path: &Path) -> Result<AudioStats, String> {
+    let sidecar = app
+        .shell()
+        .sidecar("ffmpeg")
+        .map_err(|e| format!("FFmpeg 启动失败: {}", e))?;
+
+    let filter = format!(
+        "ebur128=peak=true,silencedetect=noise={}dB:d={}",
+        SILENCE_NOISE_DB, SILENCE_MIN_DURATION
+    );
+    let output = sidecar
+        .args([
+            "-hide_banner",
+            "-nostats",
+            "-i",
+            &video_path.to_string_lossy(),
+            "-vn",
+            "-af",
+            &filter,
+            "-f",
+            "null",
+            "-",
+        ])
+        .output()
+        .await
+        .map_err(|e| format!("FFmpeg 执行失败: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "音频分析失败: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(parse_audio_stats(&String::from_utf8_lossy(&output.stderr)))
+}
+
+/// 分析单个视频的音频
+pub async fn analyze_clip_audio(app: &AppHandle, video_path: &Path) -> Result<ClipAudioReport, String> {
+    let info = get_video_info(app, video_path).await?;
+    let name = video_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    if !info.has_audio {
+        return Ok(ClipAudioReport {
+            path: video_path.to_string_lossy().to_string(),
+            name,
+            duration: info.duration,
+            has_audio: false,
+            integrated_lufs: None,
+            true_peak_dbfs: None,
+            silence_percent: 100.0,
+            is_silent: true,
+            is_clipping: false,
+        });
+    }
+
+    let stats = measure_audio(app, video_path).await?;
+    let silence_percent = if info.duration > 0.0 {
+        (stats.silence_duration / info.duration * 100.0).clamp(0.0, 100.0)
+    } else {
+        0.0
+    };
+
+    Ok(ClipAudioReport {
+        path: video_path.to_string_lossy().to_string(),
+        name,
+        duration: info.duration,
+        has_audio: true,
+        integrated_lufs: stats.integrated_lufs,
+        true_peak_dbfs: stats.true_peak_dbfs,
+        silence_percent,
+        is_silent: silence_percent >= SILENT_CLIP_PERCENT,
+        is_clipping: stats
+            .true_peak_dbfs
+            .map(|p| p >= CLIPPING_PEAK_DBFS)
+            .unwrap_or(false),
+    })
+}
+
+// 分析视频池中所有视频的响度与静音占比
+#[tauri::command]
+pub async fn analyze_pool_audio(
+    app: AppHandle,
+    dir: String,
+    max_depth: Option<usize>,
+) -> Result<Vec<ClipAudioReport>, String> {
+    check_input(&app, &dir)?;
+    let window = app
+        .get_webview_window("main")
+        .ok_or("无法获取窗口")?;
+
+    let videos = collect_videos(&dir, max_depth.unwrap_or(0))?;
+    let total = videos.len();
+    let mut reports = Vec::with_capacity(total);
+
+    for (idx, video) in videos.iter().enumerate() {
+        let _ = window.emit(
+            AUDIO_ANALYSIS_PROGRESS,
+            progress(
+                &app,
+                "audio.analyzing",
+                &[(idx + 1).to_string(), total.to_string(), video.display().to_string()],
+                (idx as f64 / total as f64 * 100.0) as u32,
+            ),
+        );
+        reports.push(analyze_clip_audio(&app, video).await?);
+    }
+
+    let _ = window.emit(
+        AUDIO_ANALYSIS_PROGRESS,
+        progress(&app, "common.done", &[], 100),
+    );
+
+    Ok(reports)
+}
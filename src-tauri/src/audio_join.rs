@@ -0,0 +1,153 @@
+use std::path::Path;
+use tauri::AppHandle;
+use tauri_plugin_shell::ShellExt;
+
+/// 解码采样率（单声道），判断重复与爆音足够
+pub const SAMPLE_RATE: f64 = 8000.0;
+/// 新结尾开头用于比对的时长（秒）
+const HEAD_WINDOW: f64 = 0.1;
+/// 重复段最短比对时长（秒），过短的波形相似没有意义
+const MIN_COMPARE: f64 = 0.02;
+/// 归一化互相关高于该值视为同一段声音重复播放
+const REPEAT_CORRELATION: f64 = 0.8;
+/// 均方根低于该值视为静音，不做重复判断
+const SILENCE_RMS: f64 = 0.01;
+/// 接缝两侧采样值相差超过该值会听到爆音（满幅为 1）
+const CLICK_JUMP: f64 = 0.1;
+/// 最多裁掉的帧数，超过说明不是接缝问题
+pub const MAX_TRIM_FRAMES: usize = 6;
+
+/// 接缝处上一片段末尾需要裁掉的帧数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JunctionTrim {
+    pub frames: usize,
+    /// 因重复播放而裁剪，否则为避免爆音
+    pub repeat: bool,
+}
+
+/// 解码一段音频为单声道浮点采样，没有音轨时返回空
+pub async fn decode_pcm(app: &AppHandle, path: &Path, start: f64, duration: f64) -> Result<Vec<f32>, String> {
+    let sidecar = app
+        .shell()
+        .sidecar("ffmpeg")
+        .map_err(|e| format!("FFmpeg 启动失败: {}", e))?;
+
+    let output = sidecar
+        .args([
+            "-v",
+            "error",
+            "-ss",
+            &format!("{:.3}", start.max(0.0)),
+            "-i",
+            &path.to_string_lossy(),
+            "-t",
+            &format!("{:.3}", duration),
+            "-map",
+            "0:a:0?",
+            "-ac",
+            "1",
+            "-ar",
+            &SAMPLE_RATE.to_string(),
+            "-f",
+            "f32le",
+            "-",
+        ])
+        .output()
+        .await
+        .map_err(|e| format!("FFmpeg 执行失败: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "解码接缝音频失败: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(output
+        .stdout
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect())
+}
+
+fn rms(samples: &[f32]) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|s| (*s as f64).powi(2)).sum::<f64>() / samples.len() as f64).sqrt()
+}
+
+/// 归一化互相关，两段都接近静音时为 0
+fn correlation(a: &[f32], b: &[f32]) -> f64 {
+    let dot: f64 = a.iter().zip(b).map(|(x, y)| *x as f64 * *y as f64).sum();
+    let energy = (rms(a) * rms(b)) * a.len().min(b.len()) as f64;
+    if energy <= f64::EPSILON {
+        0.0
+    } else {
+        dot / energy
+    }
+}
+
+/// 选择上一片段的出点，使接缝处不重复播放同一段声音、也不出现爆音
+///
+/// `cuts[k]` 为裁掉 k 帧后的出点（秒，`cuts[0]` 为原出点，依次递减）；
+/// `next_start` 为新结尾的入点。任一侧没有音轨时不裁剪
+pub async fn align_junction(
+    app: &AppHandle,
+    prev: &Path,
+    cuts: &[f64],
+    next: &Path,
+    next_start: f64,
+) -> Result<JunctionTrim, String> {
+    let none = JunctionTrim { frames: 0, repeat: false };
+    let (Some(&end), Some(&earliest)) = (cuts.first(), cuts.last()) else {
+        return Ok(none);
+    };
+    if cuts.len() < 2 {
+        return Ok(none);
+    }
+
+    let window_start = (earliest - MIN_COMPARE).max(0.0);
+    let tail = decode_pcm(app, prev, window_start, end - window_start).await?;
+    let head = decode_pcm(app, next, next_start, HEAD_WINDOW).await?;
+    if tail.is_empty() || head.is_empty() {
+        return Ok(none);
+    }
+
+    let index = |time: f64| (((time - window_start) * SAMPLE_RATE).round().max(0.0) as usize).min(tail.len());
+    let tail_end = index(end);
+    let min_compare = (MIN_COMPARE * SAMPLE_RATE) as usize;
+
+    // 新结尾开头的声音若已在上一片段末尾播放过，从重复处裁掉
+    if rms(&head) >= SILENCE_RMS {
+        let repeat = (1..cuts.len())
+            .filter_map(|k| {
+                let cut = index(cuts[k]);
+                let len = head.len().min(tail_end.saturating_sub(cut));
+                (len >= min_compare).then(|| (k, correlation(&tail[cut..cut + len], &head[..len])))
+            })
+            .filter(|(_, score)| *score >= REPEAT_CORRELATION)
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((frames, _)) = repeat {
+            return Ok(JunctionTrim { frames, repeat: true });
+        }
+    }
+
+    // 接缝两侧采样值跳变明显时，换一个能平滑衔接的出点
+    let jump = |k: usize| {
+        let cut = index(cuts[k]);
+        if cut == 0 {
+            f64::MAX
+        } else {
+            (tail[cut - 1] as f64 - head[0] as f64).abs()
+        }
+    };
+    if jump(0) <= CLICK_JUMP {
+        return Ok(none);
+    }
+    let frames = (1..cuts.len())
+        .find(|&k| jump(k) <= CLICK_JUMP)
+        .or_else(|| (1..cuts.len()).min_by(|&a, &b| jump(a).total_cmp(&jump(b))).filter(|&k| jump(k) < jump(0)))
+        .unwrap_or(0);
+    Ok(JunctionTrim { frames, repeat: false })
+}
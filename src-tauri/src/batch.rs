@@ -0,0 +1,340 @@
+use serde::Serialize;
+use std::fs;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State};
+use ts_rs::TS;
+use crate::events::{BATCH_SUMMARY, BATCH_TASK_UPDATED};
+use crate::jobs::AutoSplitParams;
+use crate::path_scope::check_output;
+use crate::video_frame_extractor::{run_auto_split, BatchProgress};
+
+/// 跳过当前视频时处理函数返回的错误前缀
+pub const SKIPPED_PREFIX: &str = "BATCH_SKIPPED:";
+/// 中止整批时处理函数返回的错误前缀
+pub const ABORTED_PREFIX: &str = "BATCH_ABORTED:";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BatchSignal {
+    SkipCurrent,
+    Abort,
+}
+
+tokio::task_local! {
+    /// 批量处理调用的处理函数所属的批量运行 ID
+    static BATCH_RUN: u64;
+}
+
+/// 当前任务所属的批量运行 ID；批量之外的交互操作为空
+pub fn current_batch_run() -> Option<u64> {
+    BATCH_RUN.try_with(|run| *run).ok()
+}
+
+/// 批量处理的控制信号（只作用于正在运行的那一批，不影响同时进行的交互操作）
+pub struct BatchControl {
+    /// 正在运行的批量 ID，0 表示没有
+    running: AtomicU64,
+    next_run: AtomicU64,
+    signal: Mutex<Option<BatchSignal>>,
+}
+
+impl BatchControl {
+    pub fn new() -> Self {
+        Self {
+            running: AtomicU64::new(0),
+            next_run: AtomicU64::new(1),
+            signal: Mutex::new(None),
+        }
+    }
+
+    fn request(&self, signal: BatchSignal) -> Result<(), String> {
+        if !self.is_active() {
+            return Err("当前没有正在运行的批量任务".to_string());
+        }
+        let mut current = self.signal.lock().unwrap();
+        // 中止优先于跳过
+        if *current != Some(BatchSignal::Abort) {
+            *current = Some(signal);
+        }
+        Ok(())
+    }
+
+    fn take(&self) -> Option<BatchSignal> {
+        self.signal.lock().unwrap().take()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.running.load(Ordering::SeqCst) != 0
+    }
+
+    /// 应用退出时中止正在运行的批量任务，返回是否有批量任务在运行
+    pub fn abort_if_active(&self) -> bool {
+        self.request(BatchSignal::Abort).is_ok()
+    }
+
+    /// 处理过程中的检查点：属于正在运行的批量且收到跳过/中止信号时返回带前缀的错误
+    pub fn check(&self) -> Result<(), String> {
+        let running = self.running.load(Ordering::SeqCst);
+        if running == 0 || current_batch_run() != Some(running) {
+            return Ok(());
+        }
+        match *self.signal.lock().unwrap() {
+            Some(BatchSignal::SkipCurrent) => Err(format!("{}已跳过当前视频", SKIPPED_PREFIX)),
+            Some(BatchSignal::Abort) => Err(format!("{}批量任务已中止", ABORTED_PREFIX)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// 检查批量控制信号（供各处理函数在耗时步骤之间调用）
+pub fn check_batch_signal(app: &AppHandle) -> Result<(), String> {
+    app.state::<BatchControl>().check()
+}
+
+#[derive(Clone, Serialize, TS)]
+#[ts(export)]
+struct BatchTaskEvent {
+    index: usize,
+    total: usize,
+    name: String,
+    status: String,
+}
+
+/// 批量操作中单个条目的结果
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct BatchItemResult {
+    /// 文件名、下载地址或第几次输出
+    pub name: String,
+    /// succeeded / failed / skipped
+    pub status: String,
+    /// 失败或跳过的原因
+    pub reason: Option<String>,
+}
+
+impl BatchItemResult {
+    pub fn succeeded(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: "succeeded".to_string(),
+            reason: None,
+        }
+    }
+
+    pub fn failed(name: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: "failed".to_string(),
+            reason: Some(reason.into()),
+        }
+    }
+
+    pub fn skipped(name: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: "skipped".to_string(),
+            reason: Some(reason.into()),
+        }
+    }
+}
+
+/// 批量操作结束后的逐项汇总（批量拆解、批量下载与多次拼接共用同一结构）
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct BatchSummary {
+    /// auto_split / download / concat
+    pub operation: String,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub items: Vec<BatchItemResult>,
+}
+
+impl BatchSummary {
+    pub fn new(operation: &str, items: Vec<BatchItemResult>) -> Self {
+        let count = |status: &str| items.iter().filter(|item| item.status == status).count();
+        Self {
+            operation: operation.to_string(),
+            succeeded: count("succeeded"),
+            failed: count("failed"),
+            skipped: count("skipped"),
+            items,
+        }
+    }
+
+    /// 发送汇总事件
+    pub fn emit(&self, app: &AppHandle) {
+        let _ = app.emit(BATCH_SUMMARY, self);
+    }
+
+    /// 失败与跳过的条目清单（每行一个），全部成功时为空
+    pub fn problems(&self) -> String {
+        self.items
+            .iter()
+            .filter_map(|item| {
+                let reason = item.reason.as_deref()?;
+                let label = if item.status == "skipped" { "跳过" } else { "失败" };
+                Some(format!("{} {}: {}", label, item.name, reason))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn save_progress(path: &str, progress: &BatchProgress) -> Result<(), String> {
+    let content =
+        serde_json::to_string_pretty(progress).map_err(|e| format!("序列化进度失败: {}", e))?;
+    fs::write(path, content).map_err(|e| format!("写入进度文件失败: {}", e))
+}
+
+// 在后台按进度文件批量自动拆解；可随时跳过当前视频或中止，之后从中断处继续
+#[tauri::command]
+pub async fn run_batch_auto_split(
+    app: AppHandle,
+    control: State<'_, BatchControl>,
+    progress_path: String,
+    params: AutoSplitParams,
+) -> Result<String, String> {
+    check_output(&app, &progress_path)?;
+    let content = fs::read_to_string(&progress_path).map_err(|e| format!("读取进度文件失败: {}", e))?;
+    let mut progress: BatchProgress =
+        serde_json::from_str(&content).map_err(|e| format!("解析进度文件失败: {}", e))?;
+
+    let run = control.next_run.fetch_add(1, Ordering::SeqCst);
+    if control.running.compare_exchange(0, run, Ordering::SeqCst, Ordering::SeqCst).is_err() {
+        return Err("已有批量任务正在运行".to_string());
+    }
+    control.take();
+
+    let result = BATCH_RUN
+        .scope(run, run_batch_loop(&app, &control, &progress_path, &mut progress, &params))
+        .await;
+
+    control.running.store(0, Ordering::SeqCst);
+    control.take();
+    result
+}
+
+async fn run_batch_loop(
+    app: &AppHandle,
+    control: &BatchControl,
+    progress_path: &str,
+    progress: &mut BatchProgress,
+    params: &AutoSplitParams,
+) -> Result<String, String> {
+    let total = progress.tasks.len();
+    let mut processed = 0;
+
+    while progress.current_index < total {
+        let index = progress.current_index;
+        let task = &mut progress.tasks[index];
+        if task.status == "completed" || task.status == "skipped" {
+            progress.current_index += 1;
+            continue;
+        }
+
+        task.status = "processing".to_string();
+        task.error = None;
+        let video_path = task.path.clone();
+        let name = task.name.clone();
+        save_progress(progress_path, progress)?;
+        let _ = app.emit(BATCH_TASK_UPDATED, BatchTaskEvent {
+            index,
+            total,
+            name: name.clone(),
+            status: "processing".to_string(),
+        });
+
+        let result = run_auto_split(app, video_path, params.clone()).await;
+
+        // 处理刚结束时收到的信号同样生效
+        let signal = control.take();
+        let aborted = signal == Some(BatchSignal::Abort)
+            || result.as_ref().is_err_and(|e| e.starts_with(ABORTED_PREFIX));
+        let task = &mut progress.tasks[index];
+        match result {
+            Ok(_) => {
+                task.status = "completed".to_string();
+                processed += 1;
+            }
+            // 中止时被打断的视频恢复为待处理，下次从这里继续
+            Err(e) if aborted && (e.starts_with(ABORTED_PREFIX) || e.starts_with(SKIPPED_PREFIX)) => {
+                task.status = "pending".to_string();
+            }
+            Err(e) if e.starts_with(SKIPPED_PREFIX) => {
+                task.status = "skipped".to_string();
+                task.error = Some(e.trim_start_matches(SKIPPED_PREFIX).to_string());
+            }
+            Err(e) => {
+                task.status = "error".to_string();
+                task.error = Some(e);
+            }
+        }
+        let status = task.status.clone();
+        if status != "pending" {
+            progress.current_index += 1;
+        }
+        save_progress(progress_path, progress)?;
+        let _ = app.emit(BATCH_TASK_UPDATED, BatchTaskEvent {
+            index,
+            total,
+            name,
+            status,
+        });
+
+        if aborted {
+            let message = format!(
+                "批量任务已中止：本次完成 {} 个，剩余 {} 个，可稍后继续",
+                processed,
+                total - progress.current_index
+            );
+            return Ok(finish_summary(app, progress, message));
+        }
+    }
+
+    let message = format!("批量任务完成：本次完成 {} 个，共 {} 个", processed, total);
+    Ok(finish_summary(app, progress, message))
+}
+
+/// 按各视频的状态发送汇总（含之前运行中已处理的视频，中止后未处理的视频不计入），
+/// 有失败或跳过的视频时把原因附在消息之后
+fn finish_summary(app: &AppHandle, progress: &BatchProgress, message: String) -> String {
+    let items = progress
+        .tasks
+        .iter()
+        .filter_map(|task| match task.status.as_str() {
+            "completed" => Some(BatchItemResult::succeeded(&task.name)),
+            "skipped" => Some(BatchItemResult::skipped(
+                &task.name,
+                task.error.clone().unwrap_or_else(|| "已跳过".to_string()),
+            )),
+            "error" => Some(BatchItemResult::failed(&task.name, task.error.clone().unwrap_or_default())),
+            _ => None,
+        })
+        .collect();
+    let summary = BatchSummary::new("auto_split", items);
+    summary.emit(app);
+    if summary.failed + summary.skipped == 0 {
+        return message;
+    }
+    format!(
+        "{}（成功 {} 个，失败 {} 个，跳过 {} 个）\n{}",
+        message,
+        summary.succeeded,
+        summary.failed,
+        summary.skipped,
+        summary.problems()
+    )
+}
+
+// 跳过批量任务中正在处理的视频
+#[tauri::command]
+pub fn skip_current_video(control: State<'_, BatchControl>) -> Result<(), String> {
+    control.request(BatchSignal::SkipCurrent)
+}
+
+// 中止批量任务的剩余部分（进度已保存，可稍后继续）
+#[tauri::command]
+pub fn abort_batch(control: State<'_, BatchControl>) -> Result<(), String> {
+    control.request(BatchSignal::Abort)
+}
@@ -0,0 +1,201 @@
+use std::ops::Range;
+use std::path::Path;
+use tauri::AppHandle;
+use tauri_plugin_shell::ShellExt;
+use crate::effects::ClipEffects;
+use crate::video_processor::{clip_output_duration, ClipTrim, VideoInfo};
+
+/// 节拍检测的采样率（只关心能量变化，不需要高采样率）
+const SAMPLE_RATE: usize = 11025;
+/// 分析窗口与步长（采样点）
+const FRAME_SIZE: usize = 1024;
+const HOP_SIZE: usize = 512;
+/// 自适应阈值的单侧窗口（帧数）与倍数
+const THRESHOLD_WINDOW: usize = 8;
+const THRESHOLD_FACTOR: f64 = 1.5;
+/// 相邻节拍的最小间隔（秒）
+const MIN_BEAT_GAP: f64 = 0.25;
+/// 对齐后片段的最短时长（秒），避免为了卡点把片段剪得太碎
+pub const MIN_ALIGNED_DURATION: f64 = 0.5;
+
+/// 背景音乐的节拍位置；拼接时背景音乐循环播放，节拍按音乐时长周期重复
+#[derive(Debug, Clone)]
+pub struct BeatGrid {
+    /// 节拍时间点（秒，相对音乐开头）
+    pub beats: Vec<f64>,
+    /// 音乐时长（秒）
+    pub period: f64,
+}
+
+impl BeatGrid {
+    /// 落在 (from, to] 内的最后一个节拍
+    pub fn last_beat_in(&self, from: f64, to: f64) -> Option<f64> {
+        if self.beats.is_empty() || self.period <= 0.0 || to <= from {
+            return None;
+        }
+        let first_loop = (from / self.period).floor().max(0.0) as u64;
+        let last_loop = (to / self.period).floor().max(0.0) as u64;
+        (first_loop..=last_loop)
+            .rev()
+            .flat_map(|k| self.beats.iter().rev().map(move |b| k as f64 * self.period + b))
+            .find(|&t| t > from && t <= to)
+    }
+}
+
+/// 解码音频为单声道浮点采样
+async fn decode_mono(app: &AppHandle, audio_path: &Path) -> Result<Vec<f32>, String> {
+    let sidecar = app
+        .shell()
+        .sidecar("ffmpeg")
+        .map_err(|e| format!("FFmpeg 启动失败: {}", e))?;
+
+    let output = sidecar
+        .args([
+            "-v",
+            "error",
+            "-i",
+            &audio_path.to_string_lossy(),
+            "-vn",
+            "-ac",
+            "1",
+            "-ar",
+            &SAMPLE_RATE.to_string(),
+            "-f",
+            "f32le",
+            "-",
+        ])
+        .output()
+        .await
+        .map_err(|e| format!("FFmpeg 执行失败: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "解码背景音乐失败: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(output
+        .stdout
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect())
+}
+
+/// 能量突增检测：对数能量的正向差分作为起音强度，超过局部均值一定倍数的局部峰值视为节拍
+fn onset_beats(samples: &[f32]) -> Vec<f64> {
+    if samples.len() < FRAME_SIZE {
+        return Vec::new();
+    }
+    let energy: Vec<f64> = (0..=(samples.len() - FRAME_SIZE) / HOP_SIZE)
+        .map(|i| {
+            let frame = &samples[i * HOP_SIZE..i * HOP_SIZE + FRAME_SIZE];
+            let sum: f64 = frame.iter().map(|&s| (s as f64) * (s as f64)).sum();
+            (1.0 + sum).ln()
+        })
+        .collect();
+    let onset: Vec<f64> = energy
+        .iter()
+        .enumerate()
+        .map(|(i, &e)| if i > 0 { (e - energy[i - 1]).max(0.0) } else { 0.0 })
+        .collect();
+
+    let hop_seconds = HOP_SIZE as f64 / SAMPLE_RATE as f64;
+    let mut beats: Vec<f64> = Vec::new();
+    for i in 1..onset.len().saturating_sub(1) {
+        if onset[i] <= 0.0 || onset[i] < onset[i - 1] || onset[i] < onset[i + 1] {
+            continue;
+        }
+        let window = &onset[i.saturating_sub(THRESHOLD_WINDOW)..(i + THRESHOLD_WINDOW + 1).min(onset.len())];
+        let mean = window.iter().sum::<f64>() / window.len() as f64;
+        if onset[i] < mean * THRESHOLD_FACTOR {
+            continue;
+        }
+        let time = i as f64 * hop_seconds;
+        if beats.last().is_none_or(|&last| time - last >= MIN_BEAT_GAP) {
+            beats.push(time);
+        }
+    }
+    beats
+}
+
+/// 检测背景音乐的节拍
+pub async fn detect_beats(app: &AppHandle, audio_path: &Path) -> Result<BeatGrid, String> {
+    let samples = decode_mono(app, audio_path).await?;
+    let beats = onset_beats(&samples);
+    if beats.is_empty() {
+        return Err("背景音乐中未检测到节拍".to_string());
+    }
+    Ok(BeatGrid {
+        beats,
+        period: samples.len() as f64 / SAMPLE_RATE as f64,
+    })
+}
+
+/// 调整 `aligned` 范围内片段的出点，使每个片段在成片中的结束时间落在节拍上
+///
+/// 背景音乐从成片开头播放，按所有片段（裁剪、变速后）的累计时长推算位置；
+/// 找不到合适节拍或渐变速的片段保持原样。返回对齐的片段数量
+pub fn align_trims(
+    grid: &BeatGrid,
+    videos_info: &[(String, VideoInfo)],
+    trims: &mut [Option<ClipTrim>],
+    effects: &[Option<ClipEffects>],
+    aligned: Range<usize>,
+) -> usize {
+    let mut cursor = 0.0;
+    let mut count = 0;
+    for (idx, (_, info)) in videos_info.iter().enumerate() {
+        let trim = trims.get(idx).copied().flatten();
+        let clip_effects = effects.get(idx).and_then(|e| e.as_ref());
+        let mut duration = clip_output_duration(info, trim, clip_effects);
+        // 匀速片段的成片时长与素材时长成正比，可以换算回出点
+        let speed = clip_effects.map(|e| e.constant_speed()).unwrap_or(Some(1.0));
+        if let (true, Some(speed)) = (aligned.contains(&idx), speed) {
+            if let Some(beat) = grid.last_beat_in(cursor + MIN_ALIGNED_DURATION, cursor + duration) {
+                let start = trim.and_then(|t| t.start);
+                duration = beat - cursor;
+                trims[idx] = Some(ClipTrim {
+                    start,
+                    end: Some(start.unwrap_or(0.0) + duration * speed),
+                });
+                count += 1;
+            }
+        }
+        cursor += duration;
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_beat_repeats_with_period() {
+        let grid = BeatGrid {
+            beats: vec![0.5, 1.5],
+            period: 2.0,
+        };
+        assert_eq!(grid.last_beat_in(1.0, 4.0), Some(3.5));
+        assert_eq!(grid.last_beat_in(0.5, 1.5), Some(1.5));
+        assert_eq!(grid.last_beat_in(0.6, 1.0), None);
+        assert_eq!(grid.last_beat_in(2.0, 1.0), None);
+    }
+
+    #[test]
+    fn onset_beats_find_clicks() {
+        assert!(onset_beats(&vec![0.0; SAMPLE_RATE]).is_empty());
+
+        // 每 0.5 秒一次短促的敲击
+        let mut samples = vec![0.0f32; SAMPLE_RATE * 2];
+        for click in [SAMPLE_RATE / 2, SAMPLE_RATE, SAMPLE_RATE * 3 / 2] {
+            samples[click..click + HOP_SIZE].fill(0.8);
+        }
+        let beats = onset_beats(&samples);
+        assert_eq!(beats.len(), 3);
+        for (beat, expected) in beats.iter().zip([0.5, 1.0, 1.5]) {
+            assert!((beat - expected).abs() < 0.1, "{beat} != {expected}");
+        }
+    }
+}
@@ -0,0 +1,286 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tauri::{AppHandle, Emitter, Manager};
+use ts_rs::TS;
+use crate::events::BENCHMARK_PROGRESS;
+use crate::i18n::progress;
+use crate::shutdown::ProcessRegistry;
+use crate::throttle::{self, run_ffmpeg};
+use crate::video_processor::VideoCodec;
+
+/// 测试片段时长（秒）
+const CLIP_DURATION: f64 = 5.0;
+/// 测试片段规格
+const CLIP_SIZE: &str = "1280x720";
+const CLIP_RATE: u32 = 30;
+/// 参与测试的编码预设（从快到慢）
+const PRESETS: [&str; 4] = ["ultrafast", "veryfast", "fast", "medium"];
+/// 推荐配置需要达到的最低 SSIM
+const MIN_RECOMMENDED_SSIM: f64 = 0.95;
+
+/// 单个编码配置
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct EncodingProfile {
+    pub codec: VideoCodec,
+    pub preset: String,
+}
+
+/// 单个配置的测试结果
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct BenchmarkEntry {
+    #[serde(flatten)]
+    pub profile: EncodingProfile,
+    pub encode_seconds: f64,
+    /// 编码速度（相对实时的倍数）
+    pub speed: f64,
+    /// 与无损源的 SSIM（0-1），无法计算时为空
+    pub ssim: Option<f64>,
+    #[ts(type = "number")]
+    pub file_size: u64,
+}
+
+/// 本机编码性能测试结果（保存在应用配置目录的 benchmark.json）
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct BenchmarkResult {
+    pub created_at: String,
+    /// 测试片段描述
+    pub clip: String,
+    pub entries: Vec<BenchmarkEntry>,
+    /// 失败的配置（如 FFmpeg 未编译对应编码器）
+    #[serde(default)]
+    pub failures: Vec<String>,
+    /// 推荐的默认编码配置：达到实时速度的配置中画质最好的；都达不到时选最快的
+    pub recommended: Option<EncodingProfile>,
+}
+
+impl BenchmarkResult {
+    fn recommend(entries: &[BenchmarkEntry]) -> Option<EncodingProfile> {
+        let realtime = entries
+            .iter()
+            .filter(|e| e.speed >= 1.0 && e.ssim.unwrap_or(0.0) >= MIN_RECOMMENDED_SSIM)
+            .max_by(|a, b| {
+                let quality = a.ssim.partial_cmp(&b.ssim).unwrap_or(std::cmp::Ordering::Equal);
+                // 画质相同时选更快的
+                quality.then(a.speed.partial_cmp(&b.speed).unwrap_or(std::cmp::Ordering::Equal))
+            });
+        realtime
+            .or_else(|| {
+                entries
+                    .iter()
+                    .max_by(|a, b| a.speed.partial_cmp(&b.speed).unwrap_or(std::cmp::Ordering::Equal))
+            })
+            .map(|e| e.profile.clone())
+    }
+}
+
+fn result_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(app
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("获取配置目录失败: {}", e))?
+        .join("benchmark.json"))
+}
+
+fn emit_progress(app: &AppHandle, key: &str, params: &[String], percent: u32) {
+    let _ = app.emit(BENCHMARK_PROGRESS, progress(app, key, params, percent));
+}
+
+/// 用 testsrc2/sine 生成无损的测试片段
+async fn generate_clip(app: &AppHandle, path: &Path) -> Result<(), String> {
+    let args: Vec<String> = [
+        "-f",
+        "lavfi",
+        "-i",
+        &format!("testsrc2=size={}:rate={}:duration={}", CLIP_SIZE, CLIP_RATE, CLIP_DURATION),
+        "-f",
+        "lavfi",
+        "-i",
+        &format!("sine=frequency=440:duration={}", CLIP_DURATION),
+        "-c:v",
+        "libx264",
+        "-preset",
+        "ultrafast",
+        "-qp",
+        "0",
+        "-pix_fmt",
+        "yuv420p",
+        "-c:a",
+        "aac",
+        "-y",
+        &path.to_string_lossy(),
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+
+    let output = run_ffmpeg(app, args).await?;
+    if !output.success() {
+        return Err(format!(
+            "生成测试片段失败: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(())
+}
+
+/// 计算编码结果与源片段的 SSIM（ssim 滤镜输出 "All:0.987654"）
+async fn measure_ssim(app: &AppHandle, encoded: &Path, source: &Path) -> Option<f64> {
+    let args: Vec<String> = [
+        "-i",
+        &encoded.to_string_lossy(),
+        "-i",
+        &source.to_string_lossy(),
+        "-lavfi",
+        "[0:v][1:v]ssim",
+        "-f",
+        "null",
+        "-",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+
+    let output = run_ffmpeg(app, args).await.ok()?;
+    if !output.success() {
+        return None;
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let value = stderr.rsplit("All:").next()?;
+    value.split_whitespace().next()?.parse().ok()
+}
+
+async fn encode_profile(
+    app: &AppHandle,
+    source: &Path,
+    output: &Path,
+    profile: &EncodingProfile,
+) -> Result<BenchmarkEntry, String> {
+    let mut args: Vec<String> = [
+        "-i",
+        &source.to_string_lossy(),
+        "-c:v",
+        profile.codec.encoder(),
+        "-preset",
+        &profile.preset,
+        "-crf",
+        &profile.codec.default_crf().to_string(),
+        "-pix_fmt",
+        "yuv420p",
+        "-an",
+        "-y",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    args.extend(throttle::current(app).output_args());
+    args.push(output.to_string_lossy().to_string());
+
+    let started = Instant::now();
+    let result = run_ffmpeg(app, args).await?;
+    let encode_seconds = started.elapsed().as_secs_f64();
+    if !result.success() {
+        return Err(format!(
+            "编码测试失败 ({} {}): {}",
+            profile.codec.encoder(),
+            profile.preset,
+            String::from_utf8_lossy(&result.stderr)
+        ));
+    }
+
+    let file_size = fs::metadata(output).map(|m| m.len()).unwrap_or(0);
+    Ok(BenchmarkEntry {
+        profile: profile.clone(),
+        encode_seconds,
+        speed: CLIP_DURATION / encode_seconds.max(0.001),
+        ssim: measure_ssim(app, output, source).await,
+        file_size,
+    })
+}
+
+// 在本机测试各编码器/预设的速度与画质，保存结果并给出推荐配置
+#[tauri::command]
+pub async fn run_benchmark(app: AppHandle) -> Result<BenchmarkResult, String> {
+    let temp_dir = std::env::temp_dir().join("mp4handler_benchmark");
+    fs::create_dir_all(&temp_dir).map_err(|e| format!("创建临时目录失败: {}", e))?;
+    let registry = app.state::<ProcessRegistry>();
+    registry.track_temp_dir(&temp_dir);
+
+    emit_progress(&app, "benchmark.generating", &[], 0);
+    let source = temp_dir.join("source.mp4");
+    generate_clip(&app, &source).await?;
+
+    let profiles: Vec<EncodingProfile> = [VideoCodec::H264, VideoCodec::Hevc]
+        .iter()
+        .flat_map(|codec| {
+            PRESETS.iter().map(move |preset| EncodingProfile {
+                codec: *codec,
+                preset: preset.to_string(),
+            })
+        })
+        .collect();
+
+    let mut entries = Vec::new();
+    let mut failures = Vec::new();
+    for (idx, profile) in profiles.iter().enumerate() {
+        emit_progress(
+            &app,
+            "benchmark.testing",
+            &[
+                profile.codec.encoder().to_string(),
+                profile.preset.clone(),
+                (idx + 1).to_string(),
+                profiles.len().to_string(),
+            ],
+            (idx as f64 / profiles.len() as f64 * 100.0) as u32,
+        );
+        let output = temp_dir.join(format!("{}_{}.mp4", profile.codec.encoder(), profile.preset));
+        // 某个编码器不可用时跳过，不影响其余测试
+        match encode_profile(&app, &source, &output, profile).await {
+            Ok(entry) => entries.push(entry),
+            Err(e) => failures.push(e),
+        }
+        let _ = fs::remove_file(&output);
+    }
+    let _ = fs::remove_dir_all(&temp_dir);
+    registry.release_temp_dir(&temp_dir);
+
+    if entries.is_empty() {
+        return Err(format!("所有编码测试均失败: {}", failures.join("; ")));
+    }
+
+    let result = BenchmarkResult {
+        created_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        clip: format!("testsrc2 {} {}fps {}s", CLIP_SIZE, CLIP_RATE, CLIP_DURATION),
+        recommended: BenchmarkResult::recommend(&entries),
+        entries,
+        failures,
+    };
+
+    let path = result_path(&app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建配置目录失败: {}", e))?;
+    }
+    let content =
+        serde_json::to_string_pretty(&result).map_err(|e| format!("序列化测试结果失败: {}", e))?;
+    fs::write(&path, content).map_err(|e| format!("保存测试结果失败: {}", e))?;
+
+    emit_progress(&app, "common.done", &[], 100);
+    Ok(result)
+}
+
+// 读取上次保存的编码性能测试结果
+#[tauri::command]
+pub fn get_benchmark_result(app: AppHandle) -> Result<Option<BenchmarkResult>, String> {
+    let path = result_path(&app)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path).map_err(|e| format!("读取测试结果失败: {}", e))?;
+    let result = serde_json::from_str(&content).map_err(|e| format!("解析测试结果失败: {}", e))?;
+    Ok(Some(result))
+}
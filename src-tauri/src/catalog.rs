@@ -0,0 +1,225 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
+use ts_rs::TS;
+use crate::events::CATALOG_PROGRESS;
+use crate::i18n::{localize_error, progress};
+use crate::path_scope::{check_input, check_output};
+use crate::stats::record_operation;
+use crate::throttle::{self, run_ffmpeg};
+use crate::video_processor::{collect_videos, get_video_info};
+
+/// 默认每隔多少秒截一张缩略图
+const DEFAULT_INTERVAL: f64 = 60.0;
+/// 默认缩略图宽度
+const DEFAULT_WIDTH: u32 = 320;
+/// 目录索引文件名
+const CATALOG_INDEX: &str = "catalog.json";
+
+/// 一张缩略图
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct CatalogThumbnail {
+    /// 相对输出目录的路径
+    pub file: String,
+    /// 在视频中的大致时间（秒）
+    pub timestamp: f64,
+}
+
+/// 一个视频的缩略图目录
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct CatalogEntry {
+    pub path: String,
+    /// 缩略图所在子目录（相对输出目录）
+    pub folder: String,
+    pub duration: f64,
+    pub width: u32,
+    pub height: u32,
+    pub thumbnails: Vec<CatalogThumbnail>,
+    /// 处理失败的原因（其余视频照常生成）
+    pub error: Option<String>,
+}
+
+/// 目录索引（写入输出目录的 catalog.json）
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct CatalogIndex {
+    pub source_dir: String,
+    pub interval: f64,
+    pub generated_at: String,
+    pub videos: Vec<CatalogEntry>,
+}
+
+/// 子目录名：相对源目录的路径，分隔符替换为下划线，避免不同子目录下的同名视频冲突
+fn folder_name(source_dir: &Path, video: &Path) -> String {
+    video
+        .strip_prefix(source_dir)
+        .unwrap_or(video)
+        .with_extension("")
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// 按固定间隔截取缩略图到子目录，返回生成的缩略图
+///
+/// 只解码关键帧（`-skip_frame nokey`），长视频也能很快完成；缩略图取间隔点附近的关键帧
+async fn extract_thumbnails(
+    app: &AppHandle,
+    video: &Path,
+    output_dir: &Path,
+    folder: &str,
+    interval: f64,
+    width: u32,
+) -> Result<Vec<CatalogThumbnail>, String> {
+    let dir = output_dir.join(folder);
+    if dir.exists() {
+        fs::remove_dir_all(&dir).map_err(|e| format!("清理缩略图目录失败: {}", e))?;
+    }
+    fs::create_dir_all(&dir).map_err(|e| format!("创建缩略图目录失败: {}", e))?;
+
+    let mut args: Vec<String> = [
+        "-skip_frame",
+        "nokey",
+        "-i",
+        &video.to_string_lossy(),
+        "-an",
+        "-vf",
+        &format!("fps=1/{},scale={}:-2", interval, width),
+        "-vsync",
+        "vfr",
+        "-q:v",
+        "4",
+        "-y",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    args.extend(throttle::current(app).output_args());
+    args.push(dir.join("thumb_%04d.jpg").to_string_lossy().to_string());
+
+    let output = run_ffmpeg(app, args).await?;
+    if !output.success() {
+        return Err(format!(
+            "截取缩略图失败: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let mut files: Vec<PathBuf> = fs::read_dir(&dir)
+        .map_err(|e| format!("读取缩略图目录失败: {}", e))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "jpg"))
+        .collect();
+    files.sort();
+    Ok(files
+        .iter()
+        .enumerate()
+        .map(|(idx, file)| CatalogThumbnail {
+            file: format!("{}/{}", folder, file.file_name().unwrap().to_string_lossy()),
+            timestamp: idx as f64 * interval,
+        })
+        .collect())
+}
+
+// 为目录中的每个视频按固定间隔截取缩略图（每个视频一个子目录），并生成 catalog.json 索引
+#[tauri::command]
+pub async fn generate_catalog_thumbnails(
+    app: AppHandle,
+    dir: String,
+    output_dir: Option<String>,
+    interval: Option<f64>,
+    width: Option<u32>,
+    max_depth: Option<usize>,
+) -> Result<CatalogIndex, String> {
+    let result = generate_catalog_internal(&app, dir, output_dir, interval, width, max_depth).await;
+    record_operation(&app, "catalog", &result);
+    result.map_err(|e| localize_error(&app, e))
+}
+
+async fn generate_catalog_internal(
+    app: &AppHandle,
+    dir: String,
+    output_dir: Option<String>,
+    interval: Option<f64>,
+    width: Option<u32>,
+    max_depth: Option<usize>,
+) -> Result<CatalogIndex, String> {
+    let source_dir = check_input(app, &dir)?;
+    // 默认输出到源目录下的 catalog 子目录
+    let output_dir = output_dir.unwrap_or_else(|| source_dir.join("catalog").to_string_lossy().to_string());
+    let output_dir = check_output(app, &output_dir)?;
+    let interval = interval.unwrap_or(DEFAULT_INTERVAL);
+    if interval <= 0.0 {
+        return Err("截图间隔必须大于 0".to_string());
+    }
+    let width = width.unwrap_or(DEFAULT_WIDTH);
+    if width == 0 || !width.is_multiple_of(2) {
+        return Err("缩略图宽度必须为大于 0 的偶数".to_string());
+    }
+    let window = app
+        .get_webview_window("main")
+        .ok_or("无法获取窗口")?;
+
+    fs::create_dir_all(&output_dir).map_err(|e| format!("创建输出目录失败: {}", e))?;
+    // 输出目录在源目录内时，不把上次生成的内容当作视频
+    let videos: Vec<PathBuf> = collect_videos(&source_dir.to_string_lossy(), max_depth.unwrap_or(0))?
+        .into_iter()
+        .filter(|v| !v.starts_with(&output_dir))
+        .collect();
+
+    let total = videos.len();
+    let mut entries = Vec::with_capacity(total);
+    for (idx, video) in videos.iter().enumerate() {
+        let _ = window.emit(
+            CATALOG_PROGRESS,
+            progress(
+                app,
+                "catalog.generating",
+                &[(idx + 1).to_string(), total.to_string(), video.display().to_string()],
+                (idx as f64 / total as f64 * 100.0) as u32,
+            ),
+        );
+
+        let folder = folder_name(&source_dir, video);
+        let mut entry = CatalogEntry {
+            path: video.to_string_lossy().to_string(),
+            folder: folder.clone(),
+            duration: 0.0,
+            width: 0,
+            height: 0,
+            thumbnails: Vec::new(),
+            error: None,
+        };
+        let result = match get_video_info(app, video).await {
+            Ok(info) => {
+                entry.duration = info.duration;
+                entry.width = info.width;
+                entry.height = info.height;
+                extract_thumbnails(app, video, &output_dir, &folder, interval, width).await
+            }
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(thumbnails) => entry.thumbnails = thumbnails,
+            Err(e) => entry.error = Some(e),
+        }
+        entries.push(entry);
+    }
+
+    let index = CatalogIndex {
+        source_dir: source_dir.to_string_lossy().to_string(),
+        interval,
+        generated_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        videos: entries,
+    };
+    let content = serde_json::to_string_pretty(&index).map_err(|e| format!("序列化目录索引失败: {}", e))?;
+    fs::write(output_dir.join(CATALOG_INDEX), content).map_err(|e| format!("写入目录索引失败: {}", e))?;
+
+    let _ = window.emit(CATALOG_PROGRESS, progress(app, "common.done", &[], 100));
+    Ok(index)
+}
@@ -0,0 +1,82 @@
+/// 等功率系数（-3dB），用于中置/环绕声道并入左右声道以及单声道复制到两侧
+const EQUAL_POWER: f64 = std::f64::consts::FRAC_1_SQRT_2;
+
+/// 输入音轨的声道布局（按声道数与 FFprobe 报告的布局名识别）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelLayout {
+    Mono,
+    Stereo,
+    /// 5.1 / 5.1(side)：FL FR FC LFE 环绕L 环绕R
+    Surround51,
+    /// 7.1：FL FR FC LFE BL BR SL SR
+    Surround71,
+    /// 其他声道数（布局未知或少见）
+    Other(u32),
+}
+
+impl ChannelLayout {
+    /// 声道数为 0 表示 FFprobe 没有报告，按立体声处理（交给 aformat 转换）
+    pub fn from_probe(channels: u32, layout: &str) -> Self {
+        match (channels, layout) {
+            (1, _) => Self::Mono,
+            (0, _) | (2, _) => Self::Stereo,
+            (6, "5.1" | "5.1(side)") => Self::Surround51,
+            (8, "7.1" | "7.1(wide)" | "7.1(wide-side)") => Self::Surround71,
+            (n, _) => Self::Other(n),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Self::Mono => "单声道".to_string(),
+            Self::Stereo => "立体声".to_string(),
+            Self::Surround51 => "5.1 声道".to_string(),
+            Self::Surround71 => "7.1 声道".to_string(),
+            Self::Other(n) => format!("{} 声道", n),
+        }
+    }
+
+    /// 左右声道的混音表达式与整体增益补偿
+    ///
+    /// 中置与环绕按等功率并入左右声道，LFE 丢弃（与 ITU 下混一致）；
+    /// 增益取系数之和的倒数，保证所有声道同时满幅时也不会削波
+    fn downmix(&self) -> Option<(String, String, f64)> {
+        let k = EQUAL_POWER;
+        match self {
+            Self::Stereo => None,
+            // 单声道复制到两侧后按等功率衰减，与立体声素材的响度保持一致
+            Self::Mono => Some(("c0".to_string(), "c0".to_string(), k)),
+            Self::Surround51 => Some((
+                format!("c0+{k}*c2+{k}*c4"),
+                format!("c1+{k}*c2+{k}*c5"),
+                1.0 / (1.0 + 2.0 * k),
+            )),
+            Self::Surround71 => Some((
+                format!("c0+{k}*c2+{k}*c4+{k}*c6"),
+                format!("c1+{k}*c2+{k}*c5+{k}*c7"),
+                1.0 / (1.0 + 3.0 * k),
+            )),
+            // 布局未知：偶数序号的声道并入左声道，奇数并入右声道
+            Self::Other(n) => {
+                let side = |first: u32| {
+                    (first..*n)
+                        .step_by(2)
+                        .map(|c| format!("c{}", c))
+                        .collect::<Vec<_>>()
+                        .join("+")
+                };
+                Some((side(0), side(1), 1.0 / n.div_ceil(2) as f64))
+            }
+        }
+    }
+
+    /// 转为立体声的滤镜链（带结尾逗号，可直接拼在 aformat 之前）；已是立体声返回空字符串
+    pub fn stereo_filter(&self) -> String {
+        match self.downmix() {
+            Some((left, right, gain)) => {
+                format!("pan=stereo|c0={}|c1={},volume={:.4},", left, right, gain)
+            }
+            None => String::new(),
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_shell::ShellExt;
+use ts_rs::TS;
+use crate::video_processor::get_video_info;
+
+/// 画面分析用的缩略图边长
+const SAMPLE_SIZE: usize = 32;
+/// 每个片段采样的帧数
+const SAMPLE_FRAMES: usize = 6;
+
+/// 抽取出的片段在成片中的排列方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ClipOrder {
+    /// 随机顺序（默认行为）
+    #[default]
+    Shuffle,
+    /// 短片段在前
+    ShortestFirst,
+    /// 长短交替
+    AlternateLongShort,
+    /// 按色温由暖到冷
+    ColorTemperature,
+    /// 按运动强度由弱到强
+    Motion,
+}
+
+impl ClipOrder {
+    /// 是否需要分析画面内容
+    fn needs_traits(&self) -> bool {
+        matches!(self, Self::ColorTemperature | Self::Motion)
+    }
+}
+
+/// 片段画面特征
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ClipTraits {
+    /// 色温倾向（-1.0 偏冷 ~ 1.0 偏暖），按红蓝通道均值之差估算
+    pub warmth: f64,
+    /// 运动强度（0.0-1.0），相邻采样帧的平均亮度差
+    pub motion: f64,
+}
+
+/// 缓存项：文件大小与修改时间不变时复用分析结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedTraits {
+    size: u64,
+    modified: u64,
+    traits: ClipTraits,
+}
+
+/// 片段画面特征缓存，保存在应用缓存目录的 clip_analysis.json
+pub struct ClipAnalysisCache {
+    path: PathBuf,
+    entries: Mutex<HashMap<String, CachedTraits>>,
+}
+
+impl ClipAnalysisCache {
+    pub fn load(path: PathBuf) -> Self {
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            entries: Mutex::new(entries),
+        }
+    }
+
+    fn get(&self, video: &Path) -> Option<ClipTraits> {
+        let (size, modified) = file_stamp(video)?;
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(&video.to_string_lossy().to_string())
+            .filter(|e| e.size == size && e.modified == modified)
+            .map(|e| e.traits)
+    }
+
+    fn insert(&self, video: &Path, traits: ClipTraits) {
+        let Some((size, modified)) = file_stamp(video) else {
+            return;
+        };
+        let mut entries = self.entries.lock().unwrap();
+        entries.insert(
+            video.to_string_lossy().to_string(),
+            CachedTraits {
+                size,
+                modified,
+                traits,
+            },
+        );
+
+        // 缓存写入失败不影响业务
+        if let Ok(content) = serde_json::to_string(&*entries) {
+            if let Some(parent) = self.path.parent() {
+                let _ = fs::create_dir_all(parent);
+            }
+            let _ = fs::write(&self.path, content);
+        }
+    }
+}
+
+/// 文件大小与修改时间（秒）
+pub(crate) fn file_stamp(path: &Path) -> Option<(u64, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    Some((metadata.len(), modified))
+}
+
+/// 采样若干帧缩略图，统计色温倾向与运动强度
+async fn analyze_traits(app: &AppHandle, video_path: &Path, duration: f64) -> Result<ClipTraits, String> {
+    let sidecar = app
+        .shell()
+        .sidecar("ffmpeg")
+        .map_err(|e| format!("FFmpeg 启动失败: {}", e))?;
+
+    let vf = format!(
+        "fps={:.6},scale={s}:{s},format=rgb24",
+        SAMPLE_FRAMES as f64 / duration.max(1.0),
+        s = SAMPLE_SIZE
+    );
+    let output = sidecar
+        .args([
+            "-v",
+            "error",
+            "-i",
+            &video_path.to_string_lossy(),
+            "-vf",
+            &vf,
+            "-frames:v",
+            &SAMPLE_FRAMES.to_string(),
+            "-f",
+            "rawvideo",
+            "-",
+        ])
+        .output()
+        .await
+        .map_err(|e| format!("FFmpeg 执行失败: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "采样画面失败: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let pixels = SAMPLE_SIZE * SAMPLE_SIZE;
+    let frames: Vec<&[u8]> = output.stdout.chunks_exact(pixels * 3).collect();
+    if frames.is_empty() {
+        return Err("采样画面为空".to_string());
+    }
+
+    let luma = |frame: &[u8], i: usize| {
+        0.299 * frame[i * 3] as f64 + 0.587 * frame[i * 3 + 1] as f64 + 0.114 * frame[i * 3 + 2] as f64
+    };
+    let mut warmth = 0.0;
+    let mut motion = 0.0;
+    for (idx, frame) in frames.iter().enumerate() {
+        for i in 0..pixels {
+            warmth += (frame[i * 3] as f64 - frame[i * 3 + 2] as f64) / 255.0;
+            if idx > 0 {
+                motion += (luma(frame, i) - luma(frames[idx - 1], i)).abs() / 255.0;
+            }
+        }
+    }
+
+    Ok(ClipTraits {
+        warmth: warmth / (frames.len() * pixels) as f64,
+        motion: if frames.len() > 1 {
+            motion / ((frames.len() - 1) * pixels) as f64
+        } else {
+            0.0
+        },
+    })
+}
+
+/// 获取片段画面特征（优先使用缓存）
+pub async fn clip_traits(app: &AppHandle, video_path: &Path, duration: f64) -> Result<ClipTraits, String> {
+    let cache = app.state::<ClipAnalysisCache>();
+    if let Some(traits) = cache.get(video_path) {
+        return Ok(traits);
+    }
+    let traits = analyze_traits(app, video_path, duration).await?;
+    cache.insert(video_path, traits);
+    Ok(traits)
+}
+
+/// 长短交替：最长、最短、次长、次短……
+fn alternate_long_short(mut items: Vec<(PathBuf, f64)>) -> Vec<PathBuf> {
+    items.sort_by(|a, b| a.1.total_cmp(&b.1));
+    let mut ordered = Vec::with_capacity(items.len());
+    let mut take_long = true;
+    while !items.is_empty() {
+        let item = if take_long { items.pop() } else { Some(items.remove(0)) };
+        ordered.extend(item.map(|(path, _)| path));
+        take_long = !take_long;
+    }
+    ordered
+}
+
+/// 按排列方式重新排序抽取出的片段
+pub async fn order_clips(app: &AppHandle, videos: Vec<PathBuf>, order: ClipOrder) -> Result<Vec<PathBuf>, String> {
+    // 从池子抽取时已经随机打乱
+    if order == ClipOrder::Shuffle {
+        return Ok(videos);
+    }
+
+    let mut items = Vec::with_capacity(videos.len());
+    for video in videos {
+        let duration = get_video_info(app, &video).await?.duration;
+        let key = if order.needs_traits() {
+            let traits = clip_traits(app, &video, duration).await?;
+            match order {
+                // 暖色在前
+                ClipOrder::ColorTemperature => -traits.warmth,
+                _ => traits.motion,
+            }
+        } else {
+            duration
+        };
+        items.push((video, key));
+    }
+
+    if order == ClipOrder::AlternateLongShort {
+        return Ok(alternate_long_short(items));
+    }
+    items.sort_by(|a, b| a.1.total_cmp(&b.1));
+    Ok(items.into_iter().map(|(path, _)| path).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alternates_longest_and_shortest() {
+        let items = vec![
+            (PathBuf::from("a"), 1.0),
+            (PathBuf::from("b"), 5.0),
+            (PathBuf::from("c"), 3.0),
+            (PathBuf::from("d"), 2.0),
+        ];
+        let ordered: Vec<PathBuf> = ["b", "a", "c", "d"].iter().map(PathBuf::from).collect();
+        assert_eq!(alternate_long_short(items), ordered);
+    }
+}
@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, State};
+use ts_rs::TS;
+use crate::path_scope::check_input;
+
+/// 片段合集：手动整理的一组片段，可代替目录作为拼接的来源
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Collection {
+    pub name: String,
+    /// 有序合集按列表顺序依次抽取片段，无序合集随机抽取
+    #[serde(default)]
+    pub ordered: bool,
+    #[serde(default)]
+    pub clips: Vec<String>,
+}
+
+impl Collection {
+    /// 合集在视频池中的 key，与目录池子区分
+    pub fn pool_key(&self) -> String {
+        format!("collection:{}", self.name)
+    }
+}
+
+/// 片段合集，保存在数据目录的 collections.json
+pub struct CollectionManager {
+    path: PathBuf,
+    collections: Mutex<BTreeMap<String, Collection>>,
+}
+
+impl CollectionManager {
+    pub fn load(path: PathBuf) -> Self {
+        let collections = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            collections: Mutex::new(collections),
+        }
+    }
+
+    /// 保存所有合集（在持有锁时调用），写入失败不影响业务
+    fn save(&self, collections: &BTreeMap<String, Collection>) {
+        if let Ok(content) = serde_json::to_string(collections) {
+            if let Some(parent) = self.path.parent() {
+                let _ = fs::create_dir_all(parent);
+            }
+            let _ = fs::write(&self.path, content);
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Result<Collection, String> {
+        let collections = self.collections.lock().unwrap();
+        collections
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("合集不存在: {}", name))
+    }
+
+    /// 修改已有合集并保存，返回修改后的合集
+    fn update(&self, name: &str, change: impl FnOnce(&mut Collection)) -> Result<Collection, String> {
+        let mut collections = self.collections.lock().unwrap();
+        let collection = collections
+            .get_mut(name)
+            .ok_or_else(|| format!("合集不存在: {}", name))?;
+        change(collection);
+        let collection = collection.clone();
+        self.save(&collections);
+        Ok(collection)
+    }
+}
+
+/// 检查片段路径，并去掉重复的片段（保留第一次出现的位置）
+fn check_clips(app: &AppHandle, clips: Vec<String>) -> Result<Vec<String>, String> {
+    let mut unique: Vec<String> = Vec::with_capacity(clips.len());
+    for clip in clips {
+        check_input(app, &clip)?;
+        if !unique.contains(&clip) {
+            unique.push(clip);
+        }
+    }
+    Ok(unique)
+}
+
+// 获取所有合集
+#[tauri::command]
+pub fn list_collections(manager: State<'_, CollectionManager>) -> Vec<Collection> {
+    manager.collections.lock().unwrap().values().cloned().collect()
+}
+
+// 创建或整体替换合集
+#[tauri::command]
+pub fn save_collection(
+    app: AppHandle,
+    manager: State<'_, CollectionManager>,
+    name: String,
+    ordered: Option<bool>,
+    clips: Vec<String>,
+) -> Result<Collection, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("合集名称不能为空".to_string());
+    }
+    let collection = Collection {
+        name: name.clone(),
+        ordered: ordered.unwrap_or(false),
+        clips: check_clips(&app, clips)?,
+    };
+    let mut collections = manager.collections.lock().unwrap();
+    collections.insert(name, collection.clone());
+    manager.save(&collections);
+    Ok(collection)
+}
+
+// 向合集末尾追加片段（已在合集中的片段保持原位置）
+#[tauri::command]
+pub fn add_collection_clips(
+    app: AppHandle,
+    manager: State<'_, CollectionManager>,
+    name: String,
+    clips: Vec<String>,
+) -> Result<Collection, String> {
+    let clips = check_clips(&app, clips)?;
+    manager.update(&name, |collection| {
+        for clip in clips {
+            if !collection.clips.contains(&clip) {
+                collection.clips.push(clip);
+            }
+        }
+    })
+}
+
+// 从合集中移除片段
+#[tauri::command]
+pub fn remove_collection_clips(
+    manager: State<'_, CollectionManager>,
+    name: String,
+    clips: Vec<String>,
+) -> Result<Collection, String> {
+    manager.update(&name, |collection| collection.clips.retain(|c| !clips.contains(c)))
+}
+
+// 删除合集（不删除片段文件）
+#[tauri::command]
+pub fn delete_collection(manager: State<'_, CollectionManager>, name: String) -> Result<(), String> {
+    let mut collections = manager.collections.lock().unwrap();
+    if collections.remove(&name).is_none() {
+        return Err(format!("合集不存在: {}", name));
+    }
+    manager.save(&collections);
+    Ok(())
+}
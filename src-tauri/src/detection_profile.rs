@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use crate::frame_similarity::{SimilarityAlgorithm, SimilarityTuning};
+use crate::settings::SettingsManager;
+use crate::video_frame_extractor::{FlashFilter, Hysteresis};
+
+/// 检测预设：按素材类型打包的场景检测参数（算法、阈值、最短时长、滞回与闪光过滤）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DetectionProfile {
+    pub name: String,
+    /// 相似度算法名称（与自动拆解一致）
+    pub algorithm: String,
+    pub threshold: f64,
+    /// 最短片段时长（秒）
+    pub min_duration: f64,
+    #[serde(default)]
+    pub hysteresis: Option<Hysteresis>,
+    #[serde(default)]
+    pub flash_filter: Option<FlashFilter>,
+}
+
+impl DetectionProfile {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("检测预设名称不能为空".to_string());
+        }
+        SimilarityAlgorithm::from_name(&self.algorithm, &SimilarityTuning::default())?;
+        if !(self.threshold > 0.0 && self.threshold <= 1.0) {
+            return Err("检测预设的阈值必须在 0 到 1 之间".to_string());
+        }
+        if self.min_duration < 0.0 {
+            return Err("检测预设的最短时长不能为负数".to_string());
+        }
+        if let Some(hysteresis) = &self.hysteresis {
+            hysteresis.validate(self.threshold)?;
+        }
+        if let Some(filter) = &self.flash_filter {
+            filter.validate()?;
+        }
+        Ok(())
+    }
+}
+
+/// 内置的素材类型预设
+pub fn builtin_profiles() -> Vec<DetectionProfile> {
+    vec![
+        // 手持拍摄，镜头晃动与补光闪烁较多
+        DetectionProfile {
+            name: "vlog".to_string(),
+            algorithm: "histogram".to_string(),
+            threshold: 0.75,
+            min_duration: 1.5,
+            hysteresis: None,
+            flash_filter: Some(FlashFilter {
+                median_window: 5,
+                min_persist: 3,
+            }),
+        },
+        // 画面运动剧烈，爆炸、技能特效容易误切
+        DetectionProfile {
+            name: "gameplay".to_string(),
+            algorithm: "frame_diff".to_string(),
+            threshold: 0.6,
+            min_duration: 2.0,
+            hysteresis: Some(Hysteresis {
+                recovered: 0.75,
+                stable_frames: 5,
+            }),
+            flash_filter: Some(FlashFilter {
+                median_window: 5,
+                min_persist: 4,
+            }),
+        },
+        // 硬切为主，色块平整，闪白较常见
+        DetectionProfile {
+            name: "anime".to_string(),
+            algorithm: "edge".to_string(),
+            threshold: 0.7,
+            min_duration: 1.0,
+            hysteresis: None,
+            flash_filter: Some(FlashFilter {
+                median_window: 5,
+                min_persist: 3,
+            }),
+        },
+        // 静态画面，换页时变化明显
+        DetectionProfile {
+            name: "slideshow".to_string(),
+            algorithm: "ssim".to_string(),
+            threshold: 0.85,
+            min_duration: 2.0,
+            hysteresis: None,
+            flash_filter: None,
+        },
+        // 讲师走动、板书变化缓慢，只在换页或切机位时切分
+        DetectionProfile {
+            name: "lecture".to_string(),
+            algorithm: "ssim".to_string(),
+            threshold: 0.8,
+            min_duration: 5.0,
+            hysteresis: Some(Hysteresis {
+                recovered: 0.9,
+                stable_frames: 10,
+            }),
+            flash_filter: None,
+        },
+    ]
+}
+
+// 列出检测预设（自定义预设覆盖同名的内置预设）
+#[tauri::command]
+pub fn list_detection_profiles(settings: State<'_, SettingsManager>) -> Vec<DetectionProfile> {
+    let custom = settings.get().detection_profiles;
+    let mut profiles: Vec<DetectionProfile> = builtin_profiles()
+        .into_iter()
+        .filter(|profile| !custom.iter().any(|c| c.name == profile.name))
+        .collect();
+    profiles.extend(custom);
+    profiles
+}
+
+// 新建或覆盖同名检测预设（与内置预设同名时覆盖内置预设）
+#[tauri::command]
+pub fn save_detection_profile(settings: State<'_, SettingsManager>, profile: DetectionProfile) -> Result<(), String> {
+    profile.validate()?;
+    settings.update(|s| {
+        match s.detection_profiles.iter_mut().find(|p| p.name == profile.name) {
+            Some(existing) => *existing = profile,
+            None => s.detection_profiles.push(profile),
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+// 删除自定义检测预设（删除覆盖内置预设的同名预设后恢复内置设置）
+#[tauri::command]
+pub fn delete_detection_profile(settings: State<'_, SettingsManager>, name: String) -> Result<(), String> {
+    settings.update(|s| {
+        let before = s.detection_profiles.len();
+        s.detection_profiles.retain(|p| p.name != name);
+        if s.detection_profiles.len() == before {
+            return Err(format!("自定义检测预设不存在: {}", name));
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
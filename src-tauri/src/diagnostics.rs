@@ -0,0 +1,274 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
+use ts_rs::TS;
+use crate::events::SELF_TEST_PROGRESS;
+use crate::jobs::AutoSplitParams;
+use crate::path_scope::{approve_temporarily, revoke};
+use crate::shutdown::ProcessRegistry;
+use crate::throttle::run_ffmpeg;
+use crate::video_frame_extractor::run_auto_split;
+use crate::video_processor::{get_video_info, run_concat, ConcatOptions};
+
+/// 每个合成场景的时长（秒），每个测试视频由两个场景组成
+const SCENE_DURATION: f64 = 2.0;
+/// 时长校验允许的误差（秒）
+const DURATION_TOLERANCE: f64 = 0.3;
+/// 合成测试视频使用的两组场景（lavfi 视频源）
+const SOURCES: [(&str, &str); 2] = [("testsrc2", "smptebars"), ("rgbtestsrc", "testsrc2")];
+
+/// 自检中的一步
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct SelfTestStep {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+/// 自检结果
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct SelfTestReport {
+    pub passed: bool,
+    pub steps: Vec<SelfTestStep>,
+    /// 自检失败时保留的工作目录，便于排查
+    pub work_dir: Option<String>,
+}
+
+/// 用 lavfi 合成一个由两个场景拼成、带正弦波音轨的测试视频
+async fn generate_source(app: &AppHandle, path: &Path, scenes: (&str, &str)) -> Result<(), String> {
+    let scene = |name: &str| format!("{}=size=320x240:rate=25:duration={}", name, SCENE_DURATION);
+    let args: Vec<String> = [
+        "-f",
+        "lavfi",
+        "-i",
+        &scene(scenes.0),
+        "-f",
+        "lavfi",
+        "-i",
+        &scene(scenes.1),
+        "-f",
+        "lavfi",
+        "-i",
+        &format!("sine=frequency=440:duration={}", SCENE_DURATION * 2.0),
+        "-filter_complex",
+        "[0:v][1:v]concat=n=2:v=1:a=0[v]",
+        "-map",
+        "[v]",
+        "-map",
+        "2:a",
+        "-c:v",
+        "libx264",
+        "-pix_fmt",
+        "yuv420p",
+        "-c:a",
+        "aac",
+        "-y",
+        &path.to_string_lossy(),
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+
+    let output = run_ffmpeg(app, args).await?;
+    if !output.success() {
+        return Err(format!(
+            "生成测试视频失败: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(())
+}
+
+fn list_mp4(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.extension().map(|e| e.eq_ignore_ascii_case("mp4")).unwrap_or(false))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+/// 检查输出文件的视频/音频流与时长，返回时长
+async fn verify_output(app: &AppHandle, path: &Path) -> Result<f64, String> {
+    let info = get_video_info(app, path).await?;
+    if info.width == 0 || info.height == 0 {
+        return Err(format!("{} 没有视频流", path.display()));
+    }
+    if !info.has_audio {
+        return Err(format!("{} 没有音频流", path.display()));
+    }
+    if info.duration <= 0.0 {
+        return Err(format!("{} 时长为 0", path.display()));
+    }
+    Ok(info.duration)
+}
+
+/// 按顺序执行自检步骤，遇到失败即停止（后续步骤依赖前面的输出）
+async fn run_steps(app: &AppHandle, work_dir: &Path, steps: &mut Vec<SelfTestStep>) -> Result<(), String> {
+    let mut step = |name: &str, result: Result<String, String>| {
+        let passed = result.is_ok();
+        let detail = result.clone().unwrap_or_else(|e| e);
+        let _ = app.emit(SELF_TEST_PROGRESS, SelfTestStep {
+            name: name.to_string(),
+            passed,
+            detail: detail.clone(),
+        });
+        steps.push(SelfTestStep {
+            name: name.to_string(),
+            passed,
+            detail,
+        });
+        result.map(|_| ())
+    };
+
+    let input_dir = work_dir.join("input");
+    let split_dir = work_dir.join("split");
+    let concat_dir = work_dir.join("concat");
+    for dir in [&input_dir, &split_dir, &concat_dir] {
+        fs::create_dir_all(dir).map_err(|e| format!("创建自检目录失败: {}", e))?;
+    }
+    // 自检目录由应用自己创建，临时加入授权范围后走与用户操作相同的路径校验，
+    // 自检结束即撤销；旧版本曾把它永久写入设置，一并移除
+    revoke(app, work_dir)?;
+    let _approval = approve_temporarily(work_dir)?;
+
+    // 1. 合成测试视频
+    let mut sources = Vec::new();
+    let mut result = Ok(String::new());
+    for (idx, scenes) in SOURCES.iter().enumerate() {
+        let path = input_dir.join(format!("selftest_{}.mp4", idx + 1));
+        if let Err(e) = generate_source(app, &path, *scenes).await {
+            result = Err(e);
+            break;
+        }
+        match verify_output(app, &path).await {
+            Ok(duration) if (duration - SCENE_DURATION * 2.0).abs() <= DURATION_TOLERANCE => {}
+            Ok(duration) => {
+                result = Err(format!("测试视频时长异常: {:.2}s", duration));
+                break;
+            }
+            Err(e) => {
+                result = Err(e);
+                break;
+            }
+        }
+        sources.push(path);
+    }
+    step("生成测试视频", result.map(|_| format!("已生成 {} 个测试视频", sources.len())))?;
+
+    // 2. 自动拆解：每个测试视频应在场景切换处拆成两段，总时长不变
+    for source in &sources {
+        let name = format!(
+            "拆解 {}",
+            source.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+        );
+        let result = run_auto_split(
+            app,
+            source.to_string_lossy().to_string(),
+            AutoSplitParams {
+                output_dir: split_dir.to_string_lossy().to_string(),
+                algorithm: "histogram".to_string(),
+                threshold: 0.8,
+                min_duration: 0.5,
+                ..AutoSplitParams::default()
+            },
+        )
+        .await;
+        if let Err(e) = result {
+            step(&name, Err(e))?;
+        }
+
+        let stem = source.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let segments = list_mp4(&split_dir.join(&stem));
+        let mut total = 0.0;
+        for segment in &segments {
+            match verify_output(app, segment).await {
+                Ok(duration) => total += duration,
+                Err(e) => step(&name, Err(e))?,
+            }
+        }
+        let result = if segments.len() < 2 {
+            Err(format!("未检测到场景切换，只生成了 {} 个片段", segments.len()))
+        } else if (total - SCENE_DURATION * 2.0).abs() > DURATION_TOLERANCE * segments.len() as f64 {
+            Err(format!("片段总时长 {:.2}s 与源视频不一致", total))
+        } else {
+            Ok(format!("{} 个片段，总时长 {:.2}s", segments.len(), total))
+        };
+        step(&name, result)?;
+    }
+
+    // 3. 随机拼接拆解出的片段
+    let result = run_concat(
+        app,
+        ConcatOptions {
+            input_dir: split_dir.to_string_lossy().to_string(),
+            random_count_min: 2,
+            random_count_max: 2,
+            max_depth: 2,
+            run_times: 1,
+            output_dir: concat_dir.to_string_lossy().to_string(),
+            ..ConcatOptions::default()
+        },
+    )
+    .await;
+    if let Err(e) = result {
+        step("拼接片段", Err(e))?;
+    }
+    let outputs = list_mp4(&concat_dir);
+    let result = match outputs.first() {
+        None => Err("拼接没有生成输出文件".to_string()),
+        Some(output) => verify_output(app, output)
+            .await
+            .map(|duration| format!("{}（{:.2}s）", output.display(), duration)),
+    };
+    step("拼接片段", result)?;
+
+    Ok(())
+}
+
+// 自检：合成测试视频并跑完整的拆解→拼接流程，检查输出的时长与音视频流
+#[tauri::command]
+pub async fn run_self_test(app: AppHandle) -> Result<SelfTestReport, String> {
+    let work_dir = std::env::temp_dir().join("mp4handler_selftest");
+    if work_dir.exists() {
+        fs::remove_dir_all(&work_dir).map_err(|e| format!("清理自检目录失败: {}", e))?;
+    }
+
+    // 自检中途退出应用时删除工作目录
+    let registry = app.state::<ProcessRegistry>();
+    registry.track_temp_dir(&work_dir);
+    let mut steps = Vec::new();
+    let result = run_steps(&app, &work_dir, &mut steps).await;
+    registry.release_temp_dir(&work_dir);
+    // 准备阶段出错时也记为一个失败的步骤
+    if let Err(e) = &result {
+        if steps.last().map(|s| s.passed).unwrap_or(true) {
+            steps.push(SelfTestStep {
+                name: "准备".to_string(),
+                passed: false,
+                detail: e.clone(),
+            });
+        }
+    }
+
+    let passed = result.is_ok();
+    let work_dir = if passed {
+        let _ = fs::remove_dir_all(&work_dir);
+        None
+    } else {
+        Some(work_dir.to_string_lossy().to_string())
+    };
+    Ok(SelfTestReport {
+        passed,
+        steps,
+        work_dir,
+    })
+}
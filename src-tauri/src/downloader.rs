@@ -2,10 +2,23 @@ use reqwest::Client;
 use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
 use futures_util::StreamExt;
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use tauri::{AppHandle, Manager, Emitter};
+use ts_rs::TS;
+use crate::batch::{BatchItemResult, BatchSummary};
+use crate::events::{DOWNLOAD_BATCH_PROGRESS, DOWNLOAD_BATCH_SUMMARY, DOWNLOAD_PROGRESS, POOL_UPDATED};
+use crate::jobs::{JobPriority, JobQueue, PostDownloadAction};
+use crate::stats::{record_operation, StatsManager};
+use crate::i18n::t;
+use crate::settings::SettingsManager;
+use crate::path_scope::{check_input, check_output, sanitize_filename};
+use crate::video_processor::{get_video_info, PoolFilters, VideoPoolManager};
 
-#[derive(serde::Serialize, Clone)]
+#[derive(serde::Serialize, Clone, TS)]
+#[ts(export)]
 struct DownloadProgress {
     url: String,
     progress: u32,
@@ -13,44 +26,353 @@ struct DownloadProgress {
     status: String,
 }
 
+/// 单个下载条目：可以是单个 URL，也可以是按优先级排列的多个镜像地址
+#[derive(serde::Deserialize, Clone, TS)]
+#[ts(export)]
+#[serde(untagged)]
+pub enum DownloadItem {
+    Url(String),
+    Mirrors { urls: Vec<String> },
+}
+
+impl DownloadItem {
+    /// 按优先级排列的候选地址（去掉空行）
+    fn candidates(&self) -> Vec<String> {
+        let urls = match self {
+            Self::Url(url) => vec![url.clone()],
+            Self::Mirrors { urls } => urls.clone(),
+        };
+        urls.into_iter()
+            .map(|u| u.trim().to_string())
+            .filter(|u| !u.is_empty())
+            .collect()
+    }
+
+    /// 主地址（用于文件名和进度事件）
+    fn primary(&self) -> String {
+        self.candidates().into_iter().next().unwrap_or_default()
+    }
+}
+
+/// 单个条目的下载结果
+#[derive(serde::Serialize, Clone, TS)]
+#[ts(export)]
+struct DownloadItemResult {
+    url: String,
+    /// 实际成功的地址
+    succeeded_url: Option<String>,
+    /// 是否由备用镜像下载成功
+    used_mirror: bool,
+    attempts: u32,
+    error: Option<String>,
+}
+
+/// 单次下载失败的原因
+enum DownloadError {
+    /// 磁盘空间不足，继续下载也不会成功
+    DiskFull(String),
+    Failed(String),
+}
+
+impl std::fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DiskFull(msg) | Self::Failed(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl From<String> for DownloadError {
+    fn from(msg: String) -> Self {
+        Self::Failed(msg)
+    }
+}
+
+/// 把写文件错误区分为磁盘已满与其他错误，`key` 为对应操作的消息 key
+fn write_error(app: &AppHandle, key: &str, e: std::io::Error) -> DownloadError {
+    let message = t(app, key, &[e.to_string()]);
+    if e.kind() == std::io::ErrorKind::StorageFull {
+        DownloadError::DiskFull(t(app, "error.disk_full_io", &[message]))
+    } else {
+        DownloadError::Failed(message)
+    }
+}
+
+/// 下载成功的结果
+struct DownloadOutcome {
+    path: PathBuf,
+    url: String,
+    attempts: u32,
+}
+
+/// 下载失败的结果：所有地址的错误与总尝试次数
+struct DownloadFailure {
+    error: String,
+    attempts: u32,
+    /// 磁盘已满后未开始下载，汇总中记为跳过
+    skipped: bool,
+}
+
+impl DownloadFailure {
+    /// 未开始下载（任务异常、地址为空）
+    fn not_attempted(error: String) -> Self {
+        Self { error, attempts: 0, skipped: false }
+    }
+
+    /// 磁盘已满，跳过尚未开始的条目
+    fn disk_full(app: &AppHandle) -> Self {
+        Self { error: t(app, "error.disk_full", &[]), attempts: 0, skipped: true }
+    }
+}
+
+/// 整批下载的汇总进度
+#[derive(serde::Serialize, Clone, TS)]
+#[ts(export)]
+struct BatchDownloadProgress {
+    files_done: usize,
+    files_failed: usize,
+    files_total: usize,
+    #[ts(type = "number")]
+    bytes_done: u64,
+    /// 已知大小文件的总字节数（尚未开始或未返回大小的文件不计入）
+    #[ts(type = "number")]
+    bytes_total: u64,
+    /// 是否所有文件的大小都已知
+    bytes_total_exact: bool,
+    speed: String,
+    #[ts(type = "number | null")]
+    eta_seconds: Option<u64>,
+}
+
+/// 整批下载结束后的汇总
+#[derive(serde::Serialize, Clone, TS)]
+#[ts(export)]
+struct BatchDownloadSummary {
+    files_total: usize,
+    succeeded: usize,
+    failed: usize,
+    #[ts(type = "number")]
+    bytes_downloaded: u64,
+    elapsed_seconds: f64,
+    average_speed: String,
+    failures: Vec<(String, String)>,
+    results: Vec<DownloadItemResult>,
+}
+
+struct BatchTrackerState {
+    bytes_done: Vec<u64>,
+    sizes: Vec<Option<u64>>,
+    files_done: usize,
+    files_failed: usize,
+    last_emit: std::time::Instant,
+}
+
+/// 汇总各个下载任务的进度，按固定间隔发送 download_batch_progress 事件
+struct BatchTracker {
+    window: tauri::WebviewWindow,
+    start: std::time::Instant,
+    state: std::sync::Mutex<BatchTrackerState>,
+    /// 任一文件遇到磁盘已满后置位，之后不再开始新的下载
+    disk_full: AtomicBool,
+}
+
+impl BatchTracker {
+    fn new(window: tauri::WebviewWindow, files_total: usize) -> Self {
+        let now = std::time::Instant::now();
+        Self {
+            window,
+            start: now,
+            state: std::sync::Mutex::new(BatchTrackerState {
+                bytes_done: vec![0; files_total],
+                sizes: vec![None; files_total],
+                files_done: 0,
+                files_failed: 0,
+                last_emit: now,
+            }),
+            disk_full: AtomicBool::new(false),
+        }
+    }
+
+    fn is_disk_full(&self) -> bool {
+        self.disk_full.load(Ordering::SeqCst)
+    }
+
+    /// 重试前清零该文件已下载的字节数
+    fn reset_file(&self, index: usize) {
+        self.state.lock().unwrap().bytes_done[index] = 0;
+    }
+
+    fn set_size(&self, index: usize, size: Option<u64>) {
+        self.state.lock().unwrap().sizes[index] = size;
+    }
+
+    fn add_bytes(&self, index: usize, bytes: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.bytes_done[index] += bytes;
+        // 限制事件频率（每 500ms 一次）
+        if state.last_emit.elapsed() >= std::time::Duration::from_millis(500) {
+            state.last_emit = std::time::Instant::now();
+            self.emit(&state);
+        }
+    }
+
+    fn finish_file(&self, success: bool) {
+        let mut state = self.state.lock().unwrap();
+        if success {
+            state.files_done += 1;
+        } else {
+            state.files_failed += 1;
+        }
+        state.last_emit = std::time::Instant::now();
+        self.emit(&state);
+    }
+
+    fn total_bytes(&self) -> u64 {
+        self.state.lock().unwrap().bytes_done.iter().sum()
+    }
+
+    fn emit(&self, state: &BatchTrackerState) {
+        let bytes_done: u64 = state.bytes_done.iter().sum();
+        let bytes_total: u64 = state.sizes.iter().flatten().sum();
+        let bytes_total_exact = state.sizes.iter().all(|s| s.is_some());
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let bytes_per_sec = if elapsed > 0.0 { bytes_done as f64 / elapsed } else { 0.0 };
+        let eta_seconds = if bytes_total_exact && bytes_per_sec > 0.0 {
+            Some((bytes_total.saturating_sub(bytes_done) as f64 / bytes_per_sec).round() as u64)
+        } else {
+            None
+        };
+
+        let _ = self.window.emit(DOWNLOAD_BATCH_PROGRESS, BatchDownloadProgress {
+            files_done: state.files_done,
+            files_failed: state.files_failed,
+            files_total: state.bytes_done.len(),
+            bytes_done,
+            bytes_total,
+            bytes_total_exact,
+            speed: format_speed(bytes_per_sec),
+            eta_seconds,
+        });
+    }
+}
+
+fn format_speed(bytes_per_sec: f64) -> String {
+    format!("{:.2} MB/s", bytes_per_sec / 1024.0 / 1024.0)
+}
+
+/// 下载完成后自动登记到的视频池
+#[derive(serde::Deserialize, Clone)]
+pub struct PoolTarget {
+    /// 池子目录，为空时使用下载目录
+    #[serde(default)]
+    pub input_dir: Option<String>,
+    #[serde(default)]
+    pub max_depth: usize,
+    /// 登记前的质量筛选条件
+    #[serde(default)]
+    pub filters: Option<PoolFilters>,
+}
+
+impl PoolTarget {
+    /// 池子目录不是下载目录时，下载目录必须在池子的扫描范围内，
+    /// 否则下次扫描池子时会丢掉登记的文件并重建池子
+    fn validate(&self, output_dir: &str) -> Result<(), String> {
+        let Some(input_dir) = self.input_dir.as_deref().filter(|d| !d.is_empty()) else {
+            return Ok(());
+        };
+        let canonical = |dir: &str| {
+            std::fs::canonicalize(dir).map_err(|e| format!("无法解析目录 {}: {}", dir, e))
+        };
+        if !scan_covers(&canonical(input_dir)?, &canonical(output_dir)?, self.max_depth) {
+            return Err(format!(
+                "下载目录不在视频池 {} 的扫描范围内（扫描深度 {}），下载的文件无法登记到该池子",
+                input_dir, self.max_depth
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 扫描 `input_dir` 到 `max_depth` 层子目录时是否包含 `output_dir` 中的文件
+fn scan_covers(input_dir: &Path, output_dir: &Path, max_depth: usize) -> bool {
+    output_dir
+        .strip_prefix(input_dir)
+        .is_ok_and(|relative| relative.components().count() <= max_depth)
+}
+
+/// 视频池更新事件
+#[derive(serde::Serialize, Clone, TS)]
+#[ts(export)]
+struct PoolUpdated {
+    input_dir: String,
+    max_depth: usize,
+    added: Vec<String>,
+    rejected: Vec<(String, String)>,
+    total: usize,
+    remaining: usize,
+}
+
 #[tauri::command]
 pub async fn batch_download(
     app: AppHandle,
-    urls: Vec<String>,
+    urls: Vec<DownloadItem>,
     output_dir: String,
     max_concurrent: usize,
+    max_retries: Option<u32>,
+    pool_target: Option<PoolTarget>,
+    mut post_action: Option<PostDownloadAction>,
 ) -> Result<String, String> {
+    check_output(&app, &output_dir)?;
+    if let Some(action) = &mut post_action {
+        action.resolve_presets(&app.state::<SettingsManager>())?;
+    }
+    if let Some(input_dir) = pool_target.as_ref().and_then(|t| t.input_dir.as_deref()).filter(|d| !d.is_empty()) {
+        check_input(&app, input_dir)?;
+    }
+
     let window = app.get_webview_window("main")
         .ok_or("无法获取窗口")?;
 
     // 创建输出目录
     tokio::fs::create_dir_all(&output_dir).await
         .map_err(|e| format!("创建目录失败: {}", e))?;
+    if let Some(target) = &pool_target {
+        target.validate(&output_dir)?;
+    }
 
     // 创建 HTTP 客户端
-    let client = Client::builder()
-        .timeout(std::time::Duration::from_secs(300))
-        .build()
-        .map_err(|e| format!("创建客户端失败: {}", e))?;
+    let client = build_client()?;
 
     // 使用 tokio 并发下载
     let mut tasks = Vec::new();
     let semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(max_concurrent));
+    let tracker = Arc::new(BatchTracker::new(window.clone(), urls.len()));
+
+    let max_retries = max_retries.unwrap_or(2);
 
-    for url in urls {
+    for (index, item) in urls.iter().cloned().enumerate() {
         let client = client.clone();
         let output_dir = output_dir.clone();
         let window = window.clone();
+        let tracker = tracker.clone();
         let permit = semaphore.clone().acquire_owned().await.unwrap();
 
         let task = tokio::spawn(async move {
-            let result = download_single_file(
-                &client,
-                &url,
-                &output_dir,
-                window.clone()
-            ).await;
+            let result = if tracker.is_disk_full() {
+                Err(DownloadFailure::disk_full(window.app_handle()))
+            } else {
+                download_with_mirrors(
+                    &client,
+                    &item,
+                    &output_dir,
+                    window.clone(),
+                    &tracker,
+                    index,
+                    max_retries,
+                ).await
+            };
 
+            tracker.finish_file(result.is_ok());
             drop(permit);
             result
         });
@@ -61,68 +383,522 @@ pub async fn batch_download(
     // 等待所有下载完成
     let mut success_count = 0;
     let mut failed_count = 0;
+    let mut queued_count = 0;
+    let mut mirror_count = 0;
+    let mut failures = Vec::new();
+    let mut results = Vec::new();
+    let mut items = Vec::new();
 
+    for (item, task) in urls.iter().zip(tasks) {
+        let url = item.primary();
+        let result = match task.await {
+            Ok(result) => result,
+            Err(e) => Err(DownloadFailure::not_attempted(t(&app, "error.job_failed", &[e.to_string()]))),
+        };
+        record_operation(&app, "download", &result.as_ref().map_err(|f| f.error.clone()));
+        match result {
+            Ok(outcome) => {
+                success_count += 1;
+                items.push(BatchItemResult::succeeded(url.clone()));
+                let used_mirror = outcome.url != url;
+                if used_mirror {
+                    mirror_count += 1;
+                }
+                results.push(DownloadItemResult {
+                    url: url.clone(),
+                    succeeded_url: Some(outcome.url),
+                    used_mirror,
+                    attempts: outcome.attempts,
+                    error: None,
+                });
+                let path = outcome.path;
+                // 下载完成后自动处理：加入任务队列，按顺序执行
+                if let Some(action) = &post_action {
+                    let spec = action.clone().into_spec(path.to_string_lossy().to_string());
+                    app.state::<JobQueue>().enqueue(&app, spec, JobPriority::Normal, None);
+                    queued_count += 1;
+                }
+                if let Some(target) = &pool_target {
+                    register_into_pool(&app, &window, &app.state::<VideoPoolManager>(), target, &output_dir, path).await;
+                }
+            }
+            Err(DownloadFailure { error: e, attempts, skipped }) => {
+                failed_count += 1;
+                items.push(if skipped {
+                    BatchItemResult::skipped(url.clone(), e.clone())
+                } else {
+                    BatchItemResult::failed(url.clone(), e.clone())
+                });
+                failures.push((url.clone(), e.clone()));
+                results.push(DownloadItemResult {
+                    url,
+                    succeeded_url: None,
+                    used_mirror: false,
+                    attempts,
+                    error: Some(e),
+                });
+            }
+        }
+    }
+
+    // 发送整批汇总
+    let elapsed = tracker.start.elapsed().as_secs_f64();
+    let bytes_downloaded = tracker.total_bytes();
+    app.state::<StatsManager>().record(|s| s.bytes_downloaded += bytes_downloaded);
+    let _ = window.emit(DOWNLOAD_BATCH_SUMMARY, BatchDownloadSummary {
+        files_total: urls.len(),
+        succeeded: success_count,
+        failed: failed_count,
+        bytes_downloaded,
+        elapsed_seconds: elapsed,
+        average_speed: format_speed(if elapsed > 0.0 { bytes_downloaded as f64 / elapsed } else { 0.0 }),
+        failures,
+        results,
+    });
+    let summary = BatchSummary::new("download", items);
+    summary.emit(&app);
+
+    let mut message = t(&app, "download.summary", &[success_count.to_string(), failed_count.to_string()]);
+    if tracker.is_disk_full() {
+        message.push_str(&t(&app, "download.summary_disk_full", &[]));
+    }
+    if mirror_count > 0 {
+        message.push_str(&t(&app, "download.summary_mirrors", &[mirror_count.to_string()]));
+    }
+    if queued_count > 0 {
+        message.push_str(&t(&app, "download.summary_queued", &[queued_count.to_string()]));
+    }
+    if summary.failed + summary.skipped > 0 {
+        message.push('\n');
+        message.push_str(&summary.problems());
+    }
+    Ok(message)
+}
+
+/// 探测并校验下载完成的文件，通过后加入目标视频池并发送 pool_updated 事件
+async fn register_into_pool(
+    app: &AppHandle,
+    window: &tauri::WebviewWindow,
+    pool_manager: &VideoPoolManager,
+    target: &PoolTarget,
+    output_dir: &str,
+    path: PathBuf,
+) {
+    let input_dir = target
+        .input_dir
+        .clone()
+        .filter(|d| !d.is_empty())
+        .unwrap_or_else(|| output_dir.to_string());
+    let name = path.to_string_lossy().to_string();
+
+    // 池子扫描只收录 MP4，其他格式登记后会在下次扫描时丢失
+    let is_mp4 = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("mp4"));
+    let rejection = if !is_mp4 {
+        Some("不是 MP4 文件，视频池不会收录".to_string())
+    } else {
+        match get_video_info(app, &path).await {
+            Ok(info) if info.width == 0 || info.height == 0 => Some("无法解析分辨率".to_string()),
+            Ok(info) if info.duration <= 0.0 => Some("无法解析时长".to_string()),
+            Ok(info) => {
+                let reasons = target
+                    .filters
+                    .as_ref()
+                    .map(|f| f.check(&info))
+                    .unwrap_or_default();
+                if reasons.is_empty() {
+                    None
+                } else {
+                    Some(reasons.join("；"))
+                }
+            }
+            Err(e) => Some(e),
+        }
+    };
+
+    let (added, rejected) = match rejection {
+        None => (vec![name], Vec::new()),
+        Some(reason) => (Vec::new(), vec![(name, reason)]),
+    };
+    let pool = if added.is_empty() {
+        None
+    } else {
+        Some(pool_manager.add_videos(&input_dir, target.max_depth, vec![path]))
+    };
+
+    let _ = window.emit(POOL_UPDATED, PoolUpdated {
+        input_dir,
+        max_depth: target.max_depth,
+        added,
+        rejected,
+        total: pool.as_ref().map(|p| p.all_videos.len()).unwrap_or(0),
+        remaining: pool.as_ref().map(|p| p.remaining_videos.len()).unwrap_or(0),
+    });
+}
+
+/// 下载计划中的单个条目
+#[derive(serde::Serialize, Clone, TS)]
+#[ts(export)]
+pub struct PlannedDownload {
+    /// 主地址
+    pub url: String,
+    /// 主地址不可用、由备用镜像探测成功时的镜像地址
+    pub mirror: Option<String>,
+    pub filename: String,
+    pub output_path: String,
+    pub status: Option<u16>,
+    #[ts(type = "number | null")]
+    pub content_length: Option<u64>,
+    pub content_type: Option<String>,
+    /// 目标文件已存在（将被覆盖）
+    pub exists: bool,
+    pub warnings: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// 下载计划（不下载任何数据）
+#[derive(serde::Serialize, TS)]
+#[ts(export)]
+pub struct DownloadPlan {
+    pub items: Vec<PlannedDownload>,
+    /// 已知大小的总字节数
+    #[ts(type = "number")]
+    pub total_bytes: u64,
+    /// 无法获取大小的条目数
+    pub unknown_size_count: usize,
+    pub warnings: Vec<String>,
+}
+
+fn build_client() -> Result<Client, String> {
+    Client::builder()
+        .timeout(std::time::Duration::from_secs(300))
+        .build()
+        .map_err(|e| format!("创建客户端失败: {}", e))
+}
+
+/// 试运行：对所有 URL 发送 HEAD 请求（主地址不可用时依次探测镜像），返回大小、类型、文件名与冲突警告
+#[tauri::command]
+pub async fn plan_batch_download(
+    app: AppHandle,
+    urls: Vec<DownloadItem>,
+    output_dir: String,
+    max_concurrent: Option<usize>,
+) -> Result<DownloadPlan, String> {
+    check_output(&app, &output_dir)?;
+    let client = build_client()?;
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(max_concurrent.unwrap_or(8).max(1)));
+
+    let urls: Vec<Vec<String>> = urls
+        .iter()
+        .map(DownloadItem::candidates)
+        .filter(|candidates| !candidates.is_empty())
+        .collect();
+
+    let mut tasks = Vec::new();
+    for candidates in urls {
+        let app = app.clone();
+        let client = client.clone();
+        let output_dir = output_dir.clone();
+        let permit = semaphore.clone().acquire_owned().await.unwrap();
+        tasks.push(tokio::spawn(async move {
+            let item = plan_single_item(&app, &client, &candidates, &output_dir).await;
+            drop(permit);
+            item
+        }));
+    }
+
+    let mut items = Vec::with_capacity(tasks.len());
     for task in tasks {
-        match task.await {
-            Ok(Ok(_)) => success_count += 1,
-            _ => failed_count += 1,
+        items.push(task.await.map_err(|e| t(&app, "error.job_failed", &[e.to_string()]))?);
+    }
+
+    // 重复 URL 与文件名冲突检查
+    let mut warnings = Vec::new();
+    let mut url_counts: HashMap<&str, usize> = HashMap::new();
+    let mut name_counts: HashMap<String, usize> = HashMap::new();
+    for item in &items {
+        *url_counts.entry(item.url.as_str()).or_default() += 1;
+        *name_counts.entry(item.filename.to_lowercase()).or_default() += 1;
+    }
+    for (url, count) in &url_counts {
+        if *count > 1 {
+            warnings.push(t(&app, "plan.duplicate_url", &[count.to_string(), url.to_string()]));
         }
     }
+    let conflicting: Vec<String> = name_counts
+        .iter()
+        .filter(|(_, count)| **count > 1)
+        .map(|(name, _)| name.clone())
+        .collect();
+    for item in items.iter_mut() {
+        if conflicting.contains(&item.filename.to_lowercase()) {
+            item.warnings.push(t(&app, "plan.same_filename", &[]));
+        }
+    }
+    for name in &conflicting {
+        warnings.push(t(&app, "plan.filename_conflict", std::slice::from_ref(name)));
+    }
+
+    let total_bytes = items.iter().filter_map(|i| i.content_length).sum();
+    let unknown_size_count = items.iter().filter(|i| i.content_length.is_none()).count();
+    let failed = items.iter().filter(|i| i.error.is_some()).count();
+    if failed > 0 {
+        warnings.push(t(&app, "plan.failed", &[failed.to_string()]));
+    }
+
+    Ok(DownloadPlan {
+        items,
+        total_bytes,
+        unknown_size_count,
+        warnings,
+    })
+}
+
+/// 探测单个下载条目：文件名以主地址为准（与实际下载一致），主地址不可用时依次探测镜像
+async fn plan_single_item(app: &AppHandle, client: &Client, candidates: &[String], output_dir: &str) -> PlannedDownload {
+    let primary = &candidates[0];
+    let filename = extract_filename(primary);
+    let output_path = Path::new(output_dir).join(&filename);
+    let mut item = PlannedDownload {
+        url: primary.clone(),
+        mirror: None,
+        filename: filename.clone(),
+        output_path: output_path.to_string_lossy().to_string(),
+        status: None,
+        content_length: None,
+        content_type: None,
+        exists: output_path.exists(),
+        warnings: Vec::new(),
+        error: None,
+    };
+
+    if item.exists {
+        item.warnings.push(t(app, "plan.exists", &[]));
+    }
+    if !filename.to_lowercase().ends_with(".mp4") {
+        item.warnings.push(t(app, "plan.not_mp4", std::slice::from_ref(&filename)));
+    }
+
+    let mut errors = Vec::new();
+    let mut response = None;
+    for url in candidates {
+        match probe_url(app, client, url).await {
+            Ok(resp) => {
+                if url != primary {
+                    item.mirror = Some(url.clone());
+                    item.warnings.push(t(app, "plan.mirror", std::slice::from_ref(url)));
+                }
+                response = Some(resp);
+                break;
+            }
+            Err((status, e)) => {
+                item.status = status;
+                errors.push(if candidates.len() > 1 { format!("{}: {}", url, e) } else { e });
+            }
+        }
+    }
+    let Some(response) = response else {
+        item.error = Some(errors.join("\n"));
+        return item;
+    };
+
+    item.status = Some(response.status().as_u16());
+    let headers = response.headers();
+    item.content_type = headers
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+    // Range 请求时从 Content-Range 中取总大小
+    item.content_length = headers
+        .get(reqwest::header::CONTENT_RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.rsplit('/').next())
+        .and_then(|v| v.parse::<u64>().ok())
+        .or_else(|| {
+            headers
+                .get(reqwest::header::CONTENT_LENGTH)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<u64>().ok())
+        });
+
+    if let Some(content_type) = &item.content_type {
+        if !content_type.starts_with("video/") && content_type != "application/octet-stream" {
+            item.warnings.push(t(app, "plan.not_video", std::slice::from_ref(content_type)));
+        }
+    }
+    if item.content_length.is_none() {
+        item.warnings.push(t(app, "plan.no_size", &[]));
+    }
 
-    Ok(format!("下载完成！成功: {}, 失败: {}", success_count, failed_count))
+    item
 }
 
+/// 只请求响应头；失败时返回状态码（如有）与原因
+async fn probe_url(app: &AppHandle, client: &Client, url: &str) -> Result<reqwest::Response, (Option<u16>, String)> {
+    let response = match client.head(url).send().await {
+        // 部分服务器不支持 HEAD，退回只请求 1 字节的 GET
+        Ok(resp) if resp.status() == reqwest::StatusCode::METHOD_NOT_ALLOWED => {
+            client.get(url).header(reqwest::header::RANGE, "bytes=0-0").send().await
+        }
+        other => other,
+    };
+    let response = response.map_err(|e| (None, t(app, "error.request", &[e.to_string()])))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        return Err((Some(status.as_u16()), t(app, "error.http", &[status.to_string()])));
+    }
+    Ok(response)
+}
+
+/// 依次尝试各镜像地址，每个地址失败后重试 `max_retries` 次
+async fn download_with_mirrors(
+    client: &Client,
+    item: &DownloadItem,
+    output_dir: &str,
+    window: tauri::WebviewWindow,
+    tracker: &BatchTracker,
+    index: usize,
+    max_retries: u32,
+) -> Result<DownloadOutcome, DownloadFailure> {
+    let candidates = item.candidates();
+    let primary = candidates
+        .first()
+        .cloned()
+        .ok_or_else(|| DownloadFailure::not_attempted(t(window.app_handle(), "error.download_url_empty", &[])))?;
+
+    // 文件名以主地址为准，镜像下载写入同一文件
+    let filename = extract_filename(&primary);
+    let output_path = Path::new(output_dir).join(&filename);
+
+    let mut attempts = 0;
+    let mut errors = Vec::new();
+    'mirrors: for url in &candidates {
+        for retry in 0..=max_retries {
+            attempts += 1;
+            if retry > 0 {
+                tokio::time::sleep(std::time::Duration::from_secs(retry as u64)).await;
+            }
+            tracker.reset_file(index);
+            match download_single_file(client, url, &primary, &output_path, window.clone(), tracker, index).await {
+                Ok(path) => {
+                    return Ok(DownloadOutcome {
+                        path,
+                        url: url.clone(),
+                        attempts,
+                    })
+                }
+                Err(DownloadError::DiskFull(e)) => {
+                    tracker.disk_full.store(true, Ordering::SeqCst);
+                    errors.push(format!("{}: {}", url, e));
+                    break 'mirrors;
+                }
+                Err(DownloadError::Failed(e)) => {
+                    let params = [url.clone(), (retry + 1).to_string(), e];
+                    errors.push(t(window.app_handle(), "download.attempt_failed", &params))
+                }
+            }
+        }
+    }
+
+    let _ = window.emit(DOWNLOAD_PROGRESS, DownloadProgress {
+        url: primary,
+        progress: 0,
+        speed: "0 MB/s".to_string(),
+        status: "failed".to_string(),
+    });
+    Err(DownloadFailure {
+        error: errors.join("\n"),
+        attempts,
+        skipped: false,
+    })
+}
+
+/// 下载单个地址到 `output_path`，进度事件使用 `event_url` 作为标识。
+/// 数据先写入 `.part` 临时文件，校验通过后再改名，失败时删除临时文件
 async fn download_single_file(
     client: &Client,
     url: &str,
-    output_dir: &str,
+    event_url: &str,
+    output_path: &Path,
+    window: tauri::WebviewWindow,
+    tracker: &BatchTracker,
+    index: usize,
+) -> Result<PathBuf, DownloadError> {
+    let mut part_name = output_path.as_os_str().to_os_string();
+    part_name.push(".part");
+    let part_path = PathBuf::from(part_name);
+
+    let result = download_to_part(client, url, event_url, &part_path, window.clone(), tracker, index).await;
+    if let Err(e) = result {
+        let _ = tokio::fs::remove_file(&part_path).await;
+        return Err(e);
+    }
+
+    tokio::fs::rename(&part_path, output_path).await
+        .map_err(|e| t(window.app_handle(), "error.rename_file", &[e.to_string()]))?;
+
+    // 发送完成状态
+    let _ = window.emit(DOWNLOAD_PROGRESS, DownloadProgress {
+        url: event_url.to_string(),
+        progress: 100,
+        speed: "0 MB/s".to_string(),
+        status: "completed".to_string(),
+    });
+
+    Ok(output_path.to_path_buf())
+}
+
+async fn download_to_part(
+    client: &Client,
+    url: &str,
+    event_url: &str,
+    part_path: &Path,
     window: tauri::WebviewWindow,
-) -> Result<(), String> {
+    tracker: &BatchTracker,
+    index: usize,
+) -> Result<(), DownloadError> {
+
     // 发送初始状态
-    let _ = window.emit("download_progress", DownloadProgress {
-        url: url.to_string(),
+    let _ = window.emit(DOWNLOAD_PROGRESS, DownloadProgress {
+        url: event_url.to_string(),
         progress: 0,
         speed: "0 MB/s".to_string(),
         status: "downloading".to_string(),
     });
 
-    // 提取文件名
-    let filename = extract_filename(url);
-    let output_path = Path::new(output_dir).join(&filename);
-
     // 发起 HTTP 请求
+    let app = window.app_handle();
     let response = client.get(url)
         .send()
         .await
-        .map_err(|e| format!("请求失败: {}", e))?;
+        .map_err(|e| t(app, "error.request", &[e.to_string()]))?;
 
     if !response.status().is_success() {
-        let _ = window.emit("download_progress", DownloadProgress {
-            url: url.to_string(),
-            progress: 0,
-            speed: "0 MB/s".to_string(),
-            status: "failed".to_string(),
-        });
-        return Err(format!("HTTP 错误: {}", response.status()));
+        return Err(t(app, "error.http", &[response.status().to_string()]).into());
     }
 
+    let expected_size = response.content_length();
     let total_size = response.content_length().unwrap_or(0);
+    tracker.set_size(index, response.content_length());
     let mut downloaded: u64 = 0;
     let mut stream = response.bytes_stream();
 
     // 创建文件
-    let mut file = File::create(&output_path).await
-        .map_err(|e| format!("创建文件失败: {}", e))?;
+    let mut file = File::create(part_path).await
+        .map_err(|e| write_error(app, "error.create_file", e))?;
 
     let start_time = std::time::Instant::now();
 
     // 流式下载
     while let Some(chunk) = stream.next().await {
-        let chunk = chunk.map_err(|e| format!("下载数据失败: {}", e))?;
+        let chunk = chunk.map_err(|e| t(app, "error.download_data", &[e.to_string()]))?;
         file.write_all(&chunk).await
-            .map_err(|e| format!("写入文件失败: {}", e))?;
+            .map_err(|e| write_error(app, "error.write_file", e))?;
 
         downloaded += chunk.len() as u64;
+        tracker.add_bytes(index, chunk.len() as u64);
 
         // 计算进度和速度
         let progress = if total_size > 0 {
@@ -140,8 +916,8 @@ async fn download_single_file(
 
         // 每下载 1MB 发送一次进度
         if downloaded % (1024 * 1024) < chunk.len() as u64 {
-            let _ = window.emit("download_progress", DownloadProgress {
-                url: url.to_string(),
+            let _ = window.emit(DOWNLOAD_PROGRESS, DownloadProgress {
+                url: event_url.to_string(),
                 progress,
                 speed,
                 status: "downloading".to_string(),
@@ -150,23 +926,40 @@ async fn download_single_file(
     }
 
     file.flush().await
-        .map_err(|e| format!("刷新文件失败: {}", e))?;
+        .map_err(|e| write_error(app, "error.flush_file", e))?;
+    file.sync_all().await
+        .map_err(|e| write_error(app, "error.sync_file", e))?;
 
-    // 发送完成状态
-    let _ = window.emit("download_progress", DownloadProgress {
-        url: url.to_string(),
-        progress: 100,
-        speed: "0 MB/s".to_string(),
-        status: "completed".to_string(),
-    });
+    // 连接中途断开时流可能正常结束，需要核对实际字节数
+    if let Some(expected) = expected_size {
+        if downloaded != expected {
+            return Err(t(app, "error.incomplete_download", &[downloaded.to_string(), expected.to_string()]).into());
+        }
+    }
 
     Ok(())
 }
 
 fn extract_filename(url: &str) -> String {
-    url.split('/')
-        .last()
-        .and_then(|s| s.split('?').next())
-        .unwrap_or("download.mp4")
-        .to_string()
+    let name = url.split(['?', '#'])
+        .next()
+        .and_then(|s| s.rsplit('/').next())
+        .unwrap_or("");
+    sanitize_filename(name, "download.mp4")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pool_scan_must_reach_download_dir() {
+        let pool = Path::new("/videos");
+        assert!(scan_covers(pool, Path::new("/videos"), 0));
+        assert!(scan_covers(pool, Path::new("/videos/new"), 1));
+        assert!(!scan_covers(pool, Path::new("/videos/new"), 0));
+        assert!(!scan_covers(pool, Path::new("/videos/a/b"), 1));
+        assert!(!scan_covers(pool, Path::new("/downloads"), 3));
+        assert!(!scan_covers(pool, Path::new("/videos2"), 3));
+    }
 }
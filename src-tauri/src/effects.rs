@@ -0,0 +1,277 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use ts_rs::TS;
+
+/// 复古（棕褐色）调色矩阵
+const SEPIA_MIXER: &str = "colorchannelmixer=.393:.769:.189:0:.349:.686:.168:0:.272:.534:.131";
+/// 速度允许的范围（atempo 单个滤镜支持 0.5-100，更低的速度串联多个）
+const MIN_SPEED: f64 = 0.25;
+const MAX_SPEED: f64 = 4.0;
+
+/// 色彩风格
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorStyle {
+    Grayscale,
+    Sepia,
+}
+
+/// 变速：起止速度相同为匀速，不同则在片段内线性渐变
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SpeedRamp {
+    pub start: f64,
+    pub end: f64,
+}
+
+impl SpeedRamp {
+    fn is_constant(&self) -> bool {
+        (self.start - self.end).abs() < 1e-6
+    }
+
+    /// 变速后的时长
+    fn output_duration(&self, duration: f64) -> f64 {
+        if self.is_constant() {
+            duration / self.start
+        } else {
+            // 速度 v(t) 线性变化时，输出时长为 ∫dt/v(t)
+            duration / (self.end - self.start) * (self.end / self.start).ln()
+        }
+    }
+
+    /// 视频时间戳表达式：输入时间 T 映射到输出时间
+    fn setpts(&self, duration: f64) -> String {
+        if self.is_constant() {
+            format!("setpts=PTS/{:.6}", self.start)
+        } else {
+            let k = (self.end - self.start) / duration.max(0.001);
+            format!(
+                "setpts=(log(1+{k:.6}*T/{s0:.6})/{k:.6})/TB",
+                k = k,
+                s0 = self.start
+            )
+        }
+    }
+
+    /// 音频变速：渐变时按平均速度变速，保证与画面总时长一致
+    fn atempo(&self, duration: f64) -> String {
+        let mut tempo = if self.is_constant() {
+            self.start
+        } else {
+            duration / self.output_duration(duration)
+        };
+        let mut filters = Vec::new();
+        while tempo < 0.5 {
+            filters.push("atempo=0.5".to_string());
+            tempo /= 0.5;
+        }
+        filters.push(format!("atempo={:.6}", tempo));
+        filters.join(",")
+    }
+}
+
+/// 画面效果，可用于单个片段或整个成片
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ClipEffects {
+    /// 3D LUT 调色文件（.cube）
+    #[serde(default)]
+    pub lut_path: Option<String>,
+    #[serde(default)]
+    pub style: Option<ColorStyle>,
+    /// 水平镜像
+    #[serde(default)]
+    pub mirror: bool,
+    /// 亮度（-1.0 ~ 1.0，0 为不变）
+    #[serde(default)]
+    pub brightness: Option<f64>,
+    /// 对比度（0.0 ~ 2.0，1 为不变）
+    #[serde(default)]
+    pub contrast: Option<f64>,
+    /// 饱和度（0.0 ~ 3.0，1 为不变）
+    #[serde(default)]
+    pub saturation: Option<f64>,
+    #[serde(default)]
+    pub speed: Option<SpeedRamp>,
+}
+
+/// 滤镜参数中的文件路径转义（Windows 盘符的冒号、反斜杠、单引号）
+fn escape_filter_path(path: &str) -> String {
+    let escaped = path
+        .replace('\\', "/")
+        .replace('\'', "\\'")
+        .replace(':', "\\:");
+    format!("'{}'", escaped)
+}
+
+impl ClipEffects {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(lut) = &self.lut_path {
+            if !Path::new(lut).exists() {
+                return Err(format!("LUT 文件不存在: {}", lut));
+            }
+        }
+        if self.brightness.is_some_and(|b| !(-1.0..=1.0).contains(&b)) {
+            return Err("亮度必须在 -1.0 到 1.0 之间".to_string());
+        }
+        if self.contrast.is_some_and(|c| !(0.0..=2.0).contains(&c)) {
+            return Err("对比度必须在 0 到 2.0 之间".to_string());
+        }
+        if self.saturation.is_some_and(|s| !(0.0..=3.0).contains(&s)) {
+            return Err("饱和度必须在 0 到 3.0 之间".to_string());
+        }
+        if let Some(speed) = &self.speed {
+            for v in [speed.start, speed.end] {
+                if !(MIN_SPEED..=MAX_SPEED).contains(&v) {
+                    return Err(format!("播放速度必须在 {} 到 {} 之间", MIN_SPEED, MAX_SPEED));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 匀速播放时的速度，渐变速返回 None
+    pub fn constant_speed(&self) -> Option<f64> {
+        match self.speed {
+            Some(speed) if !speed.is_constant() => None,
+            Some(speed) => Some(speed.start),
+            None => Some(1.0),
+        }
+    }
+
+    /// 应用效果后的时长（只有变速会改变时长）
+    pub fn output_duration(&self, duration: f64) -> f64 {
+        self.speed.map(|s| s.output_duration(duration)).unwrap_or(duration)
+    }
+
+    /// 画面滤镜链（不含输入输出标签），`duration` 为效果作用前的时长
+    pub fn video_filters(&self, duration: f64) -> Vec<String> {
+        let mut filters = Vec::new();
+        if let Some(lut) = &self.lut_path {
+            filters.push(format!("lut3d=file={}", escape_filter_path(lut)));
+        }
+        if self.brightness.is_some() || self.contrast.is_some() || self.saturation.is_some() {
+            filters.push(format!(
+                "eq=brightness={:.3}:contrast={:.3}:saturation={:.3}",
+                self.brightness.unwrap_or(0.0),
+                self.contrast.unwrap_or(1.0),
+                self.saturation.unwrap_or(1.0)
+            ));
+        }
+        match self.style {
+            Some(ColorStyle::Grayscale) => filters.push("hue=s=0".to_string()),
+            Some(ColorStyle::Sepia) => filters.push(SEPIA_MIXER.to_string()),
+            None => {}
+        }
+        if self.mirror {
+            filters.push("hflip".to_string());
+        }
+        if let Some(speed) = &self.speed {
+            filters.push(speed.setpts(duration));
+        }
+        filters
+    }
+
+    /// 音频滤镜链（只有变速需要处理音频）
+    pub fn audio_filters(&self, duration: f64) -> Vec<String> {
+        self.speed.map(|s| vec![s.atempo(duration)]).unwrap_or_default()
+    }
+}
+
+/// 变体模式的变换幅度
+const VARIATION_ZOOM: (f64, f64) = (1.01, 1.03);
+const VARIATION_SPEED: f64 = 0.02;
+const VARIATION_HUE_DEGREES: f64 = 4.0;
+const VARIATION_SATURATION: f64 = 0.03;
+const VARIATION_BRIGHTNESS: f64 = 0.02;
+
+/// 输出变体：由种子确定的轻微放大、变速与色彩偏移，相同种子生成相同的变换
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Variation {
+    #[ts(type = "number")]
+    pub seed: u64,
+    /// 放大倍数（放大后裁回原画布）
+    pub zoom: f64,
+    pub speed: f64,
+    /// 色相偏移（度）
+    pub hue: f64,
+    pub saturation: f64,
+    pub brightness: f64,
+}
+
+impl Variation {
+    pub fn from_seed(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        Self {
+            seed,
+            zoom: rng.gen_range(VARIATION_ZOOM.0..=VARIATION_ZOOM.1),
+            speed: 1.0 + rng.gen_range(-VARIATION_SPEED..=VARIATION_SPEED),
+            hue: rng.gen_range(-VARIATION_HUE_DEGREES..=VARIATION_HUE_DEGREES),
+            saturation: 1.0 + rng.gen_range(-VARIATION_SATURATION..=VARIATION_SATURATION),
+            brightness: rng.gen_range(-VARIATION_BRIGHTNESS..=VARIATION_BRIGHTNESS),
+        }
+    }
+
+    pub fn output_duration(&self, duration: f64) -> f64 {
+        duration / self.speed
+    }
+
+    /// 画面滤镜链，放大后按画布尺寸居中裁回
+    pub fn video_filters(&self, width: u32, height: u32) -> Vec<String> {
+        vec![
+            format!(
+                "scale=ceil(iw*{z:.4}/2)*2:ceil(ih*{z:.4}/2)*2,crop={w}:{h}",
+                z = self.zoom,
+                w = width,
+                h = height
+            ),
+            format!("hue=h={:.3}:s={:.4}", self.hue, self.saturation),
+            format!("eq=brightness={:.4}", self.brightness),
+            format!("setpts=PTS/{:.6}", self.speed),
+        ]
+    }
+
+    pub fn audio_filters(&self) -> Vec<String> {
+        vec![format!("atempo={:.6}", self.speed)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constant_speed_ramp() {
+        let ramp = SpeedRamp { start: 2.0, end: 2.0 };
+        assert_eq!(ramp.output_duration(10.0), 5.0);
+        assert_eq!(ramp.setpts(10.0), "setpts=PTS/2.000000");
+        assert_eq!(ramp.atempo(10.0), "atempo=2.000000");
+    }
+
+    #[test]
+    fn slow_speed_chains_atempo() {
+        let ramp = SpeedRamp { start: 0.25, end: 0.25 };
+        assert_eq!(ramp.atempo(10.0), "atempo=0.5,atempo=0.500000");
+    }
+
+    #[test]
+    fn linear_ramp_integrates_duration() {
+        let ramp = SpeedRamp { start: 1.0, end: 2.0 };
+        let duration = ramp.output_duration(10.0);
+        assert!((duration - 10.0 * 2f64.ln()).abs() < 1e-9);
+        assert_eq!(ramp.atempo(10.0), format!("atempo={:.6}", 10.0 / duration));
+    }
+
+    #[test]
+    fn filter_path_is_escaped() {
+        assert_eq!(escape_filter_path("C:\\lut\\a'b.cube"), "'C\\:/lut/a\\'b.cube'");
+    }
+}
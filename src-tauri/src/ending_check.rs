@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use tauri_plugin_shell::ShellExt;
+use ts_rs::TS;
+use crate::frame_similarity::{self, calculate_similarity, RegionMask, SimilarityAlgorithm};
+use crate::video_processor::get_video_info;
+
+/// 比对片尾时在片段内均匀取样的相对位置
+const SAMPLE_POSITIONS: [f64; 3] = [0.25, 0.5, 0.75];
+/// 取样画面统一缩放的尺寸（灰度），不同分辨率的视频也能比较
+const SAMPLE_SCALE: &str = "scale=160:90,format=gray";
+
+/// 移除最后一个片段前确认它是片尾的条件，均未设置时照常移除
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct EndingCheck {
+    /// 片尾的最短时长（秒）
+    #[serde(default)]
+    pub min_duration: Option<f64>,
+    /// 片尾的最长时长（秒），过长的片段多半是正片内容
+    #[serde(default)]
+    pub max_duration: Option<f64>,
+    /// 已知的片尾样本（以往移除的片尾视频），最后一个片段需与其中之一相似
+    #[serde(default)]
+    pub reference_videos: Vec<String>,
+    /// 与片尾样本的最低相似度（0-1）
+    #[serde(default = "default_reference_similarity")]
+    pub reference_similarity: f64,
+    /// 无法确认是片尾时跳过该文件；否则保留最后一个片段照常合成
+    #[serde(default)]
+    pub skip_if_unsure: bool,
+}
+
+fn default_reference_similarity() -> f64 {
+    0.8
+}
+
+impl EndingCheck {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.min_duration.is_some_and(|d| d < 0.0) || self.max_duration.is_some_and(|d| d <= 0.0) {
+            return Err("片尾时长范围无效".to_string());
+        }
+        if let (Some(min), Some(max)) = (self.min_duration, self.max_duration) {
+            if min > max {
+                return Err("片尾最短时长不能大于最长时长".to_string());
+            }
+        }
+        if !(0.0..=1.0).contains(&self.reference_similarity) {
+            return Err("片尾相似度必须在 0 到 1 之间".to_string());
+        }
+        Ok(())
+    }
+
+    /// 检查最后一个片段（`start`..`end` 秒）是否像片尾，不像时返回原因
+    pub async fn verify(
+        &self,
+        app: &AppHandle,
+        video_path: &Path,
+        start: f64,
+        end: f64,
+        algorithm: SimilarityAlgorithm,
+        work_dir: &Path,
+    ) -> Result<Option<String>, String> {
+        let duration = end - start;
+        if self.min_duration.is_some_and(|min| duration < min) {
+            return Ok(Some(format!("最后一个片段仅 {:.1} 秒，短于片尾最短时长", duration)));
+        }
+        if self.max_duration.is_some_and(|max| duration > max) {
+            return Ok(Some(format!("最后一个片段长 {:.1} 秒，超过片尾最长时长", duration)));
+        }
+        if self.reference_videos.is_empty() {
+            return Ok(None);
+        }
+
+        fs::create_dir_all(work_dir).map_err(|e| format!("创建临时目录失败: {}", e))?;
+        let result = self.match_references(app, video_path, start, duration, algorithm, work_dir).await;
+        let _ = fs::remove_dir_all(work_dir);
+        let best = result?;
+        if best < self.reference_similarity {
+            return Ok(Some(format!(
+                "最后一个片段与片尾样本的相似度仅 {:.2}（要求 {:.2}）",
+                best, self.reference_similarity
+            )));
+        }
+        Ok(None)
+    }
+
+    /// 与各片尾样本比对，返回最高的平均相似度
+    async fn match_references(
+        &self,
+        app: &AppHandle,
+        video_path: &Path,
+        start: f64,
+        duration: f64,
+        algorithm: SimilarityAlgorithm,
+        work_dir: &Path,
+    ) -> Result<f64, String> {
+        let samples = sample_frames(app, video_path, start, duration, work_dir, "ending").await?;
+        // 不同来源的片尾带有各自的水印，按设置忽略角落（样本太少，不做自动检测）
+        let sample_paths: Vec<String> = samples.iter().map(|p| p.to_string_lossy().to_string()).collect();
+        let mask = RegionMask::build(&frame_similarity::current(app).mask, &sample_paths);
+        let mut best: f64 = 0.0;
+        for (idx, reference) in self.reference_videos.iter().enumerate() {
+            let reference = Path::new(reference);
+            let info = get_video_info(app, reference)
+                .await
+                .map_err(|e| format!("片尾样本 {}: {}", reference.display(), e))?;
+            let reference_samples =
+                sample_frames(app, reference, 0.0, info.duration, work_dir, &format!("reference_{}", idx)).await?;
+            let mut total = 0.0;
+            for (a, b) in samples.iter().zip(&reference_samples) {
+                let (a, b) = (a.to_string_lossy(), b.to_string_lossy());
+                total += calculate_similarity(&a, &b, algorithm, mask.as_ref())?;
+            }
+            best = best.max(total / samples.len() as f64);
+        }
+        Ok(best)
+    }
+}
+
+/// 在一段时间内按固定相对位置各取一帧，缩放为统一尺寸的灰度图
+async fn sample_frames(
+    app: &AppHandle,
+    video_path: &Path,
+    start: f64,
+    duration: f64,
+    work_dir: &Path,
+    prefix: &str,
+) -> Result<Vec<PathBuf>, String> {
+    let mut paths = Vec::with_capacity(SAMPLE_POSITIONS.len());
+    for (idx, position) in SAMPLE_POSITIONS.iter().enumerate() {
+        let image_path = work_dir.join(format!("{}_{}.pgm", prefix, idx));
+        let sidecar = app
+            .shell()
+            .sidecar("ffmpeg")
+            .map_err(|e| format!("FFmpeg 启动失败: {}", e))?;
+
+        let output = sidecar
+            .args([
+                "-v",
+                "error",
+                "-ss",
+                &format!("{:.3}", start + duration * position),
+                "-i",
+                &video_path.to_string_lossy(),
+                "-frames:v",
+                "1",
+                "-vf",
+                SAMPLE_SCALE,
+                "-y",
+                &image_path.to_string_lossy(),
+            ])
+            .output()
+            .await
+            .map_err(|e| format!("FFmpeg 执行失败: {}", e))?;
+
+        if !output.status.success() || !image_path.exists() {
+            return Err(format!(
+                "截取比对画面失败: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+        paths.push(image_path);
+    }
+    Ok(paths)
+}
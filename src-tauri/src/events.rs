@@ -0,0 +1,147 @@
+// 后端发送给前端的事件：事件名与载荷类型
+//
+// 载荷类型通过 ts-rs 生成 TypeScript 定义（`cargo test` 时导出到 src/bindings），
+// 前端直接引用生成的类型，避免两端各自维护字段。
+
+use serde::Serialize;
+use tauri::AppHandle;
+use ts_rs::TS;
+
+/// 命令与事件接口的版本号，字段有不兼容的变化时递增
+pub const IPC_VERSION: u32 = 1;
+
+pub const FRAME_PROGRESS: &str = "frame_progress";
+pub const SEGMENT_PROGRESS: &str = "segment_progress";
+pub const AUTO_SPLIT_PROGRESS: &str = "auto_split_progress";
+pub const REMOVE_ENDING_PROGRESS: &str = "remove_ending_progress";
+pub const AUDIO_ANALYSIS_PROGRESS: &str = "audio_analysis_progress";
+pub const CATALOG_PROGRESS: &str = "catalog_progress";
+pub const BENCHMARK_PROGRESS: &str = "benchmark_progress";
+pub const SELF_TEST_PROGRESS: &str = "self_test_progress";
+pub const DOWNLOAD_PROGRESS: &str = "download_progress";
+pub const DOWNLOAD_BATCH_PROGRESS: &str = "download_batch_progress";
+pub const DOWNLOAD_BATCH_SUMMARY: &str = "download_batch_summary";
+pub const POOL_UPDATED: &str = "pool_updated";
+pub const BATCH_TASK_UPDATED: &str = "batch_task_updated";
+pub const BATCH_SUMMARY: &str = "batch_summary";
+pub const JOB_UPDATED: &str = "job_updated";
+pub const JOB_STATS: &str = "job_stats";
+pub const SCHEDULER_STATUS: &str = "scheduler_status";
+pub const SEGMENTATION_SESSION_RESTORED: &str = "segmentation_session_restored";
+pub const PROBE_PROGRESS: &str = "probe_progress";
+pub const FRAME_COUNT_RECONCILED: &str = "frame_count_reconciled";
+pub const CONCAT_JOB_FINISHED: &str = "concat_job_finished";
+pub const AUTO_SPLIT_DIAGNOSTICS: &str = "auto_split_diagnostics";
+pub const ENCODE_PROGRESS: &str = "encode_progress";
+pub const CONCAT_PROGRESS: &str = "concat_progress";
+
+/// 通用进度事件（帧提取、自动拆解、去结尾、音频分析、缩略图目录、性能测试）
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct ProgressEvent {
+    /// 按当前语言生成的消息
+    pub message: String,
+    /// 消息目录中的 key，前端可以自行翻译
+    pub message_key: String,
+    pub params: Vec<String>,
+    pub percent: u32,
+}
+
+/// 片段生成进度
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct SegmentProgress {
+    pub current: u32,
+    pub total: u32,
+    #[serde(rename = "segmentName")]
+    pub segment_name: String,
+    pub percent: u32,
+}
+
+/// 拼接进度（选取片段、兼容性检测、逐次输出），没有百分比
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct ConcatProgress {
+    /// 按当前语言生成的消息
+    pub message: String,
+    /// 消息目录中的 key，前端可以自行翻译
+    pub message_key: String,
+    pub params: Vec<String>,
+}
+
+/// 拼接前逐个探测视频的进度（兼容性检测、质量筛选、按时长抽取）
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct ProbeProgress {
+    /// compatibility、pool_filter 或 duration
+    pub phase: String,
+    pub current: u32,
+    pub total: u32,
+    pub file_name: String,
+}
+
+/// 单次 FFmpeg 编码的实时进度（由 -progress 输出计算）
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct EncodeProgress {
+    /// concat、segments 或 remove_ending
+    pub task: String,
+    /// 当前编码的说明（如第几次拼接、第几个片段）
+    pub label: String,
+    /// 0-100
+    pub percent: f64,
+    /// 预计剩余时间（秒），尚无编码速度时为空
+    pub eta_seconds: Option<f64>,
+    /// 编码速度（相对实时播放的倍数）
+    pub speed: Option<f64>,
+}
+
+/// 自动拆解的检测诊断，随每次拆解结果一起发送，便于调整阈值与最短时长
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct AutoSplitDiagnostics {
+    pub video_path: String,
+    /// 逐帧分析结果来自缓存
+    pub cache_hit: bool,
+    /// 相似度低于阈值的候选切换点
+    pub candidate_cuts: u32,
+    /// 因最短时长忽略的候选切换点
+    pub rejected_min_duration: u32,
+    /// 滞回检测未确认的候选切换点
+    pub rejected_unconfirmed: u32,
+    /// 被闪光过滤忽略的候选切换点
+    pub flash_suppressed: u32,
+    /// 相似度分布：[0, 0.1)、[0.1, 0.2) … [0.9, 1.0] 十个区间的帧数
+    pub similarity_histogram: Vec<u32>,
+    /// 各阶段耗时（秒）：逐帧分析、切分、生成片段
+    pub analysis_secs: f64,
+    pub split_secs: f64,
+    pub generate_secs: f64,
+}
+
+/// 后台拼接任务结束（完成、失败或被取消）
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct ConcatJobFinished {
+    pub job_id: String,
+    pub result: Option<String>,
+    pub error: Option<String>,
+    pub cancelled: bool,
+}
+
+/// 接口版本信息
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct IpcInfo {
+    pub version: u32,
+    pub app_version: String,
+}
+
+// 获取命令与事件接口的版本（前端启动时校验与生成的类型是否匹配）
+#[tauri::command]
+pub fn get_ipc_info(app: AppHandle) -> IpcInfo {
+    IpcInfo {
+        version: IPC_VERSION,
+        app_version: app.package_info().version.to_string(),
+    }
+}
@@ -0,0 +1,216 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+use crate::gop::GopSettings;
+use crate::reframe::AspectPreset;
+use crate::settings::SettingsManager;
+use crate::video_processor::{OutputVariant, VideoCodec};
+
+/// 成片超过预设时长上限时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DurationPolicy {
+    /// 按时长上限在片段边界处分成多段输出
+    #[default]
+    Split,
+    /// 减少每次使用的片段，使整个成片不超过时长上限
+    Limit,
+}
+
+/// 导出预设：按发布平台打包的输出规格（画面比例、分辨率、码率、关键帧、响度、时长上限）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportPreset {
+    pub name: String,
+    #[serde(default)]
+    pub aspect: AspectPreset,
+    /// 输出高度（等比缩放），为空则保持画布尺寸
+    #[serde(default)]
+    pub height: Option<u32>,
+    #[serde(default)]
+    pub codec: VideoCodec,
+    #[serde(default)]
+    pub crf: Option<u32>,
+    /// 视频码率上限（kbps），超过平台限制会被二次压缩
+    #[serde(default)]
+    pub max_bitrate: Option<u32>,
+    #[serde(default)]
+    pub gop: Option<GopSettings>,
+    /// 响度标准化目标（LUFS），为空则不处理
+    #[serde(default)]
+    pub loudness_target: Option<f64>,
+    /// 单个输出的最长时长（秒），拆解时过长的片段继续切分
+    #[serde(default)]
+    pub max_duration: Option<f64>,
+    /// 拼接成片超过最长时长时分段输出还是减少片段
+    #[serde(default)]
+    pub duration_policy: DurationPolicy,
+    /// 把 moov 移到文件开头，上传后可边下边播
+    #[serde(default)]
+    pub faststart: bool,
+}
+
+impl ExportPreset {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("导出预设名称不能为空".to_string());
+        }
+        if self.height.is_some_and(|h| h < 2) {
+            return Err("导出预设的输出高度无效".to_string());
+        }
+        if self.max_bitrate == Some(0) {
+            return Err("码率上限必须大于 0".to_string());
+        }
+        if let Some(gop) = &self.gop {
+            gop.validate()?;
+        }
+        if self.loudness_target.is_some_and(|l| !(-70.0..=-5.0).contains(&l)) {
+            return Err("响度目标必须在 -70 到 -5 LUFS 之间".to_string());
+        }
+        if self.max_duration.is_some_and(|d| d <= 0.0) {
+            return Err("最长时长必须大于 0".to_string());
+        }
+        Ok(())
+    }
+
+    /// 拼接使用的输出规格（单一输出，文件名不加后缀）
+    pub fn variant(&self) -> OutputVariant {
+        OutputVariant {
+            name: String::new(),
+            aspect: self.aspect,
+            height: self.height,
+            codec: self.codec,
+            crf: self.crf,
+            gop: self.gop,
+            max_bitrate: self.max_bitrate,
+            faststart: self.faststart,
+        }
+    }
+}
+
+/// 按名称取导出预设，名称为空时不使用预设
+pub fn resolve(app: &AppHandle, name: Option<&str>) -> Result<Option<ExportPreset>, String> {
+    match name.filter(|n| !n.is_empty()) {
+        Some(name) => app.state::<SettingsManager>().find_export_preset(name).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// 码率上限对应的编码参数（缓冲区取两倍码率）
+pub fn bitrate_args(kbps: u32) -> Vec<String> {
+    vec![
+        "-maxrate".to_string(),
+        format!("{}k", kbps),
+        "-bufsize".to_string(),
+        format!("{}k", kbps * 2),
+    ]
+}
+
+/// 响度标准化滤镜（EBU R128），loudnorm 会把采样率提高，之后重采样回 48k
+pub fn loudnorm_filter(target: f64) -> String {
+    format!("loudnorm=I={}:TP=-1.5:LRA=11,aresample=48000", target)
+}
+
+/// 每两秒一个关键帧，各平台转码时切片更稳定
+fn platform_gop() -> Option<GopSettings> {
+    Some(GopSettings {
+        keyframe_interval: Some(2.0),
+        ..GopSettings::default()
+    })
+}
+
+/// 内置的平台预设
+pub fn builtin_presets() -> Vec<ExportPreset> {
+    vec![
+        ExportPreset {
+            name: "youtube".to_string(),
+            aspect: AspectPreset::Landscape,
+            height: Some(1080),
+            codec: VideoCodec::H264,
+            crf: Some(20),
+            max_bitrate: Some(12000),
+            gop: platform_gop(),
+            loudness_target: Some(-14.0),
+            max_duration: None,
+            duration_policy: DurationPolicy::Split,
+            faststart: true,
+        },
+        ExportPreset {
+            name: "tiktok".to_string(),
+            aspect: AspectPreset::Portrait,
+            height: Some(1920),
+            codec: VideoCodec::H264,
+            crf: Some(21),
+            max_bitrate: Some(6000),
+            gop: platform_gop(),
+            loudness_target: Some(-14.0),
+            max_duration: Some(600.0),
+            duration_policy: DurationPolicy::Limit,
+            faststart: true,
+        },
+        ExportPreset {
+            name: "bilibili".to_string(),
+            aspect: AspectPreset::Landscape,
+            height: Some(1080),
+            codec: VideoCodec::H264,
+            crf: Some(20),
+            max_bitrate: Some(6000),
+            gop: platform_gop(),
+            loudness_target: Some(-16.0),
+            max_duration: None,
+            duration_policy: DurationPolicy::Split,
+            faststart: true,
+        },
+        ExportPreset {
+            name: "wechat".to_string(),
+            aspect: AspectPreset::Portrait,
+            height: Some(1280),
+            codec: VideoCodec::H264,
+            crf: Some(23),
+            max_bitrate: Some(4000),
+            gop: platform_gop(),
+            loudness_target: Some(-16.0),
+            max_duration: Some(3600.0),
+            duration_policy: DurationPolicy::Split,
+            faststart: true,
+        },
+    ]
+}
+
+// 列出导出预设（自定义预设覆盖同名的内置预设）
+#[tauri::command]
+pub fn list_export_presets(settings: State<'_, SettingsManager>) -> Vec<ExportPreset> {
+    let custom = settings.get().export_presets;
+    let mut presets: Vec<ExportPreset> = builtin_presets()
+        .into_iter()
+        .filter(|preset| !custom.iter().any(|c| c.name == preset.name))
+        .collect();
+    presets.extend(custom);
+    presets
+}
+
+// 新建或覆盖同名导出预设（与内置预设同名时覆盖内置预设）
+#[tauri::command]
+pub fn save_export_preset(settings: State<'_, SettingsManager>, preset: ExportPreset) -> Result<(), String> {
+    preset.validate()?;
+    settings.update(|s| {
+        match s.export_presets.iter_mut().find(|p| p.name == preset.name) {
+            Some(existing) => *existing = preset,
+            None => s.export_presets.push(preset),
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+// 删除自定义导出预设（删除覆盖内置预设的同名预设后恢复内置设置）
+#[tauri::command]
+pub fn delete_export_preset(settings: State<'_, SettingsManager>, name: String) -> Result<(), String> {
+    settings.update(|s| {
+        let before = s.export_presets.len();
+        s.export_presets.retain(|p| p.name != name);
+        if s.export_presets.len() == before {
+            return Err(format!("自定义导出预设不存在: {}", name));
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
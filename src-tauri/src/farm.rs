@@ -0,0 +1,496 @@
+// 多机渲染：一台机器作为协调端在局域网开放任务队列，其他机器作为执行节点领取任务
+//
+// 协调端与执行节点之间是简单的 HTTP + JSON 接口（需携带相同的令牌）：
+//   POST /farm/claim      领取下一个等待中的任务，没有任务时返回 204
+//   POST /farm/heartbeat  执行中定期发送，超时未收到的任务重新排队
+//   POST /farm/complete   返回执行结果，记录在协调端的任务列表中
+// 输入与输出需放在各机器都能访问的共享存储上，路径不同时由执行节点按路径映射转换；
+// 执行节点仍按本机的授权范围检查路径，共享目录需先在执行节点上选择授权。
+// 协调端默认只监听 127.0.0.1，开放给局域网时需指定监听地址（如 0.0.0.0）。
+// 接口是明文 HTTP，令牌与任务内容（含文件路径）都不加密，只应在可信的局域网内使用，
+// 不要经过公网或不可信的网络转发。
+// 任务引用的检测预设、导出预设与品牌包在协调端加入队列时已写入任务内容，执行节点不按名称查找本机设置；
+// 执行节点按路径映射转换任务中的所有文件路径（源文件、输出目录、新结尾、标题字体、片尾样本与品牌包素材）。
+
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager, State};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use ts_rs::TS;
+use crate::jobs::{run_job, JobQueue, JobSpec};
+use crate::shutdown::ProcessRegistry;
+use crate::throttle::{run_in_job, ThrottleSettings};
+
+/// 请求头中的令牌
+const TOKEN_HEADER: &str = "x-farm-token";
+/// 请求体大小上限
+const MAX_BODY: usize = 1 << 20;
+/// 读取一个请求的时间上限，空闲或过慢的连接到时关闭
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// 接受连接出错（如文件句柄耗尽）后，等待后继续监听的间隔
+const ACCEPT_RETRY: Duration = Duration::from_millis(500);
+/// 执行节点没有领到任务或连不上协调端时，等待后重试的间隔
+const WORKER_POLL: Duration = Duration::from_secs(10);
+/// 执行节点发送心跳的间隔（协调端 120 秒未收到心跳即重新分配）
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 协调端下发的任务
+#[derive(Serialize, Deserialize)]
+struct FarmTask {
+    id: String,
+    spec: JobSpec,
+    throttle: Option<ThrottleSettings>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ClaimRequest {
+    worker: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct HeartbeatRequest {
+    id: String,
+    worker: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CompleteRequest {
+    id: String,
+    worker: String,
+    result: Result<String, String>,
+}
+
+/// 路径映射：任务路径以 `from` 开头时替换为 `to`（如协调端的 `D:\share` 对应本机的 `/mnt/share`）
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct PathMapping {
+    pub from: String,
+    pub to: String,
+}
+
+/// 多机渲染状态
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct FarmStatus {
+    /// 作为协调端监听的端口
+    pub coordinator_port: Option<u16>,
+    /// 作为协调端监听的地址
+    pub coordinator_address: Option<String>,
+    /// 协调端最近一次接受连接失败的原因
+    pub coordinator_error: Option<String>,
+    /// 作为执行节点连接的协调端地址
+    pub worker_coordinator: Option<String>,
+}
+
+/// 本机的协调端与执行节点状态
+pub struct FarmState {
+    coordinator: Mutex<Option<(SocketAddr, JoinHandle<()>)>>,
+    coordinator_error: Mutex<Option<String>>,
+    worker: Mutex<Option<String>>,
+    stop_worker: AtomicBool,
+}
+
+impl FarmState {
+    pub fn new() -> Self {
+        Self {
+            coordinator: Mutex::new(None),
+            coordinator_error: Mutex::new(None),
+            worker: Mutex::new(None),
+            stop_worker: AtomicBool::new(false),
+        }
+    }
+
+    fn status(&self) -> FarmStatus {
+        let address = self.coordinator.lock().unwrap().as_ref().map(|(address, _)| *address);
+        FarmStatus {
+            coordinator_port: address.map(|a| a.port()),
+            coordinator_address: address.map(|a| a.ip().to_string()),
+            coordinator_error: self.coordinator_error.lock().unwrap().clone(),
+            worker_coordinator: self.worker.lock().unwrap().clone(),
+        }
+    }
+}
+
+/// 解析后的请求
+struct Request {
+    method: String,
+    path: String,
+    token: Option<String>,
+    body: Vec<u8>,
+}
+
+async fn read_request(stream: &mut TcpStream) -> Result<Request, String> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader
+        .read_line(&mut line)
+        .await
+        .map_err(|e| format!("读取请求失败: {}", e))?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next().ok_or("请求格式错误")?.to_string();
+    let path = parts.next().ok_or("请求格式错误")?.to_string();
+
+    let mut content_length = 0;
+    let mut token = None;
+    loop {
+        line.clear();
+        reader
+            .read_line(&mut line)
+            .await
+            .map_err(|e| format!("读取请求失败: {}", e))?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            let name = name.trim().to_ascii_lowercase();
+            if name == "content-length" {
+                content_length = value.trim().parse().map_err(|_| "Content-Length 无效")?;
+            } else if name == TOKEN_HEADER {
+                token = Some(value.trim().to_string());
+            }
+        }
+    }
+    if content_length > MAX_BODY {
+        return Err("请求体过大".to_string());
+    }
+    let mut body = vec![0; content_length];
+    reader
+        .read_exact(&mut body)
+        .await
+        .map_err(|e| format!("读取请求失败: {}", e))?;
+    Ok(Request {
+        method,
+        path,
+        token,
+        body,
+    })
+}
+
+fn parse_body<'a, T: Deserialize<'a>>(request: &'a Request) -> Result<T, (u16, String)> {
+    serde_json::from_slice(&request.body).map_err(|e| (400, format!("请求内容无效: {}", e)))
+}
+
+/// 逐字节比较令牌，耗时与不匹配的位置无关
+fn token_matches(given: Option<&str>, expected: &str) -> bool {
+    let Some(given) = given else {
+        return false;
+    };
+    let (a, b) = (given.as_bytes(), expected.as_bytes());
+    let mut diff = a.len() ^ b.len();
+    for i in 0..a.len().max(b.len()) {
+        diff |= (a.get(i).copied().unwrap_or(0) ^ b.get(i).copied().unwrap_or(0)) as usize;
+    }
+    diff == 0
+}
+
+/// 处理一个请求，返回状态码与 JSON 响应体
+fn route(app: &AppHandle, request: &Request, token: &str) -> Result<(u16, String), (u16, String)> {
+    if !token_matches(request.token.as_deref(), token) {
+        return Err((401, "令牌错误".to_string()));
+    }
+    let queue = app.state::<JobQueue>();
+    match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/farm/claim") => {
+            let claim: ClaimRequest = parse_body(request)?;
+            match queue.claim_remote(app, &claim.worker) {
+                Some(job) => {
+                    let task = FarmTask {
+                        id: job.id,
+                        spec: job.spec,
+                        throttle: job.throttle,
+                    };
+                    let body = serde_json::to_string(&task).map_err(|e| (500, e.to_string()))?;
+                    Ok((200, body))
+                }
+                None => Ok((204, String::new())),
+            }
+        }
+        ("POST", "/farm/heartbeat") => {
+            let heartbeat: HeartbeatRequest = parse_body(request)?;
+            queue
+                .heartbeat_remote(&heartbeat.id, &heartbeat.worker)
+                .map_err(|e| (409, e))?;
+            Ok((200, "{}".to_string()))
+        }
+        ("POST", "/farm/complete") => {
+            let complete: CompleteRequest = parse_body(request)?;
+            queue
+                .complete_remote(app, &complete.id, &complete.worker, complete.result)
+                .map_err(|e| (409, e))?;
+            Ok((200, "{}".to_string()))
+        }
+        _ => Err((404, "接口不存在".to_string())),
+    }
+}
+
+async fn handle_connection(app: AppHandle, mut stream: TcpStream, token: String) {
+    let request = tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut stream))
+        .await
+        .unwrap_or_else(|_| Err("读取请求超时".to_string()));
+    let (status, body) = match request {
+        Ok(request) => route(&app, &request, &token).unwrap_or_else(|(status, e)| {
+            (status, serde_json::json!({ "error": e }).to_string())
+        }),
+        Err(e) => (400, serde_json::json!({ "error": e }).to_string()),
+    };
+    let reason = match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        409 => "Conflict",
+        _ => "Internal Server Error",
+    };
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// 把任务中的路径按映射转换为本机路径
+fn map_path(mappings: &[PathMapping], path: &str) -> String {
+    mappings
+        .iter()
+        .find(|m| !m.from.is_empty() && path.starts_with(&m.from))
+        .map(|m| format!("{}{}", m.to, &path[m.from.len()..]))
+        .unwrap_or_else(|| path.to_string())
+}
+
+/// 执行节点与协调端通信
+struct FarmClient {
+    client: reqwest::Client,
+    coordinator: String,
+    token: String,
+    worker: String,
+}
+
+impl FarmClient {
+    async fn send<T: Serialize>(&self, path: &str, body: &T) -> Result<reqwest::Response, String> {
+        self.client
+            .post(format!("{}{}", self.coordinator, path))
+            .header(TOKEN_HEADER, &self.token)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(serde_json::to_vec(body).map_err(|e| format!("序列化请求失败: {}", e))?)
+            .send()
+            .await
+            .map_err(|e| format!("连接协调端失败: {}", e))
+    }
+
+    async fn post<T: Serialize>(&self, path: &str, body: &T) -> Result<reqwest::Response, String> {
+        let response = self.send(path, body).await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(format!("协调端返回错误 {}: {}", status, text));
+        }
+        Ok(response)
+    }
+
+    async fn claim(&self) -> Result<Option<FarmTask>, String> {
+        let response = self
+            .post(
+                "/farm/claim",
+                &ClaimRequest {
+                    worker: self.worker.clone(),
+                },
+            )
+            .await?;
+        if response.status() == reqwest::StatusCode::NO_CONTENT {
+            return Ok(None);
+        }
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| format!("读取任务失败: {}", e))?;
+        serde_json::from_slice(&body)
+            .map(Some)
+            .map_err(|e| format!("解析任务失败: {}", e))
+    }
+
+    /// 发送心跳，任务已被协调端重新分配时返回 false
+    async fn heartbeat(&self, id: &str) -> Result<bool, String> {
+        let response = self
+            .send(
+                "/farm/heartbeat",
+                &HeartbeatRequest {
+                    id: id.to_string(),
+                    worker: self.worker.clone(),
+                },
+            )
+            .await?;
+        Ok(response.status() != reqwest::StatusCode::CONFLICT)
+    }
+
+    async fn complete(&self, id: String, result: Result<String, String>) -> Result<(), String> {
+        self.post(
+            "/farm/complete",
+            &CompleteRequest {
+                id,
+                worker: self.worker.clone(),
+                result,
+            },
+        )
+        .await
+        .map(|_| ())
+    }
+}
+
+/// 执行一个领到的任务，执行期间定期发送心跳
+async fn run_farm_task(app: &AppHandle, client: &FarmClient, task: FarmTask, mappings: &[PathMapping]) {
+    let FarmTask { id, mut spec, throttle } = task;
+    spec.map_paths(|path| map_path(mappings, path));
+    let job = run_in_job(app, &id, throttle, run_job(app, spec));
+    tokio::pin!(job);
+    let mut ticker = tokio::time::interval(HEARTBEAT_INTERVAL);
+    let result = loop {
+        tokio::select! {
+            result = &mut job => break result,
+            _ = ticker.tick() => {
+                // 任务已被协调端重新分配时不再继续；暂时连不上协调端时继续执行
+                if let Ok(false) = client.heartbeat(&id).await {
+                    return;
+                }
+            }
+        }
+    };
+    // 结果没有送达时，协调端会在心跳超时后重新分配该任务
+    let _ = client.complete(id.clone(), result).await;
+}
+
+async fn worker_loop(app: AppHandle, client: FarmClient, mappings: Vec<PathMapping>) {
+    let state = app.state::<FarmState>();
+    while !state.stop_worker.load(Ordering::SeqCst) && !app.state::<ProcessRegistry>().is_shutting_down() {
+        match client.claim().await {
+            Ok(Some(task)) => run_farm_task(&app, &client, task, &mappings).await,
+            // 没有任务或暂时连不上协调端，稍后重试
+            Ok(None) | Err(_) => tokio::time::sleep(WORKER_POLL).await,
+        }
+    }
+    *state.worker.lock().unwrap() = None;
+}
+
+// 作为协调端开放本机任务队列，其他机器的执行节点可以领取任务（本机也继续执行队列）；
+// 默认只监听本机地址，开放给局域网时指定 bind_address（如 0.0.0.0）
+#[tauri::command]
+pub async fn start_farm_coordinator(
+    app: AppHandle,
+    state: State<'_, FarmState>,
+    port: u16,
+    token: String,
+    bind_address: Option<String>,
+) -> Result<FarmStatus, String> {
+    if token.trim().is_empty() {
+        return Err("令牌不能为空".to_string());
+    }
+    if state.coordinator.lock().unwrap().is_some() {
+        return Err("协调端已在运行".to_string());
+    }
+    let ip = match bind_address.as_deref().map(str::trim).filter(|a| !a.is_empty()) {
+        Some(address) => address
+            .parse::<IpAddr>()
+            .map_err(|_| format!("监听地址无效: {}", address))?,
+        None => IpAddr::V4(Ipv4Addr::LOCALHOST),
+    };
+    let listener = TcpListener::bind((ip, port))
+        .await
+        .map_err(|e| format!("监听 {}:{} 失败: {}", ip, port, e))?;
+    let address = listener
+        .local_addr()
+        .map_err(|e| format!("获取监听地址失败: {}", e))?;
+    *state.coordinator_error.lock().unwrap() = None;
+    let server_app = app.clone();
+    let handle = tauri::async_runtime::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tauri::async_runtime::spawn(handle_connection(server_app.clone(), stream, token.clone()));
+                }
+                // 单次接受失败不影响之后的连接，记录原因后继续监听
+                Err(e) => {
+                    *server_app.state::<FarmState>().coordinator_error.lock().unwrap() =
+                        Some(format!("接受连接失败: {}", e));
+                    tokio::time::sleep(ACCEPT_RETRY).await;
+                }
+            }
+        }
+    });
+    *state.coordinator.lock().unwrap() = Some((address, handle));
+    Ok(state.status())
+}
+
+// 停止协调端，执行节点上未完成的任务放回本机队列
+#[tauri::command]
+pub fn stop_farm_coordinator(app: AppHandle, state: State<'_, FarmState>) -> Result<FarmStatus, String> {
+    let (_, handle) = state
+        .coordinator
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or("协调端未运行")?;
+    handle.abort();
+    app.state::<JobQueue>().requeue_remote(&app, false);
+    Ok(state.status())
+}
+
+// 作为执行节点连接协调端，循环领取并执行任务
+#[tauri::command]
+pub fn start_farm_worker(
+    app: AppHandle,
+    state: State<'_, FarmState>,
+    coordinator: String,
+    token: String,
+    worker_name: Option<String>,
+    path_mappings: Option<Vec<PathMapping>>,
+) -> Result<FarmStatus, String> {
+    let coordinator = coordinator.trim().trim_end_matches('/').to_string();
+    if !coordinator.starts_with("http://") {
+        return Err("协调端地址需以 http:// 开头".to_string());
+    }
+    {
+        let mut worker = state.worker.lock().unwrap();
+        if worker.is_some() {
+            return Err("执行节点已在运行".to_string());
+        }
+        *worker = Some(coordinator.clone());
+    }
+    state.stop_worker.store(false, Ordering::SeqCst);
+
+    let worker = worker_name
+        .filter(|n| !n.trim().is_empty())
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .unwrap_or_else(|| "worker".to_string());
+    let client = FarmClient {
+        client: reqwest::Client::new(),
+        coordinator,
+        token,
+        worker,
+    };
+    tauri::async_runtime::spawn(worker_loop(app, client, path_mappings.unwrap_or_default()));
+    Ok(state.status())
+}
+
+// 停止执行节点（当前任务完成后退出）
+#[tauri::command]
+pub fn stop_farm_worker(state: State<'_, FarmState>) -> FarmStatus {
+    state.stop_worker.store(true, Ordering::SeqCst);
+    state.status()
+}
+
+// 获取多机渲染状态
+#[tauri::command]
+pub fn get_farm_status(state: State<'_, FarmState>) -> FarmStatus {
+    state.status()
+}
@@ -1,89 +1,712 @@
-use image::DynamicImage;
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, GrayImage, ImageBuffer, Pixel, RgbImage};
+use imageproc::distance_transform::Norm;
+use imageproc::{edges, gradients, morphology};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::path::Path;
+use tauri::{AppHandle, Manager, State};
+use ts_rs::TS;
+use crate::path_scope::check_input;
+use crate::settings::SettingsManager;
+
+/// 自动检测不变区域时最多读取的样本帧数
+const STATIC_SAMPLES: usize = 24;
+/// 帧数少于该值时不做自动检测（样本太少无法区分水印与静止画面）
+const MIN_STATIC_SAMPLES: usize = 5;
+/// 各样本间亮度变化不超过该值的像素视为不变
+const STATIC_RANGE: u8 = 6;
+/// 亮度始终低于该值的不变像素视为黑边，不算水印
+const DARK_LEVEL: u8 = 24;
+/// 不变像素超过画面该比例时认为视频本身是静止画面，放弃自动检测
+const MAX_STATIC_FRACTION: f64 = 0.25;
+
+/// 直方图的颜色空间
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum HistogramColorSpace {
+    /// 只比较亮度
+    #[default]
+    Gray,
+    /// 分别比较 R/G/B 三个通道
+    Rgb,
+    /// 分别比较色相/饱和度/明度，对亮度变化不如 RGB 敏感
+    Hsv,
+}
+
+/// 直方图的比较方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum HistogramCompare {
+    /// 巴氏系数
+    #[default]
+    Bhattacharyya,
+    /// 相关系数
+    Correlation,
+    /// 卡方距离
+    ChiSquare,
+}
+
+/// 直方图参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct HistogramParams {
+    /// 直方图的分组数（2-256），分组越少对轻微的亮度变化越不敏感
+    #[serde(default = "default_bins")]
+    pub bins: u32,
+    /// 颜色空间，RGB/HSV 需要彩色缩略图
+    #[serde(default)]
+    pub color_space: HistogramColorSpace,
+    #[serde(default)]
+    pub compare: HistogramCompare,
+}
+
+fn default_bins() -> u32 {
+    256
+}
+
+impl Default for HistogramParams {
+    fn default() -> Self {
+        Self {
+            bins: default_bins(),
+            color_space: HistogramColorSpace::default(),
+            compare: HistogramCompare::default(),
+        }
+    }
+}
+
+/// SSIM 参数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SsimParams {
+    /// 分块计算的窗口边长（像素），各块取平均；为空时整幅画面作为一个窗口
+    #[serde(default)]
+    pub window: Option<u32>,
+}
+
+/// 帧差异参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct FrameDiffParams {
+    /// 比较前把画面缩小的倍数，倍数越大越能忽略噪点和细小运动
+    #[serde(default = "default_downscale")]
+    pub downscale: u32,
+}
+
+fn default_downscale() -> u32 {
+    1
+}
+
+impl Default for FrameDiffParams {
+    fn default() -> Self {
+        Self {
+            downscale: default_downscale(),
+        }
+    }
+}
+
+/// 边缘检测方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeDetector {
+    /// Sobel 梯度幅值超过阈值即为边缘，速度快
+    #[default]
+    Sobel,
+    /// Canny 检测（先模糊再细化边缘），抗噪更好
+    Canny,
+}
+
+/// 边缘差异参数
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct EdgeParams {
+    #[serde(default)]
+    pub detector: EdgeDetector,
+    /// 边缘阈值：Sobel 为梯度幅值阈值，Canny 为低阈值
+    #[serde(default = "default_edge_low")]
+    pub low_threshold: f32,
+    /// Canny 的高阈值
+    #[serde(default = "default_edge_high")]
+    pub high_threshold: f32,
+    /// 边缘位置允许的偏移（像素），吸收轻微抖动和编码噪点
+    #[serde(default = "default_edge_tolerance")]
+    pub tolerance: u8,
+}
+
+fn default_edge_low() -> f32 {
+    50.0
+}
+
+fn default_edge_high() -> f32 {
+    100.0
+}
+
+fn default_edge_tolerance() -> u8 {
+    1
+}
+
+impl Default for EdgeParams {
+    fn default() -> Self {
+        Self {
+            detector: EdgeDetector::default(),
+            low_threshold: default_edge_low(),
+            high_threshold: default_edge_high(),
+            tolerance: default_edge_tolerance(),
+        }
+    }
+}
+
+/// 比较画面时忽略的区域（水印、台标），对所有算法生效
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct MaskSettings {
+    /// 四个角各忽略的宽、高比例（0-0.5），0 表示不忽略
+    #[serde(default)]
+    pub corner_fraction: f64,
+    /// 自动检测整个视频中始终不变的区域（如固定水印）并忽略
+    #[serde(default)]
+    pub auto_static: bool,
+}
+
+/// 逐帧比较前的 dHash 预筛选：哈希差异明确的相邻帧直接判定，只有难以判断的才用所选算法比较
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct PrefilterParams {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 哈希距离（0-64）不超过该值视为同一画面，相似度记为 1
+    #[serde(default = "default_same_distance")]
+    pub same_distance: u32,
+    /// 哈希距离不小于该值视为场景切换，相似度记为 0
+    #[serde(default = "default_cut_distance")]
+    pub cut_distance: u32,
+}
+
+fn default_same_distance() -> u32 {
+    3
+}
+
+fn default_cut_distance() -> u32 {
+    26
+}
+
+impl Default for PrefilterParams {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            same_distance: default_same_distance(),
+            cut_distance: default_cut_distance(),
+        }
+    }
+}
+
+/// 各相似度算法的参数（全局设置）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SimilarityTuning {
+    #[serde(default)]
+    pub histogram: HistogramParams,
+    #[serde(default)]
+    pub ssim: SsimParams,
+    #[serde(default)]
+    pub frame_diff: FrameDiffParams,
+    #[serde(default)]
+    pub edge: EdgeParams,
+    #[serde(default)]
+    pub mask: MaskSettings,
+    #[serde(default)]
+    pub prefilter: PrefilterParams,
+}
+
+impl SimilarityTuning {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(2..=256).contains(&self.histogram.bins) {
+            return Err("直方图分组数必须在 2 到 256 之间".to_string());
+        }
+        if self.ssim.window.is_some_and(|w| w < 2) {
+            return Err("SSIM 窗口边长至少为 2".to_string());
+        }
+        if self.frame_diff.downscale == 0 {
+            return Err("帧差异的缩小倍数必须大于 0".to_string());
+        }
+        if self.edge.low_threshold <= 0.0 || self.edge.high_threshold < self.edge.low_threshold {
+            return Err("边缘阈值无效：低阈值须大于 0 且不大于高阈值".to_string());
+        }
+        if !(0.0..0.5).contains(&self.mask.corner_fraction) {
+            return Err("忽略角落的比例必须在 0 到 0.5 之间".to_string());
+        }
+        if self.prefilter.same_distance >= self.prefilter.cut_distance || self.prefilter.cut_distance > 64 {
+            return Err("预筛选的哈希距离无效：相同距离须小于切换距离，且切换距离不超过 64".to_string());
+        }
+        Ok(())
+    }
+}
 
 #[derive(Debug, Clone, Copy)]
 pub enum SimilarityAlgorithm {
-    Histogram,
-    SSIM,
-    FrameDiff,
+    Histogram(HistogramParams),
+    Ssim(SsimParams),
+    FrameDiff(FrameDiffParams),
+    Edge(EdgeParams),
 }
 
 impl SimilarityAlgorithm {
-    pub fn from_str(s: &str) -> Result<Self, String> {
+    /// 按名称选择算法，参数取自 `tuning`
+    pub fn from_name(s: &str, tuning: &SimilarityTuning) -> Result<Self, String> {
         match s {
-            "histogram" => Ok(Self::Histogram),
-            "ssim" => Ok(Self::SSIM),
-            "frame_diff" => Ok(Self::FrameDiff),
+            "histogram" => Ok(Self::Histogram(tuning.histogram)),
+            "ssim" => Ok(Self::Ssim(tuning.ssim)),
+            "frame_diff" => Ok(Self::FrameDiff(tuning.frame_diff)),
+            "edge" => Ok(Self::Edge(tuning.edge)),
             _ => Err(format!("未知的算法: {}", s)),
         }
     }
+
+    /// 是否需要彩色画面（分析用的灰度帧没有颜色信息）
+    pub fn needs_color(&self) -> bool {
+        matches!(self, Self::Histogram(params) if params.color_space != HistogramColorSpace::Gray)
+    }
+}
+
+/// 全局相似度算法参数
+pub fn current(app: &AppHandle) -> SimilarityTuning {
+    app.state::<SettingsManager>().get().similarity
+}
+
+// 保存相似度算法参数（拆解、去结尾与精彩集锦的场景检测统一使用）
+#[tauri::command]
+pub fn save_similarity_settings(
+    settings: State<'_, SettingsManager>,
+    similarity: SimilarityTuning,
+) -> Result<(), String> {
+    similarity.validate()?;
+    settings.update(|s| {
+        s.similarity = similarity;
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// 待比较的图片：文件路径或 base64 编码的图片数据（可带 data URI 前缀）
+#[derive(Debug, Clone, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageSource {
+    Path(String),
+    Base64(String),
+}
+
+impl ImageSource {
+    fn load(&self, app: &AppHandle) -> Result<DynamicImage, String> {
+        match self {
+            Self::Path(path) => {
+                let path = check_input(app, path)?;
+                image::open(&path).map_err(|e| format!("无法打开图片 {}: {}", path.display(), e))
+            }
+            Self::Base64(data) => {
+                let data = data.split_once(";base64,").map(|(_, d)| d).unwrap_or(data);
+                let bytes = base64_decode(data)?;
+                image::load_from_memory(&bytes).map_err(|e| format!("无法解析图片数据: {}", e))
+            }
+        }
+    }
+}
+
+fn base64_decode(data: &str) -> Result<Vec<u8>, String> {
+    let value = |c: u8| match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' | b'-' => Some(62),
+        b'/' | b'_' => Some(63),
+        _ => None,
+    };
+    let digits: Vec<u8> = data
+        .bytes()
+        .filter(|c| !c.is_ascii_whitespace() && *c != b'=')
+        .map(|c| value(c).ok_or("图片数据不是有效的 base64"))
+        .collect::<Result<_, _>>()?;
+    let mut out = Vec::with_capacity(digits.len() * 3 / 4);
+    for chunk in digits.chunks(4) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &d)| n | (d as u32) << (18 - 6 * i));
+        out.extend_from_slice(&n.to_be_bytes()[1..chunk.len()]);
+    }
+    Ok(out)
+}
+
+// 比较两张图片的相似度（调参界面实时显示用）；algorithm 为算法名，tuning 为空时使用已保存的参数
+// 尺寸不同时把第二张缩放到第一张的尺寸
+#[tauri::command]
+pub fn compare_images(
+    app: AppHandle,
+    image1: ImageSource,
+    image2: ImageSource,
+    algorithm: String,
+    tuning: Option<SimilarityTuning>,
+) -> Result<f64, String> {
+    let tuning = match tuning {
+        Some(tuning) => {
+            tuning.validate()?;
+            tuning
+        }
+        None => current(&app),
+    };
+    let algorithm = SimilarityAlgorithm::from_name(&algorithm, &tuning)?;
+    let img1 = image1.load(&app)?;
+    let mut img2 = image2.load(&app)?;
+    if img1.width() != img2.width() || img1.height() != img2.height() {
+        img2 = img2.resize_exact(img1.width(), img1.height(), FilterType::Triangle);
+    }
+    similarity(img1, img2, algorithm)
+}
+
+/// 比较时忽略的区域（255 为忽略）
+pub struct RegionMask {
+    mask: GrayImage,
+}
+
+impl RegionMask {
+    /// 按设置生成忽略区域，没有需要忽略的像素时返回 None
+    ///
+    /// `frame_paths` 为同一视频的帧（尺寸一致），自动检测不变区域时从中均匀取样
+    pub fn build(settings: &MaskSettings, frame_paths: &[String]) -> Option<Self> {
+        if settings.corner_fraction <= 0.0 && !settings.auto_static {
+            return None;
+        }
+        let first = image::open(frame_paths.first()?).ok()?.into_luma8();
+        let (width, height) = first.dimensions();
+        let corner_w = (width as f64 * settings.corner_fraction).round() as u32;
+        let corner_h = (height as f64 * settings.corner_fraction).round() as u32;
+        let mut mask = GrayImage::from_fn(width, height, |x, y| {
+            let corner_x = x < corner_w || x >= width - corner_w;
+            let corner_y = y < corner_h || y >= height - corner_h;
+            image::Luma([if corner_x && corner_y { 255 } else { 0 }])
+        });
+
+        if settings.auto_static && frame_paths.len() >= MIN_STATIC_SAMPLES {
+            if let Some(region) = static_region(frame_paths, first) {
+                for (m, r) in mask.pixels_mut().zip(region.pixels()) {
+                    m[0] = m[0].max(r[0]);
+                }
+            }
+        }
+        mask.pixels().any(|p| p[0] > 0).then_some(Self { mask })
+    }
+
+    /// 把第二张图片的忽略区域替换为第一张的内容，使该区域不影响比较结果；尺寸不符时不处理
+    fn apply(&self, img1: DynamicImage, img2: DynamicImage) -> (DynamicImage, DynamicImage) {
+        let size = self.mask.dimensions();
+        if (img1.width(), img1.height()) != size || (img2.width(), img2.height()) != size {
+            return (img1, img2);
+        }
+        match (img1, img2) {
+            (DynamicImage::ImageLuma8(gray1), DynamicImage::ImageLuma8(mut gray2)) => {
+                self.copy_masked(&gray1, &mut gray2);
+                (gray1.into(), gray2.into())
+            }
+            (img1, img2) => {
+                let rgb1 = img1.into_rgb8();
+                let mut rgb2 = img2.into_rgb8();
+                self.copy_masked(&rgb1, &mut rgb2);
+                (rgb1.into(), rgb2.into())
+            }
+        }
+    }
+
+    fn copy_masked<P: Pixel>(
+        &self,
+        from: &ImageBuffer<P, Vec<P::Subpixel>>,
+        to: &mut ImageBuffer<P, Vec<P::Subpixel>>,
+    ) {
+        for (x, y, m) in self.mask.enumerate_pixels() {
+            if m[0] > 0 {
+                to.put_pixel(x, y, *from.get_pixel(x, y));
+            }
+        }
+    }
 }
 
-/// 计算两张图片的相似度 (0.0-1.0, 1.0表示完全相同)
+/// 找出各样本帧中始终不变的区域（固定水印、台标），黑边不算；不变区域过大时返回 None
+fn static_region(frame_paths: &[String], first: GrayImage) -> Option<GrayImage> {
+    let (width, height) = first.dimensions();
+    let step = (frame_paths.len() / STATIC_SAMPLES).max(1);
+    let mut low = first.clone();
+    let mut high = first;
+    for path in frame_paths.iter().step_by(step).skip(1) {
+        let Ok(sample) = image::open(path) else {
+            continue;
+        };
+        let sample = sample.into_luma8();
+        if sample.dimensions() != (width, height) {
+            return None;
+        }
+        for ((l, h), p) in low.pixels_mut().zip(high.pixels_mut()).zip(sample.pixels()) {
+            l[0] = l[0].min(p[0]);
+            h[0] = h[0].max(p[0]);
+        }
+    }
+
+    let region = GrayImage::from_fn(width, height, |x, y| {
+        let (l, h) = (low.get_pixel(x, y)[0], high.get_pixel(x, y)[0]);
+        image::Luma([if h - l <= STATIC_RANGE && h >= DARK_LEVEL { 255 } else { 0 }])
+    });
+    let count = region.pixels().filter(|p| p[0] > 0).count();
+    if count as f64 > (width * height) as f64 * MAX_STATIC_FRACTION {
+        return None;
+    }
+    // 水印边缘的抗锯齿像素随背景轻微变化，向外扩展几个像素
+    Some(morphology::dilate(&region, Norm::LInf, 2))
+}
+
+/// 64 位差异哈希（dHash）：缩成 9x8 灰度图，每行相邻像素比较亮度得到 8 位
+fn dhash(path: &str) -> Option<u64> {
+    let gray = image::open(path).ok()?.into_luma8();
+    let small = imageops::resize(&gray, 9, 8, FilterType::Triangle);
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if small.get_pixel(x, y)[0] < small.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    Some(hash)
+}
+
+/// 一组帧的 dHash，用于相邻帧的预筛选
+pub struct HashPrefilter {
+    hashes: Vec<Option<u64>>,
+    params: PrefilterParams,
+}
+
+impl HashPrefilter {
+    /// 并行计算每帧的哈希，未启用预筛选时返回 None
+    pub fn build(params: PrefilterParams, frame_paths: &[String]) -> Option<Self> {
+        if !params.enabled {
+            return None;
+        }
+        let hashes = frame_paths.par_iter().map(|path| dhash(path)).collect();
+        Some(Self { hashes, params })
+    }
+
+    /// 使用已缓存的哈希，未启用预筛选时返回 None
+    pub fn from_hashes(params: PrefilterParams, hashes: Vec<Option<u64>>) -> Option<Self> {
+        params.enabled.then_some(Self { hashes, params })
+    }
+
+    pub fn hashes(&self) -> &[Option<u64>] {
+        &self.hashes
+    }
+
+    /// 哈希差异明确时直接给出相似度（1 或 0），难以判断或哈希缺失时返回 None
+    pub fn decide(&self, a: usize, b: usize) -> Option<f64> {
+        let (Some(Some(hash1)), Some(Some(hash2))) = (self.hashes.get(a), self.hashes.get(b)) else {
+            return None;
+        };
+        let distance = (hash1 ^ hash2).count_ones();
+        if distance <= self.params.same_distance {
+            Some(1.0)
+        } else if distance >= self.params.cut_distance {
+            Some(0.0)
+        } else {
+            None
+        }
+    }
+}
+
+/// 计算两张图片的相似度 (0.0-1.0, 1.0表示完全相同)，比较时忽略 `mask` 中的区域
+///
+/// 除彩色直方图外只比较亮度；灰度图（分析用帧）读入后直接使用，不再做颜色转换
 pub fn calculate_similarity(
     img1_path: &str,
     img2_path: &str,
     algorithm: SimilarityAlgorithm,
+    mask: Option<&RegionMask>,
 ) -> Result<f64, String> {
     let img1 = image::open(Path::new(img1_path))
         .map_err(|e| format!("无法打开图片1: {}", e))?;
     let img2 = image::open(Path::new(img2_path))
         .map_err(|e| format!("无法打开图片2: {}", e))?;
 
+    let (img1, img2) = match mask {
+        Some(mask) => mask.apply(img1, img2),
+        None => (img1, img2),
+    };
+    similarity(img1, img2, algorithm)
+}
+
+/// 计算两张已读入图片的相似度
+fn similarity(img1: DynamicImage, img2: DynamicImage, algorithm: SimilarityAlgorithm) -> Result<f64, String> {
     match algorithm {
-        SimilarityAlgorithm::Histogram => histogram_similarity(&img1, &img2),
-        SimilarityAlgorithm::SSIM => ssim_similarity(&img1, &img2),
-        SimilarityAlgorithm::FrameDiff => frame_diff_similarity(&img1, &img2),
+        SimilarityAlgorithm::Histogram(params) => match params.color_space {
+            HistogramColorSpace::Gray => histogram_similarity(&img1.into_luma8(), &img2.into_luma8(), params),
+            HistogramColorSpace::Rgb => {
+                color_histogram_similarity(&img1.into_rgb8(), &img2.into_rgb8(), params, |p| p.0)
+            }
+            HistogramColorSpace::Hsv => {
+                color_histogram_similarity(&img1.into_rgb8(), &img2.into_rgb8(), params, rgb_to_hsv)
+            }
+        },
+        SimilarityAlgorithm::Ssim(params) => ssim_similarity(&img1.into_luma8(), &img2.into_luma8(), params.window),
+        SimilarityAlgorithm::FrameDiff(params) => {
+            let (gray1, gray2) = (downscale(img1, params.downscale), downscale(img2, params.downscale));
+            frame_diff_similarity(&gray1, &gray2)
+        }
+        SimilarityAlgorithm::Edge(params) => edge_similarity(&img1.into_luma8(), &img2.into_luma8(), params),
     }
 }
 
-/// 直方图相似度算法
-fn histogram_similarity(img1: &DynamicImage, img2: &DynamicImage) -> Result<f64, String> {
-    // 转换为灰度图
-    let gray1 = img1.to_luma8();
-    let gray2 = img2.to_luma8();
+/// 按倍数缩小为灰度图（三角滤波，相当于对邻近像素取平均）
+fn downscale(img: DynamicImage, factor: u32) -> GrayImage {
+    let gray = img.into_luma8();
+    if factor <= 1 {
+        return gray;
+    }
+    let (width, height) = gray.dimensions();
+    imageops::resize(&gray, (width / factor).max(1), (height / factor).max(1), FilterType::Triangle)
+}
 
-    if gray1.dimensions() != gray2.dimensions() {
-        return Err("图片尺寸不匹配".to_string());
+/// 单个通道的归一化直方图
+fn normalized_histogram(values: impl Iterator<Item = u8>, bins: u32, total: f64) -> Vec<f64> {
+    let mut hist = vec![0u32; bins as usize];
+    for value in values {
+        hist[value as usize * bins as usize / 256] += 1;
     }
+    hist.iter().map(|&x| x as f64 / total).collect()
+}
 
-    // 计算直方图 (256个bin)
-    let mut hist1 = vec![0u32; 256];
-    let mut hist2 = vec![0u32; 256];
+/// 巴氏系数 (Bhattacharyya coefficient)
+fn bhattacharyya(hist1: &[f64], hist2: &[f64]) -> f64 {
+    hist1.iter().zip(hist2).map(|(a, b)| (a * b).sqrt()).sum()
+}
 
-    for pixel in gray1.pixels() {
-        hist1[pixel[0] as usize] += 1;
+/// 相关系数，从 [-1, 1] 转换到 [0, 1]；两个直方图都是常数时按是否相同取 1 或 0
+fn correlation(hist1: &[f64], hist2: &[f64]) -> f64 {
+    let n = hist1.len() as f64;
+    let mean1 = hist1.iter().sum::<f64>() / n;
+    let mean2 = hist2.iter().sum::<f64>() / n;
+    let mut covar = 0.0;
+    let mut var1 = 0.0;
+    let mut var2 = 0.0;
+    for (a, b) in hist1.iter().zip(hist2) {
+        covar += (a - mean1) * (b - mean2);
+        var1 += (a - mean1) * (a - mean1);
+        var2 += (b - mean2) * (b - mean2);
+    }
+    let denominator = (var1 * var2).sqrt();
+    if denominator <= f64::EPSILON {
+        return if hist1 == hist2 { 1.0 } else { 0.0 };
     }
+    (covar / denominator + 1.0) / 2.0
+}
+
+/// 对称卡方距离（归一化直方图时范围 [0, 2]），转换为 [0, 1] 的相似度
+fn chi_square(hist1: &[f64], hist2: &[f64]) -> f64 {
+    let distance: f64 = hist1
+        .iter()
+        .zip(hist2)
+        .filter(|(a, b)| *a + *b > 0.0)
+        .map(|(a, b)| (a - b) * (a - b) / (a + b))
+        .sum();
+    1.0 - distance / 2.0
+}
 
-    for pixel in gray2.pixels() {
-        hist2[pixel[0] as usize] += 1;
+/// 按选定方式比较两个归一化直方图
+fn compare_histograms(hist1: &[f64], hist2: &[f64], compare: HistogramCompare) -> f64 {
+    match compare {
+        HistogramCompare::Bhattacharyya => bhattacharyya(hist1, hist2),
+        HistogramCompare::Correlation => correlation(hist1, hist2),
+        HistogramCompare::ChiSquare => chi_square(hist1, hist2),
     }
+}
 
-    // 归一化直方图
-    let total_pixels = (gray1.width() * gray1.height()) as f64;
-    let hist1_norm: Vec<f64> = hist1.iter().map(|&x| x as f64 / total_pixels).collect();
-    let hist2_norm: Vec<f64> = hist2.iter().map(|&x| x as f64 / total_pixels).collect();
+/// RGB 转 HSV，各分量缩放到 0-255（色相 0-360° 对应 0-255）
+fn rgb_to_hsv(p: &image::Rgb<u8>) -> [u8; 3] {
+    let [r, g, b] = p.0.map(|c| c as f64 / 255.0);
+    let max = r.max(g).max(b);
+    let delta = max - r.min(g).min(b);
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    let saturation = if max == 0.0 { 0.0 } else { delta / max };
+    [
+        (hue / 360.0 * 255.0).round().min(255.0) as u8,
+        (saturation * 255.0).round() as u8,
+        (max * 255.0).round() as u8,
+    ]
+}
 
-    // 使用巴氏距离 (Bhattacharyya distance) 计算相似度
-    let mut bc_coeff = 0.0;
-    for i in 0..256 {
-        bc_coeff += (hist1_norm[i] * hist2_norm[i]).sqrt();
+/// 按三个颜色通道（RGB 或转换后的 HSV）分别计算直方图相似度后取平均
+fn color_histogram_similarity(
+    rgb1: &RgbImage,
+    rgb2: &RgbImage,
+    params: HistogramParams,
+    convert: impl Fn(&image::Rgb<u8>) -> [u8; 3],
+) -> Result<f64, String> {
+    if rgb1.dimensions() != rgb2.dimensions() {
+        return Err("图片尺寸不匹配".to_string());
     }
 
-    Ok(bc_coeff)
+    let pixels1: Vec<[u8; 3]> = rgb1.pixels().map(&convert).collect();
+    let pixels2: Vec<[u8; 3]> = rgb2.pixels().map(&convert).collect();
+    let total_pixels = pixels1.len() as f64;
+    let mut sum = 0.0;
+    for channel in 0..3 {
+        let hist1 = normalized_histogram(pixels1.iter().map(|p| p[channel]), params.bins, total_pixels);
+        let hist2 = normalized_histogram(pixels2.iter().map(|p| p[channel]), params.bins, total_pixels);
+        sum += compare_histograms(&hist1, &hist2, params.compare);
+    }
+    Ok(sum / 3.0)
 }
 
-/// SSIM (结构相似性) 算法
-fn ssim_similarity(img1: &DynamicImage, img2: &DynamicImage) -> Result<f64, String> {
-    let gray1 = img1.to_luma8();
-    let gray2 = img2.to_luma8();
+/// 直方图相似度算法
+fn histogram_similarity(gray1: &GrayImage, gray2: &GrayImage, params: HistogramParams) -> Result<f64, String> {
+    if gray1.dimensions() != gray2.dimensions() {
+        return Err("图片尺寸不匹配".to_string());
+    }
+
+    // 计算归一化直方图
+    let total_pixels = (gray1.width() * gray1.height()) as f64;
+    let hist1_norm = normalized_histogram(gray1.pixels().map(|p| p[0]), params.bins, total_pixels);
+    let hist2_norm = normalized_histogram(gray2.pixels().map(|p| p[0]), params.bins, total_pixels);
+
+    // 默认使用巴氏距离 (Bhattacharyya distance) 计算相似度
+    Ok(compare_histograms(&hist1_norm, &hist2_norm, params.compare))
+}
 
+/// SSIM (结构相似性) 算法：按窗口分块计算后取平均，未指定窗口时整幅画面为一块
+fn ssim_similarity(gray1: &GrayImage, gray2: &GrayImage, window: Option<u32>) -> Result<f64, String> {
     if gray1.dimensions() != gray2.dimensions() {
         return Err("图片尺寸不匹配".to_string());
     }
 
     let (width, height) = gray1.dimensions();
+    let size = window.unwrap_or(width.max(height)).max(1);
+    let mut total = 0.0;
+    let mut blocks = 0;
+    for y in (0..height).step_by(size as usize) {
+        for x in (0..width).step_by(size as usize) {
+            total += ssim_block(gray1, gray2, x, y, size.min(width - x), size.min(height - y));
+            blocks += 1;
+        }
+    }
 
+    // SSIM 范围是 [-1, 1]，转换为 [0, 1]
+    Ok((total / blocks.max(1) as f64 + 1.0) / 2.0)
+}
+
+/// 单个窗口的 SSIM
+fn ssim_block(gray1: &GrayImage, gray2: &GrayImage, x0: u32, y0: u32, width: u32, height: u32) -> f64 {
     // SSIM 常量
     let k1 = 0.01;
     let k2 = 0.03;
@@ -96,8 +719,8 @@ fn ssim_similarity(img1: &DynamicImage, img2: &DynamicImage) -> Result<f64, Stri
     let mut sum2 = 0.0;
     let total_pixels = (width * height) as f64;
 
-    for y in 0..height {
-        for x in 0..width {
+    for y in y0..y0 + height {
+        for x in x0..x0 + width {
             sum1 += gray1.get_pixel(x, y)[0] as f64;
             sum2 += gray2.get_pixel(x, y)[0] as f64;
         }
@@ -111,8 +734,8 @@ fn ssim_similarity(img1: &DynamicImage, img2: &DynamicImage) -> Result<f64, Stri
     let mut var2 = 0.0;
     let mut covar = 0.0;
 
-    for y in 0..height {
-        for x in 0..width {
+    for y in y0..y0 + height {
+        for x in x0..x0 + width {
             let p1 = gray1.get_pixel(x, y)[0] as f64;
             let p2 = gray2.get_pixel(x, y)[0] as f64;
 
@@ -133,17 +756,11 @@ fn ssim_similarity(img1: &DynamicImage, img2: &DynamicImage) -> Result<f64, Stri
     let numerator = (2.0 * mean1 * mean2 + c1) * (2.0 * covar + c2);
     let denominator = (mean1 * mean1 + mean2 * mean2 + c1) * (var1 + var2 + c2);
 
-    let ssim = numerator / denominator;
-
-    // SSIM 范围是 [-1, 1]，转换为 [0, 1]
-    Ok((ssim + 1.0) / 2.0)
+    numerator / denominator
 }
 
 /// 帧差异算法 (简单的像素差异)
-fn frame_diff_similarity(img1: &DynamicImage, img2: &DynamicImage) -> Result<f64, String> {
-    let gray1 = img1.to_luma8();
-    let gray2 = img2.to_luma8();
-
+fn frame_diff_similarity(gray1: &GrayImage, gray2: &GrayImage) -> Result<f64, String> {
     if gray1.dimensions() != gray2.dimensions() {
         return Err("图片尺寸不匹配".to_string());
     }
@@ -166,3 +783,85 @@ fn frame_diff_similarity(img1: &DynamicImage, img2: &DynamicImage) -> Result<f64
 
     Ok(similarity)
 }
+
+/// 二值边缘图（边缘为 255）
+fn edge_map(gray: &GrayImage, params: EdgeParams) -> GrayImage {
+    match params.detector {
+        EdgeDetector::Canny => edges::canny(gray, params.low_threshold, params.high_threshold),
+        EdgeDetector::Sobel => {
+            let magnitudes = gradients::sobel_gradients(gray);
+            GrayImage::from_fn(gray.width(), gray.height(), |x, y| {
+                let edge = magnitudes.get_pixel(x, y)[0] as f32 >= params.low_threshold;
+                image::Luma([if edge { 255 } else { 0 }])
+            })
+        }
+    }
+}
+
+/// 边缘差异算法：比较两幅画面的边缘位置，只看构图轮廓，不受整体亮度闪烁影响
+///
+/// 两边的边缘点落在对方边缘附近（`tolerance` 像素内）的比例即相似度；两幅都没有边缘时视为相同
+fn edge_similarity(gray1: &GrayImage, gray2: &GrayImage, params: EdgeParams) -> Result<f64, String> {
+    if gray1.dimensions() != gray2.dimensions() {
+        return Err("图片尺寸不匹配".to_string());
+    }
+
+    let edges1 = edge_map(gray1, params);
+    let edges2 = edge_map(gray2, params);
+    let near1 = morphology::dilate(&edges1, Norm::LInf, params.tolerance);
+    let near2 = morphology::dilate(&edges2, Norm::LInf, params.tolerance);
+
+    let mut total = 0u64;
+    let mut matched = 0u64;
+    for (edges, near) in [(&edges1, &near2), (&edges2, &near1)] {
+        for (p, n) in edges.pixels().zip(near.pixels()) {
+            if p[0] > 0 {
+                total += 1;
+                if n[0] > 0 {
+                    matched += 1;
+                }
+            }
+        }
+    }
+
+    if total == 0 {
+        return Ok(1.0);
+    }
+    Ok(matched as f64 / total as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_bins_are_normalized() {
+        assert_eq!(normalized_histogram([0u8, 128, 255, 255].into_iter(), 2, 4.0), vec![0.25, 0.75]);
+    }
+
+    #[test]
+    fn histogram_comparisons_range_from_zero_to_one() {
+        let same = [0.1, 0.2, 0.7];
+        assert!((bhattacharyya(&same, &same) - 1.0).abs() < 1e-9);
+        assert!((correlation(&same, &same) - 1.0).abs() < 1e-9);
+        assert!((chi_square(&same, &same) - 1.0).abs() < 1e-9);
+
+        let (a, b) = ([1.0, 0.0], [0.0, 1.0]);
+        assert_eq!(bhattacharyya(&a, &b), 0.0);
+        assert!(correlation(&a, &b).abs() < 1e-9);
+        assert_eq!(chi_square(&a, &b), 0.0);
+    }
+
+    #[test]
+    fn constant_histograms_correlate_only_when_equal() {
+        assert_eq!(correlation(&[0.5, 0.5], &[0.5, 0.5]), 1.0);
+        assert_eq!(correlation(&[0.5, 0.5], &[0.25, 0.25]), 0.0);
+    }
+
+    #[test]
+    fn hsv_components_scale_to_bytes() {
+        assert_eq!(rgb_to_hsv(&image::Rgb([255, 0, 0])), [0, 255, 255]);
+        assert_eq!(rgb_to_hsv(&image::Rgb([0, 255, 0])), [85, 255, 255]);
+        assert_eq!(rgb_to_hsv(&image::Rgb([0, 0, 0])), [0, 0, 0]);
+    }
+}
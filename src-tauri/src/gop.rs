@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+use ts_rs::TS;
+use crate::settings::SettingsManager;
+use crate::video_processor::VideoCodec;
+
+/// 帧率未知时按该帧率换算关键帧间隔
+const FALLBACK_FPS: f64 = 30.0;
+
+/// 关键帧（GOP）设置，各项为空时使用编码器默认值
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct GopSettings {
+    /// 关键帧间隔（秒），对应 `-g`；如 YouTube 建议 2 秒
+    #[serde(default)]
+    pub keyframe_interval: Option<f64>,
+    /// 最小关键帧间隔（秒），对应 `keyint_min`
+    #[serde(default)]
+    pub min_keyframe_interval: Option<f64>,
+    /// 关闭场景切换时自动插入关键帧，使 GOP 严格固定
+    #[serde(default)]
+    pub disable_scene_cut: bool,
+}
+
+impl GopSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.keyframe_interval.is_some_and(|s| s <= 0.0) {
+            return Err("关键帧间隔必须大于 0".to_string());
+        }
+        if self.min_keyframe_interval.is_some_and(|s| s <= 0.0) {
+            return Err("最小关键帧间隔必须大于 0".to_string());
+        }
+        if let (Some(interval), Some(min)) = (self.keyframe_interval, self.min_keyframe_interval) {
+            if min > interval {
+                return Err("最小关键帧间隔不能大于关键帧间隔".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// 中间文件缓存目录名的后缀，关键帧设置不同的中间文件分开存放
+    pub fn key_suffix(&self) -> String {
+        let mut suffix = String::new();
+        if let Some(interval) = self.keyframe_interval {
+            suffix.push_str(&format!("_g{}", interval));
+        }
+        if let Some(min) = self.min_keyframe_interval {
+            suffix.push_str(&format!("_kmin{}", min));
+        }
+        if self.disable_scene_cut {
+            suffix.push_str("_nosc");
+        }
+        suffix
+    }
+
+    /// 编码参数：间隔按输出帧率换算为帧数，另外按时间强制关键帧，可变帧率的输出也能保持固定的时间间隔
+    pub fn encoder_args(&self, codec: VideoCodec, fps: Option<f64>) -> Vec<String> {
+        let fps = fps.filter(|f| *f > 0.0).unwrap_or(FALLBACK_FPS);
+        let frames = |seconds: f64| ((seconds * fps).round() as u32).max(1).to_string();
+        let mut args = Vec::new();
+        if let Some(interval) = self.keyframe_interval {
+            args.extend([
+                "-g".to_string(),
+                frames(interval),
+                "-force_key_frames".to_string(),
+                format!("expr:gte(t,n_forced*{})", interval),
+            ]);
+        }
+        if let Some(min) = self.min_keyframe_interval {
+            args.extend(["-keyint_min".to_string(), frames(min)]);
+        }
+        if self.disable_scene_cut {
+            match codec {
+                VideoCodec::H264 => args.extend(["-sc_threshold".to_string(), "0".to_string()]),
+                VideoCodec::Hevc => args.extend(["-x265-params".to_string(), "scenecut=0".to_string()]),
+            }
+        }
+        args
+    }
+}
+
+/// 全局关键帧设置
+pub fn current(app: &AppHandle) -> GopSettings {
+    app.state::<SettingsManager>().get().gop
+}
+
+// 保存全局关键帧设置（拼接、拆解与中间文件统一使用，输出规格可单独覆盖）
+#[tauri::command]
+pub fn save_gop_settings(settings: State<'_, SettingsManager>, gop: GopSettings) -> Result<(), String> {
+    gop.validate()?;
+    settings.update(|s| {
+        s.gop = gop;
+        Ok(())
+    })?;
+    Ok(())
+}
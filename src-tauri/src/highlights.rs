@@ -0,0 +1,358 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_shell::ShellExt;
+use ts_rs::TS;
+use crate::batch::check_batch_signal;
+use crate::events::AUTO_SPLIT_PROGRESS;
+use crate::frame_similarity::{self, SimilarityAlgorithm};
+use crate::hw_encoder::EncoderChoice;
+use crate::i18n::{localize_error, progress};
+use crate::mezzanine::concat_copy;
+use crate::path_scope::{check_input, check_output};
+use crate::stats::record_operation;
+use crate::video_frame_extractor::{
+    detect_scenes, generate_video_segments_internal, DetectionOptions, SceneDetection, SegmentExport, SegmentRange,
+};
+use crate::video_processor::get_video_info;
+
+/// 响度分析的采样率（只关心能量，不需要高采样率）
+const LOUDNESS_SAMPLE_RATE: usize = 2000;
+/// 完全静音时的响度下限（dBFS）
+const SILENCE_DBFS: f64 = -90.0;
+
+/// 集锦的选取上限
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum HighlightLimit {
+    /// 得分最高的 N 个片段
+    TopCount { count: usize },
+    /// 按得分从高到低选取，总时长不超过指定分钟数
+    TopMinutes { minutes: f64 },
+}
+
+fn default_target_duration() -> f64 {
+    8.0
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+/// 精彩集锦设置
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct HighlightOptions {
+    pub limit: HighlightLimit,
+    /// 理想的片段时长（秒），越接近得分越高
+    #[serde(default = "default_target_duration")]
+    pub target_duration: f64,
+    /// 画面运动强度的权重
+    #[serde(default = "default_weight")]
+    pub motion_weight: f64,
+    /// 响度的权重
+    #[serde(default = "default_weight")]
+    pub loudness_weight: f64,
+    /// 时长匹配度的权重
+    #[serde(default = "default_weight")]
+    pub duration_weight: f64,
+    /// 直接拼接成一个集锦视频（不保留单独的片段）
+    #[serde(default)]
+    pub concat: bool,
+}
+
+impl HighlightOptions {
+    fn validate(&self) -> Result<(), String> {
+        match self.limit {
+            HighlightLimit::TopCount { count: 0 } => {
+                return Err("集锦片段数必须大于 0".to_string());
+            }
+            HighlightLimit::TopMinutes { minutes } if minutes <= 0.0 => {
+                return Err("集锦总时长必须大于 0".to_string());
+            }
+            _ => {}
+        }
+        if self.target_duration <= 0.0 {
+            return Err("理想片段时长必须大于 0".to_string());
+        }
+        let weights = [self.motion_weight, self.loudness_weight, self.duration_weight];
+        if weights.iter().any(|w| *w < 0.0) || weights.iter().sum::<f64>() <= 0.0 {
+            return Err("评分权重不能为负，且至少有一项大于 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// 片段的起止时间（秒）
+fn segment_span(detection: &SceneDetection, segment: &SegmentRange) -> (f64, f64) {
+    let start = detection.timestamps[detection.local_index(segment.start_frame)];
+    let end = detection
+        .timestamps
+        .get(detection.local_index(segment.end_frame) + 1)
+        .copied()
+        .unwrap_or(detection.duration);
+    (start, end.max(start))
+}
+
+/// 运动强度：片段内相邻帧的平均差异（不含与上一片段的切换处）
+fn motion(detection: &SceneDetection, segment: &SegmentRange) -> f64 {
+    let start = detection.local_index(segment.start_frame);
+    let end = detection.local_index(segment.end_frame);
+    let diffs: Vec<f64> = detection.similarities[start..end.min(detection.similarities.len())]
+        .iter()
+        .map(|s| 1.0 - s)
+        .collect();
+    if diffs.is_empty() {
+        0.0
+    } else {
+        diffs.iter().sum::<f64>() / diffs.len() as f64
+    }
+}
+
+/// 解码音轨为低采样率的单声道采样
+async fn decode_loudness_samples(app: &AppHandle, video_path: &Path, audio_stream: usize) -> Result<Vec<f32>, String> {
+    let sidecar = app
+        .shell()
+        .sidecar("ffmpeg")
+        .map_err(|e| format!("FFmpeg 启动失败: {}", e))?;
+
+    let output = sidecar
+        .args([
+            "-v",
+            "error",
+            "-i",
+            &video_path.to_string_lossy(),
+            "-map",
+            &format!("0:a:{}", audio_stream),
+            "-ac",
+            "1",
+            "-ar",
+            &LOUDNESS_SAMPLE_RATE.to_string(),
+            "-f",
+            "f32le",
+            "-",
+        ])
+        .output()
+        .await
+        .map_err(|e| format!("FFmpeg 执行失败: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "解码音轨失败: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(output
+        .stdout
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect())
+}
+
+/// 时间范围内的均方根响度（dBFS）
+fn rms_dbfs(samples: &[f32], start: f64, end: f64) -> f64 {
+    let from = ((start * LOUDNESS_SAMPLE_RATE as f64) as usize).min(samples.len());
+    let to = ((end * LOUDNESS_SAMPLE_RATE as f64) as usize).clamp(from, samples.len());
+    if to == from {
+        return SILENCE_DBFS;
+    }
+    let mean_square = samples[from..to].iter().map(|s| (*s as f64).powi(2)).sum::<f64>() / (to - from) as f64;
+    (10.0 * mean_square.log10()).max(SILENCE_DBFS)
+}
+
+/// 归一化到 0-1（所有值相同时都记为 0，不影响排序）
+fn normalize(values: &[f64]) -> Vec<f64> {
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    values
+        .iter()
+        .map(|v| if max > min { (v - min) / (max - min) } else { 0.0 })
+        .collect()
+}
+
+/// 按综合得分选取片段，返回按时间顺序排列的片段
+fn select_highlights(
+    segments: &[SegmentRange],
+    spans: &[(f64, f64)],
+    scores: &[f64],
+    limit: HighlightLimit,
+) -> Vec<SegmentRange> {
+    let mut ranked: Vec<usize> = (0..segments.len()).collect();
+    ranked.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+
+    let mut chosen = match limit {
+        HighlightLimit::TopCount { count } => ranked.into_iter().take(count).collect::<Vec<_>>(),
+        HighlightLimit::TopMinutes { minutes } => {
+            // 放不下的高分片段跳过，继续尝试更短的低分片段
+            let budget = minutes * 60.0;
+            let mut total = 0.0;
+            ranked
+                .into_iter()
+                .filter(|&i| {
+                    let duration = spans[i].1 - spans[i].0;
+                    if total + duration <= budget {
+                        total += duration;
+                        true
+                    } else {
+                        false
+                    }
+                })
+                .collect()
+        }
+    };
+    chosen.sort_unstable();
+    chosen.into_iter().map(|i| segments[i].clone()).collect()
+}
+
+// 精彩集锦：检测场景后按运动强度、响度与时长匹配度评分，导出得分最高的片段
+#[tauri::command]
+pub async fn extract_highlights(
+    app: AppHandle,
+    video_path: String,
+    output_dir: String,
+    algorithm: String,
+    threshold: f64,
+    min_duration: f64,
+    options: HighlightOptions,
+) -> Result<String, String> {
+    let result = extract_highlights_internal(&app, video_path, output_dir, algorithm, threshold, min_duration, options).await;
+    record_operation(&app, "highlights", &result);
+    result.map_err(|e| localize_error(&app, e))
+}
+
+async fn extract_highlights_internal(
+    app: &AppHandle,
+    video_path: String,
+    output_dir: String,
+    algorithm: String,
+    threshold: f64,
+    min_duration: f64,
+    options: HighlightOptions,
+) -> Result<String, String> {
+    check_input(app, &video_path)?;
+    check_output(app, &output_dir)?;
+    options.validate()?;
+    let window = app
+        .get_webview_window("main")
+        .ok_or("无法获取窗口")?;
+
+    let algo = SimilarityAlgorithm::from_name(&algorithm, &frame_similarity::current(app))?;
+    let detection = detect_scenes(
+        app,
+        &window,
+        &video_path,
+        DetectionOptions {
+            algorithm: algo,
+            threshold,
+            min_duration,
+            range: None,
+            hysteresis: None,
+            flash_filter: None,
+        },
+    )
+    .await?;
+    let spans: Vec<(f64, f64)> = detection
+        .segments
+        .iter()
+        .map(|segment| segment_span(&detection, segment))
+        .collect();
+
+    // 响度：没有音轨时所有片段同分
+    let _ = window.emit(AUTO_SPLIT_PROGRESS, progress(app, "highlights.scoring", &[], 70));
+    let info = get_video_info(app, Path::new(&video_path)).await?;
+    let samples = if info.has_audio {
+        decode_loudness_samples(app, Path::new(&video_path), info.audio_stream_index).await?
+    } else {
+        Vec::new()
+    };
+
+    let motion = normalize(
+        &detection
+            .segments
+            .iter()
+            .map(|segment| motion(&detection, segment))
+            .collect::<Vec<_>>(),
+    );
+    let loudness = normalize(&spans.iter().map(|&(start, end)| rms_dbfs(&samples, start, end)).collect::<Vec<_>>());
+    let weight_sum = options.motion_weight + options.loudness_weight + options.duration_weight;
+    let scores: Vec<f64> = spans
+        .iter()
+        .enumerate()
+        .map(|(i, &(start, end))| {
+            // 时长匹配度：与理想时长之比（取较小者比较大者）
+            let duration = end - start;
+            let fit = if duration > 0.0 {
+                (duration / options.target_duration).min(options.target_duration / duration)
+            } else {
+                0.0
+            };
+            (options.motion_weight * motion[i] + options.loudness_weight * loudness[i] + options.duration_weight * fit)
+                / weight_sum
+        })
+        .collect();
+
+    let highlights = select_highlights(&detection.segments, &spans, &scores, options.limit);
+    if highlights.is_empty() {
+        return Err("没有符合集锦时长上限的片段".to_string());
+    }
+    let total: f64 = highlights
+        .iter()
+        .map(|segment| {
+            let (start, end) = segment_span(&detection, segment);
+            end - start
+        })
+        .sum();
+    let _ = window.emit(
+        AUTO_SPLIT_PROGRESS,
+        progress(
+            app,
+            "highlights.selected",
+            &[detection.segments.len().to_string(), highlights.len().to_string(), format!("{:.1}", total)],
+            75,
+        ),
+    );
+
+    // 集锦放在输出目录的 highlights 子目录，与普通拆解的片段分开
+    check_batch_signal(app)?;
+    let highlight_dir = PathBuf::from(&output_dir).join("highlights");
+    let paths = generate_video_segments_internal(
+        app.clone(),
+        video_path.clone(),
+        highlights,
+        SegmentExport {
+            output_dir: highlight_dir.to_string_lossy().to_string(),
+            encoder: EncoderChoice::Software,
+            ..SegmentExport::default()
+        },
+    )
+    .await?;
+
+    let message = if options.concat {
+        let _ = window.emit(AUTO_SPLIT_PROGRESS, progress(app, "highlights.concatenating", &[], 95));
+        let stem = Path::new(&video_path)
+            .file_stem()
+            .ok_or("无法获取视频文件名")?
+            .to_string_lossy()
+            .to_string();
+        let reel = highlight_dir.join(format!("{}_highlights.mp4", stem));
+        // 片段来自同一视频且编码参数相同，可以直接流复制拼接
+        concat_copy(app, &paths, &reel, None).await?;
+        if let Some(dir) = paths.first().and_then(|p| p.parent()) {
+            let _ = fs::remove_dir_all(dir);
+        }
+        format!("已生成精彩集锦（{} 个片段，{:.1} 秒）: {}", paths.len(), total, reel.display())
+    } else {
+        format!(
+            "已导出 {} 个精彩片段（共 {:.1} 秒）到: {}",
+            paths.len(),
+            total,
+            paths.first().and_then(|p| p.parent()).map(|d| d.display().to_string()).unwrap_or_default()
+        )
+    };
+
+    let _ = window.emit(AUTO_SPLIT_PROGRESS, progress(app, "common.done", &[], 100));
+    Ok(message)
+}
@@ -0,0 +1,225 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_shell::ShellExt;
+use ts_rs::TS;
+use crate::video_processor::{EncodeSettings, VideoCodec};
+
+/// 视频编码器选择
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum EncoderChoice {
+    /// CPU 编码（libx264 / libx265）
+    #[default]
+    Software,
+    /// 自动选择本机可用的硬件编码器，都不可用时使用 CPU 编码
+    Auto,
+    /// NVIDIA NVENC
+    Nvenc,
+    /// Intel Quick Sync
+    Qsv,
+    /// macOS VideoToolbox
+    VideoToolbox,
+}
+
+/// 实际使用的编码器
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum HwEncoder {
+    #[default]
+    Software,
+    Nvenc,
+    Qsv,
+    VideoToolbox,
+}
+
+/// 自动选择时依次尝试的硬件编码器
+#[cfg(target_os = "macos")]
+const AUTO_ORDER: &[HwEncoder] = &[HwEncoder::VideoToolbox];
+#[cfg(not(target_os = "macos"))]
+const AUTO_ORDER: &[HwEncoder] = &[HwEncoder::Nvenc, HwEncoder::Qsv];
+
+impl HwEncoder {
+    /// FFmpeg 编码器名称
+    pub fn name(&self, codec: VideoCodec) -> &'static str {
+        match (self, codec) {
+            (Self::Software, codec) => codec.encoder(),
+            (Self::Nvenc, VideoCodec::H264) => "h264_nvenc",
+            (Self::Nvenc, VideoCodec::Hevc) => "hevc_nvenc",
+            (Self::Qsv, VideoCodec::H264) => "h264_qsv",
+            (Self::Qsv, VideoCodec::Hevc) => "hevc_qsv",
+            (Self::VideoToolbox, VideoCodec::H264) => "h264_videotoolbox",
+            (Self::VideoToolbox, VideoCodec::Hevc) => "hevc_videotoolbox",
+        }
+    }
+
+    /// 输出像素格式（QSV 只接受 nv12）
+    pub fn pix_fmt(&self) -> &'static str {
+        match self {
+            Self::Qsv => "nv12",
+            _ => "yuv420p",
+        }
+    }
+
+    /// 默认的编码器预设（VideoToolbox 没有预设）
+    fn default_preset(&self) -> Option<&'static str> {
+        match self {
+            Self::Software => Some("fast"),
+            Self::Nvenc => Some("p4"),
+            Self::Qsv => Some("medium"),
+            Self::VideoToolbox => None,
+        }
+    }
+
+    /// 编码器、预设与质量参数：指定视频码率时按码率编码；
+    /// 否则用恒定质量，硬件编码器没有 CRF，按各自的恒定质量模式换算
+    pub fn video_args(&self, codec: VideoCodec, crf: u32, settings: &EncodeSettings) -> Vec<String> {
+        let mut args = vec!["-c:v".to_string(), self.name(codec).to_string()];
+        if let Some(preset) = self.default_preset() {
+            args.push("-preset".to_string());
+            args.push(settings.preset.as_deref().unwrap_or(preset).to_string());
+        }
+        let rate: Vec<String> = match (self, settings.video_bitrate) {
+            (Self::Nvenc, Some(kbps)) => vec!["-rc".into(), "vbr".into(), "-b:v".into(), format!("{}k", kbps)],
+            (_, Some(kbps)) => vec!["-b:v".into(), format!("{}k", kbps)],
+            (Self::Software, None) => vec!["-crf".into(), crf.to_string()],
+            (Self::Nvenc, None) => vec![
+                "-rc".into(),
+                "vbr".into(),
+                "-cq".into(),
+                crf.to_string(),
+                "-b:v".into(),
+                "0".into(),
+            ],
+            (Self::Qsv, None) => vec!["-global_quality".into(), crf.to_string()],
+            // VideoToolbox 的质量为 1-100（越大越好），CRF 0-51 线性换算
+            (Self::VideoToolbox, None) => vec!["-q:v".into(), (100 - crf.min(51) * 99 / 51).to_string()],
+        };
+        args.extend(rate);
+        args
+    }
+}
+
+/// 按编码格式选定的编码器
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EncoderSelection {
+    pub h264: HwEncoder,
+    pub hevc: HwEncoder,
+}
+
+impl EncoderSelection {
+    pub fn for_codec(&self, codec: VideoCodec) -> HwEncoder {
+        match codec {
+            VideoCodec::H264 => self.h264,
+            VideoCodec::Hevc => self.hevc,
+        }
+    }
+}
+
+/// 硬件编码器检测结果缓存（按 FFmpeg 编码器名称），同一次运行内只检测一次
+pub struct EncoderDetector {
+    cache: Mutex<HashMap<&'static str, bool>>,
+}
+
+impl EncoderDetector {
+    pub fn new() -> Self {
+        Self {
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+/// 用一帧测试画面试编码，能成功输出才算可用（编译了编码器但没有对应显卡时会失败）
+async fn probe(app: &AppHandle, encoder: HwEncoder, codec: VideoCodec) -> bool {
+    if encoder == HwEncoder::Software {
+        return true;
+    }
+    let name = encoder.name(codec);
+    let detector = app.state::<EncoderDetector>();
+    if let Some(&available) = detector.cache.lock().unwrap().get(name) {
+        return available;
+    }
+
+    let available = match app.shell().sidecar("ffmpeg") {
+        Ok(sidecar) => sidecar
+            .args([
+                "-v",
+                "error",
+                "-f",
+                "lavfi",
+                "-i",
+                "color=c=black:s=256x256:d=0.1",
+                "-frames:v",
+                "1",
+                "-c:v",
+                name,
+                "-pix_fmt",
+                encoder.pix_fmt(),
+                "-f",
+                "null",
+                "-",
+            ])
+            .output()
+            .await
+            .is_ok_and(|output| output.status.success()),
+        Err(_) => false,
+    };
+    detector.cache.lock().unwrap().insert(name, available);
+    available
+}
+
+/// 按选择确定某个编码格式使用的编码器，指定的硬件编码器不可用时退回 CPU 编码
+pub async fn resolve(app: &AppHandle, choice: EncoderChoice, codec: VideoCodec) -> HwEncoder {
+    let candidates: &[HwEncoder] = match choice {
+        EncoderChoice::Software => &[],
+        EncoderChoice::Auto => AUTO_ORDER,
+        EncoderChoice::Nvenc => &[HwEncoder::Nvenc],
+        EncoderChoice::Qsv => &[HwEncoder::Qsv],
+        EncoderChoice::VideoToolbox => &[HwEncoder::VideoToolbox],
+    };
+    for &encoder in candidates {
+        if probe(app, encoder, codec).await {
+            return encoder;
+        }
+    }
+    HwEncoder::Software
+}
+
+/// 为 H.264 与 HEVC 分别确定编码器（只在选择硬件编码时检测）
+pub async fn select(app: &AppHandle, choice: EncoderChoice) -> EncoderSelection {
+    EncoderSelection {
+        h264: resolve(app, choice, VideoCodec::H264).await,
+        hevc: resolve(app, choice, VideoCodec::Hevc).await,
+    }
+}
+
+/// 某个编码格式下可用的编码器
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct AvailableEncoder {
+    pub encoder: HwEncoder,
+    pub codec: VideoCodec,
+    /// FFmpeg 编码器名称
+    pub name: String,
+}
+
+// 检测本机可用的硬件编码器（CPU 编码始终可用，不在列表中）
+#[tauri::command]
+pub async fn list_hw_encoders(app: AppHandle) -> Result<Vec<AvailableEncoder>, String> {
+    let mut available = Vec::new();
+    for codec in [VideoCodec::H264, VideoCodec::Hevc] {
+        for encoder in [HwEncoder::Nvenc, HwEncoder::Qsv, HwEncoder::VideoToolbox] {
+            if probe(&app, encoder, codec).await {
+                available.push(AvailableEncoder {
+                    encoder,
+                    codec,
+                    name: encoder.name(codec).to_string(),
+                });
+            }
+        }
+    }
+    Ok(available)
+}
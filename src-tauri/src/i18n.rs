@@ -0,0 +1,404 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+use ts_rs::TS;
+use crate::events::ProgressEvent;
+use crate::settings::SettingsManager;
+
+/// 界面语言
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub enum Locale {
+    #[default]
+    #[serde(rename = "zh-CN")]
+    ZhCn,
+    #[serde(rename = "en")]
+    En,
+}
+
+/// 消息目录：(key, 中文模板, 英文模板)，模板中的 {} 依次替换为参数
+const CATALOG: &[(&str, &str, &str)] = &[
+    // 进度
+    ("common.done", "完成", "Done"),
+    ("audio.analyzing", "正在分析音频 {}/{}: {}", "Analyzing audio {}/{}: {}"),
+    ("catalog.generating", "正在截取缩略图 {}/{}: {}", "Capturing thumbnails {}/{}: {}"),
+    ("frames.extracting", "正在提取视频帧...", "Extracting frames..."),
+    ("frames.resuming", "从第 {} 帧继续提取视频帧...", "Resuming frame extraction from frame {}..."),
+    ("frames.extracting_progress", "正在提取视频帧 {}/{}", "Extracting frames {}/{}"),
+    ("frames.scanned", "已提取 {}/{} 帧", "Extracted {}/{} frames"),
+    ("frames.done", "帧提取完成", "Frame extraction complete"),
+    ("split.analyzing", "正在分析帧相似度...", "Analyzing frame similarity..."),
+    ("split.analyzed", "已分析 {}/{} 帧", "Analyzed {}/{} frames"),
+    ("split.segments_found", "识别到 {} 个片段，过滤后输出 {} 个", "Detected {} segments, {} kept after filtering"),
+    ("split.generating", "正在生成视频片段...", "Generating segments..."),
+    ("highlights.scoring", "正在为片段评分...", "Scoring segments..."),
+    (
+        "highlights.selected",
+        "共 {} 个片段，选出 {} 个，总时长 {} 秒",
+        "{} segments detected, {} selected, {}s in total",
+    ),
+    ("highlights.concatenating", "正在拼接精彩集锦...", "Concatenating highlight reel..."),
+    (
+        "ending.segments_found",
+        "识别到 {} 个片段，移除最后一个后剩余 {} 个",
+        "Detected {} segments, {} left after removing the last one",
+    ),
+    ("ending.generating_temp", "正在生成临时片段...", "Generating temporary segments..."),
+    ("ending.generating_temp_progress", "正在生成临时片段 {}/{}", "Generating temporary segment {}/{}"),
+    ("ending.checking", "正在检测视频兼容性...", "Checking video compatibility..."),
+    ("ending.composing", "正在合成视频...", "Composing video..."),
+    ("ending.appending", "正在处理 {}/{}：{}", "Processing {}/{}: {}"),
+    ("outro.learning", "正在分析示例片尾 {}/{}", "Analyzing example outro {}/{}"),
+    ("outro.matching", "正在匹配片尾 {}/{}：{}", "Matching outro {}/{}: {}"),
+    (
+        "ending.unsure_kept",
+        "无法确认最后一个片段是片尾（{}），保留该片段",
+        "Could not confirm the last segment is an outro ({}); keeping it",
+    ),
+    (
+        "ending.direct_fallback",
+        "片段数 {} 超过直接裁剪的上限 {}，改为先生成临时片段",
+        "{} segments exceed the single-pass limit of {}; generating temporary segments instead",
+    ),
+    (
+        "ending.audio_join_repeat",
+        "新结尾开头的声音与上一片段末尾重复，末尾裁掉 {} 帧（{}ms）",
+        "New ending repeats the audio at the end of the previous segment; trimmed {} frames ({}ms)",
+    ),
+    (
+        "ending.audio_join_click",
+        "接缝处音频不连续，上一片段末尾裁掉 {} 帧（{}ms）以避免爆音",
+        "Audio is discontinuous at the join; trimmed {} frames ({}ms) from the previous segment to avoid a click",
+    ),
+    (
+        "overlay.no_alpha",
+        "水印素材没有透明通道（{} {}），将以不透明矩形叠加",
+        "Watermark asset has no alpha channel ({} {}); it will be overlaid as an opaque rectangle",
+    ),
+    ("benchmark.generating", "正在生成测试片段...", "Generating test clip..."),
+    ("benchmark.testing", "正在测试 {} {} ({}/{})", "Testing {} {} ({}/{})"),
+    ("download.attempt_failed", "{} (第 {} 次): {}", "{} (attempt {}): {}"),
+    ("download.summary", "下载完成！成功: {}, 失败: {}", "Download finished! Succeeded: {}, failed: {}"),
+    ("download.summary_disk_full", "，磁盘空间不足，已停止后续下载", "; disk full, remaining downloads stopped"),
+    ("download.summary_mirrors", "，其中 {} 个使用备用地址", "; {} used a mirror"),
+    ("download.summary_queued", "，已加入处理队列: {}", "; queued for processing: {}"),
+    ("plan.exists", "目标文件已存在，将被覆盖", "Target file exists and will be overwritten"),
+    ("plan.not_mp4", "文件名不是 .mp4: {}", "File name is not .mp4: {}"),
+    ("plan.mirror", "主地址不可用，将使用镜像: {}", "Primary URL unavailable, will use mirror: {}"),
+    ("plan.not_video", "内容类型不是视频: {}", "Content type is not video: {}"),
+    ("plan.no_size", "服务器未返回文件大小", "Server did not report the file size"),
+    ("plan.duplicate_url", "URL 重复 {} 次: {}", "URL repeated {} times: {}"),
+    (
+        "plan.same_filename",
+        "与其他 URL 解析出相同的文件名，后下载的会覆盖先下载的",
+        "Resolves to the same file name as another URL; the later download overwrites the earlier one",
+    ),
+    ("plan.filename_conflict", "多个 URL 将写入同一文件: {}", "Multiple URLs will write to the same file: {}"),
+    ("plan.failed", "{} 个 URL 预检失败", "{} URLs failed the pre-check"),
+    ("concat.scanning", "正在扫描视频文件...", "Scanning video files..."),
+    ("concat.filtering", "正在按质量条件筛选视频...", "Filtering videos by quality..."),
+    (
+        "concat.tag_quota_short",
+        "池子中只有 {} 个带标签 {} 的片段，少于配额 {}",
+        "Only {} clips in the pool are tagged {}, fewer than the quota of {}",
+    ),
+    (
+        "concat.excluded",
+        "已排除 {} 个不符合质量或标签条件的视频",
+        "Excluded {} videos that do not meet the quality or tag conditions",
+    ),
+    ("concat.probing_intro", "正在检测片头视频...", "Checking intro video..."),
+    ("concat.probing_ending", "正在检测结尾视频...", "Checking ending video..."),
+    ("concat.probing_interstitials", "正在检测插播视频...", "Checking interstitial videos..."),
+    ("concat.intro_note", "片头: {}", "Intro: {}"),
+    ("concat.ending_note", "结尾: {}", "Ending: {}"),
+    ("concat.detecting_beats", "正在检测背景音乐节拍...", "Detecting background music beats..."),
+    ("concat.parallel", "共 {} 次输出，同时编码 {} 个", "{} outputs, encoding {} at a time"),
+    (
+        "concat.target_short",
+        "第 {}/{} 次：池子剩余片段不足，本次选择 {} 个视频共 {}s，未达到目标时长 {}s",
+        "Run {}/{}: not enough clips left in the pool; selected {} videos totalling {}s, short of the {}s target",
+    ),
+    ("concat.selected_duration", "第 {}/{} 次：已选择 {} 个视频，共 {}s", "Run {}/{}: selected {} videos, {}s in total"),
+    (
+        "concat.not_enough",
+        "第 {}/{} 次：请求 {} 个视频，但只找到 {} 个，将使用全部 {} 个视频",
+        "Run {}/{}: requested {} videos but only found {}; using all {}",
+    ),
+    (
+        "concat.pool_refilled",
+        "第 {}/{} 次：池子已抽完，重新填充。本次选择 {} 个视频",
+        "Run {}/{}: pool exhausted and refilled; selected {} videos",
+    ),
+    ("concat.selected", "第 {}/{} 次：已选择 {} 个视频（池子剩余 {}）", "Run {}/{}: selected {} videos ({} left in the pool)"),
+    ("concat.ordering", "第 {}/{} 次：正在按 {} 排列片段...", "Run {}/{}: ordering clips by {}..."),
+    ("concat.ending_added", "已添加结尾视频", "Ending video added"),
+    ("concat.ending_added_named", "已添加结尾视频: {}", "Ending video added: {}"),
+    ("concat.checking", "第 {}/{} 次：正在检测视频兼容性...", "Run {}/{}: checking video compatibility..."),
+    ("concat.beats_aligned", "第 {}/{} 次：{} 个片段已对齐到节拍", "Run {}/{}: {} clips aligned to the beat"),
+    (
+        "concat.duration_capped",
+        "第 {}/{} 次：为不超过时长上限 {}s 少用 {} 个片段（成片 {}s）",
+        "Run {}/{}: kept under the {}s limit by dropping {} clips ({}s output)",
+    ),
+    ("concat.interstitials_inserted", "第 {}/{} 次：已插入 {} 个插播片段", "Run {}/{}: inserted {} interstitial clips"),
+    (
+        "concat.long_clip_part",
+        "第 {}/{} 次：{} 单个时长 {}s 超过分段上限 {}s，将单独成段",
+        "Run {}/{}: {} is {}s long, over the {}s part limit; it becomes its own part",
+    ),
+    (
+        "concat.fallback_reencode",
+        "第 {}/{} 次：{}，改为重编码拼接",
+        "Run {}/{}: {}; falling back to re-encoding",
+    ),
+    (
+        "concat.encoding",
+        "第 {}/{} 次：正在拼接视频（{}，统一重编码以保证同步）...",
+        "Run {}/{}: concatenating videos ({}, re-encoding to keep sync)...",
+    ),
+    (
+        "concat.encoding_part",
+        "第 {}/{} 次：第 {}/{} 段，正在拼接视频（{}，统一重编码以保证同步）...",
+        "Run {}/{}: part {}/{}, concatenating videos ({}, re-encoding to keep sync)...",
+    ),
+    ("concat.encode_label", "第 {}/{} 次：拼接视频", "Run {}/{}: concatenation"),
+    ("concat.encode_label_part", "第 {}/{} 次：第 {}/{} 段，拼接视频", "Run {}/{}: part {}/{}, concatenation"),
+    (
+        "concat.preparing_mezzanine",
+        "第 {}/{} 次：正在准备中间文件 {}/{}: {}",
+        "Run {}/{}: preparing intermediate file {}/{}: {}",
+    ),
+    ("concat.copy_concat", "第 {}/{} 次：正在流复制拼接中间文件...", "Run {}/{}: stream-copying intermediate files..."),
+    ("concat.checking_joins", "第 {}/{} 次：正在检查 {} 处接缝...", "Run {}/{}: checking {} joins..."),
+    ("concat.run_done", "第 {}/{} 次：拼接完成", "Run {}/{}: concatenation complete"),
+    ("concat.retrying", "正在重试第 {}/{} 次输出...", "Retrying output {}/{}..."),
+    (
+        "concat.run_failed",
+        "第 {}/{} 次失败，继续生成其余输出：{}",
+        "Run {}/{} failed; continuing with the remaining outputs: {}",
+    ),
+    ("concat.done", "完成！", "Done!"),
+    // 放在其他逐次消息之后，避免反向匹配时先匹配到这条
+    ("concat.run_note", "第 {}/{} 次：{}", "Run {}/{}: {}"),
+    // 错误
+    ("error.ffmpeg_start", "FFmpeg 启动失败: {}", "Failed to start FFmpeg: {}"),
+    ("error.ffmpeg_run", "FFmpeg 执行失败: {}", "FFmpeg failed: {}"),
+    ("error.ffprobe_start", "FFprobe 启动失败: {}", "Failed to start FFprobe: {}"),
+    ("error.ffprobe_run", "FFprobe 执行失败: {}", "FFprobe failed: {}"),
+    ("error.extract_frames", "提取帧失败: {}", "Frame extraction failed: {}"),
+    ("error.generate_segment", "生成片段 {} 失败: {}", "Failed to generate segment {}: {}"),
+    ("error.generate_temp_segment", "生成临时片段 {} 失败: {}", "Failed to generate temporary segment {}: {}"),
+    ("error.job_failed", "任务执行失败: {}", "Job failed: {}"),
+    ("error.create_temp_dir", "创建临时目录失败: {}", "Failed to create temporary directory: {}"),
+    ("error.create_output_dir", "创建输出目录失败: {}", "Failed to create output directory: {}"),
+    ("error.read_video", "读取视频失败: {}", "Failed to read video: {}"),
+    ("error.open_video", "打开视频失败: {}", "Failed to open video: {}"),
+    ("error.read_progress", "读取进度文件失败: {}", "Failed to read progress file: {}"),
+    ("error.parse_progress", "解析进度文件失败: {}", "Failed to parse progress file: {}"),
+    ("error.write_progress", "写入进度文件失败: {}", "Failed to write progress file: {}"),
+    ("error.request", "请求失败: {}", "Request failed: {}"),
+    ("error.http", "HTTP 错误: {}", "HTTP error: {}"),
+    ("error.download_data", "下载数据失败: {}", "Failed to download data: {}"),
+    ("error.disk_full", "磁盘空间不足，已跳过", "Disk full, skipped"),
+    ("error.disk_full_io", "磁盘空间不足，{}", "Disk full: {}"),
+    ("error.download_url_empty", "下载地址为空", "Download URL is empty"),
+    ("error.create_file", "创建文件失败: {}", "Failed to create file: {}"),
+    ("error.write_file", "写入文件失败: {}", "Failed to write file: {}"),
+    ("error.flush_file", "刷新文件失败: {}", "Failed to flush file: {}"),
+    ("error.sync_file", "同步文件失败: {}", "Failed to sync file: {}"),
+    ("error.rename_file", "重命名文件失败: {}", "Failed to rename file: {}"),
+    (
+        "error.incomplete_download",
+        "文件不完整: 已下载 {} 字节，应为 {} 字节",
+        "Incomplete file: downloaded {} bytes, expected {}",
+    ),
+    ("error.not_enough_frames", "视频帧数不足", "Not enough frames in video"),
+    ("error.no_scene_change", "未检测到场景切换，无法拆分", "No scene change detected, cannot split"),
+    (
+        "error.no_scene_change_threshold",
+        "未检测到场景切换（相似度始终高于阈值）",
+        "No scene change detected (similarity always above threshold)",
+    ),
+    ("error.invalid_segment", "片段 {} 的帧范围无效", "Invalid frame range for segment {}"),
+    ("error.unknown_algorithm", "未知的算法: {}", "Unknown algorithm: {}"),
+    ("error.highlight_budget", "没有符合集锦时长上限的片段", "No segment fits within the highlight duration limit"),
+    ("error.input_dir_empty", "输入目录不能为空", "Input directory is required"),
+    ("error.output_dir_empty", "输出目录不能为空", "Output directory is required"),
+    ("error.random_count_zero", "随机数量必须大于 0", "Random count must be greater than 0"),
+    ("error.random_count_range", "随机数量范围不合法", "Invalid random count range"),
+    ("error.run_times_zero", "执行次数必须大于 0", "Run count must be greater than 0"),
+    ("error.dir_not_found", "目录不存在: {}", "Directory not found: {}"),
+    ("error.not_a_dir", "路径不是目录: {}", "Path is not a directory: {}"),
+    ("error.no_mp4", "在目录中未找到 MP4 文件: {}", "No MP4 files found in directory: {}"),
+    ("error.no_matching_mp4", "在目录中未找到符合条件的 MP4 文件: {}", "No matching MP4 files found in directory: {}"),
+    ("error.intro_not_found", "片头视频不存在: {}", "Intro video not found: {}"),
+    ("error.ending_not_found", "结尾视频不存在: {}", "Ending video not found: {}"),
+    ("error.interstitial_not_found", "插播视频不存在: {}", "Interstitial video not found: {}"),
+    ("error.new_ending_not_found", "新结尾视频不存在: {}", "New ending video not found: {}"),
+    ("error.watermark_not_found", "水印图片不存在: {}", "Watermark image not found: {}"),
+    ("error.bgm_not_found", "背景音乐不存在: {}", "Background music not found: {}"),
+    ("error.brand_kit_not_found", "品牌包不存在: {}", "Brand kit not found: {}"),
+    ("error.max_part_duration", "分段最大时长必须大于 0", "Maximum part duration must be greater than 0"),
+    ("error.variant_height", "输出规格的高度必须大于 0", "Output variant height must be greater than 0"),
+    (
+        "error.variant_names",
+        "多个输出规格的名称不能为空且不能重复",
+        "Output variant names must be non-empty and unique",
+    ),
+    ("error.batch_running", "已有批量任务正在运行", "A batch is already running"),
+    ("error.no_batch", "当前没有正在运行的批量任务", "No batch is running"),
+    ("error.batch_skipped", "已跳过当前视频", "Current video skipped"),
+    ("error.batch_aborted", "批量任务已中止", "Batch aborted"),
+    (
+        "path.not_approved",
+        "没有访问该路径的权限，请通过选择对话框重新选择: {}",
+        "No permission to access this path; please select it again with the file dialog: {}",
+    ),
+    ("path.not_absolute", "路径必须是绝对路径: {}", "Path must be absolute: {}"),
+    ("path.traversal", "路径不能包含 ..: {}", "Path must not contain ..: {}"),
+    ("path.not_found", "路径不存在: {}", "Path not found: {}"),
+    ("path.invalid", "无法解析路径: {}", "Cannot resolve path: {}"),
+];
+
+/// 按模板匹配文本，返回各个 {} 位置的参数
+fn match_template(template: &str, text: &str) -> Option<Vec<String>> {
+    let pieces: Vec<&str> = template.split("{}").collect();
+    let mut rest = text.strip_prefix(pieces[0])?;
+    if pieces.len() == 1 {
+        return rest.is_empty().then(Vec::new);
+    }
+
+    let mut params = Vec::new();
+    for (i, piece) in pieces.iter().enumerate().skip(1) {
+        if i == pieces.len() - 1 {
+            // 最后一个参数一直取到结尾
+            params.push(rest.strip_suffix(piece)?.to_string());
+        } else {
+            // 两个参数之间没有分隔时无法确定边界
+            if piece.is_empty() {
+                return None;
+            }
+            let pos = rest.find(piece)?;
+            params.push(rest[..pos].to_string());
+            rest = &rest[pos + piece.len()..];
+        }
+    }
+    Some(params)
+}
+
+fn fill(template: &str, params: &[String]) -> String {
+    let mut result = String::new();
+    for (i, piece) in template.split("{}").enumerate() {
+        if i > 0 {
+            result.push_str(params.get(i - 1).map(String::as_str).unwrap_or(""));
+        }
+        result.push_str(piece);
+    }
+    result
+}
+
+fn template(locale: Locale, key: &str) -> Option<&'static str> {
+    CATALOG
+        .iter()
+        .find(|(k, _, _)| *k == key)
+        .map(|(_, zh, en)| match locale {
+            Locale::ZhCn => *zh,
+            Locale::En => *en,
+        })
+}
+
+pub fn current_locale(app: &AppHandle) -> Locale {
+    app.state::<SettingsManager>().get().locale
+}
+
+/// 按当前语言生成消息；目录中没有该 key 时返回 key 本身
+pub fn t(app: &AppHandle, key: &str, params: &[String]) -> String {
+    template(current_locale(app), key)
+        .map(|t| fill(t, params))
+        .unwrap_or_else(|| key.to_string())
+}
+
+/// 进度事件内容：附带 message_key 与参数，前端也可以自行翻译
+pub fn progress(app: &AppHandle, key: &str, params: &[String], percent: u32) -> ProgressEvent {
+    ProgressEvent {
+        message: t(app, key, params),
+        message_key: key.to_string(),
+        params: params.to_vec(),
+        percent,
+    }
+}
+
+/// 把中文消息翻译为英文
+fn translate(text: &str) -> String {
+    for (_, zh, en) in CATALOG {
+        if let Some(mut params) = match_template(zh, text) {
+            // 最后一个参数通常是下一层的错误信息
+            if let Some(last) = params.last_mut() {
+                *last = translate(last);
+            }
+            return fill(en, &params);
+        }
+    }
+    text.to_string()
+}
+
+/// 把命令返回的中文错误翻译为当前语言（无法识别的部分保持原文）
+///
+/// 按模板反向匹配只是兜底：新的消息应在产生处用 `t` 按 key 生成，
+/// 这里只处理 FFmpeg 调用等尚未改为 key 的错误
+pub fn localize_error(app: &AppHandle, error: String) -> String {
+    if current_locale(app) == Locale::ZhCn {
+        return error;
+    }
+    // 保留前端依赖的错误前缀（如 BATCH_SKIPPED:）
+    let split = error
+        .find(|c: char| !(c.is_ascii_uppercase() || c == '_'))
+        .filter(|&i| i > 0 && error[i..].starts_with(':'))
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    let (prefix, message) = error.split_at(split);
+    format!("{}{}", prefix, translate(message))
+}
+
+// 设置界面语言
+#[tauri::command]
+pub fn set_locale(settings: State<'_, SettingsManager>, locale: Locale) -> Result<(), String> {
+    settings.update(|s| {
+        s.locale = locale;
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_extracts_params() {
+        assert_eq!(match_template("文件 {} 不存在", "文件 a.mp4 不存在"), Some(vec!["a.mp4".to_string()]));
+        assert_eq!(match_template("{} 到 {}", "1 到 2"), Some(vec!["1".to_string(), "2".to_string()]));
+        assert_eq!(match_template("完成", "完成"), Some(Vec::new()));
+    }
+
+    #[test]
+    fn template_mismatch_is_none() {
+        assert_eq!(match_template("完成", "完成了"), None);
+        assert_eq!(match_template("文件 {} 不存在", "目录 a 不存在"), None);
+        // 相邻的参数无法确定边界
+        assert_eq!(match_template("{}{}", "ab"), None);
+    }
+
+    #[test]
+    fn fill_replaces_placeholders() {
+        assert_eq!(fill("{} 到 {}", &["1".to_string(), "2".to_string()]), "1 到 2");
+    }
+
+    #[test]
+    fn catalog_keys_are_unique_and_templates_match() {
+        for (i, (key, zh, en)) in CATALOG.iter().enumerate() {
+            assert!(CATALOG[..i].iter().all(|(k, _, _)| k != key), "重复的 key: {}", key);
+            assert_eq!(zh.matches("{}").count(), en.matches("{}").count(), "参数个数不一致: {}", key);
+        }
+    }
+}
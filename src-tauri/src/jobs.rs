@@ -0,0 +1,924 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::Notify;
+use ts_rs::TS;
+use crate::ending_check::EndingCheck;
+use crate::events::{JOB_UPDATED, SCHEDULER_STATUS};
+use crate::export_preset::ExportPreset;
+use crate::video_frame_extractor::{run_auto_split, run_remove_ending, FlashFilter, Hysteresis, TempSegmentOptions};
+use crate::settings::{BrandKit, SettingsManager};
+use crate::shutdown::ProcessRegistry;
+use crate::throttle::{run_in_job, ThrottleSettings, JOB_CANCELLED};
+use crate::source_date::SourceDateOptions;
+use crate::video_processor::{ClipTrim, EncodeSettings, TitleConfig};
+
+/// 自动拆解参数（auto_split_video 命令、批量处理、任务队列与自检共用）
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct AutoSplitParams {
+    pub output_dir: String,
+    pub algorithm: String,
+    pub threshold: f64,
+    pub min_duration: f64,
+    #[serde(default)]
+    pub skip_first: bool,
+    #[serde(default)]
+    pub skip_last: bool,
+    #[serde(default)]
+    pub source_date: SourceDateOptions,
+    /// 片段按导出预设编码
+    #[serde(default)]
+    pub export_preset: Option<String>,
+    /// 加入队列时按名称查到的导出预设内容，执行时优先使用（其他机器上可能没有同名预设）
+    #[serde(default)]
+    #[ts(skip)]
+    pub resolved_export_preset: Option<ExportPreset>,
+    #[serde(default)]
+    pub encode_settings: Option<EncodeSettings>,
+    /// 只分析源视频的这段时间（秒）
+    #[serde(default)]
+    pub analyze_start: Option<f64>,
+    #[serde(default)]
+    pub analyze_end: Option<f64>,
+    /// 滞回检测（切分后需确认新场景稳定）
+    #[serde(default)]
+    pub hysteresis: Option<Hysteresis>,
+    /// 忽略闪光、频闪造成的单帧异常
+    #[serde(default)]
+    pub flash_filter: Option<FlashFilter>,
+    /// 检测预设名称，套用后覆盖算法、阈值与最短时长
+    #[serde(default)]
+    pub detection_profile: Option<String>,
+}
+
+impl AutoSplitParams {
+    /// 按名称查找检测预设与导出预设，把内容写入参数
+    ///
+    /// 加入队列时调用：任务可能交给其他机器执行，执行节点上不一定有同名预设，
+    /// 之后修改或删除预设也不影响已排队的任务
+    pub fn resolve_presets(&mut self, settings: &SettingsManager) -> Result<(), String> {
+        if let Some(name) = self.detection_profile.take().filter(|n| !n.is_empty()) {
+            let profile = settings.find_detection_profile(&name)?;
+            self.algorithm = profile.algorithm;
+            self.threshold = profile.threshold;
+            self.min_duration = profile.min_duration;
+            self.hysteresis = self.hysteresis.take().or(profile.hysteresis);
+            self.flash_filter = self.flash_filter.take().or(profile.flash_filter);
+        }
+        if self.resolved_export_preset.is_none() {
+            if let Some(name) = self.export_preset.as_deref().filter(|n| !n.is_empty()) {
+                self.resolved_export_preset = Some(settings.find_export_preset(name)?);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 去结尾参数（remove_ending_and_concat 命令与任务队列共用）
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct RemoveEndingParams {
+    pub output_dir: String,
+    pub algorithm: String,
+    pub threshold: f64,
+    pub min_duration: f64,
+    #[serde(default)]
+    pub new_ending_video: Option<String>,
+    #[serde(default)]
+    pub new_ending_trim: Option<ClipTrim>,
+    #[serde(default)]
+    pub brand_kit: Option<String>,
+    /// 加入队列时按名称查到的品牌包内容，执行时优先使用（其他机器上可能没有同名品牌包）
+    #[serde(default)]
+    #[ts(skip)]
+    pub resolved_brand_kit: Option<Box<BrandKit>>,
+    #[serde(default)]
+    pub shuffle_segments: bool,
+    /// 按音频波形微调新结尾的接缝
+    #[serde(default)]
+    pub audio_join: bool,
+    #[serde(default)]
+    pub temp_segments: TempSegmentOptions,
+    #[serde(default)]
+    pub ending_check: Option<EndingCheck>,
+    #[serde(default)]
+    pub encode_settings: Option<EncodeSettings>,
+    /// 只在视频末尾这么多秒内反向查找片尾，为空时分析整个视频
+    #[serde(default)]
+    pub tail_window: Option<f64>,
+    /// 打乱片段的随机种子，指定后每次打乱结果相同
+    #[serde(default)]
+    #[ts(type = "number | null")]
+    pub seed: Option<u64>,
+    /// 成片开头的标题文字
+    #[serde(default)]
+    pub title: Option<TitleConfig>,
+}
+
+impl RemoveEndingParams {
+    /// 按名称查找品牌包，把内容写入参数（同 AutoSplitParams::resolve_presets）
+    pub fn resolve_presets(&mut self, settings: &SettingsManager) -> Result<(), String> {
+        if self.resolved_brand_kit.is_none() {
+            if let Some(name) = self.brand_kit.as_deref().filter(|n| !n.is_empty()) {
+                self.resolved_brand_kit = Some(Box::new(settings.find_brand_kit(name)?));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 队列任务内容
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JobSpec {
+    AutoSplit {
+        video_path: String,
+        params: AutoSplitParams,
+    },
+    RemoveEnding {
+        video_path: String,
+        params: RemoveEndingParams,
+    },
+}
+
+impl JobSpec {
+    /// 任务处理的源文件
+    pub fn source(&self) -> &str {
+        match self {
+            Self::AutoSplit { video_path, .. } | Self::RemoveEnding { video_path, .. } => video_path,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::AutoSplit { .. } => "auto_split",
+            Self::RemoveEnding { .. } => "remove_ending",
+        }
+    }
+
+    /// 转换任务中的所有文件路径（在其他机器上执行时映射共享存储的路径）
+    pub fn map_paths(&mut self, map: impl Fn(&str) -> String) {
+        let map_opt = |path: &mut Option<String>| {
+            if let Some(path) = path {
+                *path = map(path);
+            }
+        };
+        match self {
+            Self::AutoSplit { video_path, params } => {
+                *video_path = map(video_path);
+                params.output_dir = map(&params.output_dir);
+            }
+            Self::RemoveEnding { video_path, params } => {
+                *video_path = map(video_path);
+                params.output_dir = map(&params.output_dir);
+                map_opt(&mut params.new_ending_video);
+                if let Some(title) = &mut params.title {
+                    map_opt(&mut title.font_file);
+                }
+                if let Some(check) = &mut params.ending_check {
+                    for reference in &mut check.reference_videos {
+                        *reference = map(reference);
+                    }
+                }
+                if let Some(kit) = &mut params.resolved_brand_kit {
+                    map_opt(&mut kit.intro_video);
+                    map_opt(&mut kit.outro_video);
+                    map_opt(&mut kit.title_font);
+                    if let Some(watermark) = &mut kit.watermark {
+                        watermark.image_path = map(&watermark.image_path);
+                    }
+                    if let Some(bgm) = &mut kit.bgm {
+                        bgm.audio_path = map(&bgm.audio_path);
+                    }
+                }
+            }
+        }
+    }
+
+    /// 按名称查找任务引用的预设并写入任务（见 AutoSplitParams::resolve_presets）
+    pub fn resolve_presets(&mut self, settings: &SettingsManager) -> Result<(), String> {
+        match self {
+            Self::AutoSplit { params, .. } => params.resolve_presets(settings),
+            Self::RemoveEnding { params, .. } => params.resolve_presets(settings),
+        }
+    }
+}
+
+/// 下载完成后自动执行的处理动作
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum PostDownloadAction {
+    AutoSplit(AutoSplitParams),
+    RemoveEnding(RemoveEndingParams),
+}
+
+impl PostDownloadAction {
+    /// 按名称查找处理动作引用的预设并写入参数
+    pub fn resolve_presets(&mut self, settings: &SettingsManager) -> Result<(), String> {
+        match self {
+            Self::AutoSplit(params) => params.resolve_presets(settings),
+            Self::RemoveEnding(params) => params.resolve_presets(settings),
+        }
+    }
+
+    pub fn into_spec(self, video_path: String) -> JobSpec {
+        match self {
+            Self::AutoSplit(params) => JobSpec::AutoSplit { video_path, params },
+            Self::RemoveEnding(params) => JobSpec::RemoveEnding { video_path, params },
+        }
+    }
+}
+
+/// 任务优先级：高优先级任务排在队列前面（正在执行的任务不会被打断）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum JobPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+/// 任务记录（发送给前端的 job_updated 事件也使用该结构）
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct JobRecord {
+    pub id: String,
+    pub kind: String,
+    pub source: String,
+    pub status: JobStatus,
+    #[serde(default)]
+    pub priority: JobPriority,
+    /// 等待中任务在队列中的位置（从 0 开始）
+    #[serde(default)]
+    pub queue_position: Option<usize>,
+    pub created_at: String,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    pub result: Option<String>,
+    pub error: Option<String>,
+    /// 由其他机器执行时的执行节点名称
+    #[serde(default)]
+    pub worker: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct QueuedJob {
+    id: String,
+    priority: JobPriority,
+    spec: JobSpec,
+    /// 单独指定的资源限制，为空时使用全局设置
+    throttle: Option<ThrottleSettings>,
+}
+
+/// 分给其他机器执行的任务
+struct RemoteJob {
+    job: QueuedJob,
+    worker: String,
+    /// 最近一次收到执行节点心跳的时间
+    last_seen: Instant,
+}
+
+/// 执行节点超过该时长没有心跳时，任务重新排队
+const REMOTE_LEASE: Duration = Duration::from_secs(120);
+
+/// 分给执行节点的任务内容
+pub struct ClaimedJob {
+    pub id: String,
+    pub spec: JobSpec,
+    pub throttle: Option<ThrottleSettings>,
+}
+
+#[derive(Default)]
+struct QueueInner {
+    pending: VecDeque<QueuedJob>,
+    /// 正在执行的任务（退出时与等待中的任务一起保存）
+    running: Option<QueuedJob>,
+    /// 其他机器正在执行的任务（退出时同样保存）
+    remote: Vec<RemoteJob>,
+    records: Vec<JobRecord>,
+}
+
+impl QueueInner {
+    /// 按优先级插入：排在所有优先级不低于它的任务之后
+    fn insert(&mut self, job: QueuedJob) {
+        let position = self
+            .pending
+            .iter()
+            .position(|j| j.priority < job.priority)
+            .unwrap_or(self.pending.len());
+        self.pending.insert(position, job);
+    }
+
+    /// 同步记录中的排队位置
+    fn refresh_positions(&mut self) {
+        for record in self.records.iter_mut() {
+            record.queue_position = None;
+        }
+        for (position, job) in self.pending.iter().enumerate() {
+            if let Some(record) = self.records.iter_mut().find(|r| r.id == job.id) {
+                record.queue_position = Some(position);
+            }
+        }
+    }
+}
+
+/// 全局任务队列：按优先级逐个执行，同优先级按提交顺序
+pub struct JobQueue {
+    inner: Mutex<QueueInner>,
+    notify: Notify,
+    next_id: AtomicU64,
+}
+
+fn now_string() -> String {
+    chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+impl JobQueue {
+    pub fn new() -> Self {
+        Self {
+            inner: Mutex::new(QueueInner::default()),
+            notify: Notify::new(),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// 加入队列，返回任务 ID
+    pub fn enqueue(
+        &self,
+        app: &AppHandle,
+        spec: JobSpec,
+        priority: JobPriority,
+        throttle: Option<ThrottleSettings>,
+    ) -> String {
+        let id = format!(
+            "{}-{}",
+            chrono::Local::now().format("%Y%m%d%H%M%S"),
+            self.next_id.fetch_add(1, Ordering::Relaxed)
+        );
+        let record = JobRecord {
+            id: id.clone(),
+            kind: spec.kind().to_string(),
+            source: spec.source().to_string(),
+            status: JobStatus::Pending,
+            priority,
+            queue_position: None,
+            created_at: now_string(),
+            started_at: None,
+            finished_at: None,
+            result: None,
+            error: None,
+            worker: None,
+        };
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.records.push(record);
+            inner.insert(QueuedJob {
+                id: id.clone(),
+                priority,
+                spec,
+                throttle,
+            });
+            inner.refresh_positions();
+        }
+        self.emit_pending(app);
+        self.notify.notify_one();
+        id
+    }
+
+    pub fn list(&self) -> Vec<JobRecord> {
+        self.inner.lock().unwrap().records.clone()
+    }
+
+    /// 把等待中的任务移动到指定位置（超出范围时放到队尾）
+    pub fn reorder(&self, app: &AppHandle, id: &str, position: usize) -> Result<(), String> {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            let index = inner
+                .pending
+                .iter()
+                .position(|j| j.id == id)
+                .ok_or_else(|| format!("任务不在等待队列中: {}", id))?;
+            let job = inner.pending.remove(index).unwrap();
+            let position = position.min(inner.pending.len());
+            inner.pending.insert(position, job);
+            inner.refresh_positions();
+        }
+        self.emit_pending(app);
+        Ok(())
+    }
+
+    /// 修改等待中任务的优先级，并按新优先级重新排队
+    pub fn set_priority(&self, app: &AppHandle, id: &str, priority: JobPriority) -> Result<(), String> {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            let index = inner
+                .pending
+                .iter()
+                .position(|j| j.id == id)
+                .ok_or_else(|| format!("任务不在等待队列中: {}", id))?;
+            let mut job = inner.pending.remove(index).unwrap();
+            job.priority = priority;
+            inner.insert(job);
+            if let Some(record) = inner.records.iter_mut().find(|r| r.id == id) {
+                record.priority = priority;
+            }
+            inner.refresh_positions();
+        }
+        self.emit_pending(app);
+        Ok(())
+    }
+
+    /// 排队情况变化后，通知前端所有等待中任务的最新记录
+    fn emit_pending(&self, app: &AppHandle) {
+        let records: Vec<JobRecord> = {
+            let inner = self.inner.lock().unwrap();
+            inner
+                .records
+                .iter()
+                .filter(|r| r.status == JobStatus::Pending)
+                .cloned()
+                .collect()
+        };
+        for record in records {
+            let _ = app.emit(JOB_UPDATED, record);
+        }
+    }
+
+    /// 取消等待中的任务（移出队列并标记为失败），任务不在等待队列中时返回 false
+    fn cancel_pending(&self, app: &AppHandle, id: &str) -> bool {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            let Some(index) = inner.pending.iter().position(|j| j.id == id) else {
+                return false;
+            };
+            inner.pending.remove(index);
+            inner.refresh_positions();
+        }
+        self.update(app, id, |r| {
+            r.status = JobStatus::Failed;
+            r.finished_at = Some(now_string());
+            r.error = Some(JOB_CANCELLED.to_string());
+        });
+        self.emit_pending(app);
+        true
+    }
+
+    /// 唤醒执行循环（新任务或调度设置变化时）
+    pub fn wake(&self) {
+        self.notify.notify_one();
+    }
+
+    fn has_pending(&self) -> bool {
+        !self.inner.lock().unwrap().pending.is_empty()
+    }
+
+    /// 没有正在执行或等待中的任务
+    pub fn is_idle(&self) -> bool {
+        let inner = self.inner.lock().unwrap();
+        inner.running.is_none() && inner.pending.is_empty()
+    }
+
+    /// 只在任务之间取下一个任务，正在执行的任务不会被抢占
+    fn pop(&self) -> Option<QueuedJob> {
+        let mut inner = self.inner.lock().unwrap();
+        let job = inner.pending.pop_front();
+        if job.is_some() {
+            inner.running = job.clone();
+            inner.refresh_positions();
+        }
+        job
+    }
+
+    fn finish_running(&self) {
+        self.inner.lock().unwrap().running = None;
+    }
+
+    /// 执行节点领取下一个任务；先把心跳超时的任务放回队列
+    pub fn claim_remote(&self, app: &AppHandle, worker: &str) -> Option<ClaimedJob> {
+        self.requeue_remote(app, true);
+        let job = {
+            let mut inner = self.inner.lock().unwrap();
+            let job = inner.pending.pop_front()?;
+            inner.remote.push(RemoteJob {
+                job: job.clone(),
+                worker: worker.to_string(),
+                last_seen: Instant::now(),
+            });
+            inner.refresh_positions();
+            job
+        };
+        self.update(app, &job.id, |r| {
+            r.status = JobStatus::Running;
+            r.started_at = Some(now_string());
+            r.worker = Some(worker.to_string());
+        });
+        self.emit_pending(app);
+        Some(ClaimedJob {
+            id: job.id,
+            spec: job.spec,
+            throttle: job.throttle,
+        })
+    }
+
+    /// 执行节点的心跳；任务已超时被重新分配时返回错误，执行节点应放弃该任务
+    pub fn heartbeat_remote(&self, id: &str, worker: &str) -> Result<(), String> {
+        let mut inner = self.inner.lock().unwrap();
+        let job = inner
+            .remote
+            .iter_mut()
+            .find(|j| j.job.id == id && j.worker == worker)
+            .ok_or_else(|| format!("任务已不属于该执行节点: {}", id))?;
+        job.last_seen = Instant::now();
+        Ok(())
+    }
+
+    /// 记录执行节点返回的结果
+    pub fn complete_remote(
+        &self,
+        app: &AppHandle,
+        id: &str,
+        worker: &str,
+        result: Result<String, String>,
+    ) -> Result<(), String> {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            let index = inner
+                .remote
+                .iter()
+                .position(|j| j.job.id == id && j.worker == worker)
+                .ok_or_else(|| format!("任务已不属于该执行节点: {}", id))?;
+            inner.remote.remove(index);
+        }
+        self.update(app, id, |r| {
+            r.finished_at = Some(now_string());
+            match result {
+                Ok(message) => {
+                    r.status = JobStatus::Completed;
+                    r.result = Some(message);
+                }
+                Err(e) => {
+                    r.status = JobStatus::Failed;
+                    r.error = Some(e);
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// 把远程任务（`only_expired` 为 true 时只限心跳超时的）放回队列最前面，由本机或其他执行节点重新执行
+    pub fn requeue_remote(&self, app: &AppHandle, only_expired: bool) {
+        let requeued: Vec<String> = {
+            let mut inner = self.inner.lock().unwrap();
+            let (back, keep): (Vec<RemoteJob>, Vec<RemoteJob>) = inner
+                .remote
+                .drain(..)
+                .partition(|j| !only_expired || j.last_seen.elapsed() > REMOTE_LEASE);
+            inner.remote = keep;
+            let ids = back.iter().map(|j| j.job.id.clone()).collect();
+            for job in back.into_iter().rev() {
+                inner.pending.push_front(job.job);
+            }
+            inner.refresh_positions();
+            ids
+        };
+        if requeued.is_empty() {
+            return;
+        }
+        for id in &requeued {
+            self.update(app, id, |r| {
+                r.status = JobStatus::Pending;
+                r.started_at = None;
+                r.worker = None;
+            });
+        }
+        self.emit_pending(app);
+        self.notify.notify_one();
+    }
+
+    fn queue_file(app: &AppHandle) -> Result<PathBuf, String> {
+        Ok(app
+            .path()
+            .app_data_dir()
+            .map_err(|e| format!("获取数据目录失败: {}", e))?
+            .join("job_queue.json"))
+    }
+
+    /// 保存未完成的任务（被打断的任务排在最前），下次启动时恢复
+    pub fn persist(&self, app: &AppHandle) -> Result<(), String> {
+        let jobs: Vec<QueuedJob> = {
+            let inner = self.inner.lock().unwrap();
+            inner
+                .running
+                .iter()
+                .chain(inner.remote.iter().map(|j| &j.job))
+                .chain(inner.pending.iter())
+                .cloned()
+                .collect()
+        };
+        let path = Self::queue_file(app)?;
+        if jobs.is_empty() {
+            let _ = fs::remove_file(&path);
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("创建数据目录失败: {}", e))?;
+        }
+        let content =
+            serde_json::to_string_pretty(&jobs).map_err(|e| format!("序列化任务队列失败: {}", e))?;
+        fs::write(&path, content).map_err(|e| format!("保存任务队列失败: {}", e))
+    }
+
+    /// 恢复上次退出时未完成的任务（应用启动时调用一次）
+    pub fn restore(&self, app: &AppHandle) -> Result<(), String> {
+        let path = Self::queue_file(app)?;
+        if !path.exists() {
+            return Ok(());
+        }
+        let content = fs::read_to_string(&path).map_err(|e| format!("读取任务队列失败: {}", e))?;
+        let jobs: Vec<QueuedJob> =
+            serde_json::from_str(&content).map_err(|e| format!("解析任务队列失败: {}", e))?;
+        let _ = fs::remove_file(&path);
+        for mut job in jobs {
+            // 旧版本保存的任务只记录了预设名称；预设已删除时保留名称，执行时再报错
+            let _ = job.spec.resolve_presets(&app.state::<SettingsManager>());
+            self.enqueue(app, job.spec, job.priority, job.throttle);
+        }
+        Ok(())
+    }
+
+    /// 更新任务记录并通知前端
+    fn update<F>(&self, app: &AppHandle, id: &str, f: F)
+    where
+        F: FnOnce(&mut JobRecord),
+    {
+        let record = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.records.iter_mut().find(|r| r.id == id).map(|r| {
+                f(r);
+                r.clone()
+            })
+        };
+        if let Some(record) = record {
+            let _ = app.emit(JOB_UPDATED, record);
+        }
+    }
+}
+
+/// 调度暂停状态事件
+#[derive(Clone, Serialize, TS)]
+#[ts(export)]
+struct SchedulerStatus {
+    paused: bool,
+    reason: Option<String>,
+}
+
+/// 空闲模式下重新检查是否可以执行的间隔
+const SCHEDULER_POLL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// 启动后台执行循环（应用启动时调用一次）
+pub fn start_worker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut paused_reason: Option<String> = None;
+        loop {
+            // 空闲模式：只在任务之间暂停，等待空闲或允许的时间段
+            if app.state::<JobQueue>().has_pending() {
+                let reason = app.state::<SettingsManager>().get().scheduler.block_reason();
+                if reason != paused_reason {
+                    paused_reason = reason.clone();
+                    let _ = app.emit(SCHEDULER_STATUS, SchedulerStatus {
+                        paused: reason.is_some(),
+                        reason: reason.clone(),
+                    });
+                }
+                if reason.is_some() {
+                    let queue = app.state::<JobQueue>();
+                    let _ = tokio::time::timeout(SCHEDULER_POLL, queue.notify.notified()).await;
+                    continue;
+                }
+            }
+
+            if app.state::<ProcessRegistry>().is_shutting_down() {
+                break;
+            }
+            let job = {
+                let queue = app.state::<JobQueue>();
+                queue.pop()
+            };
+            let Some(job) = job else {
+                let queue = app.state::<JobQueue>();
+                queue.notify.notified().await;
+                continue;
+            };
+
+            app.state::<JobQueue>().update(&app, &job.id, |r| {
+                r.status = JobStatus::Running;
+                r.started_at = Some(now_string());
+            });
+
+            let result = run_in_job(&app, &job.id, job.throttle, run_job(&app, job.spec)).await;
+
+            // 退出时被打断的任务保持为执行中，由退出流程保存，下次启动重新执行
+            if app.state::<ProcessRegistry>().is_shutting_down() {
+                break;
+            }
+            app.state::<JobQueue>().finish_running();
+
+            app.state::<JobQueue>().update(&app, &job.id, |r| {
+                r.finished_at = Some(now_string());
+                match result {
+                    Ok(message) => {
+                        r.status = JobStatus::Completed;
+                        r.result = Some(message);
+                    }
+                    Err(e) => {
+                        r.status = JobStatus::Failed;
+                        r.error = Some(e);
+                    }
+                }
+            });
+        }
+    });
+}
+
+pub async fn run_job(app: &AppHandle, spec: JobSpec) -> Result<String, String> {
+    match spec {
+        JobSpec::AutoSplit { video_path, params } => {
+            run_auto_split(app, video_path, params).await
+        }
+        JobSpec::RemoveEnding { video_path, params } => {
+            run_remove_ending(app, video_path, params).await
+        }
+    }
+}
+
+// 提交任务到队列
+#[tauri::command]
+pub fn enqueue_job(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+    spec: JobSpec,
+    priority: Option<JobPriority>,
+    throttle: Option<ThrottleSettings>,
+) -> Result<String, String> {
+    if let Some(throttle) = &throttle {
+        throttle.validate()?;
+    }
+    let mut spec = spec;
+    spec.resolve_presets(&app.state::<SettingsManager>())?;
+    Ok(queue.enqueue(&app, spec, priority.unwrap_or_default(), throttle))
+}
+
+// 调整等待中任务的位置
+#[tauri::command]
+pub fn reorder_job(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+    id: String,
+    position: usize,
+) -> Result<(), String> {
+    queue.reorder(&app, &id, position)
+}
+
+// 修改等待中任务的优先级
+#[tauri::command]
+pub fn set_job_priority(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+    id: String,
+    priority: JobPriority,
+) -> Result<(), String> {
+    queue.set_priority(&app, &id, priority)
+}
+
+// 取消任务：等待中的队列任务直接移出队列；正在执行的任务（队列任务或后台拼接）结束其 FFmpeg 进程并删除未完成的输出
+#[tauri::command]
+pub fn cancel_job(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+    registry: State<'_, ProcessRegistry>,
+    job_id: String,
+) -> Result<(), String> {
+    if queue.cancel_pending(&app, &job_id) || registry.cancel_job(&job_id) {
+        return Ok(());
+    }
+    Err(format!("任务不存在或已结束: {}", job_id))
+}
+
+// 列出所有任务
+#[tauri::command]
+pub fn list_jobs(queue: State<'_, JobQueue>) -> Vec<JobRecord> {
+    queue.list()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::video_processor::{BgmConfig, WatermarkConfig};
+
+    fn remove_ending_params() -> RemoveEndingParams {
+        serde_json::from_value(serde_json::json!({
+            "output_dir": "/mnt/share/out",
+            "algorithm": "histogram",
+            "threshold": 0.8,
+            "min_duration": 1.0,
+        }))
+        .unwrap()
+    }
+
+    fn settings() -> SettingsManager {
+        SettingsManager::load(std::env::temp_dir().join("mp4handler_jobs_test_missing.json"))
+    }
+
+    #[test]
+    fn map_paths_covers_every_remove_ending_path() {
+        let watermark: WatermarkConfig =
+            serde_json::from_value(serde_json::json!({ "image_path": "/mnt/share/logo.png" })).unwrap();
+        let bgm: BgmConfig = serde_json::from_value(serde_json::json!({ "audio_path": "/mnt/share/bgm.mp3" })).unwrap();
+        let mut params = remove_ending_params();
+        params.new_ending_video = Some("/mnt/share/ending.mp4".to_string());
+        params.title = Some(TitleConfig {
+            text: "标题".to_string(),
+            font_file: Some("/mnt/share/font.ttf".to_string()),
+            font_size: 48,
+            duration: 3.0,
+        });
+        params.ending_check = Some(
+            serde_json::from_value(serde_json::json!({ "reference_videos": ["/mnt/share/ref.mp4"] })).unwrap(),
+        );
+        params.resolved_brand_kit = Some(Box::new(BrandKit {
+            name: "kit".to_string(),
+            intro_video: Some("/mnt/share/intro.mp4".to_string()),
+            intro_trim: None,
+            outro_video: Some("/mnt/share/outro.mp4".to_string()),
+            outro_trim: None,
+            watermark: Some(watermark),
+            bgm: Some(bgm),
+            title_font: Some("/mnt/share/kit.ttf".to_string()),
+        }));
+        let mut spec = JobSpec::RemoveEnding {
+            video_path: "/mnt/share/in.mp4".to_string(),
+            params,
+        };
+        spec.map_paths(|path| path.replace("/mnt/share", "Z:"));
+
+        let json = serde_json::to_string(&spec).unwrap();
+        assert!(!json.contains("/mnt/share"), "{}", json);
+        assert_eq!(json.matches("Z:").count(), 10);
+    }
+
+    #[test]
+    fn resolve_presets_inlines_detection_profile_and_export_preset() {
+        let mut params = AutoSplitParams {
+            output_dir: "/out".to_string(),
+            algorithm: "histogram".to_string(),
+            threshold: 0.5,
+            min_duration: 0.1,
+            export_preset: Some("youtube".to_string()),
+            detection_profile: Some("anime".to_string()),
+            hysteresis: Some(Hysteresis { recovered: 0.9, stable_frames: 2 }),
+            ..AutoSplitParams::default()
+        };
+        params.resolve_presets(&settings()).unwrap();
+
+        assert_eq!(params.detection_profile, None);
+        assert_eq!(params.algorithm, "edge");
+        assert_eq!(params.threshold, 0.7);
+        assert_eq!(params.min_duration, 1.0);
+        // 单独指定的滞回优先，闪光过滤取自预设
+        assert_eq!(params.hysteresis.map(|h| h.stable_frames), Some(2));
+        assert_eq!(params.flash_filter.map(|f| f.min_persist), Some(3));
+        assert_eq!(params.resolved_export_preset.map(|p| p.name), Some("youtube".to_string()));
+    }
+
+    #[test]
+    fn resolve_presets_rejects_unknown_names() {
+        let mut params = remove_ending_params();
+        params.brand_kit = Some("missing".to_string());
+        assert!(params.resolve_presets(&settings()).is_err());
+
+        let mut params = AutoSplitParams {
+            export_preset: Some("missing".to_string()),
+            ..AutoSplitParams::default()
+        };
+        assert!(params.resolve_presets(&settings()).is_err());
+    }
+}
@@ -0,0 +1,230 @@
+use serde::Serialize;
+use std::path::Path;
+use tauri::AppHandle;
+use tauri_plugin_shell::ShellExt;
+use ts_rs::TS;
+
+/// 接缝前后各检查的秒数
+const JOIN_WINDOW: f64 = 0.25;
+/// 比较用的缩略画面边长（灰度）
+const SAMPLE_SIZE: usize = 32;
+/// 相邻两帧平均像素差低于该值视为重复帧（0-255）
+const DUPLICATE_DIFF: f64 = 0.5;
+/// 平均亮度低于该值视为黑帧（0-255，limited range 的黑为 16）
+const BLACK_LUMA: f64 = 20.0;
+/// 帧间隔超过正常间隔的倍数视为丢帧
+const GAP_FACTOR: f64 = 1.5;
+
+/// 需要注意的接缝
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct JoinIssue {
+    pub output: String,
+    /// 第几处接缝（从 1 开始）
+    pub index: usize,
+    /// 接缝在成片中的时间（秒）
+    pub time: f64,
+    pub problems: Vec<String>,
+}
+
+impl JoinIssue {
+    pub fn describe(&self) -> String {
+        format!(
+            "{} 第 {} 处接缝（{:.2}s）：{}",
+            Path::new(&self.output).file_name().unwrap_or_default().to_string_lossy(),
+            self.index,
+            self.time,
+            self.problems.join("；")
+        )
+    }
+}
+
+/// 接缝附近每一帧的时间戳
+async fn frame_times(app: &AppHandle, output: &Path, start: f64, end: f64) -> Result<Vec<f64>, String> {
+    let sidecar = app
+        .shell()
+        .sidecar("ffprobe")
+        .map_err(|e| format!("FFprobe 启动失败: {}", e))?;
+
+    let output = sidecar
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "v:0",
+            "-read_intervals",
+            &format!("{:.3}%{:.3}", start, end),
+            "-show_entries",
+            "frame=best_effort_timestamp_time",
+            "-of",
+            "csv=p=0",
+            &output.to_string_lossy(),
+        ])
+        .output()
+        .await
+        .map_err(|e| format!("FFprobe 执行失败: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "读取帧时间戳失败: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    // read_intervals 从前一个关键帧开始读，只保留窗口内的帧
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.trim().trim_end_matches(',').parse::<f64>().ok())
+        .filter(|t| *t >= start && *t <= end)
+        .collect())
+}
+
+/// 解码接缝附近的帧为缩略灰度画面
+async fn frame_samples(app: &AppHandle, output: &Path, start: f64, duration: f64) -> Result<Vec<Vec<u8>>, String> {
+    let sidecar = app
+        .shell()
+        .sidecar("ffmpeg")
+        .map_err(|e| format!("FFmpeg 启动失败: {}", e))?;
+
+    let output = sidecar
+        .args([
+            "-v",
+            "error",
+            "-ss",
+            &format!("{:.3}", start),
+            "-i",
+            &output.to_string_lossy(),
+            "-t",
+            &format!("{:.3}", duration),
+            "-map",
+            "0:v:0",
+            "-vf",
+            &format!("scale={0}:{0},format=gray", SAMPLE_SIZE),
+            "-vsync",
+            "passthrough",
+            "-f",
+            "rawvideo",
+            "-",
+        ])
+        .output()
+        .await
+        .map_err(|e| format!("FFmpeg 执行失败: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "解码接缝画面失败: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(output
+        .stdout
+        .chunks_exact(SAMPLE_SIZE * SAMPLE_SIZE)
+        .map(|frame| frame.to_vec())
+        .collect())
+}
+
+fn mean_diff(a: &[u8], b: &[u8]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x.abs_diff(*y) as f64).sum::<f64>() / a.len() as f64
+}
+
+fn mean_luma(frame: &[u8]) -> f64 {
+    frame.iter().map(|p| *p as f64).sum::<f64>() / frame.len() as f64
+}
+
+/// 时间戳的问题：间隔明显大于正常间隔（丢帧）或时间戳不递增
+fn timing_problems(times: &[f64]) -> Vec<String> {
+    let mut intervals: Vec<f64> = times.windows(2).map(|w| w[1] - w[0]).filter(|d| *d > 0.0).collect();
+    if intervals.is_empty() {
+        return Vec::new();
+    }
+    intervals.sort_by(|a, b| a.total_cmp(b));
+    let normal = intervals[intervals.len() / 2];
+
+    let mut problems = Vec::new();
+    for pair in times.windows(2) {
+        let gap = pair[1] - pair[0];
+        if gap <= 0.0 {
+            problems.push(format!("{:.3}s 处时间戳重复", pair[1]));
+        } else if gap > normal * GAP_FACTOR {
+            problems.push(format!(
+                "{:.3}s 处帧间隔 {:.0}ms（正常 {:.0}ms），可能丢帧",
+                pair[0],
+                gap * 1000.0,
+                normal * 1000.0
+            ));
+        }
+    }
+    problems
+}
+
+/// 画面的问题：运动画面中孤立的重复帧（卡顿），以及接缝处的黑帧
+///
+/// 静止画面本身就是连续的重复帧，只有前后都在变化、单独停住的一帧才算卡顿
+fn picture_problems(frames: &[Vec<u8>], check_black: bool) -> Vec<String> {
+    let duplicates: Vec<bool> = frames.windows(2).map(|w| mean_diff(&w[0], &w[1]) < DUPLICATE_DIFF).collect();
+    // 窗口两端的帧没有另一侧可比，只判断中间的
+    let stutters = (1..duplicates.len().saturating_sub(1))
+        .filter(|&i| duplicates[i] && !duplicates[i - 1] && !duplicates[i + 1])
+        .count();
+
+    let mut problems = Vec::new();
+    if stutters > 0 {
+        problems.push(format!("{} 处单帧重复（卡顿）", stutters));
+    }
+    if check_black {
+        let black = frames.iter().filter(|f| mean_luma(f) < BLACK_LUMA).count();
+        // 整个窗口都是黑帧说明素材本身是黑场
+        if black > 0 && black < frames.len() {
+            problems.push(format!("{} 个黑帧", black));
+        }
+    }
+    problems
+}
+
+/// 检查输出文件各接缝前后的帧，返回有重复帧、黑帧或丢帧的接缝
+///
+/// `joins` 为各接缝在成片中的时间；转场本身会淡入黑场时不检查黑帧（`check_black` 为 false）
+pub async fn verify_joins(
+    app: &AppHandle,
+    output: &Path,
+    joins: &[f64],
+    check_black: bool,
+) -> Result<Vec<JoinIssue>, String> {
+    let mut issues = Vec::new();
+    for (idx, &time) in joins.iter().enumerate() {
+        let start = (time - JOIN_WINDOW).max(0.0);
+        let end = time + JOIN_WINDOW;
+        let mut problems = timing_problems(&frame_times(app, output, start, end).await?);
+        problems.extend(picture_problems(&frame_samples(app, output, start, end - start).await?, check_black));
+        if !problems.is_empty() {
+            issues.push(JoinIssue {
+                output: output.to_string_lossy().to_string(),
+                index: idx + 1,
+                time,
+                problems,
+            });
+        }
+    }
+    Ok(issues)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn regular_timing_has_no_problems() {
+        assert!(timing_problems(&[0.0, 0.04, 0.08, 0.12]).is_empty());
+        assert!(timing_problems(&[0.0]).is_empty());
+    }
+
+    #[test]
+    fn dropped_and_repeated_frames_are_reported() {
+        let problems = timing_problems(&[0.0, 0.04, 0.08, 0.2, 0.24]);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("0.080s"), "{}", problems[0]);
+
+        assert_eq!(timing_problems(&[0.0, 0.04, 0.04, 0.08]), vec!["0.040s 处时间戳重复"]);
+    }
+}
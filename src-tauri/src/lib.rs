@@ -1,7 +1,48 @@
 mod video_processor;
 mod video_frame_extractor;
+mod analysis_cache;
+mod preanalysis;
 mod frame_similarity;
 mod downloader;
+mod settings;
+mod reframe;
+mod audio_analysis;
+mod audio_join;
+mod jobs;
+mod path_scope;
+mod stats;
+mod report;
+mod sessions;
+mod batch;
+mod scheduler;
+mod throttle;
+mod benchmark;
+mod diagnostics;
+mod ending_check;
+mod i18n;
+mod events;
+mod shutdown;
+mod clip_order;
+mod beat_sync;
+mod effects;
+mod mezzanine;
+mod channel_layout;
+mod overlay;
+mod highlights;
+mod source_date;
+mod catalog;
+mod tags;
+mod collections;
+mod transition;
+mod join_check;
+mod gop;
+mod hw_encoder;
+mod export_preset;
+mod detection_profile;
+mod outro_template;
+mod farm;
+
+use tauri::Manager;
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
@@ -11,28 +52,143 @@ fn greet(name: &str) -> String {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let pool_manager = video_processor::VideoPoolManager::new();  // 新增
+    let mut builder = tauri::Builder::default();
+    // 只允许运行一个实例：视频池、批量进度和任务队列都是按单进程设计的，
+    // 再次启动时把已有窗口切到前台
+    #[cfg(desktop)]
+    {
+        builder = builder.plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.unminimize();
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+        }));
+    }
 
-    tauri::Builder::default()
-        .manage(pool_manager)  // 新增：注册全局状态
+    builder
+        .manage(jobs::JobQueue::new())
+        .manage(batch::BatchControl::new())
+        .manage(shutdown::ProcessRegistry::new())
+        .manage(farm::FarmState::new())
+        .manage(video_frame_extractor::MetadataProbeControl::new())
+        .manage(hw_encoder::EncoderDetector::new())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_shell::init())
+        .setup(|app| {
+            let config_dir = app.path().app_config_dir()?;
+            app.manage(settings::SettingsManager::load(config_dir.join("settings.json")));
+            app.manage(stats::StatsManager::load(config_dir.join("stats.json")));
+            // 视频池的抽取进度，崩溃重启后恢复
+            let data_dir = app.path().app_data_dir()?;
+            app.manage(video_processor::VideoPoolManager::load(data_dir.join("video_pools.json")));
+            app.manage(tags::TagManager::load(data_dir.join("clip_tags.json")));
+            app.manage(collections::CollectionManager::load(data_dir.join("collections.json")));
+            app.manage(outro_template::OutroTemplateManager::load(data_dir.join("outro_templates.json")));
+            let cache_dir = app.path().app_cache_dir()?;
+            app.manage(clip_order::ClipAnalysisCache::load(cache_dir.join("clip_analysis.json")));
+            app.manage(preanalysis::MetadataCache::load(cache_dir.join("video_info.json")));
+            app.manage(preanalysis::Preanalyzer::load(cache_dir.join("preanalysis.json")));
+            path_scope::install(app.handle());
+            // 恢复上次退出时未完成的任务队列
+            let _ = app.state::<jobs::JobQueue>().restore(app.handle());
+            jobs::start_worker(app.handle().clone());
+            preanalysis::start(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             video_processor::concat_videos,
             video_processor::concat_videos_with_reencode,
+            video_processor::render_selection_preview,
+            video_processor::retry_failed_runs,
+            video_processor::pool_create,
+            video_processor::pool_reset,
+            video_processor::pool_status,
+            video_processor::pool_list,
+            video_processor::pool_set_blacklist,
             video_frame_extractor::get_video_metadata,
+            video_frame_extractor::cancel_metadata_probe,
             video_frame_extractor::extract_all_frames,
             video_frame_extractor::generate_video_segments,
+            video_frame_extractor::get_cut_preview_frames,
             video_frame_extractor::list_mp4_files,
             video_frame_extractor::load_batch_progress,
             video_frame_extractor::save_batch_progress,
             video_frame_extractor::delete_video_file,
             video_frame_extractor::auto_split_video,
             video_frame_extractor::remove_ending_and_concat,
+            video_frame_extractor::append_ending_to_folder,
+            outro_template::learn_outro_template,
+            outro_template::list_outro_templates,
+            outro_template::delete_outro_template,
+            outro_template::remove_outro_by_template,
+            highlights::extract_highlights,
             downloader::batch_download,
+            downloader::plan_batch_download,
+            audio_analysis::analyze_pool_audio,
+            catalog::generate_catalog_thumbnails,
+            tags::add_clip_tags,
+            tags::remove_clip_tags,
+            tags::get_clip_tags,
+            tags::apply_tag_rules,
+            collections::list_collections,
+            collections::save_collection,
+            collections::add_collection_clips,
+            collections::remove_collection_clips,
+            collections::delete_collection,
+            jobs::enqueue_job,
+            jobs::list_jobs,
+            jobs::reorder_job,
+            jobs::set_job_priority,
+            jobs::cancel_job,
+            farm::start_farm_coordinator,
+            farm::stop_farm_coordinator,
+            farm::start_farm_worker,
+            farm::stop_farm_worker,
+            farm::get_farm_status,
+            scheduler::save_scheduler_settings,
+            throttle::save_throttle_settings,
+            gop::save_gop_settings,
+            frame_similarity::save_similarity_settings,
+            frame_similarity::compare_images,
+            hw_encoder::list_hw_encoders,
+            export_preset::list_export_presets,
+            export_preset::save_export_preset,
+            export_preset::delete_export_preset,
+            detection_profile::list_detection_profiles,
+            detection_profile::save_detection_profile,
+            detection_profile::delete_detection_profile,
+            mezzanine::get_mezzanine_cache_size,
+            mezzanine::clear_mezzanine_cache,
+            analysis_cache::get_analysis_cache_size,
+            analysis_cache::clear_analysis_cache,
+            preanalysis::save_preanalysis_settings,
+            preanalysis::get_preanalysis_status,
+            benchmark::run_benchmark,
+            benchmark::get_benchmark_result,
+            diagnostics::run_self_test,
+            i18n::set_locale,
+            events::get_ipc_info,
+            settings::get_settings,
+            settings::list_brand_kits,
+            settings::save_brand_kit,
+            settings::delete_brand_kit,
+            stats::get_statistics,
+            report::export_batch_report,
+            sessions::load_segmentation_session,
+            sessions::save_segmentation_session,
+            sessions::undo_segmentation_session,
+            batch::run_batch_auto_split,
+            batch::skip_current_video,
+            batch::abort_batch,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::ExitRequested { api, code, .. } = event {
+                shutdown::handle_exit_requested(app, &api, code);
+            }
+        });
 }
@@ -0,0 +1,305 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use ts_rs::TS;
+use crate::gop::{self, GopSettings};
+use crate::sessions::content_hash;
+use crate::throttle::{self, run_ffmpeg};
+use crate::video_processor::{EncodeSettings, VideoCodec, VideoInfo};
+
+/// 中间文件的统一音频参数
+const AUDIO_SAMPLE_RATE: &str = "48000";
+/// 中间文件的统一时间基，保证流复制拼接时时间戳连续
+const VIDEO_TIMESCALE: &str = "90000";
+/// 中间文件固定的编码预设、像素格式与音频码率（kbps）
+const PRESET: &str = "fast";
+const PIX_FMT: &str = "yuv420p";
+const AUDIO_BITRATE: u32 = 192;
+
+/// 中间文件规格：池子片段统一转成该规格后可直接流复制拼接
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct MezzanineProfile {
+    pub width: u32,
+    pub height: u32,
+    pub fps: u32,
+    #[serde(default)]
+    pub codec: VideoCodec,
+    #[serde(default)]
+    pub crf: Option<u32>,
+}
+
+impl MezzanineProfile {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.width == 0 || self.height == 0 || !self.width.is_multiple_of(2) || !self.height.is_multiple_of(2) {
+            return Err("中间文件的宽高必须为大于 0 的偶数".to_string());
+        }
+        if self.fps == 0 {
+            return Err("中间文件的帧率必须大于 0".to_string());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn crf(&self) -> u32 {
+        self.crf.unwrap_or_else(|| self.codec.default_crf())
+    }
+
+    /// 拼接的编码设置与中间文件的编码参数不一致时返回原因（此时不能直接流复制）
+    pub fn encode_mismatch(&self, settings: &EncodeSettings) -> Option<&'static str> {
+        if settings.crf.is_some_and(|crf| crf != self.crf()) || settings.video_bitrate.is_some() {
+            return Some("画质或码率设置与中间文件规格不一致");
+        }
+        if settings.preset.as_deref().is_some_and(|p| p != PRESET) {
+            return Some("编码预设与中间文件规格不一致");
+        }
+        if settings.pix_fmt.as_deref().is_some_and(|p| p != PIX_FMT) {
+            return Some("像素格式与中间文件规格不一致");
+        }
+        if settings.audio_bitrate.is_some_and(|b| b != AUDIO_BITRATE) {
+            return Some("音频码率与中间文件规格不一致");
+        }
+        None
+    }
+
+    /// 缓存子目录名，规格不同的中间文件分开存放
+    fn key(&self) -> String {
+        format!(
+            "{}x{}_{}fps_{}_crf{}",
+            self.width,
+            self.height,
+            self.fps,
+            self.codec.encoder(),
+            self.crf()
+        )
+    }
+}
+
+/// 中间文件缓存目录（应用缓存目录下）
+fn cache_root(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_cache_dir()
+        .map(|dir| dir.join("mezzanine"))
+        .map_err(|e| format!("无法获取缓存目录: {}", e))
+}
+
+/// 把片段转成中间文件规格：等比缩放补边、统一帧率、编码与音频格式，没有音轨的补静音
+async fn normalize(
+    app: &AppHandle,
+    source: &Path,
+    info: &VideoInfo,
+    profile: &MezzanineProfile,
+    gop: &GopSettings,
+    output: &Path,
+) -> Result<(), String> {
+    let (w, h) = (profile.width, profile.height);
+    let mut args: Vec<String> = vec!["-i".to_string(), source.to_string_lossy().to_string()];
+    let audio_map = if info.has_audio {
+        format!("0:a:{}", info.audio_stream_index)
+    } else {
+        args.extend([
+            "-f".to_string(),
+            "lavfi".to_string(),
+            "-i".to_string(),
+            format!("anullsrc=channel_layout=stereo:sample_rate={}", AUDIO_SAMPLE_RATE),
+        ]);
+        "1:a:0".to_string()
+    };
+    args.extend([
+        "-map".to_string(),
+        format!("0:v:{}", info.video_stream_index),
+        "-map".to_string(),
+        audio_map,
+        "-vf".to_string(),
+        format!(
+            "scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2,setsar=1,fps={fps},format={PIX_FMT}",
+            fps = profile.fps
+        ),
+        "-af".to_string(),
+        format!(
+            "{}aformat=sample_rates={}:channel_layouts=stereo",
+            info.channel_layout().map(|l| l.stereo_filter()).unwrap_or_default(),
+            AUDIO_SAMPLE_RATE
+        ),
+        "-c:v".to_string(),
+        profile.codec.encoder().to_string(),
+        "-preset".to_string(),
+        PRESET.to_string(),
+        "-crf".to_string(),
+        profile.crf().to_string(),
+    ]);
+    args.extend(gop.encoder_args(profile.codec, Some(profile.fps as f64)));
+    if profile.codec == VideoCodec::Hevc {
+        args.extend(["-tag:v".to_string(), "hvc1".to_string()]);
+    }
+    args.extend([
+        "-video_track_timescale".to_string(),
+        VIDEO_TIMESCALE.to_string(),
+        "-c:a".to_string(),
+        "aac".to_string(),
+        "-b:a".to_string(),
+        format!("{}k", AUDIO_BITRATE),
+        "-t".to_string(),
+        format!("{:.6}", info.duration),
+        "-f".to_string(),
+        "mp4".to_string(),
+        "-y".to_string(),
+    ]);
+    args.extend(throttle::current(app).output_args());
+    args.push(output.to_string_lossy().to_string());
+
+    let result = run_ffmpeg(app, args).await?;
+    if !result.success() {
+        return Err(format!(
+            "生成中间文件失败: {}",
+            String::from_utf8_lossy(&result.stderr)
+        ));
+    }
+    Ok(())
+}
+
+/// 获取片段的中间文件，缓存中没有时先生成
+pub async fn ensure_cached(
+    app: &AppHandle,
+    source: &Path,
+    info: &VideoInfo,
+    profile: &MezzanineProfile,
+) -> Result<PathBuf, String> {
+    let gop = gop::current(app);
+    let dir = cache_root(app)?.join(format!("{}{}", profile.key(), gop.key_suffix()));
+    // 指定了非默认的流时，同一文件会得到不同的中间文件
+    let streams = match (info.video_stream_index, info.has_audio, info.audio_stream_index) {
+        (0, true, 0) => String::new(),
+        (video, true, audio) => format!("_v{}a{}", video, audio),
+        (video, false, _) => format!("_v{}", video),
+    };
+    let cached = dir.join(format!("{}{}.mp4", content_hash(source)?, streams));
+    if cached.exists() {
+        return Ok(cached);
+    }
+    fs::create_dir_all(&dir).map_err(|e| format!("创建缓存目录失败: {}", e))?;
+
+    // 先写临时文件再改名，并行的输出同时转换同一片段时不会读到半成品
+    let partial = dir.join(format!("{}.{}.part", cached.file_stem().unwrap().to_string_lossy(), rand::random::<u32>()));
+    if let Err(e) = normalize(app, source, info, profile, &gop, &partial).await {
+        let _ = fs::remove_file(&partial);
+        return Err(e);
+    }
+    fs::rename(&partial, &cached).map_err(|e| format!("写入缓存失败: {}", e))?;
+    Ok(cached)
+}
+
+/// 用 concat 分离器流复制拼接同规格的中间文件，可指定输出的 creation_time
+pub async fn concat_copy(
+    app: &AppHandle,
+    clips: &[PathBuf],
+    output: &Path,
+    creation_time: Option<&str>,
+) -> Result<(), String> {
+    let list_path = output.with_extension("concat.txt");
+    let list: String = clips
+        .iter()
+        .map(|clip| format!("file '{}'\n", clip.to_string_lossy().replace('\'', "'\\''")))
+        .collect();
+    fs::write(&list_path, list).map_err(|e| format!("写入拼接列表失败: {}", e))?;
+
+    let mut args: Vec<String> = [
+        "-f",
+        "concat",
+        "-safe",
+        "0",
+        "-i",
+        &list_path.to_string_lossy(),
+        "-c",
+        "copy",
+        "-movflags",
+        "+faststart",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    if let Some(time) = creation_time {
+        args.extend(["-metadata".to_string(), format!("creation_time={}", time)]);
+    }
+    args.extend(["-y".to_string(), output.to_string_lossy().to_string()]);
+    let result = run_ffmpeg(app, args).await;
+    let _ = fs::remove_file(&list_path);
+
+    let result = result?;
+    if !result.success() {
+        return Err(format!(
+            "FFmpeg 执行失败: {}",
+            String::from_utf8_lossy(&result.stderr)
+        ));
+    }
+    Ok(())
+}
+
+/// 缓存目录占用的空间（字节）
+fn dir_size(dir: &Path) -> u64 {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum()
+}
+
+// 获取中间文件缓存占用的空间（字节）
+#[tauri::command]
+pub fn get_mezzanine_cache_size(app: AppHandle) -> Result<u64, String> {
+    Ok(dir_size(&cache_root(&app)?))
+}
+
+// 清空中间文件缓存，返回释放的空间（字节）
+#[tauri::command]
+pub fn clear_mezzanine_cache(app: AppHandle) -> Result<u64, String> {
+    let root = cache_root(&app)?;
+    let size = dir_size(&root);
+    if root.exists() {
+        fs::remove_dir_all(&root).map_err(|e| format!("清理缓存目录失败: {}", e))?;
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile() -> MezzanineProfile {
+        MezzanineProfile {
+            width: 1920,
+            height: 1080,
+            fps: 30,
+            codec: VideoCodec::H264,
+            crf: None,
+        }
+    }
+
+    #[test]
+    fn default_encode_settings_match() {
+        assert_eq!(profile().encode_mismatch(&EncodeSettings::default()), None);
+        let same = EncodeSettings {
+            crf: Some(23),
+            audio_bitrate: Some(192),
+            pix_fmt: Some("yuv420p".to_string()),
+            ..EncodeSettings::default()
+        };
+        assert_eq!(profile().encode_mismatch(&same), None);
+    }
+
+    #[test]
+    fn differing_encode_settings_block_copy() {
+        let settings = [
+            EncodeSettings { crf: Some(18), ..EncodeSettings::default() },
+            EncodeSettings { video_bitrate: Some(8000), ..EncodeSettings::default() },
+            EncodeSettings { pix_fmt: Some("yuv420p10le".to_string()), ..EncodeSettings::default() },
+            EncodeSettings { audio_bitrate: Some(320), ..EncodeSettings::default() },
+            EncodeSettings { preset: Some("slow".to_string()), ..EncodeSettings::default() },
+        ];
+        for settings in &settings {
+            assert!(profile().encode_mismatch(settings).is_some(), "{:?}", settings);
+        }
+    }
+}
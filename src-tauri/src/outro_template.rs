@@ -0,0 +1,440 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_shell::ShellExt;
+use ts_rs::TS;
+use crate::audio_join::{decode_pcm, SAMPLE_RATE};
+use crate::events::REMOVE_ENDING_PROGRESS;
+use crate::i18n::{localize_error, progress};
+use crate::path_scope::{check_input, check_output};
+use crate::stats::record_operation;
+use crate::video_frame_extractor::{append_ending, list_mp4_files};
+use crate::video_processor::{get_video_info, ClipTrim};
+
+/// 指纹的取样间隔（秒）
+const STEP: f64 = 0.5;
+/// 指纹画面的边长（灰度）
+const SAMPLE_SIZE: usize = 32;
+/// 平均像素差达到该值时画面相似度为 0（0-255）
+const DIFF_SCALE: f64 = 64.0;
+/// 低于该响度的音频按静音处理（dB）
+const SILENCE_DB: f64 = -60.0;
+/// 综合相似度中音频所占的比重
+const AUDIO_WEIGHT: f64 = 0.3;
+/// 匹配时在片尾长度之外多搜索的秒数（片尾后可能还有黑场等内容）
+const SEARCH_MARGIN: f64 = 10.0;
+/// 默认的最低匹配相似度
+const DEFAULT_THRESHOLD: f64 = 0.8;
+
+/// 标注了片尾区间的示例视频
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct OutroExample {
+    pub video_path: String,
+    /// 片尾开始时间（秒）
+    pub start: f64,
+    /// 片尾结束时间（秒），为空表示到视频结尾
+    #[serde(default)]
+    pub end: Option<f64>,
+}
+
+/// 片尾模板：多个示例片尾按时间对齐后取平均的画面与响度指纹
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutroTemplate {
+    pub name: String,
+    /// 示例片尾的平均时长（秒）
+    pub duration: f64,
+    pub examples: usize,
+    /// 每 `STEP` 秒一帧的缩略灰度画面
+    pub frames: Vec<Vec<u8>>,
+    /// 每 `STEP` 秒的响度（dB），示例没有音轨时为空
+    #[serde(default)]
+    pub audio: Vec<f64>,
+}
+
+/// 片尾模板概要（不含指纹数据）
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct OutroTemplateInfo {
+    pub name: String,
+    pub duration: f64,
+    pub examples: usize,
+    pub has_audio: bool,
+}
+
+impl OutroTemplate {
+    fn info(&self) -> OutroTemplateInfo {
+        OutroTemplateInfo {
+            name: self.name.clone(),
+            duration: self.duration,
+            examples: self.examples,
+            has_audio: !self.audio.is_empty(),
+        }
+    }
+}
+
+/// 片尾模板，保存在数据目录的 outro_templates.json
+pub struct OutroTemplateManager {
+    path: PathBuf,
+    templates: Mutex<BTreeMap<String, OutroTemplate>>,
+}
+
+impl OutroTemplateManager {
+    pub fn load(path: PathBuf) -> Self {
+        let templates = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            templates: Mutex::new(templates),
+        }
+    }
+
+    /// 保存所有模板（在持有锁时调用），写入失败不影响业务
+    fn save(&self, templates: &BTreeMap<String, OutroTemplate>) {
+        if let Ok(content) = serde_json::to_string(templates) {
+            if let Some(parent) = self.path.parent() {
+                let _ = fs::create_dir_all(parent);
+            }
+            let _ = fs::write(&self.path, content);
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Result<OutroTemplate, String> {
+        self.templates
+            .lock()
+            .unwrap()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("片尾模板不存在: {}", name))
+    }
+}
+
+/// 解码一段画面为每 `STEP` 秒一帧的缩略灰度图
+async fn frame_fingerprint(app: &AppHandle, path: &Path, start: f64, duration: f64) -> Result<Vec<Vec<u8>>, String> {
+    let sidecar = app
+        .shell()
+        .sidecar("ffmpeg")
+        .map_err(|e| format!("FFmpeg 启动失败: {}", e))?;
+
+    let output = sidecar
+        .args([
+            "-v",
+            "error",
+            "-ss",
+            &format!("{:.3}", start.max(0.0)),
+            "-i",
+            &path.to_string_lossy(),
+            "-t",
+            &format!("{:.3}", duration),
+            "-map",
+            "0:v:0",
+            "-vf",
+            &format!("fps={},scale={1}:{1},format=gray", 1.0 / STEP, SAMPLE_SIZE),
+            "-f",
+            "rawvideo",
+            "-",
+        ])
+        .output()
+        .await
+        .map_err(|e| format!("FFmpeg 执行失败: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "解码片尾画面失败: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(output
+        .stdout
+        .chunks_exact(SAMPLE_SIZE * SAMPLE_SIZE)
+        .map(|frame| frame.to_vec())
+        .collect())
+}
+
+/// 每 `STEP` 秒的响度（dB），没有音轨时为空
+async fn audio_fingerprint(app: &AppHandle, path: &Path, start: f64, duration: f64) -> Result<Vec<f64>, String> {
+    let samples = decode_pcm(app, path, start, duration).await?;
+    Ok(samples
+        .chunks_exact((STEP * SAMPLE_RATE) as usize)
+        .map(|chunk| {
+            let rms = (chunk.iter().map(|s| (*s as f64).powi(2)).sum::<f64>() / chunk.len() as f64).sqrt();
+            (20.0 * rms.log10()).max(SILENCE_DB)
+        })
+        .collect())
+}
+
+/// 逐位置取平均，长度按最短的一组截齐
+fn average<T>(groups: &[Vec<T>], mean: impl Fn(&[&T]) -> T) -> Vec<T> {
+    let len = groups.iter().map(Vec::len).min().unwrap_or(0);
+    (0..len)
+        .map(|i| mean(&groups.iter().map(|g| &g[i]).collect::<Vec<_>>()))
+        .collect()
+}
+
+fn frame_similarity(a: &[u8], b: &[u8]) -> f64 {
+    let diff = a.iter().zip(b).map(|(x, y)| x.abs_diff(*y) as f64).sum::<f64>() / a.len() as f64;
+    (1.0 - diff / DIFF_SCALE).max(0.0)
+}
+
+/// 在视频结尾附近滑动比对模板，返回最相似位置要去掉的结尾时长（片尾开始到视频结束）与相似度
+async fn find_outro(app: &AppHandle, template: &OutroTemplate, video_path: &Path) -> Result<Option<(f64, f64)>, String> {
+    let duration = get_video_info(app, video_path).await?.duration;
+    let window_start = (duration - template.duration - SEARCH_MARGIN).max(0.0);
+    let frames = frame_fingerprint(app, video_path, window_start, duration - window_start).await?;
+    let audio = if template.audio.is_empty() {
+        Vec::new()
+    } else {
+        audio_fingerprint(app, video_path, window_start, duration - window_start).await?
+    };
+
+    let len = template.frames.len();
+    if len == 0 || frames.len() < len {
+        return Ok(None);
+    }
+    let best = (0..=frames.len() - len)
+        .map(|offset| {
+            let picture = (0..len)
+                .map(|i| frame_similarity(&frames[offset + i], &template.frames[i]))
+                .sum::<f64>()
+                / len as f64;
+            let aligned: Vec<f64> = template
+                .audio
+                .iter()
+                .zip(audio.iter().skip(offset))
+                .map(|(t, a)| 1.0 - (t - a).abs() / -SILENCE_DB)
+                .collect();
+            let score = if aligned.is_empty() {
+                picture
+            } else {
+                picture * (1.0 - AUDIO_WEIGHT) + aligned.iter().sum::<f64>() / aligned.len() as f64 * AUDIO_WEIGHT
+            };
+            (duration - window_start - offset as f64 * STEP, score)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1));
+    Ok(best)
+}
+
+// 从标注了片尾区间的示例视频学习片尾模板（同名模板会被覆盖）
+#[tauri::command]
+pub async fn learn_outro_template(
+    app: AppHandle,
+    name: String,
+    examples: Vec<OutroExample>,
+) -> Result<OutroTemplateInfo, String> {
+    let result = learn_outro_template_internal(&app, name, examples).await;
+    record_operation(&app, "learn_outro", &result);
+    result.map_err(|e| localize_error(&app, e))
+}
+
+async fn learn_outro_template_internal(
+    app: &AppHandle,
+    name: String,
+    examples: Vec<OutroExample>,
+) -> Result<OutroTemplateInfo, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("片尾模板名称不能为空".to_string());
+    }
+    if examples.is_empty() {
+        return Err("至少需要一个示例视频".to_string());
+    }
+
+    let mut frame_groups = Vec::new();
+    let mut audio_groups = Vec::new();
+    let mut total_duration = 0.0;
+    for (idx, example) in examples.iter().enumerate() {
+        check_input(app, &example.video_path)?;
+        let path = Path::new(&example.video_path);
+        let _ = app.emit(
+            REMOVE_ENDING_PROGRESS,
+            progress(
+                app,
+                "outro.learning",
+                &[(idx + 1).to_string(), examples.len().to_string()],
+                (idx * 100 / examples.len()) as u32,
+            ),
+        );
+        let info = get_video_info(app, path).await?;
+        let trim = ClipTrim {
+            start: Some(example.start),
+            end: example.end,
+        };
+        trim.validate(&example.video_path, info.duration)?;
+        let duration = trim.trimmed_duration(info.duration);
+        frame_groups.push(frame_fingerprint(app, path, example.start, duration).await?);
+        audio_groups.push(audio_fingerprint(app, path, example.start, duration).await?);
+        total_duration += duration;
+    }
+
+    let frames = average(&frame_groups, |group| {
+        (0..group[0].len())
+            .map(|p| (group.iter().map(|f| f[p] as u32).sum::<u32>() / group.len() as u32) as u8)
+            .collect()
+    });
+    if frames.is_empty() {
+        return Err("示例片尾过短，无法生成模板".to_string());
+    }
+    // 任一示例没有音轨时只比对画面
+    let audio = average(&audio_groups, |group| group.iter().copied().sum::<f64>() / group.len() as f64);
+
+    let template = OutroTemplate {
+        name: name.clone(),
+        duration: total_duration / examples.len() as f64,
+        examples: examples.len(),
+        frames,
+        audio,
+    };
+    let info = template.info();
+    let manager = app.state::<OutroTemplateManager>();
+    let mut templates = manager.templates.lock().unwrap();
+    templates.insert(name, template);
+    manager.save(&templates);
+    Ok(info)
+}
+
+// 获取所有片尾模板
+#[tauri::command]
+pub fn list_outro_templates(manager: State<'_, OutroTemplateManager>) -> Vec<OutroTemplateInfo> {
+    manager.templates.lock().unwrap().values().map(OutroTemplate::info).collect()
+}
+
+// 删除片尾模板
+#[tauri::command]
+pub fn delete_outro_template(manager: State<'_, OutroTemplateManager>, name: String) -> Result<(), String> {
+    let mut templates = manager.templates.lock().unwrap();
+    if templates.remove(&name).is_none() {
+        return Err(format!("片尾模板不存在: {}", name));
+    }
+    manager.save(&templates);
+    Ok(())
+}
+
+// 按片尾模板批量处理文件夹：找到片尾后去掉，可追加新结尾；未匹配到的视频跳过
+#[tauri::command]
+pub async fn remove_outro_by_template(
+    app: AppHandle,
+    template: String,
+    input_dir: String,
+    output_dir: String,
+    new_ending_video: Option<String>,
+    new_ending_trim: Option<ClipTrim>,
+    threshold: Option<f64>,
+) -> Result<String, String> {
+    let result = remove_outro_by_template_internal(
+        &app,
+        template,
+        input_dir,
+        output_dir,
+        new_ending_video,
+        new_ending_trim,
+        threshold.unwrap_or(DEFAULT_THRESHOLD),
+    )
+    .await;
+    record_operation(&app, "remove_outro", &result);
+    result.map_err(|e| localize_error(&app, e))
+}
+
+async fn remove_outro_by_template_internal(
+    app: &AppHandle,
+    template: String,
+    input_dir: String,
+    output_dir: String,
+    new_ending_video: Option<String>,
+    new_ending_trim: Option<ClipTrim>,
+    threshold: f64,
+) -> Result<String, String> {
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err("匹配相似度必须在 0 到 1 之间".to_string());
+    }
+    let template = app.state::<OutroTemplateManager>().get(&template)?;
+    check_output(app, &output_dir)?;
+    let ending_path = match new_ending_video.filter(|p| !p.is_empty()) {
+        Some(ending) => {
+            check_input(app, &ending)?;
+            let path = PathBuf::from(&ending);
+            if !path.exists() {
+                return Err(format!("新结尾视频不存在: {}", ending));
+            }
+            Some(path)
+        }
+        None => None,
+    };
+
+    let videos = list_mp4_files(app.clone(), input_dir)?;
+    if videos.is_empty() {
+        return Err("文件夹中没有 MP4 文件".to_string());
+    }
+
+    let total = videos.len();
+    let mut failures = Vec::new();
+    for (idx, video) in videos.iter().enumerate() {
+        let path = Path::new(video);
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let _ = app.emit(
+            REMOVE_ENDING_PROGRESS,
+            progress(
+                app,
+                "outro.matching",
+                &[(idx + 1).to_string(), total.to_string(), name.clone()],
+                (idx * 100 / total) as u32,
+            ),
+        );
+
+        let result = match find_outro(app, &template, path).await {
+            Ok(Some((trim_end, score))) if score >= threshold => {
+                let ending = ending_path.as_deref().map(|p| (p, new_ending_trim));
+                append_ending(app, video, &output_dir, trim_end, ending).await
+            }
+            Ok(Some((_, score))) => Err(format!("未匹配到片尾（最高相似度 {:.2}），已跳过", score)),
+            Ok(None) => Err("视频短于片尾模板，已跳过".to_string()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            failures.push(format!("{}: {}", name, e));
+        }
+    }
+
+    let _ = app.emit(
+        REMOVE_ENDING_PROGRESS,
+        progress(app, "common.done", &[], 100),
+    );
+
+    if failures.is_empty() {
+        Ok(format!("已处理 {} 个视频，输出目录: {}", total, output_dir))
+    } else {
+        Ok(format!(
+            "已处理 {} 个视频，未处理 {} 个：\n{}",
+            total - failures.len(),
+            failures.len(),
+            failures.join("\n")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn average_truncates_to_shortest_example() {
+        let groups = vec![vec![1.0, 2.0, 3.0], vec![3.0, 4.0]];
+        let mean = average(&groups, |values: &[&f64]| values.iter().copied().sum::<f64>() / values.len() as f64);
+        assert_eq!(mean, vec![2.0, 3.0]);
+    }
+
+    #[test]
+    fn frame_similarity_scales_mean_difference() {
+        assert_eq!(frame_similarity(&[10, 20], &[10, 20]), 1.0);
+        assert_eq!(frame_similarity(&[0, 0], &[32, 32]), 0.5);
+        assert_eq!(frame_similarity(&[0, 0], &[255, 255]), 0.0);
+    }
+}
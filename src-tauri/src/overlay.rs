@@ -0,0 +1,112 @@
+use std::path::Path;
+use tauri::AppHandle;
+use tauri_plugin_shell::ShellExt;
+use crate::video_processor::WatermarkConfig;
+
+/// 静态图片的编码（只有一帧，不需要循环）
+const STILL_IMAGE_CODECS: [&str; 5] = ["png", "mjpeg", "bmp", "tiff", "webp"];
+
+/// 叠加素材（水印等）的探测结果
+#[derive(Debug, Clone)]
+pub struct OverlayAsset {
+    pub codec: String,
+    pub pix_fmt: String,
+    /// 素材带透明通道
+    pub has_alpha: bool,
+    /// 动态素材（视频、GIF），叠加时循环播放
+    pub animated: bool,
+}
+
+impl OverlayAsset {
+    /// 素材输入前的参数
+    ///
+    /// WebM 中 VP8/VP9 的透明通道存放在附加数据里，FFmpeg 自带的解码器会丢弃，只有 libvpx 能解出
+    pub fn input_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.has_alpha {
+            match self.codec.as_str() {
+                "vp8" => args.extend(["-c:v".to_string(), "libvpx".to_string()]),
+                "vp9" => args.extend(["-c:v".to_string(), "libvpx-vp9".to_string()]),
+                _ => {}
+            }
+        }
+        if self.animated {
+            args.extend(["-stream_loop".to_string(), "-1".to_string()]);
+        }
+        args
+    }
+}
+
+/// 像素格式是否带透明通道（调色板格式的 PNG 可能带透明色，按带透明处理）
+fn pix_fmt_has_alpha(pix_fmt: &str) -> bool {
+    pix_fmt.starts_with("yuva")
+        || pix_fmt.starts_with("gbrap")
+        || pix_fmt.starts_with("ya")
+        || pix_fmt == "pal8"
+        || ["rgba", "bgra", "argb", "abgr", "rgba64", "bgra64"]
+            .iter()
+            .any(|f| pix_fmt.starts_with(f))
+}
+
+/// 探测叠加素材的编码与透明通道
+pub async fn probe_overlay(app: &AppHandle, path: &Path) -> Result<OverlayAsset, String> {
+    let sidecar = app
+        .shell()
+        .sidecar("ffprobe")
+        .map_err(|e| format!("FFprobe 启动失败: {}", e))?;
+
+    let output = sidecar
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "v:0",
+            "-show_entries",
+            "stream=codec_name,pix_fmt:stream_tags=alpha_mode",
+            "-of",
+            "json",
+            &path.to_string_lossy(),
+        ])
+        .output()
+        .await
+        .map_err(|e| format!("FFprobe 执行失败: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "FFprobe 执行失败: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("解析 FFprobe 输出失败: {}", e))?;
+    let stream = json["streams"]
+        .as_array()
+        .and_then(|streams| streams.first())
+        .ok_or(format!("叠加素材中没有图像: {}", path.display()))?;
+
+    let codec = stream["codec_name"].as_str().unwrap_or("unknown").to_string();
+    let pix_fmt = stream["pix_fmt"].as_str().unwrap_or("").to_string();
+    // WebM 的透明通道不体现在像素格式上，由 alpha_mode 标签标记
+    let alpha_mode = stream["tags"]
+        .as_object()
+        .and_then(|tags| tags.iter().find(|(k, _)| k.eq_ignore_ascii_case("alpha_mode")))
+        .and_then(|(_, v)| v.as_str())
+        == Some("1");
+
+    Ok(OverlayAsset {
+        has_alpha: alpha_mode || pix_fmt_has_alpha(&pix_fmt),
+        animated: !STILL_IMAGE_CODECS.contains(&codec.as_str()),
+        codec,
+        pix_fmt,
+    })
+}
+
+/// 探测水印素材并记录到配置中
+pub async fn inspect_watermark<'a>(
+    app: &AppHandle,
+    watermark: &'a mut WatermarkConfig,
+) -> Result<&'a OverlayAsset, String> {
+    let asset = probe_overlay(app, Path::new(&watermark.image_path)).await?;
+    Ok(watermark.asset.insert(asset))
+}
@@ -0,0 +1,272 @@
+use serde::Serialize;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use tauri::scope::fs::Event;
+use tauri::{AppHandle, Manager};
+use crate::i18n::t;
+use crate::settings::SettingsManager;
+
+/// 路径校验失败的类型
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PathErrorKind {
+    /// 路径不在用户通过对话框授权的目录内
+    NotApproved,
+    /// 路径包含 `..` 等跳出目录的成分
+    Traversal,
+    /// 路径不存在
+    NotFound,
+    /// 相对路径或无法解析的路径
+    Invalid,
+}
+
+/// 结构化的路径权限错误，命令返回时序列化为 JSON 字符串，前端可按 `kind` 区分处理
+#[derive(Debug, Clone, Serialize)]
+pub struct PathError {
+    pub kind: PathErrorKind,
+    pub path: String,
+    pub message: String,
+}
+
+impl PathError {
+    /// `key` 为消息目录中的 key，消息按当前语言生成，路径作为唯一参数
+    fn new(app: &AppHandle, kind: PathErrorKind, path: &Path, key: &str) -> Self {
+        let path = path.to_string_lossy().to_string();
+        Self {
+            kind,
+            message: t(app, key, std::slice::from_ref(&path)),
+            path,
+        }
+    }
+}
+
+impl From<PathError> for String {
+    fn from(e: PathError) -> Self {
+        serde_json::to_string(&e).unwrap_or(e.message)
+    }
+}
+
+/// 监听对话框授权的路径（对话框选择文件/目录后会加入 asset 协议作用域），
+/// 记录到设置中，重启后仍然有效
+pub fn install(app: &AppHandle) {
+    let handle = app.clone();
+    app.asset_protocol_scope().listen(move |event| {
+        if let Event::PathAllowed(path) = event {
+            let _ = approve(&handle, path);
+        }
+    });
+}
+
+/// 应用自己创建的临时目录（如自检工作目录）的授权，只保存在内存中
+static TEMPORARY_APPROVALS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// 临时授权，离开作用域时撤销
+pub struct TemporaryApproval(PathBuf);
+
+impl Drop for TemporaryApproval {
+    fn drop(&mut self) {
+        let mut approvals = TEMPORARY_APPROVALS.lock().unwrap();
+        if let Some(pos) = approvals.iter().position(|p| *p == self.0) {
+            approvals.remove(pos);
+        }
+    }
+}
+
+/// 临时把应用自己创建的目录加入授权范围（不写入设置），返回的守卫释放时撤销
+pub fn approve_temporarily(path: &Path) -> Result<TemporaryApproval, String> {
+    let path = path
+        .canonicalize()
+        .map_err(|e| format!("无法解析路径 {}: {}", path.display(), e))?;
+    TEMPORARY_APPROVALS.lock().unwrap().push(path.clone());
+    Ok(TemporaryApproval(path))
+}
+
+/// 从保存的授权范围中移除路径（路径不存在或未授权时忽略）
+pub fn revoke(app: &AppHandle, path: &Path) -> Result<(), String> {
+    let Ok(path) = path.canonicalize() else {
+        return Ok(());
+    };
+    let path = path.to_string_lossy().to_string();
+    app.state::<SettingsManager>().update(|s| {
+        s.approved_paths.retain(|p| *p != path);
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// 把对话框选择的路径加入授权范围并保存到设置
+pub fn approve(app: &AppHandle, path: &Path) -> Result<(), String> {
+    let path = path
+        .canonicalize()
+        .map_err(|e| format!("无法解析路径 {}: {}", path.display(), e))?
+        .to_string_lossy()
+        .to_string();
+    app.state::<SettingsManager>().update(|s| {
+        if !s.approved_paths.contains(&path) {
+            s.approved_paths.push(path);
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// 检查已规范化的路径是否位于授权目录（或就是授权文件）内
+fn ensure_approved(app: &AppHandle, original: &Path, resolved: PathBuf) -> Result<PathBuf, PathError> {
+    let approved = app
+        .state::<SettingsManager>()
+        .get()
+        .approved_paths
+        .iter()
+        .any(|root| resolved.starts_with(root))
+        || TEMPORARY_APPROVALS
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|root| resolved.starts_with(root));
+    if approved {
+        Ok(resolved)
+    } else {
+        Err(PathError::new(app, PathErrorKind::NotApproved, original, "path.not_approved"))
+    }
+}
+
+/// 拒绝相对路径与包含 `..` 的路径
+fn check_components(app: &AppHandle, path: &Path) -> Result<(), PathError> {
+    if !path.is_absolute() {
+        return Err(PathError::new(app, PathErrorKind::Invalid, path, "path.not_absolute"));
+    }
+    if path.components().any(|c| matches!(c, Component::ParentDir)) {
+        return Err(PathError::new(app, PathErrorKind::Traversal, path, "path.traversal"));
+    }
+    Ok(())
+}
+
+/// 校验输入文件或目录：必须存在且位于授权范围内，返回规范化后的路径
+pub fn check_input(app: &AppHandle, path: &str) -> Result<PathBuf, PathError> {
+    let original = Path::new(path);
+    check_components(app, original)?;
+    let resolved = original
+        .canonicalize()
+        .map_err(|_| PathError::new(app, PathErrorKind::NotFound, original, "path.not_found"))?;
+    ensure_approved(app, original, resolved)
+}
+
+/// 校验输出路径（可以尚不存在）：按最近的已存在上级目录规范化后检查授权范围
+pub fn check_output(app: &AppHandle, path: &str) -> Result<PathBuf, PathError> {
+    let original = Path::new(path);
+    check_components(app, original)?;
+
+    let mut existing = original;
+    let mut rest = Vec::new();
+    while !existing.exists() {
+        let (Some(parent), Some(name)) = (existing.parent(), existing.file_name()) else {
+            return Err(PathError::new(app, PathErrorKind::Invalid, original, "path.invalid"));
+        };
+        rest.push(name.to_os_string());
+        existing = parent;
+    }
+
+    let mut resolved = existing
+        .canonicalize()
+        .map_err(|_| PathError::new(app, PathErrorKind::Invalid, original, "path.invalid"))?;
+    for name in rest.into_iter().rev() {
+        resolved.push(name);
+    }
+    ensure_approved(app, original, resolved)
+}
+
+/// 解码 URL 中的 `%XX` 转义
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+            if let Ok(b) = u8::from_str_radix(hex, 16) {
+                out.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+/// 文件名的最大字节数（多数文件系统的上限）
+const MAX_FILENAME_BYTES: usize = 255;
+
+/// Windows 保留的设备名，带扩展名时同样指向设备
+const RESERVED_NAMES: &[&str] = &["CON", "PRN", "AUX", "NUL"];
+
+/// 把 URL 等外部来源的名字清理成安全的文件名：解码转义、去掉目录成分与非法字符，
+/// 设备名前加下划线，过长时保留扩展名截断；结果为空时使用 `fallback`
+pub fn sanitize_filename(name: &str, fallback: &str) -> String {
+    let decoded = percent_decode(name);
+    let last = decoded.rsplit(['/', '\\']).next().unwrap_or("");
+    let cleaned: String = last
+        .chars()
+        .filter(|c| !c.is_control() && !matches!(c, '<' | '>' | ':' | '"' | '|' | '?' | '*'))
+        .collect();
+    let cleaned = cleaned.trim().trim_start_matches('.').trim_end_matches(['.', ' ']);
+    if cleaned.is_empty() {
+        return fallback.to_string();
+    }
+    let name = if is_reserved_name(cleaned) {
+        format!("_{}", cleaned)
+    } else {
+        cleaned.to_string()
+    };
+    truncate_filename(&name)
+}
+
+fn is_reserved_name(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or("").trim_end().to_ascii_uppercase();
+    RESERVED_NAMES.contains(&stem.as_str())
+        || (stem.len() == 4
+            && (stem.starts_with("COM") || stem.starts_with("LPT"))
+            && matches!(stem.as_bytes()[3], b'1'..=b'9'))
+}
+
+/// 截断到 `MAX_FILENAME_BYTES` 以内，扩展名不太长时保留扩展名
+fn truncate_filename(name: &str) -> String {
+    if name.len() <= MAX_FILENAME_BYTES {
+        return name.to_string();
+    }
+    let ext = name.rfind('.').map(|i| &name[i..]).filter(|e| e.len() <= 16).unwrap_or("");
+    let mut end = MAX_FILENAME_BYTES - ext.len();
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{}", name[..end].trim_end_matches(['.', ' ']), ext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filename_drops_directories_and_illegal_chars() {
+        assert_eq!(sanitize_filename("a%2F..%2Fb%3F.mp4", "x.mp4"), "b.mp4");
+        assert_eq!(sanitize_filename("..\\\\evil<>.mp4", "x.mp4"), "evil.mp4");
+        assert_eq!(sanitize_filename("...", "x.mp4"), "x.mp4");
+    }
+
+    #[test]
+    fn reserved_device_names_are_prefixed() {
+        assert_eq!(sanitize_filename("CON.mp4", "x.mp4"), "_CON.mp4");
+        assert_eq!(sanitize_filename("nul", "x.mp4"), "_nul");
+        assert_eq!(sanitize_filename("com1.tar.mp4", "x.mp4"), "_com1.tar.mp4");
+        assert_eq!(sanitize_filename("console.mp4", "x.mp4"), "console.mp4");
+        assert_eq!(sanitize_filename("COM10.mp4", "x.mp4"), "COM10.mp4");
+    }
+
+    #[test]
+    fn long_names_keep_extension() {
+        let name = sanitize_filename(&format!("{}.mp4", "视".repeat(100)), "x.mp4");
+        assert!(name.len() <= MAX_FILENAME_BYTES);
+        assert!(name.ends_with("视.mp4"));
+    }
+}
@@ -0,0 +1,426 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::Notify;
+use ts_rs::TS;
+use crate::analysis_cache;
+use crate::clip_order::{clip_traits, file_stamp};
+use crate::frame_similarity::{SimilarityAlgorithm, SimilarityTuning};
+use crate::jobs::JobQueue;
+use crate::path_scope::check_input;
+use crate::scheduler::{idle_seconds, IDLE_DETECTION_SUPPORTED};
+use crate::settings::SettingsManager;
+use crate::shutdown::ProcessRegistry;
+use crate::video_frame_extractor::preanalyze_frames;
+use crate::video_processor::{collect_videos, get_video_info, VideoInfo};
+
+/// 未启用、电脑不空闲或没有新文件时，再次检查的间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+/// 缓存文件的最短保存间隔：分析大量文件时不必每个文件都重写整个 JSON
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 素材池目录的后台预分析：空闲时为新文件预先计算元数据、逐帧相似度与画面特征，
+/// 之后的自动拆解、视频池筛选直接使用缓存
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct PreanalysisSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 需要预分析的素材池目录
+    #[serde(default)]
+    pub directories: Vec<String>,
+    /// 递归深度，与视频池一致
+    #[serde(default)]
+    pub max_depth: usize,
+    /// 无键鼠输入超过该分钟数才开始分析；为空时只要任务队列空闲即可
+    #[serde(default)]
+    pub idle_minutes: Option<u32>,
+    /// 预先计算逐帧相似度的算法（名称与自动拆解一致）
+    #[serde(default = "default_algorithms")]
+    pub algorithms: Vec<String>,
+}
+
+fn default_algorithms() -> Vec<String> {
+    vec!["ssim".to_string()]
+}
+
+impl Default for PreanalysisSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directories: Vec::new(),
+            max_depth: 0,
+            idle_minutes: None,
+            algorithms: default_algorithms(),
+        }
+    }
+}
+
+impl PreanalysisSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.idle_minutes == Some(0) {
+            return Err("空闲分钟数必须大于 0".to_string());
+        }
+        if self.idle_minutes.is_some() && !IDLE_DETECTION_SUPPORTED {
+            return Err("当前平台不支持检测空闲状态，请清空空闲分钟数".to_string());
+        }
+        for name in &self.algorithms {
+            SimilarityAlgorithm::from_name(name, &SimilarityTuning::default())?;
+        }
+        Ok(())
+    }
+
+    /// 已启用、任务队列空闲且电脑空闲足够久时才开始下一个文件（不会打断正在分析的文件），
+    /// 否则返回等待的原因；未启用时原因为空
+    fn block_reason(&self, app: &AppHandle) -> Result<(), Option<String>> {
+        if !self.enabled || self.directories.is_empty() {
+            return Err(None);
+        }
+        if !app.state::<JobQueue>().is_idle() {
+            return Err(Some("等待任务队列空闲".to_string()));
+        }
+        match (self.idle_minutes, idle_seconds()) {
+            (None, _) => Ok(()),
+            (Some(minutes), Some(idle)) if idle >= minutes as u64 * 60 => Ok(()),
+            (Some(minutes), Some(_)) => Err(Some(format!("等待电脑空闲 {} 分钟", minutes))),
+            (Some(_), None) => Err(Some("无法检测空闲状态，请清空空闲分钟数".to_string())),
+        }
+    }
+}
+
+/// 按间隔保存的 JSON 缓存文件：距离上次保存不足间隔时只记下有未保存的修改，由 `flush` 补写
+struct JsonFile {
+    path: PathBuf,
+    dirty: AtomicBool,
+    last_save: Mutex<Option<Instant>>,
+}
+
+impl JsonFile {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            dirty: AtomicBool::new(false),
+            last_save: Mutex::new(None),
+        }
+    }
+
+    /// 内容修改后调用，返回是否应当立即保存
+    fn changed(&self) -> bool {
+        let mut last_save = self.last_save.lock().unwrap();
+        if last_save.is_some_and(|t| t.elapsed() < SAVE_INTERVAL) {
+            self.dirty.store(true, Ordering::SeqCst);
+            return false;
+        }
+        *last_save = Some(Instant::now());
+        self.dirty.store(false, Ordering::SeqCst);
+        true
+    }
+
+    /// 有未保存的修改时返回 true，并视为已保存
+    fn take_dirty(&self) -> bool {
+        if !self.dirty.swap(false, Ordering::SeqCst) {
+            return false;
+        }
+        *self.last_save.lock().unwrap() = Some(Instant::now());
+        true
+    }
+
+    // 缓存写入失败不影响业务
+    fn write(&self, content: Option<String>) {
+        let Some(content) = content else {
+            return;
+        };
+        if let Some(parent) = self.path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let _ = fs::write(&self.path, content);
+    }
+}
+
+/// 缓存项：文件大小与修改时间不变时复用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedInfo {
+    size: u64,
+    modified: u64,
+    info: VideoInfo,
+}
+
+/// 视频元数据缓存，保存在应用缓存目录的 video_info.json；后台预分析写入，视频池筛选优先读取
+pub struct MetadataCache {
+    file: JsonFile,
+    entries: Mutex<HashMap<String, CachedInfo>>,
+}
+
+impl MetadataCache {
+    pub fn load(path: PathBuf) -> Self {
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            file: JsonFile::new(path),
+            entries: Mutex::new(entries),
+        }
+    }
+
+    fn get(&self, video: &Path) -> Option<VideoInfo> {
+        let (size, modified) = file_stamp(video)?;
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(&video.to_string_lossy().to_string())
+            .filter(|e| e.size == size && e.modified == modified)
+            .map(|e| e.info.clone())
+    }
+
+    fn insert(&self, video: &Path, info: VideoInfo) {
+        let Some((size, modified)) = file_stamp(video) else {
+            return;
+        };
+        // 持有锁时只序列化，写文件在释放锁之后
+        let content = {
+            let mut entries = self.entries.lock().unwrap();
+            entries.insert(
+                video.to_string_lossy().to_string(),
+                CachedInfo {
+                    size,
+                    modified,
+                    info,
+                },
+            );
+            self.file.changed().then(|| serde_json::to_string(&*entries).ok()).flatten()
+        };
+        self.file.write(content);
+    }
+
+    /// 保存尚未写入文件的缓存项
+    fn flush(&self) {
+        if self.file.take_dirty() {
+            let content = serde_json::to_string(&*self.entries.lock().unwrap()).ok();
+            self.file.write(content);
+        }
+    }
+}
+
+/// 获取视频信息（优先使用缓存）
+pub async fn video_info(app: &AppHandle, video_path: &Path) -> Result<VideoInfo, String> {
+    let cache = app.state::<MetadataCache>();
+    if let Some(info) = cache.get(video_path) {
+        return Ok(info);
+    }
+    let info = get_video_info(app, video_path).await?;
+    cache.insert(video_path, info.clone());
+    Ok(info)
+}
+
+/// 已预分析的文件：大小、修改时间与当时计算的相似度序列，
+/// 文件变化或算法参数调整后重新分析
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AnalyzedFile {
+    size: u64,
+    modified: u64,
+    series: Vec<String>,
+}
+
+/// 后台预分析进度
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[ts(export)]
+pub struct PreanalysisStatus {
+    /// 正在分析的文件
+    pub current: Option<String>,
+    /// 已完成预分析的文件数
+    pub analyzed: u32,
+    /// 上次扫描时等待分析的文件数
+    pub pending: u32,
+    /// 最近一次分析失败的文件与原因
+    pub last_error: Option<String>,
+    /// 已启用但暂不分析的原因
+    pub waiting: Option<String>,
+}
+
+/// 后台预分析状态，已分析的文件记录保存在应用缓存目录的 preanalysis.json
+pub struct Preanalyzer {
+    file: JsonFile,
+    analyzed: Mutex<HashMap<String, AnalyzedFile>>,
+    status: Mutex<PreanalysisStatus>,
+    notify: Notify,
+}
+
+impl Preanalyzer {
+    pub fn load(path: PathBuf) -> Self {
+        let analyzed: HashMap<String, AnalyzedFile> = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            file: JsonFile::new(path),
+            analyzed: Mutex::new(analyzed),
+            status: Mutex::new(PreanalysisStatus::default()),
+            notify: Notify::new(),
+        }
+    }
+
+    /// 文件未分析过、已变化或缺少某个算法的结果时需要分析
+    fn needs_analysis(&self, video: &Path, series: &[String]) -> bool {
+        let Some((size, modified)) = file_stamp(video) else {
+            return false;
+        };
+        let analyzed = self.analyzed.lock().unwrap();
+        !analyzed.get(&video.to_string_lossy().to_string()).is_some_and(|a| {
+            a.size == size && a.modified == modified && series.iter().all(|key| a.series.contains(key))
+        })
+    }
+
+    fn mark_analyzed(&self, video: &Path, series: Vec<String>) {
+        let Some((size, modified)) = file_stamp(video) else {
+            return;
+        };
+        let content = {
+            let mut analyzed = self.analyzed.lock().unwrap();
+            analyzed.insert(
+                video.to_string_lossy().to_string(),
+                AnalyzedFile {
+                    size,
+                    modified,
+                    series,
+                },
+            );
+            self.file.changed().then(|| serde_json::to_string(&*analyzed).ok()).flatten()
+        };
+        self.file.write(content);
+    }
+
+    fn flush(&self) {
+        if self.file.take_dirty() {
+            let content = serde_json::to_string(&*self.analyzed.lock().unwrap()).ok();
+            self.file.write(content);
+        }
+    }
+
+    fn update_status(&self, f: impl FnOnce(&mut PreanalysisStatus)) {
+        f(&mut self.status.lock().unwrap());
+    }
+}
+
+/// 扫描素材池目录，返回需要分析的文件（目录不存在时跳过）
+fn pending_videos(analyzer: &Preanalyzer, settings: &PreanalysisSettings, series: &[String]) -> Vec<PathBuf> {
+    settings
+        .directories
+        .iter()
+        .filter_map(|dir| collect_videos(dir, settings.max_depth).ok())
+        .flatten()
+        .filter(|video| analyzer.needs_analysis(video, series))
+        .collect()
+}
+
+/// 预先计算一个文件的元数据、画面特征与各算法的逐帧相似度
+async fn analyze_video(app: &AppHandle, video: &Path, algorithms: &[SimilarityAlgorithm]) -> Result<(), String> {
+    let info = video_info(app, video).await?;
+    clip_traits(app, video, info.duration).await?;
+    for &algo in algorithms {
+        preanalyze_frames(app, &video.to_string_lossy(), algo).await?;
+    }
+    Ok(())
+}
+
+/// 保存预分析过程中尚未写入的缓存（空闲等待前与应用退出时调用）
+pub fn flush(app: &AppHandle) {
+    app.state::<MetadataCache>().flush();
+    app.state::<Preanalyzer>().flush();
+}
+
+/// 启动后台预分析循环（应用启动时调用一次），每次只分析一个文件，之后重新检查是否空闲
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if app.state::<ProcessRegistry>().is_shutting_down() {
+                flush(&app);
+                break;
+            }
+            let settings = app.state::<SettingsManager>().get();
+            let preanalysis = settings.preanalysis;
+            let analyzer = app.state::<Preanalyzer>();
+
+            let blocked = preanalysis.block_reason(&app);
+            analyzer.update_status(|s| s.waiting = blocked.clone().err().flatten());
+            if blocked.is_ok() {
+                let algorithms: Vec<SimilarityAlgorithm> = preanalysis
+                    .algorithms
+                    .iter()
+                    .filter_map(|name| SimilarityAlgorithm::from_name(name, &settings.similarity).ok())
+                    .collect();
+                let series: Vec<String> = algorithms
+                    .iter()
+                    .map(|&algo| analysis_cache::series_key(algo, &settings.similarity))
+                    .collect();
+                let pending = pending_videos(&analyzer, &preanalysis, &series);
+                analyzer.update_status(|s| s.pending = pending.len() as u32);
+
+                if let Some(video) = pending.first() {
+                    analyzer.update_status(|s| s.current = Some(video.to_string_lossy().to_string()));
+                    let result = analyze_video(&app, video, &algorithms).await;
+                    // 失败的文件同样记录，文件变化前不再重试
+                    analyzer.mark_analyzed(video, series);
+                    analyzer.update_status(|s| {
+                        s.current = None;
+                        s.pending = s.pending.saturating_sub(1);
+                        match result {
+                            Ok(()) => s.analyzed += 1,
+                            Err(e) => s.last_error = Some(format!("{}: {}", video.display(), e)),
+                        }
+                    });
+                    continue;
+                }
+            }
+
+            flush(&app);
+            let _ = tokio::time::timeout(POLL_INTERVAL, analyzer.notify.notified()).await;
+        }
+    });
+}
+
+// 保存后台预分析设置
+#[tauri::command]
+pub fn save_preanalysis_settings(
+    app: AppHandle,
+    settings: State<'_, SettingsManager>,
+    analyzer: State<'_, Preanalyzer>,
+    preanalysis: PreanalysisSettings,
+) -> Result<(), String> {
+    preanalysis.validate()?;
+    for dir in &preanalysis.directories {
+        check_input(&app, dir)?;
+    }
+    settings.update(|s| {
+        s.preanalysis = preanalysis;
+        Ok(())
+    })?;
+    // 设置变化后立即重新判断是否可以分析
+    analyzer.notify.notify_one();
+    Ok(())
+}
+
+// 获取后台预分析进度
+#[tauri::command]
+pub fn get_preanalysis_status(analyzer: State<'_, Preanalyzer>) -> PreanalysisStatus {
+    analyzer.status.lock().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saves_are_debounced_until_flush() {
+        let file = JsonFile::new(PathBuf::from("cache.json"));
+        assert!(file.changed());
+        assert!(!file.changed());
+        assert!(!file.changed());
+        assert!(file.take_dirty());
+        assert!(!file.take_dirty());
+    }
+}
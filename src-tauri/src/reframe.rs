@@ -0,0 +1,291 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::AppHandle;
+use tauri_plugin_shell::ShellExt;
+
+/// 采样分析用的缩略图边长（灰度）
+const PROFILE_SIZE: usize = 64;
+/// 每个视频采样的帧数
+const PROFILE_SAMPLES: usize = 8;
+
+/// 输出画面比例预设
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AspectPreset {
+    /// 跟随目标分辨率（默认行为）
+    #[default]
+    Source,
+    /// 横屏 16:9
+    Landscape,
+    /// 竖屏 9:16
+    Portrait,
+    /// 方形 1:1
+    Square,
+}
+
+impl AspectPreset {
+    /// 根据目标分辨率计算该预设的画布尺寸（保证为偶数）
+    pub fn canvas_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let long = width.max(height);
+        let short = width.min(height);
+        let (w, h) = match self {
+            Self::Source => (width, height),
+            Self::Landscape => (long, long * 9 / 16),
+            Self::Portrait => (long * 9 / 16, long),
+            Self::Square => (short, short),
+        };
+        (w / 2 * 2, h / 2 * 2)
+    }
+
+    /// 输出文件名后缀
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            Self::Source => "source",
+            Self::Landscape => "16x9",
+            Self::Portrait => "9x16",
+            Self::Square => "1x1",
+        }
+    }
+}
+
+/// 目标分辨率的确定方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetResolution {
+    /// 第一个输入的分辨率（默认行为）
+    #[default]
+    First,
+    /// 所有输入中像素最多的分辨率
+    AutoMax,
+    /// 所有输入中出现次数最多的分辨率（次数相同取较大者）
+    AutoMode,
+    /// 指定分辨率
+    Explicit { width: u32, height: u32 },
+}
+
+impl TargetResolution {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Self::Explicit { width, height } = self {
+            if *width == 0 || *height == 0 || width % 2 != 0 || height % 2 != 0 {
+                return Err("目标分辨率的宽高必须为大于 0 的偶数".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// 根据本次输入的分辨率列表计算目标分辨率
+    pub fn resolve(&self, sizes: &[(u32, u32)]) -> Option<(u32, u32)> {
+        let area = |&(w, h): &(u32, u32)| w as u64 * h as u64;
+        match self {
+            Self::First => sizes.first().copied(),
+            Self::AutoMax => sizes.iter().copied().max_by_key(area),
+            Self::AutoMode => {
+                let mut counts: Vec<((u32, u32), usize)> = Vec::new();
+                for size in sizes {
+                    match counts.iter_mut().find(|(s, _)| s == size) {
+                        Some((_, count)) => *count += 1,
+                        None => counts.push((*size, 1)),
+                    }
+                }
+                counts
+                    .into_iter()
+                    .max_by_key(|(size, count)| (*count, area(size)))
+                    .map(|(size, _)| size)
+            }
+            Self::Explicit { width, height } => Some((*width, *height)),
+        }
+    }
+}
+
+/// 补边区域的填充方式
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PadFill {
+    /// 黑色（默认行为）
+    #[default]
+    Black,
+    /// 指定颜色（FFmpeg 颜色名或 #RRGGBB）
+    Color(String),
+    /// 用放大模糊的画面本身填充
+    Blur,
+}
+
+impl PadFill {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Self::Color(color) = self {
+            // 颜色会直接写进滤镜参数，只允许颜色名、十六进制与透明度写法
+            if color.is_empty() || !color.chars().all(|c| c.is_ascii_alphanumeric() || "#@.".contains(c)) {
+                return Err(format!("补边颜色不合法: {}", color));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 安全区：画面四周保留的边距（像素），内容缩放到边距以内，边距按补边方式填充
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct SafeArea {
+    #[serde(default)]
+    pub top: u32,
+    #[serde(default)]
+    pub right: u32,
+    #[serde(default)]
+    pub bottom: u32,
+    #[serde(default)]
+    pub left: u32,
+}
+
+impl SafeArea {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// 扣除边距后的内容区域尺寸（保证为偶数），边距过大时返回 None
+    pub fn content_size(&self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let w = width.checked_sub(self.left + self.right)? / 2 * 2;
+        let h = height.checked_sub(self.top + self.bottom)? / 2 * 2;
+        (w >= 2 && h >= 2).then_some((w, h))
+    }
+}
+
+/// 画面适配方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReframeMode {
+    /// 等比缩放后补边（默认行为）
+    #[default]
+    Fit,
+    /// 等比放大后居中裁剪
+    CenterCrop,
+    /// 等比放大后按运动/细节能量选择裁剪位置
+    Smart,
+}
+
+/// 视频画面能量分布（按列/按行累计），用于智能裁剪定位
+#[derive(Debug, Clone)]
+pub struct FocusProfile {
+    columns: Vec<f64>,
+    rows: Vec<f64>,
+}
+
+impl FocusProfile {
+    /// 计算裁剪窗口中心（0.0-1.0），`width_frac`/`height_frac` 为窗口占画面的比例
+    pub fn center(&self, width_frac: f64, height_frac: f64) -> (f64, f64) {
+        (
+            best_window_center(&self.columns, width_frac),
+            best_window_center(&self.rows, height_frac),
+        )
+    }
+}
+
+/// 在能量序列上滑动窗口，返回能量最大窗口的中心位置
+fn best_window_center(energy: &[f64], window_frac: f64) -> f64 {
+    let n = energy.len();
+    let window = ((window_frac.clamp(0.0, 1.0) * n as f64).round() as usize).clamp(1, n.max(1));
+    if n == 0 || window >= n {
+        return 0.5;
+    }
+
+    // 轻微的居中偏好，避免能量相近时窗口贴边
+    let weighted: Vec<f64> = energy
+        .iter()
+        .enumerate()
+        .map(|(i, &e)| {
+            let offset = (i as f64 + 0.5) / n as f64 - 0.5;
+            e * (1.0 - 0.3 * offset.abs())
+        })
+        .collect();
+
+    let mut sum: f64 = weighted[..window].iter().sum();
+    let mut best_sum = sum;
+    let mut best_start = 0;
+    for start in 1..=(n - window) {
+        sum += weighted[start + window - 1] - weighted[start - 1];
+        if sum > best_sum {
+            best_sum = sum;
+            best_start = start;
+        }
+    }
+    (best_start as f64 + window as f64 / 2.0) / n as f64
+}
+
+/// 采样若干帧，统计运动（帧间差）与细节（梯度）能量分布
+pub async fn analyze_focus(
+    app: &AppHandle,
+    video_path: &Path,
+    duration: f64,
+) -> Result<FocusProfile, String> {
+    let sidecar = app
+        .shell()
+        .sidecar("ffmpeg")
+        .map_err(|e| format!("FFmpeg 启动失败: {}", e))?;
+
+    let sample_rate = PROFILE_SAMPLES as f64 / duration.max(1.0);
+    let vf = format!(
+        "fps={:.6},scale={s}:{s},format=gray",
+        sample_rate,
+        s = PROFILE_SIZE
+    );
+    let output = sidecar
+        .args([
+            "-v",
+            "error",
+            "-i",
+            &video_path.to_string_lossy(),
+            "-vf",
+            &vf,
+            "-frames:v",
+            &PROFILE_SAMPLES.to_string(),
+            "-f",
+            "rawvideo",
+            "-",
+        ])
+        .output()
+        .await
+        .map_err(|e| format!("FFmpeg 执行失败: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "采样画面失败: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let frame_len = PROFILE_SIZE * PROFILE_SIZE;
+    let frames: Vec<&[u8]> = output.stdout.chunks_exact(frame_len).collect();
+    if frames.is_empty() {
+        return Err("采样画面为空".to_string());
+    }
+
+    let mut columns = vec![0.0f64; PROFILE_SIZE];
+    let mut rows = vec![0.0f64; PROFILE_SIZE];
+    for (idx, frame) in frames.iter().enumerate() {
+        for y in 0..PROFILE_SIZE {
+            for x in 0..PROFILE_SIZE {
+                let p = frame[y * PROFILE_SIZE + x] as f64;
+                // 细节能量：水平 + 垂直梯度
+                let gx = if x + 1 < PROFILE_SIZE {
+                    (frame[y * PROFILE_SIZE + x + 1] as f64 - p).abs()
+                } else {
+                    0.0
+                };
+                let gy = if y + 1 < PROFILE_SIZE {
+                    (frame[(y + 1) * PROFILE_SIZE + x] as f64 - p).abs()
+                } else {
+                    0.0
+                };
+                // 运动能量：与上一采样帧的差异（权重更高）
+                let motion = if idx > 0 {
+                    (p - frames[idx - 1][y * PROFILE_SIZE + x] as f64).abs()
+                } else {
+                    0.0
+                };
+                let energy = 0.5 * (gx + gy) + 2.0 * motion;
+                columns[x] += energy;
+                rows[y] += energy;
+            }
+        }
+    }
+
+    Ok(FocusProfile { columns, rows })
+}
@@ -0,0 +1,282 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use tauri_plugin_shell::ShellExt;
+use crate::path_scope::check_output;
+use crate::video_frame_extractor::BatchProgress;
+use crate::video_processor::get_video_info;
+
+/// 缩略图宽度（像素）
+const THUMBNAIL_WIDTH: u32 = 160;
+
+/// 报告中的一个输出文件
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportOutput {
+    pub path: String,
+    pub duration: f64,
+}
+
+/// 报告中的一项（一个输入视频，或一次拼接用到的一组视频）
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportEntry {
+    pub inputs: Vec<String>,
+    pub status: String,
+    pub outputs: Vec<ReportOutput>,
+    pub error: Option<String>,
+    /// 成功但需要注意的问题（如接缝检查的结果）
+    pub warnings: Vec<String>,
+}
+
+/// 一批处理的报告，可导出为 CSV 与自包含的 HTML
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessingReport {
+    pub title: String,
+    pub created_at: String,
+    /// 本批处理使用的参数（名称, 值）
+    pub settings: Vec<(String, String)>,
+    pub entries: Vec<ReportEntry>,
+}
+
+impl ProcessingReport {
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            created_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            settings: Vec::new(),
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn setting(&mut self, name: &str, value: impl ToString) {
+        self.settings.push((name.to_string(), value.to_string()));
+    }
+
+    /// 每个输出一行；没有输出的项单独占一行
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("\u{feff}input,status,output,duration,error,warnings\n");
+        for entry in &self.entries {
+            let inputs = entry.inputs.join(" | ");
+            let error = entry.error.as_deref().unwrap_or("");
+            let warnings = entry.warnings.join(" | ");
+            if entry.outputs.is_empty() {
+                csv.push_str(&csv_row(&[&inputs, &entry.status, "", "", error, &warnings]));
+            }
+            for output in &entry.outputs {
+                let duration = format!("{:.2}", output.duration);
+                csv.push_str(&csv_row(&[&inputs, &entry.status, &output.path, &duration, error, &warnings]));
+            }
+        }
+        csv
+    }
+
+    /// 生成 HTML，缩略图以 data URI 内嵌，单个文件即可分享
+    pub async fn to_html(&self, app: &AppHandle) -> String {
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str(&format!("<title>{}</title>\n", escape_html(&self.title)));
+        html.push_str(
+            "<style>body{font-family:sans-serif;margin:24px}table{border-collapse:collapse;width:100%}\
+             th,td{border:1px solid #ccc;padding:6px;vertical-align:top;text-align:left}\
+             .failed{color:#c00}.outputs div{margin-bottom:6px}img{display:block}</style>\n",
+        );
+        html.push_str("</head>\n<body>\n");
+        html.push_str(&format!(
+            "<h1>{}</h1>\n<p>生成时间：{}</p>\n",
+            escape_html(&self.title),
+            escape_html(&self.created_at)
+        ));
+
+        if !self.settings.is_empty() {
+            html.push_str("<h2>参数</h2>\n<table>\n");
+            for (name, value) in &self.settings {
+                html.push_str(&format!(
+                    "<tr><th>{}</th><td>{}</td></tr>\n",
+                    escape_html(name),
+                    escape_html(value)
+                ));
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("<h2>结果</h2>\n<table>\n<tr><th>输入</th><th>状态</th><th>输出</th><th>错误</th><th>提示</th></tr>\n");
+        for entry in &self.entries {
+            let inputs = entry
+                .inputs
+                .iter()
+                .map(|i| escape_html(i))
+                .collect::<Vec<_>>()
+                .join("<br>");
+            let mut outputs = String::new();
+            for output in &entry.outputs {
+                outputs.push_str("<div>");
+                if let Some(thumb) = thumbnail_data_uri(app, Path::new(&output.path), output.duration).await {
+                    outputs.push_str(&format!("<img src=\"{}\" width=\"{}\">", thumb, THUMBNAIL_WIDTH));
+                }
+                outputs.push_str(&format!(
+                    "{}（{:.2}s）</div>",
+                    escape_html(&output.path),
+                    output.duration
+                ));
+            }
+            let class = if entry.error.is_some() { " class=\"failed\"" } else { "" };
+            let warnings = entry
+                .warnings
+                .iter()
+                .map(|w| escape_html(w))
+                .collect::<Vec<_>>()
+                .join("<br>");
+            html.push_str(&format!(
+                "<tr{}><td>{}</td><td>{}</td><td class=\"outputs\">{}</td><td>{}</td><td>{}</td></tr>\n",
+                class,
+                inputs,
+                escape_html(&entry.status),
+                outputs,
+                escape_html(entry.error.as_deref().unwrap_or("")),
+                warnings
+            ));
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+}
+
+fn csv_row(fields: &[&str]) -> String {
+    let escaped: Vec<String> = fields
+        .iter()
+        .map(|f| {
+            if f.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", f.replace('"', "\"\""))
+            } else {
+                f.to_string()
+            }
+        })
+        .collect();
+    format!("{}\n", escaped.join(","))
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn base64_encode(data: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        out.push(TABLE[(n >> 18) as usize & 63] as char);
+        out.push(TABLE[(n >> 12) as usize & 63] as char);
+        out.push(if chunk.len() > 1 { TABLE[(n >> 6) as usize & 63] as char } else { '=' });
+        out.push(if chunk.len() > 2 { TABLE[n as usize & 63] as char } else { '=' });
+    }
+    out
+}
+
+/// 取视频中间一帧作为缩略图，失败时返回 None（报告中不显示图片）
+async fn thumbnail_data_uri(app: &AppHandle, video_path: &Path, duration: f64) -> Option<String> {
+    let sidecar = app.shell().sidecar("ffmpeg").ok()?;
+    let output = sidecar
+        .args([
+            "-v",
+            "error",
+            "-ss",
+            &format!("{:.3}", duration / 2.0),
+            "-i",
+            &video_path.to_string_lossy(),
+            "-frames:v",
+            "1",
+            "-vf",
+            &format!("scale={}:-2", THUMBNAIL_WIDTH),
+            "-f",
+            "image2pipe",
+            "-vcodec",
+            "mjpeg",
+            "-",
+        ])
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() || output.stdout.is_empty() {
+        return None;
+    }
+    Some(format!("data:image/jpeg;base64,{}", base64_encode(&output.stdout)))
+}
+
+/// 把报告写成 `{stem}.csv` 与 `{stem}.html`，返回两个文件路径
+pub async fn write_report(
+    app: &AppHandle,
+    report: &ProcessingReport,
+    output_dir: &Path,
+    stem: &str,
+) -> Result<Vec<PathBuf>, String> {
+    fs::create_dir_all(output_dir).map_err(|e| format!("创建报告目录失败: {}", e))?;
+
+    let csv_path = output_dir.join(format!("{}.csv", stem));
+    fs::write(&csv_path, report.to_csv()).map_err(|e| format!("写入 CSV 报告失败: {}", e))?;
+
+    let html_path = output_dir.join(format!("{}.html", stem));
+    fs::write(&html_path, report.to_html(app).await)
+        .map_err(|e| format!("写入 HTML 报告失败: {}", e))?;
+
+    Ok(vec![csv_path, html_path])
+}
+
+// 根据批量拆解进度文件导出处理报告
+#[tauri::command]
+pub async fn export_batch_report(app: AppHandle, progress_path: String) -> Result<Vec<String>, String> {
+    check_output(&app, &progress_path)?;
+    let content = fs::read_to_string(&progress_path).map_err(|e| format!("读取进度文件失败: {}", e))?;
+    let progress: BatchProgress =
+        serde_json::from_str(&content).map_err(|e| format!("解析进度文件失败: {}", e))?;
+    // 输出目录来自进度文件内容，读取片段与写入报告前同样按允许的目录校验
+    check_output(&app, &progress.output_dir)?;
+
+    let mut report = ProcessingReport::new("批量拆解报告");
+    report.setting("输入目录", &progress.input_dir);
+    report.setting("输出目录", &progress.output_dir);
+    report.setting("视频数量", progress.tasks.len());
+
+    for task in &progress.tasks {
+        // 片段输出在 输出目录/视频名称/ 下
+        let stem = Path::new(&task.path)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let segment_dir = Path::new(&progress.output_dir).join(&stem);
+        let mut segment_paths: Vec<PathBuf> = fs::read_dir(&segment_dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .map(|e| e.path())
+                    .filter(|p| p.extension().map(|e| e.eq_ignore_ascii_case("mp4")).unwrap_or(false))
+                    .collect()
+            })
+            .unwrap_or_default();
+        segment_paths.sort();
+
+        let mut outputs = Vec::new();
+        for path in segment_paths {
+            let duration = get_video_info(&app, &path).await.map(|i| i.duration).unwrap_or(0.0);
+            outputs.push(ReportOutput {
+                path: path.to_string_lossy().to_string(),
+                duration,
+            });
+        }
+
+        report.entries.push(ReportEntry {
+            inputs: vec![task.path.clone()],
+            status: task.status.clone(),
+            outputs,
+            error: task.error.clone(),
+            warnings: Vec::new(),
+        });
+    }
+
+    let stem = format!("batch_report_{}", chrono::Local::now().format("%Y%m%d_%H%M%S"));
+    let paths = write_report(&app, &report, Path::new(&progress.output_dir), &stem).await?;
+    Ok(paths.iter().map(|p| p.to_string_lossy().to_string()).collect())
+}
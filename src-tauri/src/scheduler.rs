@@ -0,0 +1,185 @@
+use chrono::{NaiveTime, Timelike};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use ts_rs::TS;
+use crate::jobs::JobQueue;
+use crate::settings::SettingsManager;
+
+/// 任务调度设置
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SchedulerSettings {
+    /// 只在空闲时执行队列任务（任务之间暂停，不会打断正在执行的任务）
+    #[serde(default)]
+    pub idle_only: bool,
+    /// 无键鼠输入超过该分钟数视为空闲
+    #[serde(default)]
+    pub idle_minutes: Option<u32>,
+    /// 允许执行的时间段，格式 "HH:MM"，可以跨零点（如 01:00–07:00 或 22:00–06:00）
+    #[serde(default)]
+    pub window_start: Option<String>,
+    #[serde(default)]
+    pub window_end: Option<String>,
+}
+
+impl SchedulerSettings {
+    fn window(&self) -> Result<Option<(NaiveTime, NaiveTime)>, String> {
+        match (self.window_start.as_deref(), self.window_end.as_deref()) {
+            (Some(start), Some(end)) if !start.is_empty() && !end.is_empty() => {
+                let parse = |s: &str| {
+                    NaiveTime::parse_from_str(s, "%H:%M").map_err(|_| format!("时间格式应为 HH:MM: {}", s))
+                };
+                Ok(Some((parse(start)?, parse(end)?)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        self.window()?;
+        if self.idle_minutes == Some(0) {
+            return Err("空闲分钟数必须大于 0".to_string());
+        }
+        if self.idle_only && self.idle_minutes.is_none() && self.window()?.is_none() {
+            return Err("空闲模式需要设置空闲分钟数或允许执行的时间段".to_string());
+        }
+        // 无法检测空闲状态时只按时间段执行，没有时间段的队列会一直等待
+        if self.idle_only && self.idle_minutes.is_some() && !IDLE_DETECTION_SUPPORTED && self.window()?.is_none() {
+            return Err("当前平台不支持检测空闲状态，请设置允许执行的时间段".to_string());
+        }
+        Ok(())
+    }
+
+    /// 当前不允许执行任务时返回原因
+    pub fn block_reason(&self) -> Option<String> {
+        if !self.idle_only {
+            return None;
+        }
+
+        if let Ok(Some((start, end))) = self.window() {
+            let now = chrono::Local::now().time();
+            let now = NaiveTime::from_hms_opt(now.hour(), now.minute(), 0).unwrap_or(now);
+            let inside = if start <= end {
+                now >= start && now < end
+            } else {
+                now >= start || now < end
+            };
+            if inside {
+                return None;
+            }
+        }
+
+        if let Some(minutes) = self.idle_minutes {
+            match idle_seconds() {
+                Some(idle) if idle >= minutes as u64 * 60 => return None,
+                Some(_) => return Some(format!("等待电脑空闲 {} 分钟", minutes)),
+                None => return Some("无法检测空闲状态，等待允许的时间段".to_string()),
+            }
+        }
+
+        Some(format!(
+            "等待允许的时间段 {}–{}",
+            self.window_start.as_deref().unwrap_or(""),
+            self.window_end.as_deref().unwrap_or("")
+        ))
+    }
+}
+
+/// 当前平台能否检测空闲状态（`idle_seconds` 是否可能返回值）
+pub const IDLE_DETECTION_SUPPORTED: bool = cfg!(any(target_os = "windows", target_os = "macos"));
+
+/// 距离上次键鼠输入的秒数；当前平台不支持时返回 None
+#[cfg(target_os = "windows")]
+pub fn idle_seconds() -> Option<u64> {
+    #[repr(C)]
+    struct LastInputInfo {
+        cb_size: u32,
+        dw_time: u32,
+    }
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetLastInputInfo(plii: *mut LastInputInfo) -> i32;
+    }
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetTickCount() -> u32;
+    }
+
+    let mut info = LastInputInfo {
+        cb_size: std::mem::size_of::<LastInputInfo>() as u32,
+        dw_time: 0,
+    };
+    // SAFETY: info 是按 Win32 LASTINPUTINFO 布局声明的有效结构体
+    let ok = unsafe { GetLastInputInfo(&mut info) };
+    if ok == 0 {
+        return None;
+    }
+    // SAFETY: 无参数的系统调用
+    let now = unsafe { GetTickCount() };
+    Some(now.wrapping_sub(info.dw_time) as u64 / 1000)
+}
+
+/// 距离上次键鼠输入的秒数；当前平台不支持时返回 None
+#[cfg(target_os = "macos")]
+pub fn idle_seconds() -> Option<u64> {
+    // HIDIdleTime 单位为纳秒
+    let output = std::process::Command::new("ioreg")
+        .args(["-c", "IOHIDSystem", "-d", "4"])
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout.lines().find(|l| l.contains("\"HIDIdleTime\""))?;
+    let value = line.split('=').nth(1)?.trim().parse::<u64>().ok()?;
+    Some(value / 1_000_000_000)
+}
+
+/// 距离上次键鼠输入的秒数；当前平台不支持时返回 None
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn idle_seconds() -> Option<u64> {
+    None
+}
+
+// 保存任务调度设置
+#[tauri::command]
+pub fn save_scheduler_settings(
+    settings: State<'_, SettingsManager>,
+    queue: State<'_, JobQueue>,
+    scheduler: SchedulerSettings,
+) -> Result<(), String> {
+    scheduler.validate()?;
+    settings.update(|s| {
+        s.scheduler = scheduler;
+        Ok(())
+    })?;
+    // 设置变化后立即重新判断是否可以执行
+    queue.wake();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idle(minutes: Option<u32>, window: Option<(&str, &str)>) -> SchedulerSettings {
+        SchedulerSettings {
+            idle_only: true,
+            idle_minutes: minutes,
+            window_start: window.map(|w| w.0.to_string()),
+            window_end: window.map(|w| w.1.to_string()),
+        }
+    }
+
+    #[test]
+    fn idle_mode_needs_a_condition() {
+        assert!(idle(None, None).validate().is_err());
+        assert!(idle(Some(0), Some(("01:00", "07:00"))).validate().is_err());
+        assert!(idle(None, Some(("01:00", "7:0x"))).validate().is_err());
+        assert!(idle(None, Some(("22:00", "06:00"))).validate().is_ok());
+        assert!(idle(Some(10), Some(("01:00", "07:00"))).validate().is_ok());
+    }
+
+    #[test]
+    fn idle_minutes_alone_need_idle_detection() {
+        assert_eq!(idle(Some(10), None).validate().is_ok(), IDLE_DETECTION_SUPPORTED);
+    }
+}
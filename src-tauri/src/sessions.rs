@@ -0,0 +1,200 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use ts_rs::TS;
+use crate::path_scope::check_input;
+use crate::video_frame_extractor::SegmentRange;
+
+/// 计算内容指纹时读取的头/尾字节数
+const HASH_SAMPLE_BYTES: u64 = 1024 * 1024;
+/// 每个会话保留的撤销步数
+const MAX_HISTORY: usize = 50;
+
+/// 分段编辑的一个状态
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SegmentationSnapshot {
+    pub segments: Vec<SegmentRange>,
+    /// 与 segments 一一对应的片段标签
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub notes: String,
+    pub updated_at: String,
+}
+
+/// 单个视频的手动分段会话（按内容指纹保存，视频改名或移动后仍可恢复）
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SegmentationSession {
+    pub content_hash: String,
+    /// 最近一次保存时的视频路径
+    pub video_path: String,
+    pub current: SegmentationSnapshot,
+    /// 之前的状态，最新的在最后
+    #[serde(default)]
+    pub history: Vec<SegmentationSnapshot>,
+}
+
+/// FNV-1a 哈希：结果跨进程、跨版本稳定，用于保存到磁盘的指纹
+pub struct Fnv1a(u64);
+
+impl Fnv1a {
+    pub fn new() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 视频内容指纹：文件大小 + 头尾各 1MB 的 FNV-1a 哈希
+pub fn content_hash(video_path: &Path) -> Result<String, String> {
+    let mut file = File::open(video_path).map_err(|e| format!("打开视频失败: {}", e))?;
+    let size = file
+        .metadata()
+        .map_err(|e| format!("读取文件信息失败: {}", e))?
+        .len();
+
+    let mut hash = Fnv1a::new();
+    let mut feed = |bytes: &[u8]| hash.feed(bytes);
+    feed(&size.to_le_bytes());
+
+    let mut buf = vec![0u8; HASH_SAMPLE_BYTES.min(size) as usize];
+    file.read_exact(&mut buf).map_err(|e| format!("读取视频失败: {}", e))?;
+    feed(&buf);
+    if size > HASH_SAMPLE_BYTES {
+        let tail = HASH_SAMPLE_BYTES.min(size - HASH_SAMPLE_BYTES);
+        file.seek(SeekFrom::End(-(tail as i64)))
+            .map_err(|e| format!("读取视频失败: {}", e))?;
+        let mut buf = vec![0u8; tail as usize];
+        file.read_exact(&mut buf).map_err(|e| format!("读取视频失败: {}", e))?;
+        feed(&buf);
+    }
+
+    Ok(format!("{:016x}", hash.finish()))
+}
+
+fn session_path(app: &AppHandle, hash: &str) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("获取数据目录失败: {}", e))?
+        .join("segmentation_sessions");
+    Ok(dir.join(format!("{}.json", hash)))
+}
+
+fn read_session(path: &Path) -> Result<Option<SegmentationSession>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(path).map_err(|e| format!("读取分段会话失败: {}", e))?;
+    let session = serde_json::from_str(&content).map_err(|e| format!("解析分段会话失败: {}", e))?;
+    Ok(Some(session))
+}
+
+fn write_session(path: &Path, session: &SegmentationSession) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建会话目录失败: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(session)
+        .map_err(|e| format!("序列化分段会话失败: {}", e))?;
+    fs::write(path, content).map_err(|e| format!("写入分段会话失败: {}", e))
+}
+
+/// 查找视频已保存的分段会话（不校验路径权限，供内部自动恢复使用）
+pub fn find_session(app: &AppHandle, video_path: &str) -> Result<Option<SegmentationSession>, String> {
+    let hash = content_hash(Path::new(video_path))?;
+    read_session(&session_path(app, &hash)?)
+}
+
+// 加载视频的分段会话
+#[tauri::command]
+pub fn load_segmentation_session(
+    app: AppHandle,
+    video_path: String,
+) -> Result<Option<SegmentationSession>, String> {
+    check_input(&app, &video_path)?;
+    find_session(&app, &video_path)
+}
+
+// 保存视频的分段会话（旧状态进入撤销历史）
+#[tauri::command]
+pub fn save_segmentation_session(
+    app: AppHandle,
+    video_path: String,
+    segments: Vec<SegmentRange>,
+    labels: Option<Vec<String>>,
+    notes: Option<String>,
+) -> Result<SegmentationSession, String> {
+    check_input(&app, &video_path)?;
+    let hash = content_hash(Path::new(&video_path))?;
+    let path = session_path(&app, &hash)?;
+
+    let snapshot = SegmentationSnapshot {
+        segments,
+        labels: labels.unwrap_or_default(),
+        notes: notes.unwrap_or_default(),
+        updated_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+    };
+
+    let session = match read_session(&path)? {
+        Some(mut session) => {
+            let previous = std::mem::replace(&mut session.current, snapshot);
+            session.history.push(previous);
+            if session.history.len() > MAX_HISTORY {
+                let excess = session.history.len() - MAX_HISTORY;
+                session.history.drain(..excess);
+            }
+            session.video_path = video_path;
+            session
+        }
+        None => SegmentationSession {
+            content_hash: hash,
+            video_path,
+            current: snapshot,
+            history: Vec::new(),
+        },
+    };
+
+    write_session(&path, &session)?;
+    Ok(session)
+}
+
+// 撤销到上一次保存的分段状态，没有历史时返回 None
+#[tauri::command]
+pub fn undo_segmentation_session(
+    app: AppHandle,
+    video_path: String,
+) -> Result<Option<SegmentationSession>, String> {
+    check_input(&app, &video_path)?;
+    let hash = content_hash(Path::new(&video_path))?;
+    let path = session_path(&app, &hash)?;
+
+    let Some(mut session) = read_session(&path)? else {
+        return Ok(None);
+    };
+    let Some(previous) = session.history.pop() else {
+        return Ok(None);
+    };
+    session.current = previous;
+
+    write_session(&path, &session)?;
+    Ok(Some(session))
+}
@@ -0,0 +1,221 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, State};
+use crate::detection_profile::{builtin_profiles, DetectionProfile};
+use crate::export_preset::{builtin_presets, ExportPreset};
+use crate::frame_similarity::SimilarityTuning;
+use crate::gop::GopSettings;
+use crate::i18n::Locale;
+use crate::path_scope::check_input;
+use crate::preanalysis::PreanalysisSettings;
+use crate::scheduler::SchedulerSettings;
+use crate::throttle::ThrottleSettings;
+use crate::video_processor::{BgmConfig, ClipTrim, WatermarkConfig};
+
+/// 品牌包：片头 + 片尾 + 水印 + 背景音乐，可一键套用到拼接/去结尾任务
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrandKit {
+    pub name: String,
+    #[serde(default)]
+    pub intro_video: Option<String>,
+    #[serde(default)]
+    pub intro_trim: Option<ClipTrim>,
+    #[serde(default)]
+    pub outro_video: Option<String>,
+    #[serde(default)]
+    pub outro_trim: Option<ClipTrim>,
+    #[serde(default)]
+    pub watermark: Option<WatermarkConfig>,
+    #[serde(default)]
+    pub bgm: Option<BgmConfig>,
+    /// 标题字体文件：任务的标题文字没有单独指定字体时使用
+    #[serde(default)]
+    pub title_font: Option<String>,
+}
+
+impl BrandKit {
+    /// 品牌包引用的素材文件
+    fn assets(&self) -> [(&'static str, Option<&str>); 5] {
+        [
+            ("片头视频", self.intro_video.as_deref()),
+            ("片尾视频", self.outro_video.as_deref()),
+            ("水印图片", self.watermark.as_ref().map(|w| w.image_path.as_str())),
+            ("背景音乐", self.bgm.as_ref().map(|b| b.audio_path.as_str())),
+            ("标题字体", self.title_font.as_deref()),
+        ]
+    }
+
+    /// 检查品牌包引用的素材文件是否都存在
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("品牌包名称不能为空".to_string());
+        }
+        for (label, path) in self.assets() {
+            if let Some(path) = path {
+                if !path.is_empty() && !Path::new(path).exists() {
+                    return Err(format!("品牌包 {} 的{}不存在: {}", self.name, label, path));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 持久化的应用设置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AppSettings {
+    #[serde(default)]
+    pub brand_kits: Vec<BrandKit>,
+    /// 用户通过选择对话框授权过的文件与目录（规范化后的绝对路径）
+    #[serde(default)]
+    pub approved_paths: Vec<String>,
+    #[serde(default)]
+    pub scheduler: SchedulerSettings,
+    /// 全局编码资源限制（队列任务可单独覆盖）
+    #[serde(default)]
+    pub throttle: ThrottleSettings,
+    /// 进度与错误消息使用的语言
+    #[serde(default)]
+    pub locale: Locale,
+    /// 输出的关键帧设置
+    #[serde(default)]
+    pub gop: GopSettings,
+    /// 自定义导出预设（与内置预设同名时覆盖内置预设）
+    #[serde(default)]
+    pub export_presets: Vec<ExportPreset>,
+    /// 场景检测的相似度算法参数
+    #[serde(default)]
+    pub similarity: SimilarityTuning,
+    /// 自定义检测预设（与内置预设同名时覆盖内置预设）
+    #[serde(default)]
+    pub detection_profiles: Vec<DetectionProfile>,
+    /// 素材池目录的后台预分析
+    #[serde(default)]
+    pub preanalysis: PreanalysisSettings,
+}
+
+/// 全局设置管理器（保存在应用配置目录的 settings.json）
+pub struct SettingsManager {
+    path: PathBuf,
+    settings: Mutex<AppSettings>,
+}
+
+impl SettingsManager {
+    /// 从文件加载设置，文件不存在或损坏时使用默认值
+    pub fn load(path: PathBuf) -> Self {
+        let settings = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            settings: Mutex::new(settings),
+        }
+    }
+
+    /// 获取当前设置的副本
+    pub fn get(&self) -> AppSettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    /// 修改设置并立即写回磁盘
+    pub fn update<F>(&self, f: F) -> Result<AppSettings, String>
+    where
+        F: FnOnce(&mut AppSettings) -> Result<(), String>,
+    {
+        let mut settings = self.settings.lock().unwrap();
+        let mut updated = settings.clone();
+        f(&mut updated)?;
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("创建配置目录失败: {}", e))?;
+        }
+        let content = serde_json::to_string_pretty(&updated)
+            .map_err(|e| format!("序列化设置失败: {}", e))?;
+        fs::write(&self.path, content).map_err(|e| format!("写入设置文件失败: {}", e))?;
+
+        *settings = updated.clone();
+        Ok(updated)
+    }
+
+    /// 按名称查找品牌包
+    pub fn find_brand_kit(&self, name: &str) -> Result<BrandKit, String> {
+        self.settings
+            .lock()
+            .unwrap()
+            .brand_kits
+            .iter()
+            .find(|kit| kit.name == name)
+            .cloned()
+            .ok_or_else(|| format!("品牌包不存在: {}", name))
+    }
+
+    /// 按名称查找导出预设，优先使用自定义预设
+    pub fn find_export_preset(&self, name: &str) -> Result<ExportPreset, String> {
+        let custom = self.settings.lock().unwrap().export_presets.clone();
+        custom
+            .into_iter()
+            .chain(builtin_presets())
+            .find(|preset| preset.name == name)
+            .ok_or_else(|| format!("导出预设不存在: {}", name))
+    }
+
+    /// 按名称查找检测预设，优先使用自定义预设
+    pub fn find_detection_profile(&self, name: &str) -> Result<DetectionProfile, String> {
+        let custom = self.settings.lock().unwrap().detection_profiles.clone();
+        custom
+            .into_iter()
+            .chain(builtin_profiles())
+            .find(|profile| profile.name == name)
+            .ok_or_else(|| format!("检测预设不存在: {}", name))
+    }
+}
+
+// 获取全部设置
+#[tauri::command]
+pub fn get_settings(settings: State<'_, SettingsManager>) -> AppSettings {
+    settings.get()
+}
+
+// 列出所有品牌包
+#[tauri::command]
+pub fn list_brand_kits(settings: State<'_, SettingsManager>) -> Vec<BrandKit> {
+    settings.get().brand_kits
+}
+
+// 新建或覆盖同名品牌包
+#[tauri::command]
+pub fn save_brand_kit(
+    app: AppHandle,
+    settings: State<'_, SettingsManager>,
+    brand_kit: BrandKit,
+) -> Result<(), String> {
+    brand_kit.validate()?;
+    for path in brand_kit.assets().into_iter().filter_map(|(_, p)| p).filter(|p| !p.is_empty()) {
+        check_input(&app, path)?;
+    }
+    settings.update(|s| {
+        match s.brand_kits.iter_mut().find(|kit| kit.name == brand_kit.name) {
+            Some(existing) => *existing = brand_kit,
+            None => s.brand_kits.push(brand_kit),
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+// 删除品牌包
+#[tauri::command]
+pub fn delete_brand_kit(settings: State<'_, SettingsManager>, name: String) -> Result<(), String> {
+    settings.update(|s| {
+        let before = s.brand_kits.len();
+        s.brand_kits.retain(|kit| kit.name != name);
+        if s.brand_kits.len() == before {
+            return Err(format!("品牌包不存在: {}", name));
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
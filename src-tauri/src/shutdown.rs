@@ -0,0 +1,158 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, ExitRequestApi, Manager};
+use tauri_plugin_shell::process::CommandChild;
+use crate::batch::BatchControl;
+use crate::jobs::JobQueue;
+use crate::preanalysis;
+
+/// 退出时等待批量任务保存进度的最长时间
+const BATCH_STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 运行中的 FFmpeg 子进程与临时目录，退出时统一清理
+pub struct ProcessRegistry {
+    children: Mutex<HashMap<u32, CommandChild>>,
+    /// 子进程所属的任务（在任务上下文中启动的进程），取消任务时按任务结束
+    child_jobs: Mutex<HashMap<u32, String>>,
+    /// 正在执行的任务与其中已请求取消的任务
+    active_jobs: Mutex<HashSet<String>>,
+    cancelled_jobs: Mutex<HashSet<String>>,
+    temp_dirs: Mutex<Vec<PathBuf>>,
+    shutting_down: AtomicBool,
+}
+
+impl ProcessRegistry {
+    pub fn new() -> Self {
+        Self {
+            children: Mutex::new(HashMap::new()),
+            child_jobs: Mutex::new(HashMap::new()),
+            active_jobs: Mutex::new(HashSet::new()),
+            cancelled_jobs: Mutex::new(HashSet::new()),
+            temp_dirs: Mutex::new(Vec::new()),
+            shutting_down: AtomicBool::new(false),
+        }
+    }
+
+    /// 登记子进程，返回 pid
+    pub fn register_child(&self, child: CommandChild) -> u32 {
+        let pid = child.pid();
+        self.children.lock().unwrap().insert(pid, child);
+        pid
+    }
+
+    /// 子进程已结束，移出登记
+    pub fn release_child(&self, pid: u32) {
+        self.children.lock().unwrap().remove(&pid);
+        self.child_jobs.lock().unwrap().remove(&pid);
+    }
+
+    pub fn kill_child(&self, pid: u32) {
+        self.child_jobs.lock().unwrap().remove(&pid);
+        if let Some(child) = self.children.lock().unwrap().remove(&pid) {
+            let _ = child.kill();
+        }
+    }
+
+    /// 记录子进程所属的任务
+    pub fn assign_job(&self, pid: u32, job_id: &str) {
+        self.child_jobs.lock().unwrap().insert(pid, job_id.to_string());
+    }
+
+    pub fn begin_job(&self, job_id: &str) {
+        self.active_jobs.lock().unwrap().insert(job_id.to_string());
+    }
+
+    /// 任务结束，清除登记与取消标记
+    pub fn end_job(&self, job_id: &str) {
+        self.active_jobs.lock().unwrap().remove(job_id);
+        self.cancelled_jobs.lock().unwrap().remove(job_id);
+    }
+
+    /// 取消正在执行的任务：标记取消并结束其所有子进程，任务不存在时返回 false
+    pub fn cancel_job(&self, job_id: &str) -> bool {
+        if !self.active_jobs.lock().unwrap().contains(job_id) {
+            return false;
+        }
+        self.cancelled_jobs.lock().unwrap().insert(job_id.to_string());
+        let pids: Vec<u32> = self
+            .child_jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, job)| job.as_str() == job_id)
+            .map(|(pid, _)| *pid)
+            .collect();
+        for pid in pids {
+            self.kill_child(pid);
+        }
+        true
+    }
+
+    pub fn is_cancelled(&self, job_id: &str) -> bool {
+        self.cancelled_jobs.lock().unwrap().contains(job_id)
+    }
+
+    /// 登记临时目录：正常结束时由处理函数自己删除，异常退出时在这里补删
+    pub fn track_temp_dir(&self, dir: &Path) {
+        self.temp_dirs.lock().unwrap().push(dir.to_path_buf());
+    }
+
+    pub fn release_temp_dir(&self, dir: &Path) {
+        self.temp_dirs.lock().unwrap().retain(|d| d != dir);
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
+    fn kill_all(&self) {
+        for (_, child) in self.children.lock().unwrap().drain() {
+            let _ = child.kill();
+        }
+    }
+
+    fn remove_temp_dirs(&self) {
+        for dir in self.temp_dirs.lock().unwrap().drain(..) {
+            let _ = fs::remove_dir_all(&dir);
+        }
+    }
+}
+
+/// 应用退出请求：先阻止退出，中止批量任务、结束子进程、保存队列并清理临时目录后再退出
+pub fn handle_exit_requested(app: &AppHandle, api: &ExitRequestApi, code: Option<i32>) {
+    let registry = app.state::<ProcessRegistry>();
+    if registry.shutting_down.swap(true, Ordering::SeqCst) {
+        // 清理已完成，这次是清理后发起的退出
+        return;
+    }
+    api.prevent_exit();
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        shutdown(&app).await;
+        app.exit(code.unwrap_or(0));
+    });
+}
+
+async fn shutdown(app: &AppHandle) {
+    let registry = app.state::<ProcessRegistry>();
+    let control = app.state::<BatchControl>();
+
+    // 批量任务收到中止信号后会把当前视频恢复为待处理并保存进度
+    let batch_running = control.abort_if_active();
+    registry.kill_all();
+    if batch_running {
+        let deadline = tokio::time::Instant::now() + BATCH_STOP_TIMEOUT;
+        while control.is_active() && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    let _ = app.state::<JobQueue>().persist(app);
+    preanalysis::flush(app);
+    registry.remove_temp_dirs();
+}
@@ -0,0 +1,55 @@
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use ts_rs::TS;
+use crate::video_processor::VideoInfo;
+
+/// 早于该年份的 creation_time 视为无效（未设置时钟的设备会写入 1970 或 1904 年）
+const MIN_VALID_YEAR: i32 = 1980;
+
+/// 按源视频拍摄时间命名与标记输出
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SourceDateOptions {
+    /// 拆出的片段按拍摄时间命名（源视频拍摄时间 + 片段起点）
+    #[serde(default)]
+    pub name_by_recording_time: bool,
+    /// 输出文件的 creation_time 设为源视频的拍摄时间（拼接取最早的一个）
+    #[serde(default)]
+    pub keep_creation_time: bool,
+}
+
+/// 解析 creation_time 标签（ISO 8601，部分设备不带时区，按 UTC 处理）
+fn parse_creation_time(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").map(|t| t.and_utc()))
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f").map(|t| t.and_utc()))
+        .ok()
+        .filter(|t| t.year() >= MIN_VALID_YEAR)
+}
+
+/// 源视频的拍摄时间：优先使用 creation_time 标签，没有时使用文件修改时间
+pub fn recorded_at(info: &VideoInfo, path: &Path) -> Option<DateTime<Utc>> {
+    info.creation_time
+        .as_deref()
+        .and_then(parse_creation_time)
+        .or_else(|| {
+            fs::metadata(path)
+                .and_then(|m| m.modified())
+                .ok()
+                .map(DateTime::<Utc>::from)
+        })
+}
+
+/// 写入 creation_time 元数据的格式
+pub fn metadata_value(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%dT%H:%M:%S%.6fZ").to_string()
+}
+
+/// 文件名中的时间（本地时区）
+pub fn file_label(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local).format("%Y%m%d_%H%M%S").to_string()
+}
@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+/// 单类操作的执行次数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OperationCount {
+    pub total: u64,
+    pub failed: u64,
+}
+
+/// 累计统计数据
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Statistics {
+    /// 处理过的源视频数量
+    #[serde(default)]
+    pub videos_processed: u64,
+    /// 生成的输出文件（片段/成片）数量
+    #[serde(default)]
+    pub segments_produced: u64,
+    /// 输出视频总时长（秒）
+    #[serde(default)]
+    pub output_duration: f64,
+    /// 以流复制（不重编码）方式输出的视频时长（秒）
+    #[serde(default)]
+    pub copy_mode_duration: f64,
+    #[serde(default)]
+    pub bytes_downloaded: u64,
+    /// 按操作类型统计的执行/失败次数
+    #[serde(default)]
+    pub operations: BTreeMap<String, OperationCount>,
+}
+
+/// 返回给前端的统计汇总（附带计算后的指标）
+#[derive(Debug, Clone, Serialize)]
+pub struct StatisticsSummary {
+    #[serde(flatten)]
+    pub stats: Statistics,
+    /// 流复制节省的编码时长（小时，按实时编码速度估算）
+    pub encode_hours_saved: f64,
+    /// 各操作类型的失败率（0-1）
+    pub failure_rates: BTreeMap<String, f64>,
+}
+
+impl From<Statistics> for StatisticsSummary {
+    fn from(stats: Statistics) -> Self {
+        let failure_rates = stats
+            .operations
+            .iter()
+            .filter(|(_, count)| count.total > 0)
+            .map(|(kind, count)| (kind.clone(), count.failed as f64 / count.total as f64))
+            .collect();
+        Self {
+            encode_hours_saved: stats.copy_mode_duration / 3600.0,
+            failure_rates,
+            stats,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatisticsReport {
+    pub lifetime: StatisticsSummary,
+    pub session: StatisticsSummary,
+    /// 本次启动时间
+    pub session_started_at: String,
+}
+
+/// 统计管理器：累计数据保存在应用配置目录的 stats.json，本次会话数据只在内存中
+pub struct StatsManager {
+    path: PathBuf,
+    started_at: String,
+    /// (累计, 本次会话)
+    stats: Mutex<(Statistics, Statistics)>,
+}
+
+impl StatsManager {
+    pub fn load(path: PathBuf) -> Self {
+        let lifetime = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            started_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            stats: Mutex::new((lifetime, Statistics::default())),
+        }
+    }
+
+    /// 同时更新累计与本次会话数据，并写回累计数据
+    pub fn record<F>(&self, f: F)
+    where
+        F: Fn(&mut Statistics),
+    {
+        let mut stats = self.stats.lock().unwrap();
+        f(&mut stats.0);
+        f(&mut stats.1);
+
+        // 统计数据写入失败不影响业务
+        if let Ok(content) = serde_json::to_string_pretty(&stats.0) {
+            if let Some(parent) = self.path.parent() {
+                let _ = fs::create_dir_all(parent);
+            }
+            let _ = fs::write(&self.path, content);
+        }
+    }
+
+    pub fn report(&self) -> StatisticsReport {
+        let stats = self.stats.lock().unwrap();
+        StatisticsReport {
+            lifetime: stats.0.clone().into(),
+            session: stats.1.clone().into(),
+            session_started_at: self.started_at.clone(),
+        }
+    }
+}
+
+/// 记录一次操作的结果
+pub fn record_operation<T>(app: &AppHandle, kind: &str, result: &Result<T, String>) {
+    app.state::<StatsManager>().record(|s| {
+        let count = s.operations.entry(kind.to_string()).or_default();
+        count.total += 1;
+        if result.is_err() {
+            count.failed += 1;
+        }
+    });
+}
+
+/// 记录一次输出：`videos` 个源视频生成了 `outputs` 个文件，总时长 `duration` 秒
+pub fn record_output(app: &AppHandle, videos: usize, outputs: usize, duration: f64, copied: bool) {
+    app.state::<StatsManager>().record(|s| {
+        s.videos_processed += videos as u64;
+        s.segments_produced += outputs as u64;
+        s.output_duration += duration;
+        if copied {
+            s.copy_mode_duration += duration;
+        }
+    });
+}
+
+// 获取累计与本次会话的统计数据
+#[tauri::command]
+pub fn get_statistics(stats: State<'_, StatsManager>) -> StatisticsReport {
+    stats.report()
+}
@@ -0,0 +1,299 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, State};
+use ts_rs::TS;
+use crate::i18n::localize_error;
+use crate::path_scope::check_input;
+use crate::stats::record_operation;
+use crate::video_processor::{collect_videos, get_video_info, ExcludedVideo, PoolFilters};
+
+/// 片段标签，保存在数据目录的 clip_tags.json（键为片段路径）
+pub struct TagManager {
+    path: PathBuf,
+    tags: Mutex<BTreeMap<String, BTreeSet<String>>>,
+}
+
+impl TagManager {
+    pub fn load(path: PathBuf) -> Self {
+        let tags = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            tags: Mutex::new(tags),
+        }
+    }
+
+    /// 保存所有标签（在持有锁时调用），写入失败不影响业务
+    fn save(&self, tags: &BTreeMap<String, BTreeSet<String>>) {
+        if let Ok(content) = serde_json::to_string(tags) {
+            if let Some(parent) = self.path.parent() {
+                let _ = fs::create_dir_all(parent);
+            }
+            let _ = fs::write(&self.path, content);
+        }
+    }
+
+    /// 片段的标签
+    pub fn tags_of(&self, video: &Path) -> BTreeSet<String> {
+        let tags = self.tags.lock().unwrap();
+        tags.get(&video.to_string_lossy().to_string()).cloned().unwrap_or_default()
+    }
+
+    /// 给片段添加标签，返回新增的标签数
+    fn add(&self, videos: &[String], new_tags: &[String]) -> usize {
+        let mut tags = self.tags.lock().unwrap();
+        let mut added = 0;
+        for video in videos {
+            let entry = tags.entry(video.clone()).or_default();
+            for tag in new_tags {
+                if entry.insert(tag.clone()) {
+                    added += 1;
+                }
+            }
+        }
+        self.save(&tags);
+        added
+    }
+
+    /// 移除片段的标签，没有标签的片段从列表中删除
+    fn remove(&self, videos: &[String], old_tags: &[String]) {
+        let mut tags = self.tags.lock().unwrap();
+        for video in videos {
+            if let Some(entry) = tags.get_mut(video) {
+                for tag in old_tags {
+                    entry.remove(tag);
+                }
+                if entry.is_empty() {
+                    tags.remove(video);
+                }
+            }
+        }
+        self.save(&tags);
+    }
+}
+
+/// 去掉首尾空白，丢弃空标签
+fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    tags.into_iter()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+/// 自动打标签的规则：文件名包含指定文字且满足质量条件的片段打上标签
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagRule {
+    pub tag: String,
+    /// 文件名（不区分大小写）需要包含的文字
+    #[serde(default)]
+    pub name_contains: Option<String>,
+    #[serde(default)]
+    pub filters: PoolFilters,
+}
+
+impl TagRule {
+    fn matches_name(&self, video: &Path) -> bool {
+        match &self.name_contains {
+            Some(pattern) => video
+                .file_name()
+                .map(|n| n.to_string_lossy().to_lowercase().contains(&pattern.to_lowercase()))
+                .unwrap_or(false),
+            None => true,
+        }
+    }
+}
+
+/// 标签配额：每次抽取至少包含 `min` 个带该标签的片段
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TagQuota {
+    pub tag: String,
+    pub min: usize,
+}
+
+/// 拼接时的标签条件
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TagFilter {
+    /// 只使用带有其中任一标签的片段（为空则不限制）
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// 带有其中任一标签的片段不进入池子
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub quotas: Vec<TagQuota>,
+}
+
+impl TagFilter {
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty() && self.quotas.is_empty()
+    }
+
+    /// 配额之和不能超过每次抽取的最少数量
+    pub fn validate(&self, random_count_min: usize) -> Result<(), String> {
+        let total: usize = self.quotas.iter().map(|q| q.min).sum();
+        if total > random_count_min {
+            return Err(format!(
+                "标签配额之和 {} 超过了每次抽取的最少数量 {}",
+                total, random_count_min
+            ));
+        }
+        Ok(())
+    }
+
+    /// 按包含/排除标签筛选池子视频，返回（保留列表，排除列表）
+    pub fn apply(&self, manager: &TagManager, videos: Vec<PathBuf>) -> (Vec<PathBuf>, Vec<ExcludedVideo>) {
+        let mut kept = Vec::new();
+        let mut excluded = Vec::new();
+        for video in videos {
+            let tags = manager.tags_of(&video);
+            let mut reasons: Vec<String> = self
+                .exclude
+                .iter()
+                .filter(|t| tags.contains(*t))
+                .map(|t| format!("带有排除标签 {}", t))
+                .collect();
+            if !self.include.is_empty() && !self.include.iter().any(|t| tags.contains(t)) {
+                reasons.push(format!("没有以下任一标签: {}", self.include.join(", ")));
+            }
+            if reasons.is_empty() {
+                kept.push(video);
+            } else {
+                excluded.push(ExcludedVideo {
+                    path: video,
+                    reasons,
+                });
+            }
+        }
+        (kept, excluded)
+    }
+
+    /// 各配额在池子中的候选片段
+    pub fn draw_quotas(&self, manager: &TagManager, pool: &[PathBuf]) -> Vec<DrawQuota> {
+        self.quotas
+            .iter()
+            .filter(|q| q.min > 0)
+            .map(|q| DrawQuota {
+                tag: q.tag.clone(),
+                min: q.min,
+                candidates: pool
+                    .iter()
+                    .filter(|v| manager.tags_of(v).contains(&q.tag))
+                    .cloned()
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+/// 抽取时需要满足的配额
+#[derive(Debug, Clone)]
+pub struct DrawQuota {
+    pub tag: String,
+    pub min: usize,
+    pub candidates: HashSet<PathBuf>,
+}
+
+// 给片段添加标签，返回新增的标签数
+#[tauri::command]
+pub fn add_clip_tags(
+    app: AppHandle,
+    manager: State<'_, TagManager>,
+    paths: Vec<String>,
+    tags: Vec<String>,
+) -> Result<usize, String> {
+    for path in &paths {
+        check_input(&app, path)?;
+    }
+    Ok(manager.add(&paths, &normalize_tags(tags)))
+}
+
+// 移除片段的标签
+#[tauri::command]
+pub fn remove_clip_tags(
+    app: AppHandle,
+    manager: State<'_, TagManager>,
+    paths: Vec<String>,
+    tags: Vec<String>,
+) -> Result<(), String> {
+    for path in &paths {
+        check_input(&app, path)?;
+    }
+    manager.remove(&paths, &normalize_tags(tags));
+    Ok(())
+}
+
+// 获取片段的标签
+#[tauri::command]
+pub fn get_clip_tags(
+    manager: State<'_, TagManager>,
+    paths: Vec<String>,
+) -> BTreeMap<String, Vec<String>> {
+    paths
+        .into_iter()
+        .map(|path| {
+            let tags = manager.tags_of(Path::new(&path)).into_iter().collect();
+            (path, tags)
+        })
+        .collect()
+}
+
+// 按规则给目录中的片段自动打标签，返回新增的标签数
+#[tauri::command]
+pub async fn apply_tag_rules(
+    app: AppHandle,
+    manager: State<'_, TagManager>,
+    dir: String,
+    max_depth: Option<usize>,
+    rules: Vec<TagRule>,
+) -> Result<usize, String> {
+    let result = apply_tag_rules_internal(&app, &manager, &dir, max_depth.unwrap_or(0), rules).await;
+    record_operation(&app, "tag_rules", &result);
+    result.map_err(|e| localize_error(&app, e))
+}
+
+async fn apply_tag_rules_internal(
+    app: &AppHandle,
+    manager: &TagManager,
+    dir: &str,
+    max_depth: usize,
+    rules: Vec<TagRule>,
+) -> Result<usize, String> {
+    check_input(app, dir)?;
+    if rules.iter().any(|r| r.tag.trim().is_empty()) {
+        return Err("标签规则的标签不能为空".to_string());
+    }
+
+    // 按标签汇总后一次写入
+    let mut matched: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for video in collect_videos(dir, max_depth)? {
+        let candidates: Vec<&TagRule> = rules.iter().filter(|r| r.matches_name(&video)).collect();
+        if candidates.is_empty() {
+            continue;
+        }
+        // 只有规则带质量条件时才探测视频，探测失败的视频不满足质量条件
+        let info = if candidates.iter().any(|r| !r.filters.is_empty()) {
+            get_video_info(app, &video).await.ok()
+        } else {
+            None
+        };
+        for rule in candidates {
+            if rule.filters.is_empty() || info.as_ref().is_some_and(|i| rule.filters.check(i).is_empty()) {
+                matched
+                    .entry(rule.tag.trim().to_string())
+                    .or_default()
+                    .push(video.to_string_lossy().to_string());
+            }
+        }
+    }
+    Ok(matched
+        .into_iter()
+        .map(|(tag, videos)| manager.add(&videos, &[tag]))
+        .sum())
+}
@@ -0,0 +1,505 @@
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_shell::process::CommandEvent;
+use tauri_plugin_shell::ShellExt;
+use ts_rs::TS;
+use crate::events::{EncodeProgress, ENCODE_PROGRESS, JOB_STATS};
+use crate::settings::SettingsManager;
+use crate::shutdown::ProcessRegistry;
+
+/// 队列任务运行时上报 CPU 占用的间隔
+const STATS_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// 编码资源限制：让后台编码给其他程序留出余量
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ThrottleSettings {
+    /// FFmpeg 编码/滤镜线程数（libx264 的 threads 参数同样由 -threads 设置），为空则由 FFmpeg 自动决定
+    #[serde(default)]
+    pub threads: Option<u32>,
+    /// 以较低的进程优先级运行 FFmpeg
+    #[serde(default)]
+    pub low_priority: bool,
+    /// 只允许 FFmpeg 使用这些 CPU 核心（从 0 开始的序号），为空则不限制；macOS 不支持
+    #[serde(default)]
+    pub cpu_affinity: Option<Vec<usize>>,
+    /// 多次拼接输出时同时运行的 FFmpeg 数量，为空则按 CPU 核数自动决定
+    #[serde(default)]
+    pub max_parallel_runs: Option<u32>,
+}
+
+impl ThrottleSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.threads == Some(0) {
+            return Err("线程数必须大于 0".to_string());
+        }
+        if self.max_parallel_runs == Some(0) {
+            return Err("并行数量必须大于 0".to_string());
+        }
+        if let Some(cores) = &self.cpu_affinity {
+            if !AFFINITY_SUPPORTED {
+                return Err("当前平台不支持设置 CPU 亲和性".to_string());
+            }
+            let available = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+            if cores.is_empty() {
+                return Err("CPU 亲和性至少需要一个核心".to_string());
+            }
+            if let Some(core) = cores.iter().find(|&&c| c >= available.min(64)) {
+                return Err(format!("CPU 核心序号 {} 超出范围（共 {} 个核心）", core, available));
+            }
+        }
+        Ok(())
+    }
+
+    /// 同时运行的拼接输出数量：未设置时每 4 个核心一个，最多 4 个
+    pub fn parallel_runs(&self) -> usize {
+        match self.max_parallel_runs {
+            Some(n) => n as usize,
+            None => {
+                let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+                (cores / 4).clamp(1, 4)
+            }
+        }
+    }
+
+    /// 放在所有输入之前的全局参数（滤镜线程数）
+    fn global_args(&self) -> Vec<String> {
+        match self.threads {
+            Some(n) => vec![
+                "-filter_threads".to_string(),
+                n.to_string(),
+                "-filter_complex_threads".to_string(),
+                n.to_string(),
+            ],
+            None => Vec::new(),
+        }
+    }
+
+    /// 放在输出文件之前的编码线程数参数
+    pub fn output_args(&self) -> Vec<String> {
+        match self.threads {
+            Some(n) => vec!["-threads".to_string(), n.to_string()],
+            None => Vec::new(),
+        }
+    }
+}
+
+/// 正在执行的队列任务
+#[derive(Clone)]
+struct JobContext {
+    job_id: String,
+    throttle: ThrottleSettings,
+}
+
+tokio::task_local! {
+    static JOB_CONTEXT: JobContext;
+}
+
+/// 任务被取消时返回的错误
+pub const JOB_CANCELLED: &str = "任务已取消";
+
+/// 在队列任务的上下文中执行：任务内启动的 FFmpeg 使用任务自己的资源限制（为空时使用全局设置），
+/// 并通过 job_stats 事件上报 CPU 占用
+pub async fn run_in_job<F: Future>(
+    app: &AppHandle,
+    job_id: &str,
+    throttle: Option<ThrottleSettings>,
+    f: F,
+) -> F::Output {
+    let context = JobContext {
+        job_id: job_id.to_string(),
+        throttle: throttle.unwrap_or_else(|| global(app)),
+    };
+    let registry = app.state::<ProcessRegistry>();
+    registry.begin_job(job_id);
+    let output = JOB_CONTEXT.scope(context, f).await;
+    registry.end_job(job_id);
+    output
+}
+
+/// 当前任务已被取消时返回错误（任务内各步骤之间调用，取消后不再启动新的进程）
+pub fn check_job_cancelled(app: &AppHandle) -> Result<(), String> {
+    match JOB_CONTEXT.try_with(|c| c.job_id.clone()) {
+        Ok(job_id) if app.state::<ProcessRegistry>().is_cancelled(&job_id) => Err(JOB_CANCELLED.to_string()),
+        _ => Ok(()),
+    }
+}
+
+fn global(app: &AppHandle) -> ThrottleSettings {
+    app.state::<SettingsManager>().get().throttle
+}
+
+/// 当前生效的资源限制
+pub fn current(app: &AppHandle) -> ThrottleSettings {
+    JOB_CONTEXT
+        .try_with(|c| c.throttle.clone())
+        .unwrap_or_else(|_| global(app))
+}
+
+/// FFmpeg 执行结果
+pub struct FfmpegOutput {
+    pub code: Option<i32>,
+    pub stderr: Vec<u8>,
+}
+
+impl FfmpegOutput {
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+}
+
+/// job_stats 事件
+#[derive(Clone, Serialize, TS)]
+#[ts(export)]
+struct JobStats {
+    job_id: String,
+    /// 整机 CPU 占用（0-100）
+    cpu_percent: f64,
+    threads: Option<u32>,
+    low_priority: bool,
+}
+
+/// 从 FFmpeg 的 -progress 输出（每行 key=value，每组以 progress= 结束）计算编码进度
+struct ProgressParser {
+    task: String,
+    label: String,
+    /// 输出总时长（秒）
+    duration: f64,
+    out_time: f64,
+    speed: Option<f64>,
+}
+
+impl ProgressParser {
+    /// 处理一行输出，读完一组时返回进度
+    fn line(&mut self, line: &str) -> Option<EncodeProgress> {
+        let (key, value) = line.trim().split_once('=')?;
+        match key {
+            // out_time_ms 实际单位也是微秒
+            "out_time_us" | "out_time_ms" => {
+                if let Ok(us) = value.parse::<i64>() {
+                    self.out_time = us.max(0) as f64 / 1_000_000.0;
+                }
+                None
+            }
+            "speed" => {
+                self.speed = value.trim_end_matches('x').trim().parse().ok().filter(|s: &f64| *s > 0.0);
+                None
+            }
+            "progress" => {
+                let done = value == "end";
+                let remaining = if done { 0.0 } else { (self.duration - self.out_time).max(0.0) };
+                Some(EncodeProgress {
+                    task: self.task.clone(),
+                    label: self.label.clone(),
+                    percent: if done { 100.0 } else { (self.out_time / self.duration * 100.0).clamp(0.0, 100.0) },
+                    eta_seconds: self.speed.map(|s| remaining / s),
+                    speed: self.speed,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// 按当前资源限制运行 FFmpeg（args 中的输出参数需自行带上 output_args）
+pub async fn run_ffmpeg(app: &AppHandle, args: Vec<String>) -> Result<FfmpegOutput, String> {
+    run_ffmpeg_inner(app, args, None).await
+}
+
+/// 同 `run_ffmpeg`，并按输出总时长 `duration`（秒）发送 encode_progress 事件
+pub async fn run_ffmpeg_with_progress(
+    app: &AppHandle,
+    args: Vec<String>,
+    task: &str,
+    label: String,
+    duration: f64,
+) -> Result<FfmpegOutput, String> {
+    let parser = (duration > 0.0).then(|| ProgressParser {
+        task: task.to_string(),
+        label,
+        duration,
+        out_time: 0.0,
+        speed: None,
+    });
+    run_ffmpeg_inner(app, args, parser).await
+}
+
+async fn run_ffmpeg_inner(
+    app: &AppHandle,
+    args: Vec<String>,
+    mut parser: Option<ProgressParser>,
+) -> Result<FfmpegOutput, String> {
+    check_job_cancelled(app)?;
+    let throttle = current(app);
+    let sidecar = app
+        .shell()
+        .sidecar("ffmpeg")
+        .map_err(|e| format!("FFmpeg 启动失败: {}", e))?;
+
+    let progress_args: &[&str] = if parser.is_some() {
+        &["-progress", "pipe:1", "-nostats"]
+    } else {
+        &[]
+    };
+    let (mut rx, child) = sidecar
+        .args(throttle.global_args())
+        .args(progress_args)
+        .args(args)
+        .spawn()
+        .map_err(|e| format!("FFmpeg 执行失败: {}", e))?;
+    let child_pid = child.pid();
+    // 登记子进程，应用退出或取消任务时统一结束
+    let registry = app.state::<ProcessRegistry>();
+    let pid = registry.register_child(child);
+    apply_process_limits(child_pid, &throttle).await;
+
+    let job_id = JOB_CONTEXT.try_with(|c| c.job_id.clone()).ok();
+    if let Some(job_id) = &job_id {
+        registry.assign_job(pid, job_id);
+    }
+    let mut sampler = CpuSampler::default();
+    let mut ticker = tokio::time::interval(STATS_INTERVAL);
+    let mut stderr = Vec::new();
+    let mut code = None;
+    let mut error = None;
+    loop {
+        tokio::select! {
+            event = rx.recv() => match event {
+                Some(CommandEvent::Stderr(line)) => {
+                    stderr.extend_from_slice(&line);
+                    stderr.push(b'\n');
+                }
+                Some(CommandEvent::Stdout(line)) => {
+                    if let Some(progress) = parser.as_mut().and_then(|p| p.line(&String::from_utf8_lossy(&line))) {
+                        let _ = app.emit(ENCODE_PROGRESS, progress);
+                    }
+                }
+                Some(CommandEvent::Error(e)) => {
+                    error = Some(format!("FFmpeg 执行失败: {}", e));
+                    break;
+                }
+                Some(CommandEvent::Terminated(payload)) => code = payload.code,
+                Some(_) => {}
+                None => break,
+            },
+            _ = ticker.tick(), if job_id.is_some() => {
+                if let (Some(job_id), Some(cpu_percent)) = (&job_id, sampler.sample()) {
+                    let _ = app.emit(JOB_STATS, JobStats {
+                        job_id: job_id.clone(),
+                        cpu_percent,
+                        threads: throttle.threads,
+                        low_priority: throttle.low_priority,
+                    });
+                }
+            }
+        }
+    }
+
+    registry.release_child(pid);
+    check_job_cancelled(app)?;
+    match error {
+        Some(e) => Err(e),
+        None => Ok(FfmpegOutput { code, stderr }),
+    }
+}
+
+/// 当前平台能否设置 CPU 亲和性
+const AFFINITY_SUPPORTED: bool = cfg!(any(target_os = "linux", target_os = "windows"));
+
+/// 按资源限制调整已启动的 FFmpeg 进程：降低优先级、限制可用的 CPU 核心（失败时忽略，不影响处理）
+pub async fn apply_process_limits(pid: u32, throttle: &ThrottleSettings) {
+    if throttle.low_priority {
+        lower_priority(pid).await;
+    }
+    if let Some(cores) = throttle.cpu_affinity.as_deref().filter(|c| !c.is_empty()) {
+        set_affinity(pid, cores).await;
+    }
+}
+
+#[cfg(unix)]
+async fn lower_priority(pid: u32) {
+    let _ = tokio::process::Command::new("renice")
+        .args(["-n", "10", "-p", &pid.to_string()])
+        .output()
+        .await;
+}
+
+#[cfg(target_os = "windows")]
+async fn lower_priority(pid: u32) {
+    use std::ffi::c_void;
+    const PROCESS_SET_INFORMATION: u32 = 0x0200;
+    const BELOW_NORMAL_PRIORITY_CLASS: u32 = 0x4000;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn OpenProcess(access: u32, inherit: i32, pid: u32) -> *mut c_void;
+        fn SetPriorityClass(process: *mut c_void, class: u32) -> i32;
+        fn CloseHandle(handle: *mut c_void) -> i32;
+    }
+
+    // SAFETY: 句柄只在非空时使用，并在使用后关闭
+    unsafe {
+        let handle = OpenProcess(PROCESS_SET_INFORMATION, 0, pid);
+        if !handle.is_null() {
+            SetPriorityClass(handle, BELOW_NORMAL_PRIORITY_CLASS);
+            CloseHandle(handle);
+        }
+    }
+}
+
+#[cfg(not(any(unix, target_os = "windows")))]
+async fn lower_priority(_pid: u32) {}
+
+/// 限制进程（含已创建的线程）只在指定核心上运行
+#[cfg(target_os = "linux")]
+async fn set_affinity(pid: u32, cores: &[usize]) {
+    let list = cores.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(",");
+    let _ = tokio::process::Command::new("taskset")
+        .args(["-a", "-p", "-c", &list, &pid.to_string()])
+        .output()
+        .await;
+}
+
+/// 限制进程只在指定核心上运行
+#[cfg(target_os = "windows")]
+async fn set_affinity(pid: u32, cores: &[usize]) {
+    use std::ffi::c_void;
+    const PROCESS_SET_INFORMATION: u32 = 0x0200;
+    const PROCESS_QUERY_INFORMATION: u32 = 0x0400;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn OpenProcess(access: u32, inherit: i32, pid: u32) -> *mut c_void;
+        fn SetProcessAffinityMask(process: *mut c_void, mask: usize) -> i32;
+        fn CloseHandle(handle: *mut c_void) -> i32;
+    }
+
+    let mask = cores.iter().filter(|&&c| c < usize::BITS as usize).fold(0usize, |m, &c| m | 1 << c);
+    // SAFETY: 句柄只在非空时使用，并在使用后关闭
+    unsafe {
+        let handle = OpenProcess(PROCESS_SET_INFORMATION | PROCESS_QUERY_INFORMATION, 0, pid);
+        if !handle.is_null() {
+            SetProcessAffinityMask(handle, mask);
+            CloseHandle(handle);
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+async fn set_affinity(_pid: u32, _cores: &[usize]) {}
+
+/// 整机 CPU 占用采样：按两次采样之间的忙碌时间计算
+#[derive(Default)]
+struct CpuSampler {
+    /// 上次采样的 (忙碌, 总计) 累计时间
+    last: Option<(u64, u64)>,
+}
+
+impl CpuSampler {
+    #[cfg(any(target_os = "linux", target_os = "windows"))]
+    fn sample(&mut self) -> Option<f64> {
+        let (busy, total) = cpu_times()?;
+        let previous = self.last.replace((busy, total));
+        let (last_busy, last_total) = previous?;
+        let total_delta = total.saturating_sub(last_total);
+        if total_delta == 0 {
+            return None;
+        }
+        Some(busy.saturating_sub(last_busy) as f64 / total_delta as f64 * 100.0)
+    }
+
+    /// macOS 没有累计时间接口，直接汇总所有进程的瞬时占用
+    #[cfg(target_os = "macos")]
+    fn sample(&mut self) -> Option<f64> {
+        let output = std::process::Command::new("ps")
+            .args(["-A", "-o", "%cpu="])
+            .output()
+            .ok()?;
+        let sum: f64 = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|l| l.trim().parse::<f64>().ok())
+            .sum();
+        let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        Some((sum / cores as f64).min(100.0))
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+    fn sample(&mut self) -> Option<f64> {
+        None
+    }
+}
+
+/// /proc/stat 第一行：user nice system idle iowait irq softirq steal
+#[cfg(target_os = "linux")]
+fn cpu_times() -> Option<(u64, u64)> {
+    let content = std::fs::read_to_string("/proc/stat").ok()?;
+    let line = content.lines().next()?.strip_prefix("cpu ")?;
+    let values: Vec<u64> = line
+        .split_whitespace()
+        .take(8)
+        .filter_map(|v| v.parse().ok())
+        .collect();
+    if values.len() < 5 {
+        return None;
+    }
+    let total: u64 = values.iter().sum();
+    let idle = values[3] + values[4];
+    Some((total - idle, total))
+}
+
+#[cfg(target_os = "windows")]
+fn cpu_times() -> Option<(u64, u64)> {
+    #[repr(C)]
+    #[derive(Default)]
+    struct FileTime {
+        low: u32,
+        high: u32,
+    }
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetSystemTimes(idle: *mut FileTime, kernel: *mut FileTime, user: *mut FileTime) -> i32;
+    }
+
+    let (mut idle, mut kernel, mut user) = (FileTime::default(), FileTime::default(), FileTime::default());
+    // SAFETY: 三个参数都是按 Win32 FILETIME 布局声明的有效结构体
+    let ok = unsafe { GetSystemTimes(&mut idle, &mut kernel, &mut user) };
+    if ok == 0 {
+        return None;
+    }
+    let value = |t: &FileTime| (t.high as u64) << 32 | t.low as u64;
+    // 内核时间包含空闲时间
+    let total = value(&kernel) + value(&user);
+    Some((total.saturating_sub(value(&idle)), total))
+}
+
+// 保存全局编码资源限制
+#[tauri::command]
+pub fn save_throttle_settings(
+    settings: State<'_, SettingsManager>,
+    throttle: ThrottleSettings,
+) -> Result<(), String> {
+    throttle.validate()?;
+    settings.update(|s| {
+        s.throttle = throttle;
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_affinity(cores: Vec<usize>) -> ThrottleSettings {
+        ThrottleSettings {
+            cpu_affinity: Some(cores),
+            ..ThrottleSettings::default()
+        }
+    }
+
+    #[test]
+    fn affinity_needs_existing_cores() {
+        assert_eq!(with_affinity(vec![0]).validate().is_ok(), AFFINITY_SUPPORTED);
+        assert!(with_affinity(Vec::new()).validate().is_err());
+        assert!(with_affinity(vec![usize::MAX]).validate().is_err());
+    }
+}
@@ -0,0 +1,206 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// 转场样式，对应 FFmpeg xfade 的 transition 参数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum TransitionStyle {
+    #[default]
+    Fade,
+    FadeBlack,
+    Dissolve,
+    WipeLeft,
+    WipeRight,
+    SlideLeft,
+    SlideRight,
+}
+
+impl TransitionStyle {
+    fn xfade_name(&self) -> &'static str {
+        match self {
+            Self::Fade => "fade",
+            Self::FadeBlack => "fadeblack",
+            Self::Dissolve => "dissolve",
+            Self::WipeLeft => "wipeleft",
+            Self::WipeRight => "wiperight",
+            Self::SlideLeft => "slideleft",
+            Self::SlideRight => "slideright",
+        }
+    }
+}
+
+fn default_transition_duration() -> f64 {
+    0.5
+}
+
+/// 片段之间的转场：画面用 xfade 重叠过渡，声音单独设置交叉淡化时长
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Transition {
+    #[serde(default)]
+    pub style: TransitionStyle,
+    /// 画面转场时长（秒），相邻片段在这段时间内重叠
+    #[serde(default = "default_transition_duration")]
+    pub duration: f64,
+    /// 声音交叉淡化时长（秒），为空时与画面转场相同；声音突然切换比画面更明显，通常设得更长
+    #[serde(default)]
+    pub audio_duration: Option<f64>,
+}
+
+impl Transition {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.duration <= 0.0 {
+            return Err("转场时长必须大于 0".to_string());
+        }
+        if self.audio_duration.is_some_and(|d| d < 0.0) {
+            return Err("声音交叉淡化时长不能为负数".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn audio_duration(&self) -> f64 {
+        self.audio_duration.unwrap_or(self.duration)
+    }
+
+    /// 转场重叠掉的总时长
+    pub fn overlap(&self, clips: usize) -> f64 {
+        self.duration * clips.saturating_sub(1) as f64
+    }
+
+    /// 用转场代替 concat 连接各片段的 `[v{i}][a{i}]`，输出到 `out_v`、`out_a`
+    ///
+    /// 第 k 个片段在成片中的起点为前面片段时长之和减去 k 个转场时长，即第 k 个 xfade 的 offset。
+    /// 声音不用 acrossfade 串联（淡化时长与画面不同时会逐段偏移），而是每段按起点延迟后混音：
+    /// 前一段在结尾淡出、后一段在开头淡入，淡化时长可以长于或短于画面重叠，总时长始终与画面一致
+    pub fn join_filters(&self, durations: &[f64], out_v: &str, out_a: &str) -> Result<Vec<String>, String> {
+        let count = durations.len();
+        let vd = self.duration;
+        let ad = self.audio_duration();
+        for (idx, duration) in durations.iter().enumerate() {
+            // 中间的片段两端都有转场
+            let edges = if idx == 0 || idx == count - 1 { 1.0 } else { 2.0 };
+            if *duration <= vd * edges {
+                return Err(format!(
+                    "第 {} 个片段时长 {:.2}s 不足以容纳 {:.2}s 的转场",
+                    idx + 1,
+                    duration,
+                    vd
+                ));
+            }
+            if ad * edges > *duration {
+                return Err(format!(
+                    "第 {} 个片段时长 {:.2}s 不足以容纳 {:.2}s 的声音交叉淡化",
+                    idx + 1,
+                    duration,
+                    ad
+                ));
+            }
+        }
+
+        let mut parts = self.video_join_filters(durations, "", out_v);
+        let starts = self.clip_starts(durations);
+
+        let mut mix_inputs = String::new();
+        for (idx, (start, duration)) in starts.iter().zip(durations).enumerate() {
+            let mut filters = Vec::new();
+            if ad > 0.0 && idx > 0 {
+                filters.push(format!("afade=t=in:st=0:d={:.6}", ad));
+            }
+            if ad > 0.0 && idx < count - 1 {
+                filters.push(format!("afade=t=out:st={:.6}:d={:.6}", duration - ad, ad));
+            }
+            if *start > 0.0 {
+                filters.push(format!("adelay=delays={}:all=1", (start * 1000.0).round() as u64));
+            }
+            if filters.is_empty() {
+                filters.push("anull".to_string());
+            }
+            parts.push(format!("[a{idx}]{}[xa{idx}]", filters.join(",")));
+            mix_inputs.push_str(&format!("[xa{}]", idx));
+        }
+        parts.push(format!(
+            "{mix_inputs}amix=inputs={count}:duration=longest:dropout_transition=0:normalize=0{out_a}"
+        ));
+        Ok(parts)
+    }
+
+    /// 只用转场连接各片段的画面 `[v{i}{suffix}]`，输出到 `out_v`
+    ///
+    /// 同一组片段渲染多个画布时，其余画布的画面用此单独连接；片段时长已由 `join_filters` 检查
+    pub fn video_join_filters(&self, durations: &[f64], suffix: &str, out_v: &str) -> Vec<String> {
+        let count = durations.len();
+        let starts = self.clip_starts(durations);
+        let mut parts = Vec::new();
+        let mut previous = format!("[v0{}]", suffix);
+        for (idx, offset) in starts.iter().enumerate().skip(1) {
+            let label = if idx == count - 1 {
+                out_v.to_string()
+            } else {
+                format!("[xv{}{}]", idx, suffix)
+            };
+            parts.push(format!(
+                "{previous}[v{idx}{suffix}]xfade=transition={}:duration={vd:.6}:offset={offset:.6}{label}",
+                self.style.xfade_name(),
+                vd = self.duration,
+            ));
+            previous = label;
+        }
+        parts
+    }
+
+    /// 各片段在成片中的起点：前面片段时长之和减去之前的转场时长
+    fn clip_starts(&self, durations: &[f64]) -> Vec<f64> {
+        let mut starts = Vec::with_capacity(durations.len());
+        let mut start = 0.0;
+        for duration in durations {
+            starts.push(start);
+            start += duration - self.duration;
+        }
+        starts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fade(audio_duration: Option<f64>) -> Transition {
+        Transition {
+            style: TransitionStyle::Fade,
+            duration: 1.0,
+            audio_duration,
+        }
+    }
+
+    #[test]
+    fn join_filters_offset_each_clip() {
+        let parts = fade(None).join_filters(&[3.0, 3.0, 3.0], "[outv]", "[outa]").unwrap();
+        assert_eq!(
+            parts,
+            vec![
+                "[v0][v1]xfade=transition=fade:duration=1.000000:offset=2.000000[xv1]",
+                "[xv1][v2]xfade=transition=fade:duration=1.000000:offset=4.000000[outv]",
+                "[a0]afade=t=out:st=2.000000:d=1.000000[xa0]",
+                "[a1]afade=t=in:st=0:d=1.000000,afade=t=out:st=2.000000:d=1.000000,adelay=delays=2000:all=1[xa1]",
+                "[a2]afade=t=in:st=0:d=1.000000,adelay=delays=4000:all=1[xa2]",
+                "[xa0][xa1][xa2]amix=inputs=3:duration=longest:dropout_transition=0:normalize=0[outa]",
+            ]
+        );
+    }
+
+    #[test]
+    fn join_filters_reject_short_clips() {
+        assert!(fade(None).join_filters(&[3.0, 1.5, 3.0], "[outv]", "[outa]").is_err());
+        assert!(fade(Some(2.0)).join_filters(&[3.0, 3.0], "[outv]", "[outa]").is_ok());
+        assert!(fade(Some(4.0)).join_filters(&[3.0, 3.0], "[outv]", "[outa]").is_err());
+    }
+
+    #[test]
+    fn video_join_filters_use_canvas_suffix() {
+        assert_eq!(
+            fade(None).video_join_filters(&[3.0, 3.0], "_1", "[outv_1]"),
+            vec!["[v0_1][v1_1]xfade=transition=fade:duration=1.000000:offset=2.000000[outv_1]"]
+        );
+    }
+}
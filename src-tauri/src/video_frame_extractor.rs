@@ -3,14 +3,42 @@ use std::collections::hash_map::DefaultHasher;
 use std::fs;
 use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use tauri::{AppHandle, Emitter, Manager};
+use std::time::Instant;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_shell::process::CommandEvent;
 use tauri_plugin_shell::ShellExt;
 use rayon::prelude::*;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use crate::frame_similarity::{calculate_similarity, SimilarityAlgorithm};
-use crate::video_processor::{check_video_compatibility_for_paths, build_concat_filter};
+use rand::SeedableRng;
+use ts_rs::TS;
+use crate::events::{
+    AutoSplitDiagnostics, SegmentProgress, AUTO_SPLIT_DIAGNOSTICS, AUTO_SPLIT_PROGRESS, FRAME_COUNT_RECONCILED, FRAME_PROGRESS, REMOVE_ENDING_PROGRESS,
+    SEGMENTATION_SESSION_RESTORED, SEGMENT_PROGRESS,
+};
+use crate::analysis_cache::{self, SeriesEntry};
+use crate::audio_join::{align_junction, MAX_TRIM_FRAMES};
+use crate::batch::{check_batch_signal, current_batch_run};
+use crate::i18n::{localize_error, progress, t};
+use crate::jobs::{AutoSplitParams, RemoveEndingParams};
+use crate::frame_similarity::{self, calculate_similarity, HashPrefilter, RegionMask, SimilarityAlgorithm};
+use crate::export_preset::{self, bitrate_args, loudnorm_filter, ExportPreset};
+use crate::gop;
+use crate::hw_encoder::{self, EncoderChoice, HwEncoder};
+use crate::overlay::inspect_watermark;
+use crate::path_scope::{check_input, check_output};
+use crate::sessions::{content_hash, find_session, Fnv1a};
+use crate::settings::SettingsManager;
+use crate::shutdown::ProcessRegistry;
+use crate::source_date::{file_label, metadata_value, recorded_at, SourceDateOptions};
+use crate::stats::{record_operation, record_output};
+use crate::throttle::{self, apply_process_limits, run_ffmpeg, run_ffmpeg_with_progress};
+use crate::video_processor::{
+    build_concat_command_args, build_concat_filter, check_video_compatibility_for_paths,
+    get_video_info, share_inputs, Canvas, ClipTrim, ConcatExtras, EncodeOutput, EncodeSettings, VideoCodec,
+};
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct VideoMetadata {
@@ -20,6 +48,9 @@ pub struct VideoMetadata {
     pub duration: f64,
     pub total_frames: u32,
     pub codec: String,
+    /// 帧数来自容器记录或按时长估算，未逐帧统计
+    #[serde(default)]
+    pub frames_estimated: bool,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -29,17 +60,80 @@ pub struct FrameInfo {
     pub image_path: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct SegmentRange {
     pub start_frame: u32,
     pub end_frame: u32,
 }
 
+/// 单次滤镜图直接裁剪时最多的片段数，超过后滤镜分支过多，改为先生成临时片段
+const DIRECT_MAX_SEGMENTS: usize = 64;
+
+/// 去结尾时临时片段的生成方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum TempSegmentMode {
+    /// 按 CRF 重新编码为普通 H.264 片段
+    Reencode,
+    /// 全关键帧的高质量中间文件（音频不压缩），合成时画质损失更小
+    Intra,
+    /// 不生成临时文件，在最终滤镜图中按时间裁剪原视频，只编码一次；
+    /// 片段超过上限时改为重新编码临时片段
+    #[default]
+    Direct,
+}
+
+/// 去结尾的临时片段设置
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TempSegmentOptions {
+    #[serde(default)]
+    pub mode: TempSegmentMode,
+    /// 临时片段的 CRF，为空时重新编码用 18、全关键帧用 12
+    #[serde(default)]
+    pub crf: Option<u32>,
+}
+
+impl TempSegmentOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.crf.is_some_and(|crf| crf > 51) {
+            return Err("临时片段的 CRF 必须在 0 到 51 之间".to_string());
+        }
+        Ok(())
+    }
+
+    /// 临时片段的编码参数（不含输入与输出路径）
+    fn encode_args(&self) -> Vec<String> {
+        let args: &[&str] = match self.mode {
+            TempSegmentMode::Intra => &["-c:v", "libx264", "-preset", "veryfast", "-g", "1", "-c:a", "pcm_s16le"],
+            _ => &["-c:v", "libx264", "-preset", "fast", "-c:a", "aac", "-b:a", "192k"],
+        };
+        let default_crf = if self.mode == TempSegmentMode::Intra { 12 } else { 18 };
+        let mut args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        args.extend(["-crf".to_string(), self.crf.unwrap_or(default_crf).to_string()]);
+        args
+    }
+
+    /// 全关键帧片段的音频不压缩，MP4 不支持 PCM，改用 MOV 封装
+    fn extension(&self) -> &'static str {
+        if self.mode == TempSegmentMode::Intra {
+            "mov"
+        } else {
+            "mp4"
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct VideoTask {
     pub path: String,
     pub name: String,
     pub status: String,
+    /// 处理失败时的错误信息（用于导出报告）
+    #[serde(default)]
+    pub error: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -50,7 +144,43 @@ pub struct BatchProgress {
     pub current_index: usize,
 }
 
-fn parse_rational(value: &str) -> Option<f64> {
+/// 批量拆解中单个视频的片段进度，保存在该视频的片段输出目录，全部片段生成后删除；
+/// 中止后继续时跳过已生成的片段，只重新生成中断的片段及之后的片段
+#[derive(Serialize, Deserialize, Default)]
+struct SegmentResume {
+    /// 片段划分与编码参数的指纹，变化后已生成的片段不再复用
+    plan: u64,
+    /// 已完整生成的片段文件名
+    completed: Vec<String>,
+}
+
+impl SegmentResume {
+    const FILE_NAME: &'static str = ".segments_progress.json";
+
+    fn load(dir: &Path, plan: u64) -> Self {
+        fs::read_to_string(dir.join(Self::FILE_NAME))
+            .ok()
+            .and_then(|content| serde_json::from_str::<Self>(&content).ok())
+            .filter(|resume| resume.plan == plan)
+            .unwrap_or(Self {
+                plan,
+                completed: Vec::new(),
+            })
+    }
+
+    /// 写入失败只影响下次继续，不影响本次生成
+    fn save(&self, dir: &Path) {
+        if let Ok(content) = serde_json::to_string(self) {
+            let _ = fs::write(dir.join(Self::FILE_NAME), content);
+        }
+    }
+
+    fn clear(dir: &Path) {
+        let _ = fs::remove_file(dir.join(Self::FILE_NAME));
+    }
+}
+
+pub(crate) fn parse_rational(value: &str) -> Option<f64> {
     let trimmed = value.trim();
     if trimmed.is_empty() || trimmed == "N/A" {
         return None;
@@ -70,9 +200,7 @@ fn parse_rational(value: &str) -> Option<f64> {
 fn normalize_timestamps(mut timestamps: Vec<f64>) -> Vec<f64> {
     let mut last = 0.0f64;
     for ts in timestamps.iter_mut() {
-        if !ts.is_finite() || *ts < 0.0 {
-            *ts = last;
-        } else if *ts < last {
+        if !ts.is_finite() || *ts < last {
             *ts = last;
         }
         last = *ts;
@@ -98,7 +226,7 @@ async fn probe_frame_timestamps(
         .map_err(|e| format!("FFprobe 启动失败: {}", e))?;
 
     let output = sidecar
-        .args(&[
+        .args([
             "-v",
             "error",
             "-select_streams",
@@ -161,40 +289,142 @@ fn calculate_hash(path: &str) -> String {
     format!("{:x}", hasher.finish())
 }
 
+/// 逐帧统计期间检查取消信号的间隔
+const PROBE_CANCEL_POLL: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// 元数据读取的取消信号：每次取消递增代数，读取开始时记下代数，代数变化即表示已取消
+pub struct MetadataProbeControl {
+    generation: AtomicU64,
+}
+
+impl MetadataProbeControl {
+    pub fn new() -> Self {
+        Self {
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+}
+
 // 获取视频元数据
+//
+// 默认逐帧解码统计准确帧数，长视频可能需要很久；`quick` 为 true 时不解码，帧数按 时长 × 帧率 估算
 #[tauri::command]
 pub async fn get_video_metadata(
     app: AppHandle,
     video_path: String,
+    quick: Option<bool>,
 ) -> Result<VideoMetadata, String> {
-    get_video_metadata_internal(&app, &video_path).await
+    check_input(&app, &video_path)?;
+    probe_video_metadata(&app, &video_path, quick.unwrap_or(false)).await
 }
 
-// 内部使用的元数据获取
+// 取消正在进行的逐帧统计，读取中的元数据改为快速估算后返回
+#[tauri::command]
+pub fn cancel_metadata_probe(control: State<'_, MetadataProbeControl>) {
+    control.generation.fetch_add(1, Ordering::SeqCst);
+}
+
+// 内部使用的元数据获取（统计准确帧数）
 async fn get_video_metadata_internal(
     app: &AppHandle,
     video_path: &str,
 ) -> Result<VideoMetadata, String> {
+    probe_video_metadata(app, video_path, false).await
+}
+
+fn metadata_args(video_path: &str, count_frames: bool) -> Vec<String> {
+    let mut args = vec!["-v", "error", "-select_streams", "v:0"];
+    if count_frames {
+        args.push("-count_frames");
+    }
+    args.extend([
+        "-show_entries",
+        "stream=codec_name,width,height,r_frame_rate,avg_frame_rate,nb_read_frames,nb_frames",
+        "-show_entries",
+        "format=duration",
+        "-of",
+        "json",
+        video_path,
+    ]);
+    args.into_iter().map(|s| s.to_string()).collect()
+}
+
+/// 逐帧统计帧数的 FFprobe：批量任务被跳过/中止时返回错误，用户取消时返回 None
+async fn count_frames_probe(app: &AppHandle, video_path: &str) -> Result<Option<Vec<u8>>, String> {
+    let control = app.state::<MetadataProbeControl>();
+    let generation = control.generation();
     let sidecar = app
         .shell()
         .sidecar("ffprobe")
         .map_err(|e| format!("FFprobe 启动失败: {}", e))?;
+    let (mut rx, child) = sidecar
+        .args(metadata_args(video_path, true))
+        .spawn()
+        .map_err(|e| format!("FFprobe 执行失败: {}", e))?;
+    let registry = app.state::<ProcessRegistry>();
+    let pid = registry.register_child(child);
+
+    let mut ticker = tokio::time::interval(PROBE_CANCEL_POLL);
+    let mut stdout = Vec::new();
+    let mut stderr = String::new();
+    let mut exit_code = None;
+    loop {
+        tokio::select! {
+            event = rx.recv() => match event {
+                Some(CommandEvent::Stdout(line)) => {
+                    stdout.extend_from_slice(&line);
+                    stdout.push(b'\n');
+                }
+                Some(CommandEvent::Stderr(line)) => {
+                    stderr.push_str(&String::from_utf8_lossy(&line));
+                    stderr.push('\n');
+                }
+                Some(CommandEvent::Error(e)) => {
+                    registry.release_child(pid);
+                    return Err(format!("FFprobe 执行失败: {}", e));
+                }
+                Some(CommandEvent::Terminated(payload)) => exit_code = payload.code,
+                Some(_) => {}
+                None => break,
+            },
+            _ = ticker.tick() => {
+                if let Err(e) = check_batch_signal(app) {
+                    registry.kill_child(pid);
+                    return Err(e);
+                }
+                if control.generation() != generation {
+                    registry.kill_child(pid);
+                    return Ok(None);
+                }
+            }
+        }
+    }
+    registry.release_child(pid);
+
+    if exit_code != Some(0) {
+        return Err(format!("FFprobe 失败: {}", stderr));
+    }
+    Ok(Some(stdout))
+}
 
+/// 读取视频元数据；逐帧统计被取消或 `quick` 为 true 时使用容器记录的帧数或按时长估算
+async fn probe_video_metadata(app: &AppHandle, video_path: &str, quick: bool) -> Result<VideoMetadata, String> {
+    if !quick {
+        if let Some(stdout) = count_frames_probe(app, video_path).await? {
+            return parse_metadata(&stdout);
+        }
+    }
+
+    let sidecar = app
+        .shell()
+        .sidecar("ffprobe")
+        .map_err(|e| format!("FFprobe 启动失败: {}", e))?;
     let output = sidecar
-        .args(&[
-            "-v",
-            "error",
-            "-select_streams",
-            "v:0",
-            "-count_frames",
-            "-show_entries",
-            "stream=codec_name,width,height,r_frame_rate,avg_frame_rate,nb_read_frames,nb_frames",
-            "-show_entries",
-            "format=duration",
-            "-of",
-            "json",
-            video_path,
-        ])
+        .args(metadata_args(video_path, false))
         .output()
         .await
         .map_err(|e| format!("FFprobe 执行失败: {}", e))?;
@@ -205,8 +435,11 @@ async fn get_video_metadata_internal(
             String::from_utf8_lossy(&output.stderr)
         ));
     }
+    parse_metadata(&output.stdout)
+}
 
-    let json_str = String::from_utf8_lossy(&output.stdout);
+fn parse_metadata(stdout: &[u8]) -> Result<VideoMetadata, String> {
+    let json_str = String::from_utf8_lossy(stdout);
     let json: serde_json::Value =
         serde_json::from_str(&json_str).map_err(|e| format!("解析 JSON 失败: {}", e))?;
 
@@ -241,9 +474,10 @@ async fn get_video_metadata_internal(
             .ok_or("无法获取视频时长")?
     };
 
-    let total_frames = stream["nb_read_frames"]
+    let counted_frames = stream["nb_read_frames"]
         .as_str()
-        .and_then(|s| s.parse::<u64>().ok())
+        .and_then(|s| s.parse::<u64>().ok());
+    let total_frames = counted_frames
         .or_else(|| {
             stream["nb_frames"]
                 .as_str()
@@ -273,6 +507,7 @@ async fn get_video_metadata_internal(
         duration,
         total_frames,
         codec,
+        frames_estimated: counted_frames.is_none(),
     })
 }
 
@@ -281,269 +516,1088 @@ async fn get_video_metadata_internal(
 pub async fn extract_all_frames(
     app: AppHandle,
     video_path: String,
+    analyze_start: Option<f64>,
+    analyze_end: Option<f64>,
 ) -> Result<Vec<FrameInfo>, String> {
+    check_input(&app, &video_path)?;
+    let range = AnalyzeRange::new(analyze_start, analyze_end)?;
     let window = app
         .get_webview_window("main")
         .ok_or("无法获取窗口")?;
 
-    // 获取视频元数据
-    let metadata = get_video_metadata_internal(&app, &video_path).await?;
-
-    // 创建临时目录
-    let video_hash = calculate_hash(&video_path);
-    let temp_dir = std::env::temp_dir()
-        .join(format!("mp4handler_{}", video_hash))
-        .join("frames");
+    let frames = extract_frames_cached(&app, &video_path, FrameMode::Preview, range, Some(&window)).await?;
 
-    // 清理旧的帧
-    if temp_dir.exists() {
-        fs::remove_dir_all(&temp_dir).map_err(|e| format!("清理临时目录失败: {}", e))?;
+    // 自动恢复：该视频有保存过的分段会话时通知前端
+    if let Ok(Some(session)) = find_session(&app, &video_path) {
+        let _ = window.emit(SEGMENTATION_SESSION_RESTORED, session);
     }
-    fs::create_dir_all(&temp_dir).map_err(|e| format!("创建临时目录失败: {}", e))?;
 
-    // 使用 FFmpeg 提取所有帧（中等分辨率）
-    let output_pattern = temp_dir.join("frame_%05d.jpg");
-    let sidecar = app
-        .shell()
-        .sidecar("ffmpeg")
-        .map_err(|e| format!("FFmpeg 启动失败: {}", e))?;
-
-    let _ = window.emit(
-        "frame_progress",
-        serde_json::json!({
-            "message": "正在提取视频帧...",
-            "percent": 0,
-        }),
-    );
-
-    let vf_filter = "scale=320:-1".to_string();
+    Ok(frames)
+}
 
-    let output = sidecar
-        .args(&[
-            "-i",
-            &video_path,
-            "-vf",
-            &vf_filter,
-            "-vsync",
-            "0",
-            "-q:v",
-            "3",
-            "-y",
-            output_pattern.to_str().unwrap(),
-        ])
-        .output()
-        .await
-        .map_err(|e| format!("FFmpeg 执行失败: {}", e))?;
+/// 帧数差异超过该比例（且至少差 FRAME_COUNT_MIN_DIFF 帧）时标记为需要注意
+const FRAME_COUNT_TOLERANCE: f64 = 0.01;
+const FRAME_COUNT_MIN_DIFF: u32 = 2;
+
+/// 元数据帧数与实际提取帧数的核对结果
+///
+/// 元数据的帧数来自 ffprobe 统计，提取使用 `-vsync 0` 按原始时间戳输出，
+/// 可变帧率或有损坏帧的文件两者可能不同；界面的帧滑块应以实际提取的帧为准
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct FrameCountReport {
+    pub video_path: String,
+    /// 元数据中的帧数
+    pub metadata_frames: u32,
+    /// 元数据帧数是否为估算值
+    pub metadata_estimated: bool,
+    /// 探测到的帧时间戳数量
+    pub probed_frames: u32,
+    /// 实际提取的帧数，即以此为准的帧数
+    pub frame_count: u32,
+    /// 每个提取帧按元数据帧率换算的帧号（恒定帧率时与序号相同，可变帧率或丢帧时出现跳跃）
+    pub nominal_frames: Vec<u32>,
+    /// 差异超过容差
+    pub flagged: bool,
+    pub message: Option<String>,
+}
 
-    if !output.status.success() {
-        return Err(format!(
-            "提取帧失败: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ));
+fn reconcile_frame_count(
+    video_path: &str,
+    metadata: &VideoMetadata,
+    probed_frames: usize,
+    frames: &[FrameInfo],
+) -> FrameCountReport {
+    let frame_count = frames.len() as u32;
+    let first = frames.first().map(|f| f.timestamp).unwrap_or(0.0);
+    let nominal_frames = frames
+        .iter()
+        .map(|f| ((f.timestamp - first) * metadata.fps).round().max(0.0) as u32)
+        .collect();
+    let diff = metadata.total_frames.abs_diff(frame_count);
+    let flagged = diff >= FRAME_COUNT_MIN_DIFF
+        && diff as f64 > metadata.total_frames.max(frame_count) as f64 * FRAME_COUNT_TOLERANCE;
+    let message = flagged.then(|| {
+        format!(
+            "元数据帧数 {}{} 与实际提取的 {} 帧相差 {} 帧（探测到 {} 个时间戳），以实际提取的帧为准",
+            metadata.total_frames,
+            if metadata.frames_estimated { "（估算）" } else { "" },
+            frame_count,
+            diff,
+            probed_frames
+        )
+    });
+    FrameCountReport {
+        video_path: video_path.to_string(),
+        metadata_frames: metadata.total_frames,
+        metadata_estimated: metadata.frames_estimated,
+        probed_frames: probed_frames as u32,
+        frame_count,
+        nominal_frames,
+        flagged,
+        message,
     }
+}
 
-    // 扫描生成的帧文件
-    let mut frames = Vec::new();
-    let mut entries: Vec<_> = fs::read_dir(&temp_dir)
-        .map_err(|e| format!("读取临时目录失败: {}", e))?
-        .filter_map(|e| e.ok())
-        .collect();
+/// 帧缓存目录的清单：记录源文件签名与帧时间戳，用于中断后续传
+#[derive(Serialize, Deserialize)]
+struct FrameCacheManifest {
+    file_size: u64,
+    modified: u64,
+    timestamps: Vec<f64>,
+    /// 提取时使用的滤镜，规格变化后缓存失效
+    #[serde(default)]
+    filter: String,
+    /// FFmpeg 是否已完整提取所有帧
+    complete: bool,
+}
 
-    entries.sort_by_key(|e| e.path());
+const FRAME_MANIFEST: &str = "manifest.json";
 
-    let frame_timestamps = get_video_frame_timestamps(&app, &video_path).await?;
-    let limit = std::cmp::min(entries.len(), frame_timestamps.len());
-    for (idx, entry) in entries.iter().take(limit).enumerate() {
-        let path = entry.path();
-        if path.extension().and_then(|s| s.to_str()) == Some("jpg") {
-            let frame_number = idx as u32;
-            let timestamp = frame_timestamps
-                .get(idx)
-                .copied()
-                .unwrap_or_else(|| frame_number as f64 / metadata.fps.max(1.0));
+/// 帧提取规格，两种规格分目录缓存
+#[derive(Debug, Clone, Copy)]
+enum FrameMode {
+    /// 界面展示用的彩色缩略图
+    Preview,
+    /// 只用于相似度分析：按比较分辨率直接输出灰度图，省去 JPEG 编解码与每次比较时的颜色转换
+    Analysis,
+}
 
-            frames.push(FrameInfo {
-                frame_number,
-                timestamp,
-                image_path: path.to_string_lossy().to_string(),
-            });
+impl FrameMode {
+    fn dir_name(self) -> &'static str {
+        match self {
+            Self::Preview => "frames",
+            Self::Analysis => "frames_gray",
+        }
+    }
 
-            // 发送进度
-            if idx % 30 == 0 || idx == limit.saturating_sub(1) {
-                let _ = window.emit(
-                    "frame_progress",
-                    serde_json::json!({
-                        "message": format!("已提取 {}/{} 帧", idx + 1, limit),
-                        "percent": ((idx + 1) as f64 / limit as f64 * 100.0) as u32,
-                    }),
-                );
-            }
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Preview => "jpg",
+            // PGM 不压缩，写入和读取都几乎没有开销
+            Self::Analysis => "pgm",
         }
     }
 
-    Ok(frames)
+    fn filter(self) -> &'static str {
+        match self {
+            Self::Preview => "scale=320:-1",
+            // 相似度只看整体亮度分布，160 宽足够区分镜头
+            Self::Analysis => "scale=160:-2,format=gray",
+        }
+    }
 }
 
-// 生成视频片段
-#[tauri::command]
-pub async fn generate_video_segments(
-    app: AppHandle,
-    video_path: String,
-    segments: Vec<SegmentRange>,
-    output_dir: String,
-) -> Result<String, String> {
-    let window = app
-        .get_webview_window("main")
-        .ok_or("无法获取窗口")?;
+/// 只分析源视频的一段时间（秒），如只在最后几分钟里查找片尾，不必解码整个文件
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnalyzeRange {
+    pub start: f64,
+    /// 为空时分析到视频结尾
+    pub end: Option<f64>,
+}
 
-    // 获取视频元数据
-    let metadata = get_video_metadata_internal(&app, &video_path).await?;
+impl AnalyzeRange {
+    /// 由命令参数构造，均未指定（或从头分析到结尾）时返回 None
+    pub fn new(start: Option<f64>, end: Option<f64>) -> Result<Option<Self>, String> {
+        let start = start.unwrap_or(0.0);
+        if start < 0.0 || end.is_some_and(|e| e <= start) {
+            return Err("分析范围无效：结束时间必须大于开始时间".to_string());
+        }
+        if start == 0.0 && end.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(Self { start, end }))
+    }
 
-    // 创建输出目录：视频所在目录/视频名称/
-    let video_name = Path::new(&video_path)
-        .file_stem()
-        .ok_or("无法获取视频文件名")?
-        .to_string_lossy();
-    let output_base_dir = PathBuf::from(&output_dir).join(&*video_name);
-    fs::create_dir_all(&output_base_dir).map_err(|e| format!("创建输出目录失败: {}", e))?;
+    /// 缓存目录与缓存文件名使用的标记（毫秒）
+    pub fn tag(&self) -> String {
+        let ms = |t: f64| (t * 1000.0).round() as u64;
+        match self.end {
+            Some(end) => format!("{}-{}", ms(self.start), ms(end)),
+            None => format!("{}-end", ms(self.start)),
+        }
+    }
 
-    let frame_timestamps = get_video_frame_timestamps(&app, &video_path).await?;
-    let total_frames = frame_timestamps.len();
+    /// 范围内的帧序号区间 [first, last)
+    fn frame_span(&self, timestamps: &[f64]) -> (usize, usize) {
+        let first = timestamps.partition_point(|t| *t < self.start);
+        let last = match self.end {
+            Some(end) => timestamps.partition_point(|t| *t < end),
+            None => timestamps.len(),
+        };
+        (first, last.max(first))
+    }
+}
 
-    // 逐个生成片段
-    for (idx, segment) in segments.iter().enumerate() {
-        let segment_num = idx + 1;
-        let output_file = output_base_dir.join(format!("{}_{}.mp4", video_name, segment_num));
+/// 滞回检测：相似度低于切分阈值只是候选切换点，之后连续若干对相邻帧的相似度
+/// 都回到恢复阈值以上（新场景已稳定）才确认切分，避免噪点较多的画面反复误切
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Hysteresis {
+    /// 恢复阈值，不低于切分阈值
+    pub recovered: f64,
+    /// 需要连续稳定的帧数
+    pub stable_frames: u32,
+}
 
-        let start_idx = segment.start_frame as usize;
-        let end_idx = segment.end_frame as usize;
-        if start_idx >= total_frames || end_idx >= total_frames || start_idx > end_idx {
-            return Err(format!("片段 {} 的帧范围无效", segment_num));
+impl Hysteresis {
+    pub fn validate(&self, threshold: f64) -> Result<(), String> {
+        if self.recovered < threshold || self.recovered > 1.0 {
+            return Err("恢复阈值必须在切分阈值与 1 之间".to_string());
         }
+        if self.stable_frames == 0 {
+            return Err("稳定帧数必须大于 0".to_string());
+        }
+        Ok(())
+    }
+}
 
-        let start_time = frame_timestamps[start_idx];
-        let end_time_exclusive = if end_idx + 1 < total_frames {
-            frame_timestamps[end_idx + 1]
-        } else {
-            metadata.duration.max(frame_timestamps[end_idx])
-        };
-        let duration = (end_time_exclusive - start_time).max(0.0);
-
-        // 发送进度
-        let _ = window.emit(
-            "segment_progress",
-            serde_json::json!({
-                "current": segment_num,
-                "total": segments.len(),
-                "segmentName": format!("{}_{}.mp4", video_name, segment_num),
-                "percent": (segment_num as f32 / segments.len() as f32 * 100.0) as u32,
-            }),
-        );
+fn default_median_window() -> usize {
+    5
+}
 
-        // 使用 FFmpeg 精确切片（重新编码以保证帧精度和编码一致性）
-        let sidecar = app
-            .shell()
-            .sidecar("ffmpeg")
-            .map_err(|e| format!("FFmpeg 启动失败: {}", e))?;
+/// 闪光、频闪过滤：闪光帧与前后帧都不相似，会在相似度序列中留下相邻的两个低谷，
+/// 切出只有一两帧的片段。切换后的新画面需要持续若干帧不再变化才算切换，
+/// 期间再次出现的低谷（画面恢复）一并忽略；中值滤波后仍低于阈值的连续低谷视为渐变转场，不受影响
+///
+/// 中值滤波窗口决定多长的连续低谷算渐变转场：窗口为 5 时闪光留下的两个低谷被滤掉，
+/// 三帧及以上的溶解、淡入淡出保留
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct FlashFilter {
+    /// 中值滤波窗口（帧数，不小于 3 的奇数）
+    #[serde(default = "default_median_window")]
+    pub median_window: usize,
+    /// 切换后新画面至少持续的帧数
+    pub min_persist: u32,
+}
 
-        let output = sidecar
-            .args(&[
-                "-i",
-                &video_path,
-                "-ss",
-                &start_time.to_string(),
-                "-t",
-                &duration.to_string(),
-                "-vf",
-                "setpts=PTS-STARTPTS",
-                "-vsync",
-                "vfr",
-                "-c:v",
-                "libx264",
-                "-preset",
-                "fast",
-                "-crf",
-                "18",
-                "-c:a",
-                "aac",
-                "-b:a",
-                "192k",
-                "-af",
-                "aresample=async=1:first_pts=0,asetpts=PTS-STARTPTS",
-                "-fflags",
-                "+genpts",
-                "-avoid_negative_ts",
-                "make_zero",
-                "-y",
-                output_file.to_str().unwrap(),
-            ])
-            .output()
-            .await
-            .map_err(|e| format!("FFmpeg 执行失败: {}", e))?;
+impl FlashFilter {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.median_window < 3 || self.median_window.is_multiple_of(2) {
+            return Err("中值滤波窗口必须是不小于 3 的奇数".to_string());
+        }
+        if self.min_persist < 2 {
+            return Err("新画面持续帧数至少为 2".to_string());
+        }
+        Ok(())
+    }
 
-        if !output.status.success() {
-            return Err(format!(
-                "生成片段 {} 失败: {}",
-                segment_num,
-                String::from_utf8_lossy(&output.stderr)
-            ));
+    /// 返回用于切分的相似度序列：被判定为闪光的低谷改为 1.0（视为没有变化）
+    fn apply(&self, similarities: &[f64], threshold: f64) -> Vec<f64> {
+        let persist = self.min_persist as usize;
+        let smoothed = median_filter(similarities, self.median_window);
+        let mut filtered = similarities.to_vec();
+        // 闪光期间（含画面恢复时的低谷）的最后一项
+        let mut anomaly_end = None;
+
+        for (i, &similarity) in similarities.iter().enumerate() {
+            if similarity >= threshold || smoothed[i] < threshold {
+                continue;
+            }
+            let in_anomaly = anomaly_end.is_some_and(|end| i <= end);
+            let changes_again = similarities[i + 1..(i + persist).min(similarities.len())]
+                .iter()
+                .any(|s| *s < threshold);
+            if in_anomaly || changes_again {
+                filtered[i] = 1.0;
+                anomaly_end = Some(i + persist - 1);
+            }
         }
+
+        filtered
     }
+}
 
-    Ok(format!(
-        "成功生成 {} 个视频片段到: {}",
-        segments.len(),
-        output_base_dir.display()
-    ))
+/// 中值滤波，窗口在序列两端截断
+fn median_filter(values: &[f64], window: usize) -> Vec<f64> {
+    let half = window / 2;
+    (0..values.len())
+        .map(|i| {
+            let mut neighbours = values[i.saturating_sub(half)..(i + half + 1).min(values.len())].to_vec();
+            neighbours.sort_by(|a, b| a.total_cmp(b));
+            neighbours[neighbours.len() / 2]
+        })
+        .collect()
 }
 
-// 列出目录中的所有 MP4 文件
-#[tauri::command]
-pub fn list_mp4_files(dir_path: String) -> Result<Vec<String>, String> {
-    let path = Path::new(&dir_path);
-    if !path.is_dir() {
-        return Err("路径不是一个目录".to_string());
-    }
+/// 切分过程中各类候选切换点的数量
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SplitCounts {
+    /// 相似度低于阈值的候选切换点
+    pub candidates: u32,
+    /// 距离上次切分不足最短时长而忽略的
+    pub too_short: u32,
+    /// 滞回检测中新场景未能稳定而放弃的
+    pub unconfirmed: u32,
+}
 
-    let mut mp4_files = Vec::new();
-    let entries = fs::read_dir(path).map_err(|e| format!("读取目录失败: {}", e))?;
+/// 按阈值与最短帧数计算切分点（含起始帧），第 i 项相似度对应第 `first_frame + i + 1` 帧
+fn scene_split_points(
+    similarities: &[f64],
+    first_frame: u32,
+    threshold: f64,
+    min_frames: u32,
+    hysteresis: Option<Hysteresis>,
+) -> (Vec<u32>, SplitCounts) {
+    let mut split_points = vec![first_frame]; // 起始帧
+    let mut last_split_frame = first_frame;
+    let mut counts = SplitCounts::default();
+    // 滞回检测中等待确认的切换点与已稳定的帧数
+    let mut pending: Option<(u32, u32)> = None;
 
-    for entry in entries.filter_map(|e| e.ok()) {
-        let path = entry.path();
-        if path.is_file() {
-            if let Some(ext) = path.extension() {
-                if ext.to_string_lossy().to_lowercase() == "mp4" {
-                    mp4_files.push(path.to_string_lossy().to_string());
+    // 串行处理切分点（需要维护状态）
+    for (i, &similarity) in similarities.iter().enumerate() {
+        let frame_number = first_frame + i as u32 + 1;
+
+        // 如果相似度低于阈值，且距离上次切分点足够远
+        if similarity < threshold {
+            counts.candidates += 1;
+            let frames_since_last_split = frame_number - last_split_frame;
+            if frames_since_last_split >= min_frames {
+                match hysteresis {
+                    // 确认期间再次低于阈值时以最后一次切换为准
+                    Some(_) => {
+                        if pending.replace((frame_number, 0)).is_some() {
+                            counts.unconfirmed += 1;
+                        }
+                    }
+                    None => {
+                        split_points.push(frame_number);
+                        last_split_frame = frame_number;
+                    }
                 }
+            } else {
+                counts.too_short += 1;
+            }
+            continue;
+        }
+
+        if let (Some(hysteresis), Some((cut, stable))) = (hysteresis, pending) {
+            let stable = if similarity >= hysteresis.recovered { stable + 1 } else { 0 };
+            if stable >= hysteresis.stable_frames {
+                split_points.push(cut);
+                last_split_frame = cut;
+                pending = None;
+            } else {
+                pending = Some((cut, stable));
             }
         }
     }
+    if pending.is_some() {
+        counts.unconfirmed += 1;
+    }
 
-    mp4_files.sort();
-    Ok(mp4_files)
+    (split_points, counts)
 }
 
-// 加载批量拆解进度
-#[tauri::command]
-pub fn load_batch_progress(progress_path: String) -> Result<Option<BatchProgress>, String> {
-    let path = Path::new(&progress_path);
-    if !path.exists() {
-        return Ok(None);
+/// 源文件签名（大小 + 修改时间），变化后缓存失效
+fn file_signature(video_path: &str) -> Result<(u64, u64), String> {
+    let meta = fs::metadata(video_path).map_err(|e| format!("读取文件信息失败: {}", e))?;
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Ok((meta.len(), modified))
+}
+
+fn frame_file(dir: &Path, mode: FrameMode, number: usize) -> PathBuf {
+    dir.join(format!("frame_{:05}.{}", number, mode.extension()))
+}
+
+/// 统计从 frame_00001 开始连续存在且非空的帧文件数
+fn count_contiguous_frames(dir: &Path, mode: FrameMode) -> usize {
+    let mut count = 0;
+    while fs::metadata(frame_file(dir, mode, count + 1))
+        .map(|m| m.len() > 0)
+        .unwrap_or(false)
+    {
+        count += 1;
     }
+    count
+}
 
-    let content = fs::read_to_string(path).map_err(|e| format!("读取进度文件失败: {}", e))?;
-    let progress: BatchProgress =
-        serde_json::from_str(&content).map_err(|e| format!("解析进度文件失败: {}", e))?;
+/// 删除编号大于 `keep` 的帧文件（续传前清理可能不完整的尾部）
+fn remove_frames_after(dir: &Path, mode: FrameMode, keep: usize) -> Result<(), String> {
+    for entry in fs::read_dir(dir).map_err(|e| format!("读取临时目录失败: {}", e))?.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let number = name
+            .strip_prefix("frame_")
+            .and_then(|n| n.strip_suffix(mode.extension()))
+            .and_then(|n| n.strip_suffix('.'))
+            .and_then(|n| n.parse::<usize>().ok());
+        if number.is_some_and(|n| n > keep) {
+            fs::remove_file(entry.path()).map_err(|e| format!("清理临时帧失败: {}", e))?;
+        }
+    }
+    Ok(())
+}
 
-    Ok(Some(progress))
+/// 帧缓存目录：同一视频的各规格帧都放在 `mp4handler_<hash>` 下，界面浏览、自动拆解与去结尾共用；
+/// 限定分析范围时按范围另建目录
+fn frame_cache_dir(video_path: &str, mode: FrameMode, range: Option<AnalyzeRange>) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("mp4handler_{}", calculate_hash(video_path)));
+    match range {
+        Some(range) => dir.join(format!("{}_{}", mode.dir_name(), range.tag())),
+        None => dir.join(mode.dir_name()),
+    }
 }
 
-// 保存批量拆解进度
-#[tauri::command]
+/// 读取属于当前文件且规格一致的帧清单
+fn load_frame_manifest(dir: &Path, video_path: &str, mode: FrameMode) -> Result<Option<FrameCacheManifest>, String> {
+    let (file_size, modified) = file_signature(video_path)?;
+    Ok(fs::read_to_string(dir.join(FRAME_MANIFEST))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .filter(|m: &FrameCacheManifest| {
+            m.file_size == file_size && m.modified == modified && m.filter == mode.filter()
+        }))
+}
+
+/// 该规格（与范围）的帧是否已完整提取
+fn frames_complete(video_path: &str, mode: FrameMode, range: Option<AnalyzeRange>) -> bool {
+    let dir = frame_cache_dir(video_path, mode, range);
+    matches!(load_frame_manifest(&dir, video_path, mode), Ok(Some(m)) if m.complete)
+        && count_contiguous_frames(&dir, mode) > 0
+}
+
+fn write_frame_manifest(dir: &Path, manifest: &FrameCacheManifest) -> Result<(), String> {
+    let content = serde_json::to_string(manifest).map_err(|e| format!("序列化帧清单失败: {}", e))?;
+    fs::write(dir.join(FRAME_MANIFEST), content).map_err(|e| format!("写入帧清单失败: {}", e))
+}
+
+/// 按指定规格提取全部帧（或分析范围内的帧）到临时目录。已完整提取过的视频直接复用；
+/// 上次中断时保留已完成的帧，从最后一个完整帧处用 `-ss` 继续提取。
+/// 帧序号始终按整个视频计数，限定范围时第一帧的序号不为 0
+async fn extract_frames_cached(
+    app: &AppHandle,
+    video_path: &str,
+    mode: FrameMode,
+    range: Option<AnalyzeRange>,
+    window: Option<&tauri::WebviewWindow>,
+) -> Result<Vec<FrameInfo>, String> {
+    let metadata = get_video_metadata_internal(app, video_path).await?;
+
+    // 创建临时目录
+    let temp_dir = frame_cache_dir(video_path, mode, range);
+
+    // 校验缓存是否属于当前文件与规格，否则清理旧的帧
+    let mut manifest = match load_frame_manifest(&temp_dir, video_path, mode)? {
+        Some(manifest) => manifest,
+        None => {
+            let (file_size, modified) = file_signature(video_path)?;
+            if temp_dir.exists() {
+                fs::remove_dir_all(&temp_dir).map_err(|e| format!("清理临时目录失败: {}", e))?;
+            }
+            fs::create_dir_all(&temp_dir).map_err(|e| format!("创建临时目录失败: {}", e))?;
+
+            // 先探测时间戳并写入清单，中断后续传时不必重新探测
+            let manifest = FrameCacheManifest {
+                file_size,
+                modified,
+                timestamps: get_video_frame_timestamps(app, video_path).await?,
+                filter: mode.filter().to_string(),
+                complete: false,
+            };
+            write_frame_manifest(&temp_dir, &manifest)?;
+            manifest
+        }
+    };
+    let frame_timestamps = manifest.timestamps.clone();
+    let (first, last) = range
+        .map(|r| r.frame_span(&frame_timestamps))
+        .unwrap_or((0, frame_timestamps.len()));
+    if range.is_some() && first == last {
+        return Err("分析范围内没有视频帧".to_string());
+    }
+
+    if !manifest.complete || count_contiguous_frames(&temp_dir, mode) == 0 {
+        // 最后一个帧文件可能没有写完，丢弃后从它开始重新提取
+        let done = count_contiguous_frames(&temp_dir, mode).saturating_sub(1);
+        remove_frames_after(&temp_dir, mode, done)?;
+
+        if let Some(window) = window {
+            let payload = if done > 0 {
+                progress(app, "frames.resuming", &[(done + 1).to_string()], 0)
+            } else {
+                progress(app, "frames.extracting", &[], 0)
+            };
+            let _ = window.emit(FRAME_PROGRESS, payload);
+        }
+
+        // 使用 FFmpeg 提取所有帧
+        let output_pattern = temp_dir.join(format!("frame_%05d.{}", mode.extension()));
+        let sidecar = app
+            .shell()
+            .sidecar("ffmpeg")
+            .map_err(|e| format!("FFmpeg 启动失败: {}", e))?;
+
+        let mut args: Vec<String> = Vec::new();
+        // 从分析范围的第一帧（或续传的帧）开始
+        let start_index = first + done;
+        if start_index > 0 {
+            let resume_at = frame_timestamps
+                .get(start_index)
+                .copied()
+                .unwrap_or_else(|| start_index as f64 / metadata.fps.max(1.0));
+            // 稍微提前一点，避免浮点误差跳过续传的第一帧
+            let margin = (resume_at - frame_timestamps[start_index - 1]).max(0.0) / 4.0;
+            args.extend(["-ss".to_string(), format!("{:.6}", (resume_at - margin).max(0.0))]);
+        }
+        args.extend([
+            "-i".to_string(),
+            video_path.to_string(),
+            "-vf".to_string(),
+            mode.filter().to_string(),
+            "-vsync".to_string(),
+            "0".to_string(),
+        ]);
+        if let FrameMode::Preview = mode {
+            args.extend(["-q:v".to_string(), "3".to_string()]);
+        }
+        if range.is_some() {
+            args.extend(["-frames:v".to_string(), (last - first - done).to_string()]);
+        }
+        args.extend([
+            "-start_number".to_string(),
+            (done + 1).to_string(),
+            "-y".to_string(),
+        ]);
+        let throttle = throttle::current(app);
+        args.extend(throttle.output_args());
+        args.push(output_pattern.to_string_lossy().to_string());
+
+        // -progress 输出到 stdout，边提取边汇报进度
+        let (mut rx, child) = sidecar
+            .args(["-nostats", "-progress", "pipe:1"])
+            .args(args)
+            .spawn()
+            .map_err(|e| format!("FFmpeg 执行失败: {}", e))?;
+        let child_pid = child.pid();
+        let registry = app.state::<ProcessRegistry>();
+        let pid = registry.register_child(child);
+        apply_process_limits(child_pid, &throttle).await;
+
+        let total = (last - first).max(1);
+        let mut stderr = String::new();
+        let mut last_percent = None;
+        let mut exit_code = None;
+        while let Some(event) = rx.recv().await {
+            match event {
+                CommandEvent::Stdout(line) => {
+                    // 批量任务被跳过/中止时结束 FFmpeg（已提取的帧保留，下次可续传）
+                    if let Err(e) = check_batch_signal(app) {
+                        registry.kill_child(pid);
+                        return Err(e);
+                    }
+                    let line = String::from_utf8_lossy(&line);
+                    let Some(frame) = line.trim().strip_prefix("frame=").and_then(|v| v.parse::<usize>().ok()) else {
+                        continue;
+                    };
+                    // 提取阶段占 0-95%，之后的整理阶段占剩余部分
+                    let extracted = (done + frame).min(total);
+                    let percent = (extracted as f64 / total as f64 * 95.0) as u32;
+                    if last_percent != Some(percent) {
+                        last_percent = Some(percent);
+                        if let Some(window) = window {
+                            let _ = window.emit(
+                                FRAME_PROGRESS,
+                                progress(
+                                    app,
+                                    "frames.extracting_progress",
+                                    &[extracted.to_string(), total.to_string()],
+                                    percent,
+                                ),
+                            );
+                        }
+                    }
+                }
+                CommandEvent::Stderr(line) => {
+                    stderr.push_str(&String::from_utf8_lossy(&line));
+                    stderr.push('\n');
+                }
+                CommandEvent::Error(e) => {
+                    registry.release_child(pid);
+                    return Err(format!("FFmpeg 执行失败: {}", e));
+                }
+                CommandEvent::Terminated(payload) => exit_code = payload.code,
+                _ => {}
+            }
+        }
+        registry.release_child(pid);
+
+        if exit_code != Some(0) {
+            return Err(format!("提取帧失败: {}", stderr));
+        }
+
+        manifest.complete = true;
+        write_frame_manifest(&temp_dir, &manifest)?;
+    }
+
+    // 扫描生成的帧文件
+    let mut frames = Vec::new();
+    let limit = std::cmp::min(count_contiguous_frames(&temp_dir, mode), last - first);
+    for idx in 0..limit {
+        let path = frame_file(&temp_dir, mode, idx + 1);
+        let frame_number = (first + idx) as u32;
+        let timestamp = frame_timestamps
+            .get(first + idx)
+            .copied()
+            .unwrap_or_else(|| frame_number as f64 / metadata.fps.max(1.0));
+
+        frames.push(FrameInfo {
+            frame_number,
+            timestamp,
+            image_path: path.to_string_lossy().to_string(),
+        });
+
+        // 发送进度
+        if let Some(window) = window {
+            if idx % 30 == 0 || idx == limit.saturating_sub(1) {
+                let _ = window.emit(
+                    FRAME_PROGRESS,
+                    progress(
+                        app,
+                        "frames.scanned",
+                        &[(idx + 1).to_string(), limit.to_string()],
+                        95 + ((idx + 1) as f64 / limit as f64 * 5.0) as u32,
+                    ),
+                );
+            }
+        }
+    }
+
+    // 提取完成后核对帧数，界面据此校正帧滑块（只提取部分范围时不核对）
+    if let Some(window) = window.filter(|_| range.is_none()) {
+        let _ = window.emit(
+            FRAME_COUNT_RECONCILED,
+            reconcile_frame_count(video_path, &metadata, frame_timestamps.len(), &frames),
+        );
+    }
+
+    Ok(frames)
+}
+
+/// 切点预览：切点前最后一帧与切点后第一帧（原始分辨率）
+#[derive(Serialize, Deserialize)]
+pub struct CutPreview {
+    pub before: Option<FrameInfo>,
+    pub after: FrameInfo,
+}
+
+/// 精确解码指定帧为原始分辨率 PNG
+async fn extract_exact_frame(
+    app: &AppHandle,
+    video_path: &str,
+    timestamps: &[f64],
+    index: usize,
+    output_dir: &Path,
+) -> Result<FrameInfo, String> {
+    let timestamp = timestamps[index];
+    // 向前留出小段余量，避免浮点误差跳到下一帧；不超过与上一帧间隔的四分之一
+    let margin = if index > 0 {
+        ((timestamp - timestamps[index - 1]) / 4.0).min(0.001)
+    } else {
+        0.0
+    };
+    let seek = (timestamp - margin).max(0.0);
+    let image_path = output_dir.join(format!("frame_{:06}.png", index));
+
+    let sidecar = app
+        .shell()
+        .sidecar("ffmpeg")
+        .map_err(|e| format!("FFmpeg 启动失败: {}", e))?;
+
+    let output = sidecar
+        .args([
+            "-v",
+            "error",
+            "-ss",
+            &format!("{:.6}", seek),
+            "-i",
+            video_path,
+            "-frames:v",
+            "1",
+            "-y",
+            image_path.to_str().unwrap(),
+        ])
+        .output()
+        .await
+        .map_err(|e| format!("FFmpeg 执行失败: {}", e))?;
+
+    if !output.status.success() || !image_path.exists() {
+        return Err(format!(
+            "解码第 {} 帧失败: {}",
+            index,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(FrameInfo {
+        frame_number: index as u32,
+        timestamp,
+        image_path: image_path.to_string_lossy().to_string(),
+    })
+}
+
+// 获取切点前后两帧的原始分辨率画面（切点帧为新片段的第一帧）
+#[tauri::command]
+pub async fn get_cut_preview_frames(
+    app: AppHandle,
+    video_path: String,
+    frame_index: u32,
+) -> Result<CutPreview, String> {
+    check_input(&app, &video_path)?;
+
+    let timestamps = get_video_frame_timestamps(&app, &video_path).await?;
+    let index = frame_index as usize;
+    if index >= timestamps.len() {
+        return Err(format!("帧序号超出范围: {}（共 {} 帧）", index, timestamps.len()));
+    }
+
+    let preview_dir = std::env::temp_dir()
+        .join(format!("mp4handler_{}", calculate_hash(&video_path)))
+        .join("cut_preview");
+    fs::create_dir_all(&preview_dir).map_err(|e| format!("创建临时目录失败: {}", e))?;
+
+    let before = if index > 0 {
+        Some(extract_exact_frame(&app, &video_path, &timestamps, index - 1, &preview_dir).await?)
+    } else {
+        None
+    };
+    let after = extract_exact_frame(&app, &video_path, &timestamps, index, &preview_dir).await?;
+
+    Ok(CutPreview { before, after })
+}
+
+/// 片段的输出目录、命名与编码选项
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SegmentExport {
+    pub output_dir: String,
+    #[serde(default)]
+    pub source_date: SourceDateOptions,
+    /// 片段按导出预设编码
+    #[serde(default)]
+    pub export_preset: Option<String>,
+    /// 已查到的导出预设内容（队列任务加入时写入），优先于按名称查找
+    #[serde(skip)]
+    pub resolved_export_preset: Option<ExportPreset>,
+    #[serde(default)]
+    pub encoder: EncoderChoice,
+    #[serde(default)]
+    pub encode_settings: EncodeSettings,
+}
+
+// 生成视频片段
+#[tauri::command]
+pub async fn generate_video_segments(
+    app: AppHandle,
+    video_path: String,
+    segments: Vec<SegmentRange>,
+    options: SegmentExport,
+) -> Result<String, String> {
+    let result = generate_video_segments_internal(app.clone(), video_path, segments, options)
+        .await
+        .map(|paths| segments_summary(&paths));
+    record_operation(&app, "generate_segments", &result);
+    result.map_err(|e| localize_error(&app, e))
+}
+
+/// 生成结果的提示信息
+fn segments_summary(paths: &[PathBuf]) -> String {
+    format!(
+        "成功生成 {} 个视频片段到: {}",
+        paths.len(),
+        paths
+            .first()
+            .and_then(|p| p.parent())
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_default()
+    )
+}
+
+/// 由场景检测结果生成诊断信息（生成片段的耗时在生成后补上）
+fn detection_diagnostics(video_path: &str, detection: &SceneDetection) -> AutoSplitDiagnostics {
+    let mut histogram = vec![0u32; 10];
+    for similarity in &detection.similarities {
+        let bucket = ((similarity.clamp(0.0, 1.0) * 10.0) as usize).min(9);
+        histogram[bucket] += 1;
+    }
+    let stats = detection.stats;
+    AutoSplitDiagnostics {
+        video_path: video_path.to_string(),
+        cache_hit: stats.cache_hit,
+        candidate_cuts: stats.counts.candidates,
+        rejected_min_duration: stats.counts.too_short,
+        rejected_unconfirmed: stats.counts.unconfirmed,
+        flash_suppressed: stats.flash_suppressed,
+        similarity_histogram: histogram,
+        analysis_secs: stats.analysis_secs,
+        split_secs: stats.split_secs,
+        generate_secs: 0.0,
+    }
+}
+
+/// 诊断信息的简短说明，附在拆解结果之后
+fn diagnostics_summary(diagnostics: &AutoSplitDiagnostics) -> String {
+    format!(
+        "检测诊断：候选切换 {} 个（最短时长忽略 {}，未稳定 {}，闪光过滤 {}），逐帧分析{}，耗时 分析 {:.1}s / 切分 {:.1}s / 生成 {:.1}s",
+        diagnostics.candidate_cuts,
+        diagnostics.rejected_min_duration,
+        diagnostics.rejected_unconfirmed,
+        diagnostics.flash_suppressed,
+        if diagnostics.cache_hit { "命中缓存" } else { "未命中缓存" },
+        diagnostics.analysis_secs,
+        diagnostics.split_secs,
+        diagnostics.generate_secs
+    )
+}
+
+/// 把超过 `max_duration` 的片段按帧继续切分，切点落在帧的起点上
+fn split_long_segments(segments: Vec<SegmentRange>, timestamps: &[f64], max_duration: f64) -> Vec<SegmentRange> {
+    let mut result = Vec::with_capacity(segments.len());
+    for segment in segments {
+        let end = segment.end_frame as usize;
+        let mut start = segment.start_frame as usize;
+        // 帧范围无效的片段原样保留，生成时报错
+        if end >= timestamps.len() || start > end {
+            result.push(segment);
+            continue;
+        }
+        while start <= end {
+            let limit = timestamps[start] + max_duration;
+            let mut stop = start;
+            while stop < end && timestamps[stop + 1] <= limit {
+                stop += 1;
+            }
+            result.push(SegmentRange {
+                start_frame: start as u32,
+                end_frame: stop as u32,
+            });
+            start = stop + 1;
+        }
+    }
+    result
+}
+
+// 内部使用的片段生成（自动拆解、精彩集锦复用），返回生成的片段文件
+//
+// 指定导出预设时按预设的编码、分辨率、码率、关键帧与响度输出，过长的片段按时长上限继续切分；
+// 片段保持源画面比例，不套用预设的画面比例
+pub async fn generate_video_segments_internal(
+    app: AppHandle,
+    video_path: String,
+    segments: Vec<SegmentRange>,
+    options: SegmentExport,
+) -> Result<Vec<PathBuf>, String> {
+    let SegmentExport { output_dir, source_date, export_preset, resolved_export_preset, encoder, encode_settings } =
+        options;
+    let encode_settings = &encode_settings;
+    check_input(&app, &video_path)?;
+    check_output(&app, &output_dir)?;
+    encode_settings.validate()?;
+    let preset = match resolved_export_preset {
+        Some(preset) => Some(preset),
+        None => export_preset::resolve(&app, export_preset.as_deref())?,
+    };
+    let window = app
+        .get_webview_window("main")
+        .ok_or("无法获取窗口")?;
+
+    // 获取视频元数据
+    let metadata = get_video_metadata_internal(&app, &video_path).await?;
+
+    // 创建输出目录：视频所在目录/视频名称/
+    let video_name = Path::new(&video_path)
+        .file_stem()
+        .ok_or("无法获取视频文件名")?
+        .to_string_lossy();
+    let output_base_dir = PathBuf::from(&output_dir).join(&*video_name);
+    fs::create_dir_all(&output_base_dir).map_err(|e| format!("创建输出目录失败: {}", e))?;
+
+    let frame_timestamps = get_video_frame_timestamps(&app, &video_path).await?;
+    let total_frames = frame_timestamps.len();
+    let segments = match preset.as_ref().and_then(|p| p.max_duration) {
+        Some(max_duration) => split_long_segments(segments, &frame_timestamps, max_duration),
+        None => segments,
+    };
+    let mut total_duration = 0.0;
+    let mut outputs: Vec<PathBuf> = Vec::new();
+    let throttle = throttle::current(&app);
+    let gop = preset.as_ref().and_then(|p| p.gop).unwrap_or_else(|| gop::current(&app));
+    let codec = preset.as_ref().map(|p| p.codec).unwrap_or(VideoCodec::H264);
+    let encoder = hw_encoder::resolve(&app, encoder, codec).await;
+    // 未指定预设时片段作为后续拼接的素材，用较高质量编码
+    let crf = encode_settings.crf.or(preset.as_ref().and_then(|p| p.crf)).unwrap_or(18);
+    let mut video_filter = "setpts=PTS-STARTPTS".to_string();
+    let mut audio_filter = "aresample=async=1:first_pts=0,asetpts=PTS-STARTPTS".to_string();
+    if let Some(height) = preset.as_ref().and_then(|p| p.height) {
+        video_filter.push_str(&format!(",scale=-2:{},setsar=1", height));
+    }
+    if let Some(target) = preset.as_ref().and_then(|p| p.loudness_target) {
+        audio_filter.push_str(&format!(",{}", loudnorm_filter(target)));
+    }
+
+    // 源视频的拍摄时间，片段的拍摄时间 = 源视频拍摄时间 + 片段起点
+    let recorded = if source_date.name_by_recording_time || source_date.keep_creation_time {
+        let info = get_video_info(&app, Path::new(&video_path)).await?;
+        recorded_at(&info, Path::new(&video_path))
+    } else {
+        None
+    };
+
+    // 批量拆解时记录片段进度，中止后继续时跳过已生成的片段（源文件或参数变化后重新生成）
+    let mut resume = match current_batch_run() {
+        Some(_) => {
+            let mut plan = Fnv1a::new();
+            plan.feed(content_hash(Path::new(&video_path))?.as_bytes());
+            plan.feed(
+                format!("{:?}|{:?}|{:?}|{:?}|{:?}", segments, source_date, preset, encoder, encode_settings)
+                    .as_bytes(),
+            );
+            Some(SegmentResume::load(&output_base_dir, plan.finish()))
+        }
+        None => None,
+    };
+    let mut generated = 0;
+
+    // 逐个生成片段
+    for (idx, segment) in segments.iter().enumerate() {
+        check_batch_signal(&app)?;
+        let segment_num = idx + 1;
+        let start_idx = segment.start_frame as usize;
+        let end_idx = segment.end_frame as usize;
+        if start_idx >= total_frames || end_idx >= total_frames || start_idx > end_idx {
+            return Err(format!("片段 {} 的帧范围无效", segment_num));
+        }
+
+        let start_time = frame_timestamps[start_idx];
+        let end_time_exclusive = if end_idx + 1 < total_frames {
+            frame_timestamps[end_idx + 1]
+        } else {
+            metadata.duration.max(frame_timestamps[end_idx])
+        };
+        let duration = (end_time_exclusive - start_time).max(0.0);
+        let segment_time = recorded.map(|t| t + chrono::Duration::milliseconds((start_time * 1000.0) as i64));
+
+        // 按拍摄时间命名时，同一秒内开始的片段追加序号区分
+        let mut segment_name = match segment_time.filter(|_| source_date.name_by_recording_time) {
+            Some(time) => format!("{}_{}.mp4", video_name, file_label(time)),
+            None => format!("{}_{}.mp4", video_name, segment_num),
+        };
+        if outputs.iter().any(|p| p.ends_with(&segment_name)) {
+            segment_name = format!("{}_{}.mp4", segment_name.trim_end_matches(".mp4"), segment_num);
+        }
+        let output_file = output_base_dir.join(&segment_name);
+        if resume.as_ref().is_some_and(|r| r.completed.contains(&segment_name)) && output_file.is_file() {
+            outputs.push(output_file);
+            continue;
+        }
+
+        // 发送进度
+        let _ = window.emit(
+            SEGMENT_PROGRESS,
+            SegmentProgress {
+                current: segment_num as u32,
+                total: segments.len() as u32,
+                segment_name: segment_name.clone(),
+                percent: (segment_num as f32 / segments.len() as f32 * 100.0) as u32,
+            },
+        );
+
+        // 使用 FFmpeg 精确切片（重新编码以保证帧精度和编码一致性）
+        let mut args: Vec<String> = [
+            "-i",
+            &video_path,
+            "-ss",
+            &start_time.to_string(),
+            "-t",
+            &duration.to_string(),
+            "-vf",
+            &video_filter,
+            "-vsync",
+            "vfr",
+            "-c:a",
+            "aac",
+            "-b:a",
+            &encode_settings.audio_bitrate_arg(),
+            "-af",
+            &audio_filter,
+            "-fflags",
+            "+genpts",
+            "-avoid_negative_ts",
+            "make_zero",
+            "-y",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        args.extend(encoder.video_args(codec, crf, encode_settings));
+        match &encode_settings.pix_fmt {
+            Some(pix_fmt) => args.extend(["-pix_fmt".to_string(), pix_fmt.clone()]),
+            None if encoder != HwEncoder::Software => {
+                args.extend(["-pix_fmt".to_string(), encoder.pix_fmt().to_string()])
+            }
+            None => {}
+        }
+        args.extend(gop.encoder_args(codec, Some(metadata.fps)));
+        if let Some(kbps) = preset.as_ref().and_then(|p| p.max_bitrate) {
+            args.extend(bitrate_args(kbps));
+        }
+        if codec == VideoCodec::Hevc {
+            args.extend(["-tag:v".to_string(), "hvc1".to_string()]);
+        }
+        if preset.as_ref().is_some_and(|p| p.faststart) {
+            args.extend(["-movflags".to_string(), "+faststart".to_string()]);
+        }
+        if let Some(time) = segment_time.filter(|_| source_date.keep_creation_time) {
+            args.extend(["-metadata".to_string(), format!("creation_time={}", metadata_value(time))]);
+        }
+        args.extend(throttle.output_args());
+        args.push(output_file.to_string_lossy().to_string());
+
+        let output = run_ffmpeg_with_progress(&app, args, "segments", segment_name.clone(), duration).await?;
+
+        if !output.success() {
+            return Err(format!(
+                "生成片段 {} 失败: {}",
+                segment_num,
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+        total_duration += duration;
+        generated += 1;
+        outputs.push(output_file);
+        if let Some(resume) = resume.as_mut() {
+            resume.completed.push(segment_name);
+            resume.save(&output_base_dir);
+        }
+    }
+    if resume.is_some() {
+        SegmentResume::clear(&output_base_dir);
+    }
+
+    record_output(&app, 1, generated, total_duration, false);
+
+    Ok(outputs)
+}
+
+// 列出目录中的所有 MP4 文件
+#[tauri::command]
+pub fn list_mp4_files(app: AppHandle, dir_path: String) -> Result<Vec<String>, String> {
+    check_input(&app, &dir_path)?;
+    let path = Path::new(&dir_path);
+    if !path.is_dir() {
+        return Err("路径不是一个目录".to_string());
+    }
+
+    let mut mp4_files = Vec::new();
+    let entries = fs::read_dir(path).map_err(|e| format!("读取目录失败: {}", e))?;
+
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.is_file() {
+            if let Some(ext) = path.extension() {
+                if ext.to_string_lossy().to_lowercase() == "mp4" {
+                    mp4_files.push(path.to_string_lossy().to_string());
+                }
+            }
+        }
+    }
+
+    mp4_files.sort();
+    Ok(mp4_files)
+}
+
+// 加载批量拆解进度
+#[tauri::command]
+pub fn load_batch_progress(
+    app: AppHandle,
+    progress_path: String,
+) -> Result<Option<BatchProgress>, String> {
+    check_output(&app, &progress_path)?;
+    let path = Path::new(&progress_path);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(path).map_err(|e| format!("读取进度文件失败: {}", e))?;
+    let progress: BatchProgress =
+        serde_json::from_str(&content).map_err(|e| format!("解析进度文件失败: {}", e))?;
+
+    Ok(Some(progress))
+}
+
+// 保存批量拆解进度
+#[tauri::command]
 pub fn save_batch_progress(
+    app: AppHandle,
     progress_path: String,
     progress: BatchProgress,
 ) -> Result<(), String> {
+    check_output(&app, &progress_path)?;
     let content =
         serde_json::to_string_pretty(&progress).map_err(|e| format!("序列化进度失败: {}", e))?;
     fs::write(&progress_path, content).map_err(|e| format!("写入进度文件失败: {}", e))?;
@@ -553,7 +1607,8 @@ pub fn save_batch_progress(
 
 // 删除视频文件
 #[tauri::command]
-pub fn delete_video_file(file_path: String) -> Result<(), String> {
+pub fn delete_video_file(app: AppHandle, file_path: String) -> Result<(), String> {
+    check_input(&app, &file_path)?;
     let path = Path::new(&file_path);
     if !path.exists() {
         return Err("文件不存在".to_string());
@@ -563,134 +1618,349 @@ pub fn delete_video_file(file_path: String) -> Result<(), String> {
     Ok(())
 }
 
-// 自动拆解视频（基于帧相似度）
-#[tauri::command]
-pub async fn auto_split_video(
-    app: AppHandle,
-    video_path: String,
-    output_dir: String,
-    algorithm: String,
-    threshold: f64,
-    min_duration: f64,
-    skip_first: bool,   // 新增：掐头
-    skip_last: bool,    // 新增：去尾
-) -> Result<String, String> {
-    let window = app
-        .get_webview_window("main")
-        .ok_or("无法获取窗口")?;
+/// 场景检测结果
+pub struct SceneDetection {
+    pub segments: Vec<SegmentRange>,
+    /// 相邻帧的相似度，第 i 项为第 i 帧与第 i+1 帧
+    pub similarities: Vec<f64>,
+    /// 每帧的时间戳（秒）
+    pub timestamps: Vec<f64>,
+    pub duration: f64,
+    /// 分析的第一帧序号：限定分析范围时相似度与时间戳从这一帧开始，片段仍按整个视频的帧序号
+    pub first_frame: u32,
+    pub stats: DetectionStats,
+}
 
-    // 解析算法
-    let algo = SimilarityAlgorithm::from_str(&algorithm)?;
+/// 场景检测的统计，用于排查阈值、最短时长等参数设置的问题
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DetectionStats {
+    /// 逐帧分析结果来自缓存
+    pub cache_hit: bool,
+    /// 提取帧与计算相似度的耗时（秒）
+    pub analysis_secs: f64,
+    /// 切分的耗时（秒）
+    pub split_secs: f64,
+    pub counts: SplitCounts,
+    /// 被闪光过滤忽略的候选切换点
+    pub flash_suppressed: u32,
+}
 
-    // 获取视频元数据
-    let metadata = get_video_metadata_internal(&app, &video_path).await?;
+impl SceneDetection {
+    /// 整个视频的帧序号在 `timestamps` / `similarities` 中的位置
+    pub fn local_index(&self, frame: u32) -> usize {
+        frame.saturating_sub(self.first_frame) as usize
+    }
+}
 
-    // 提取所有帧
-    let _ = window.emit(
-        "auto_split_progress",
-        serde_json::json!({
-            "message": "正在提取视频帧...",
-            "percent": 0,
-        }),
-    );
+/// 场景检测参数
+pub struct DetectionOptions {
+    pub algorithm: SimilarityAlgorithm,
+    pub threshold: f64,
+    pub min_duration: f64,
+    /// 只分析源视频的这段时间
+    pub range: Option<AnalyzeRange>,
+    pub hysteresis: Option<Hysteresis>,
+    pub flash_filter: Option<FlashFilter>,
+}
 
-    let frames = extract_all_frames_internal(&app, &video_path).await?;
+/// 逐帧对比相似度，按阈值与最短时长切出场景片段（进度发送到自动拆解事件，占 0-70%）
+pub async fn detect_scenes(
+    app: &AppHandle,
+    window: &tauri::WebviewWindow,
+    video_path: &str,
+    options: DetectionOptions,
+) -> Result<SceneDetection, String> {
+    let DetectionOptions { algorithm: algo, threshold, min_duration, range, hysteresis, flash_filter } = options;
+    let started = Instant::now();
+    // 获取视频元数据
+    let metadata = get_video_metadata_internal(app, video_path).await?;
 
-    if frames.len() < 2 {
+    // 同一视频以相同算法与参数分析过时直接复用逐帧结果，只按新的阈值与时长重新切分
+    let tuning = frame_similarity::current(app);
+    let key = analysis_cache::series_key(algo, &tuning);
+    let cached = analysis_cache::load(app, video_path, range);
+    let hit = cached.as_ref().and_then(|c| {
+        c.similarities(&key)
+            .map(|similarities| (c.first_frame, c.timestamps.clone(), similarities))
+    });
+    let cache_hit = hit.is_some();
+    let (first_frame, timestamps, similarities) = match hit {
+        Some(hit) => hit,
+        None => {
+            let cached_hashes = cached.and_then(|c| c.hashes);
+            analyze_frames(app, Some(window), video_path, algo, tuning, range, cached_hashes).await?
+        }
+    };
+
+    if timestamps.len() < 2 {
         return Err("视频帧数不足".to_string());
     }
 
+    let analysis_secs = started.elapsed().as_secs_f64();
+    let split_started = Instant::now();
+
     // 计算最小帧数
     let min_frames = (min_duration * metadata.fps).round() as u32;
 
-    // 逐帧对比，找到切分点
+    let mut flash_suppressed = 0;
+    let (mut split_points, counts) = match flash_filter {
+        Some(filter) => {
+            let filtered = filter.apply(&similarities, threshold);
+            flash_suppressed = similarities
+                .iter()
+                .zip(&filtered)
+                .filter(|(raw, filtered)| **raw < threshold && **filtered >= threshold)
+                .count() as u32;
+            scene_split_points(&filtered, first_frame, threshold, min_frames, hysteresis)
+        }
+        None => scene_split_points(&similarities, first_frame, threshold, min_frames, hysteresis),
+    };
+
+    // 发送最终进度
+    let total_frames = timestamps.len();
     let _ = window.emit(
-        "auto_split_progress",
-        serde_json::json!({
-            "message": "正在分析帧相似度...",
-            "percent": 10,
-        }),
+        AUTO_SPLIT_PROGRESS,
+        progress(app, "split.analyzed", &[total_frames.to_string(), total_frames.to_string()], 70),
     );
 
-    let mut split_points = vec![0u32]; // 起始帧
-    let mut last_split_frame = 0u32;
+    // 添加结束帧
+    let last_frame = first_frame + total_frames as u32 - 1;
+    if split_points.last() != Some(&last_frame) {
+        split_points.push(last_frame);
+    }
+
+    // 生成片段范围
+    let mut segments = Vec::new();
+    for i in 0..split_points.len() - 1 {
+        segments.push(SegmentRange {
+            start_frame: split_points[i],
+            end_frame: split_points[i + 1] - 1,
+        });
+    }
+
+    if segments.is_empty() {
+        return Err("未检测到场景切换，无法拆分".to_string());
+    }
+
+    Ok(SceneDetection {
+        segments,
+        similarities,
+        timestamps,
+        duration: metadata.duration,
+        first_frame,
+        stats: DetectionStats {
+            cache_hit,
+            analysis_secs,
+            split_secs: split_started.elapsed().as_secs_f64(),
+            counts,
+            flash_suppressed,
+        },
+    })
+}
+
+/// 提取所有帧（或分析范围内的帧）并并行计算相邻帧相似度，结果写入逐帧分析缓存；
+/// 返回第一帧序号、时间戳与相似度（进度占 0-70%，没有窗口时不发送）
+async fn analyze_frames(
+    app: &AppHandle,
+    window: Option<&tauri::WebviewWindow>,
+    video_path: &str,
+    algo: SimilarityAlgorithm,
+    tuning: frame_similarity::SimilarityTuning,
+    range: Option<AnalyzeRange>,
+    cached_hashes: Option<Vec<Option<u64>>>,
+) -> Result<(u32, Vec<f64>, Vec<f64>), String> {
+    let key = analysis_cache::series_key(algo, &tuning);
+    // 提取所有帧
+    if let Some(window) = window {
+        let _ = window.emit(
+            AUTO_SPLIT_PROGRESS,
+            progress(app, "frames.extracting", &[], 0),
+        );
+    }
+
+    let frames = extract_all_frames_internal(app, video_path, algo.needs_color(), range).await?;
+
+    if frames.len() < 2 {
+        return Err("视频帧数不足".to_string());
+    }
+
+    // 比较时忽略水印、台标等区域；启用预筛选时先算每帧的哈希（缓存中有则直接使用）
+    let frame_paths: Vec<String> = frames.iter().map(|f| f.image_path.clone()).collect();
+    let mask = RegionMask::build(&tuning.mask, &frame_paths);
+    let prefilter = match cached_hashes.filter(|h| h.len() == frames.len()) {
+        Some(hashes) => HashPrefilter::from_hashes(tuning.prefilter, hashes),
+        None => HashPrefilter::build(tuning.prefilter, &frame_paths),
+    };
+
+    // 逐帧对比
+    if let Some(window) = window {
+        let _ = window.emit(
+            AUTO_SPLIT_PROGRESS,
+            progress(app, "split.analyzing", &[], 10),
+        );
+    }
 
     // 并行计算所有帧对的相似度
     let progress_counter = Arc::new(AtomicUsize::new(0));
     let total_frames = frames.len();
-    let window_clone = window.clone();
+    let window_clone = window.cloned();
 
-    let similarities: Vec<(usize, f64)> = (1..frames.len())
+    let similarities: Vec<f64> = (1..frames.len())
         .into_par_iter()
         .map(|i| {
             let prev_frame = &frames[i - 1];
             let curr_frame = &frames[i];
 
-            let similarity = calculate_similarity(
-                &prev_frame.image_path,
-                &curr_frame.image_path,
-                algo,
-            ).unwrap_or(1.0); // 出错时默认为完全相似
+            let similarity = match prefilter.as_ref().and_then(|p| p.decide(i - 1, i)) {
+                Some(similarity) => similarity,
+                None => calculate_similarity(
+                    &prev_frame.image_path,
+                    &curr_frame.image_path,
+                    algo,
+                    mask.as_ref(),
+                ).unwrap_or(1.0), // 出错时默认为完全相似
+            };
 
             // 更新进度计数器
             let current = progress_counter.fetch_add(1, Ordering::Relaxed);
 
             // 每 100 帧发送一次进度（减少开销）
-            if current % 100 == 0 {
+            if let Some(window) = window_clone.as_ref().filter(|_| current.is_multiple_of(100)) {
                 let percent = 10 + ((current as f64 / total_frames as f64) * 60.0) as u32;
-                let _ = window_clone.emit(
-                    "auto_split_progress",
-                    serde_json::json!({
-                        "message": format!("已分析 {}/{} 帧", current, total_frames),
-                        "percent": percent,
-                    }),
+                let _ = window.emit(
+                    AUTO_SPLIT_PROGRESS,
+                    progress(
+                        app,
+                        "split.analyzed",
+                        &[current.to_string(), total_frames.to_string()],
+                        percent,
+                    ),
                 );
             }
 
-            (i, similarity)
+            similarity
         })
         .collect();
 
-    // 串行处理切分点（需要维护状态）
-    for (i, similarity) in similarities {
-        let curr_frame = &frames[i];
-
-        // 如果相似度低于阈值，且距离上次切分点足够远
-        if similarity < threshold {
-            let frames_since_last_split = curr_frame.frame_number - last_split_frame;
-            if frames_since_last_split >= min_frames {
-                split_points.push(curr_frame.frame_number);
-                last_split_frame = curr_frame.frame_number;
-            }
-        }
-    }
-
-    // 发送最终进度
-    let _ = window.emit(
-        "auto_split_progress",
-        serde_json::json!({
-            "message": format!("已分析 {}/{} 帧", total_frames, total_frames),
-            "percent": 70,
-        }),
+    let first_frame = frames[0].frame_number;
+    let timestamps: Vec<f64> = frames.iter().map(|f| f.timestamp).collect();
+    analysis_cache::store(
+        app,
+        video_path,
+        range,
+        SeriesEntry {
+            first_frame,
+            timestamps: &timestamps,
+            hashes: prefilter.as_ref().map(|p| p.hashes()),
+            key: &key,
+            similarities: &similarities,
+        },
     );
+    Ok((first_frame, timestamps, similarities))
+}
 
-    // 添加结束帧
-    if split_points.last() != Some(&(frames.len() as u32 - 1)) {
-        split_points.push(frames.len() as u32 - 1);
+/// 后台预分析：按指定算法计算逐帧相似度写入分析缓存，已有结果时跳过；
+/// 为此新提取的帧在完成后删除，不长期占用临时目录
+pub async fn preanalyze_frames(app: &AppHandle, video_path: &str, algo: SimilarityAlgorithm) -> Result<(), String> {
+    let tuning = frame_similarity::current(app);
+    let key = analysis_cache::series_key(algo, &tuning);
+    let cached = analysis_cache::load(app, video_path, None);
+    if cached.as_ref().is_some_and(|c| c.similarities(&key).is_some()) {
+        return Ok(());
     }
 
-    // 生成片段范围
-    let mut segments = Vec::new();
-    for i in 0..split_points.len() - 1 {
-        segments.push(SegmentRange {
-            start_frame: split_points[i],
-            end_frame: split_points[i + 1] - 1,
-        });
+    let mode = analysis_mode(video_path, algo.needs_color(), None);
+    let reused = frames_complete(video_path, mode, None);
+    let hashes = cached.and_then(|c| c.hashes);
+    let result = analyze_frames(app, None, video_path, algo, tuning, None, hashes).await;
+    if !reused {
+        let _ = fs::remove_dir_all(frame_cache_dir(video_path, mode, None));
     }
+    result.map(|_| ())
+}
 
-    if segments.is_empty() {
-        return Err("未检测到场景切换，无法拆分".to_string());
+// 自动拆解视频（基于帧相似度）
+#[tauri::command]
+pub async fn auto_split_video(
+    app: AppHandle,
+    video_path: String,
+    params: AutoSplitParams,
+) -> Result<String, String> {
+    run_auto_split(&app, video_path, params).await
+}
+
+/// 按参数自动拆解一个视频并记录统计（命令、批量处理、任务队列与自检共用）
+pub(crate) async fn run_auto_split(
+    app: &AppHandle,
+    video_path: String,
+    params: AutoSplitParams,
+) -> Result<String, String> {
+    let result = auto_split_video_internal(app.clone(), video_path, params).await;
+    record_operation(app, "auto_split", &result);
+    result.map_err(|e| localize_error(app, e))
+}
+
+async fn auto_split_video_internal(
+    app: AppHandle,
+    video_path: String,
+    params: AutoSplitParams,
+) -> Result<String, String> {
+    let AutoSplitParams {
+        output_dir,
+        algorithm,
+        threshold,
+        min_duration,
+        skip_first,
+        skip_last,
+        source_date,
+        export_preset,
+        resolved_export_preset,
+        encode_settings,
+        analyze_start,
+        analyze_end,
+        hysteresis,
+        flash_filter,
+        detection_profile,
+    } = params;
+    check_input(&app, &video_path)?;
+    check_output(&app, &output_dir)?;
+    let range = AnalyzeRange::new(analyze_start, analyze_end)?;
+
+    // 套用检测预设（单独指定的滞回与闪光过滤优先）
+    let (algorithm, threshold, min_duration, hysteresis, flash_filter) =
+        match detection_profile.filter(|n| !n.is_empty()) {
+            Some(name) => {
+                let profile = app.state::<SettingsManager>().find_detection_profile(&name)?;
+                (
+                    profile.algorithm,
+                    profile.threshold,
+                    profile.min_duration,
+                    hysteresis.or(profile.hysteresis),
+                    flash_filter.or(profile.flash_filter),
+                )
+            }
+            None => (algorithm, threshold, min_duration, hysteresis, flash_filter),
+        };
+    if let Some(hysteresis) = &hysteresis {
+        hysteresis.validate(threshold)?;
     }
+    if let Some(filter) = &flash_filter {
+        filter.validate()?;
+    }
+    let window = app
+        .get_webview_window("main")
+        .ok_or("无法获取窗口")?;
+
+    let algo = SimilarityAlgorithm::from_name(&algorithm, &frame_similarity::current(&app))?;
+    let detection = detect_scenes(
+        &app,
+        &window,
+        &video_path,
+        DetectionOptions { algorithm: algo, threshold, min_duration, range, hysteresis, flash_filter },
+    )
+    .await?;
+    let mut diagnostics = detection_diagnostics(&video_path, &detection);
+    let mut segments = detection.segments;
 
     // 新增：根据掐头去尾选项过滤片段
     let original_count = segments.len();
@@ -710,176 +1980,84 @@ pub async fn auto_split_video(
 
     // 发送过滤信息
     let _ = window.emit(
-        "auto_split_progress",
-        serde_json::json!({
-            "message": format!(
-                "识别到 {} 个片段，过滤后输出 {} 个",
-                original_count, segments.len()
-            ),
-            "percent": 70,
-        }),
+        AUTO_SPLIT_PROGRESS,
+        progress(
+            &app,
+            "split.segments_found",
+            &[original_count.to_string(), segments.len().to_string()],
+            70,
+        ),
     );
 
     // 生成视频片段
     let _ = window.emit(
-        "auto_split_progress",
-        serde_json::json!({
-            "message": "正在生成视频片段...",
-            "percent": 70,
-        }),
-    );
-
-    let result = generate_video_segments(app, video_path, segments, output_dir).await?;
-
-    let _ = window.emit(
-        "auto_split_progress",
-        serde_json::json!({
-            "message": "完成",
-            "percent": 100,
-        }),
+        AUTO_SPLIT_PROGRESS,
+        progress(&app, "split.generating", &[], 70),
     );
 
-    Ok(result)
-}
-
-// 内部使用的帧提取（不发送进度事件）
-async fn extract_all_frames_internal(
-    app: &AppHandle,
-    video_path: &str,
-) -> Result<Vec<FrameInfo>, String> {
-    let metadata = get_video_metadata_internal(app, video_path).await?;
-
-    // 创建临时目录
-    let video_hash = calculate_hash(video_path);
-    let temp_dir = std::env::temp_dir()
-        .join(format!("mp4handler_{}", video_hash))
-        .join("frames");
-
-    // 清理旧的帧
-    if temp_dir.exists() {
-        fs::remove_dir_all(&temp_dir).map_err(|e| format!("清理临时目录失败: {}", e))?;
-    }
-    fs::create_dir_all(&temp_dir).map_err(|e| format!("创建临时目录失败: {}", e))?;
-
-    // 使用 FFmpeg 提取所有帧
-    let output_pattern = temp_dir.join("frame_%05d.jpg");
-    let sidecar = app
-        .shell()
-        .sidecar("ffmpeg")
-        .map_err(|e| format!("FFmpeg 启动失败: {}", e))?;
-
-    let vf_filter = "scale=320:-1".to_string();
-
-    let output = sidecar
-        .args(&[
-            "-i",
-            video_path,
-            "-vf",
-            &vf_filter,
-            "-vsync",
-            "0",
-            "-q:v",
-            "3",
-            "-y",
-            output_pattern.to_str().unwrap(),
-        ])
-        .output()
-        .await
-        .map_err(|e| format!("FFmpeg 执行失败: {}", e))?;
-
-    if !output.status.success() {
-        return Err(format!(
-            "提取帧失败: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ));
-    }
-
-    // 扫描生成的帧文件
-    let mut frames = Vec::new();
-    let mut entries: Vec<_> = fs::read_dir(&temp_dir)
-        .map_err(|e| format!("读取临时目录失败: {}", e))?
-        .filter_map(|e| e.ok())
-        .collect();
-
-    entries.sort_by_key(|e| e.path());
-
-    let frame_timestamps = get_video_frame_timestamps(app, video_path).await?;
-    let limit = std::cmp::min(entries.len(), frame_timestamps.len());
-    for (idx, entry) in entries.iter().take(limit).enumerate() {
-        let path = entry.path();
-        if path.extension().and_then(|s| s.to_str()) == Some("jpg") {
-            let frame_number = idx as u32;
-            let timestamp = frame_timestamps
-                .get(idx)
-                .copied()
-                .unwrap_or_else(|| frame_number as f64 / metadata.fps.max(1.0));
+    check_batch_signal(&app)?;
+    let generate_started = Instant::now();
+    let paths = generate_video_segments_internal(
+        app.clone(),
+        video_path,
+        segments,
+        SegmentExport {
+            output_dir,
+            source_date,
+            export_preset,
+            resolved_export_preset,
+            encoder: EncoderChoice::Software,
+            encode_settings: encode_settings.unwrap_or_default(),
+        },
+    )
+    .await?;
+    diagnostics.generate_secs = generate_started.elapsed().as_secs_f64();
 
-            frames.push(FrameInfo {
-                frame_number,
-                timestamp,
-                image_path: path.to_string_lossy().to_string(),
-            });
-        }
-    }
+    let _ = window.emit(
+        AUTO_SPLIT_PROGRESS,
+        progress(&app, "common.done", &[], 100),
+    );
+    let _ = window.emit(AUTO_SPLIT_DIAGNOSTICS, &diagnostics);
 
-    Ok(frames)
+    Ok(format!("{}\n{}", segments_summary(&paths), diagnostics_summary(&diagnostics)))
 }
 
-// 去结尾并合成视频
-#[tauri::command]
-pub async fn remove_ending_and_concat(
-    app: AppHandle,
-    video_path: String,
-    output_dir: String,
-    algorithm: String,
+/// 去结尾：逐帧分析整个视频，按阈值与最短时长切出所有片段（进度占 0-60%）
+async fn ending_segments_forward(
+    app: &AppHandle,
+    window: &tauri::WebviewWindow,
+    video_path: &str,
+    algo: SimilarityAlgorithm,
     threshold: f64,
-    min_duration: f64,
-    new_ending_video: Option<String>,
-    shuffle_segments: bool,
-) -> Result<String, String> {
-    let window = app
-        .get_webview_window("main")
-        .ok_or("无法获取窗口")?;
-
-    // 解析算法
-    let algo = SimilarityAlgorithm::from_str(&algorithm)?;
-
-    // 获取视频元数据
-    let metadata = get_video_metadata_internal(&app, &video_path).await?;
-
+    min_frames: u32,
+) -> Result<Vec<SegmentRange>, String> {
     // 提取所有帧
     let _ = window.emit(
-        "remove_ending_progress",
-        serde_json::json!({
-            "message": "正在提取视频帧...",
-            "percent": 0,
-        }),
+        REMOVE_ENDING_PROGRESS,
+        progress(app, "frames.extracting", &[], 0),
     );
 
-    let frames = extract_all_frames_internal(&app, &video_path).await?;
+    let frames = extract_all_frames_internal(app, video_path, algo.needs_color(), None).await?;
 
     if frames.len() < 2 {
         return Err("视频帧数不足".to_string());
     }
 
     let _ = window.emit(
-        "remove_ending_progress",
-        serde_json::json!({
-            "message": "帧提取完成",
-            "percent": 10,
-        }),
+        REMOVE_ENDING_PROGRESS,
+        progress(app, "frames.done", &[], 10),
     );
 
-    // 计算最小帧数
-    let min_frames = (min_duration * metadata.fps).round() as u32;
+    // 比较时忽略水印、台标等区域；启用预筛选时先算每帧的哈希
+    let tuning = frame_similarity::current(app);
+    let frame_paths: Vec<String> = frames.iter().map(|f| f.image_path.clone()).collect();
+    let mask = RegionMask::build(&tuning.mask, &frame_paths);
+    let prefilter = HashPrefilter::build(tuning.prefilter, &frame_paths);
 
     // 并行计算相似度
     let _ = window.emit(
-        "remove_ending_progress",
-        serde_json::json!({
-            "message": "正在分析帧相似度...",
-            "percent": 10,
-        }),
+        REMOVE_ENDING_PROGRESS,
+        progress(app, "split.analyzing", &[], 10),
     );
 
     let mut split_points = vec![0u32];
@@ -895,22 +2073,28 @@ pub async fn remove_ending_and_concat(
             let prev_frame = &frames[i - 1];
             let curr_frame = &frames[i];
 
-            let similarity = calculate_similarity(
-                &prev_frame.image_path,
-                &curr_frame.image_path,
-                algo,
-            ).unwrap_or(1.0);
+            let similarity = match prefilter.as_ref().and_then(|p| p.decide(i - 1, i)) {
+                Some(similarity) => similarity,
+                None => calculate_similarity(
+                    &prev_frame.image_path,
+                    &curr_frame.image_path,
+                    algo,
+                    mask.as_ref(),
+                ).unwrap_or(1.0),
+            };
 
             let current = progress_counter.fetch_add(1, Ordering::Relaxed);
 
-            if current % 100 == 0 {
+            if current.is_multiple_of(100) {
                 let percent = 10 + ((current as f64 / total_frames as f64) * 50.0) as u32;
                 let _ = window_clone.emit(
-                    "remove_ending_progress",
-                    serde_json::json!({
-                        "message": format!("已分析 {}/{} 帧", current, total_frames),
-                        "percent": percent,
-                    }),
+                    REMOVE_ENDING_PROGRESS,
+                    progress(
+                        app,
+                        "split.analyzed",
+                        &[current.to_string(), total_frames.to_string()],
+                        percent,
+                    ),
                 );
             }
 
@@ -932,11 +2116,8 @@ pub async fn remove_ending_and_concat(
     }
 
     let _ = window.emit(
-        "remove_ending_progress",
-        serde_json::json!({
-            "message": format!("已分析 {}/{} 帧", total_frames, total_frames),
-            "percent": 60,
-        }),
+        REMOVE_ENDING_PROGRESS,
+        progress(app, "split.analyzed", &[total_frames.to_string(), total_frames.to_string()], 60),
     );
 
     // 添加结束帧
@@ -953,14 +2134,264 @@ pub async fn remove_ending_and_concat(
         });
     }
 
-    let original_count = segments.len();
 
-    // 移除最后一个片段
+    Ok(segments)
+}
+
+/// 去结尾的反向检测：只分析末尾的一段时间，从后往前找到最后一个场景切换，
+/// 切换点之前的内容作为一个片段保留，之后的作为片尾（片尾不短于最短时长）
+async fn ending_segments_reverse(
+    app: &AppHandle,
+    window: &tauri::WebviewWindow,
+    video_path: &str,
+    algo: SimilarityAlgorithm,
+    threshold: f64,
+    min_frames: u32,
+    range: Option<AnalyzeRange>,
+) -> Result<Vec<SegmentRange>, String> {
+    let _ = window.emit(
+        REMOVE_ENDING_PROGRESS,
+        progress(app, "frames.extracting", &[], 0),
+    );
+
+    let frames = extract_all_frames_internal(app, video_path, algo.needs_color(), range).await?;
+
+    if frames.len() < 2 {
+        return Err("视频帧数不足".to_string());
+    }
+
+    let _ = window.emit(
+        REMOVE_ENDING_PROGRESS,
+        progress(app, "split.analyzing", &[], 10),
+    );
+
+    let tuning = frame_similarity::current(app);
+    let frame_paths: Vec<String> = frames.iter().map(|f| f.image_path.clone()).collect();
+    let mask = RegionMask::build(&tuning.mask, &frame_paths);
+    let prefilter = HashPrefilter::build(tuning.prefilter, &frame_paths);
+    let last_frame = frames[frames.len() - 1].frame_number;
+
+    // 从最后一帧往前逐对比较，遇到第一个满足条件的切换即停止
+    let mut cut = None;
+    for i in (1..frames.len()).rev() {
+        let frame_number = frames[i].frame_number;
+        if last_frame + 1 - frame_number < min_frames {
+            continue;
+        }
+        let similarity = match prefilter.as_ref().and_then(|p| p.decide(i - 1, i)) {
+            Some(similarity) => similarity,
+            None => calculate_similarity(&frames[i - 1].image_path, &frames[i].image_path, algo, mask.as_ref())
+                .unwrap_or(1.0),
+        };
+        if similarity < threshold {
+            cut = Some(frame_number);
+            break;
+        }
+    }
+
+    let _ = window.emit(
+        REMOVE_ENDING_PROGRESS,
+        progress(app, "split.analyzed", &[frames.len().to_string(), frames.len().to_string()], 60),
+    );
+
+    let cut = cut.ok_or("结尾窗口内未检测到场景切换，可以加大窗口或调高阈值")?;
+    if cut == 0 {
+        return Ok(vec![SegmentRange { start_frame: 0, end_frame: last_frame }]);
+    }
+    Ok(vec![
+        SegmentRange { start_frame: 0, end_frame: cut - 1 },
+        SegmentRange { start_frame: cut, end_frame: last_frame },
+    ])
+}
+
+// 内部使用的帧提取（不发送进度事件）
+// 界面已完整提取过缩略图时直接复用，切换手动/自动模式不必重新提取；否则提取分析用的灰度帧
+// 算法需要颜色（按通道比较直方图）时总是使用彩色缩略图
+async fn extract_all_frames_internal(
+    app: &AppHandle,
+    video_path: &str,
+    color: bool,
+    range: Option<AnalyzeRange>,
+) -> Result<Vec<FrameInfo>, String> {
+    extract_frames_cached(app, video_path, analysis_mode(video_path, color, range), range, None).await
+}
+
+/// 分析用的帧规格：需要彩色画面或已有完整的预览帧时用预览帧，否则提取分析用的灰度小图
+fn analysis_mode(video_path: &str, color: bool, range: Option<AnalyzeRange>) -> FrameMode {
+    if color || frames_complete(video_path, FrameMode::Preview, range) {
+        FrameMode::Preview
+    } else {
+        FrameMode::Analysis
+    }
+}
+
+// 去结尾并合成视频
+#[tauri::command]
+pub async fn remove_ending_and_concat(
+    app: AppHandle,
+    video_path: String,
+    params: RemoveEndingParams,
+) -> Result<String, String> {
+    run_remove_ending(&app, video_path, params).await
+}
+
+/// 按参数去结尾并合成一个视频，记录统计（命令与任务队列共用）
+pub(crate) async fn run_remove_ending(
+    app: &AppHandle,
+    video_path: String,
+    params: RemoveEndingParams,
+) -> Result<String, String> {
+    let result = remove_ending_and_concat_internal(app.clone(), video_path, params).await;
+    record_operation(app, "remove_ending", &result);
+    result.map_err(|e| localize_error(app, e))
+}
+
+async fn remove_ending_and_concat_internal(
+    app: AppHandle,
+    video_path: String,
+    params: RemoveEndingParams,
+) -> Result<String, String> {
+    let RemoveEndingParams {
+        output_dir,
+        algorithm,
+        threshold,
+        min_duration,
+        mut new_ending_video,
+        mut new_ending_trim,
+        brand_kit,
+        resolved_brand_kit,
+        shuffle_segments,
+        audio_join,
+        temp_segments,
+        ending_check,
+        encode_settings,
+        tail_window,
+        seed,
+        title,
+    } = params;
+    let encode_settings = encode_settings.unwrap_or_default();
+    let window = app
+        .get_webview_window("main")
+        .ok_or("无法获取窗口")?;
+    temp_segments.validate()?;
+    encode_settings.validate()?;
+    if tail_window.is_some_and(|tail| tail <= 0.0 || !tail.is_finite()) {
+        return Err("结尾检测窗口必须大于 0 秒".to_string());
+    }
+    if let Some(check) = &ending_check {
+        check.validate()?;
+    }
+
+    // 套用品牌包（单独指定的新结尾优先）
+    let mut intro = None;
+    let mut extras = ConcatExtras {
+        title: title.filter(|t| !t.text.is_empty()),
+        ..ConcatExtras::default()
+    };
+    let kit = match resolved_brand_kit {
+        Some(kit) => Some(*kit),
+        None => brand_kit
+            .filter(|n| !n.is_empty())
+            .map(|name| app.state::<SettingsManager>().find_brand_kit(&name))
+            .transpose()?,
+    };
+    if let Some(kit) = kit {
+        if let Some(title) = extras.title.as_mut() {
+            title.apply_brand_font(&kit);
+        }
+        if new_ending_video.as_deref().unwrap_or("").is_empty() && kit.outro_video.is_some() {
+            new_ending_video = kit.outro_video.clone();
+            new_ending_trim = kit.outro_trim;
+        }
+        if let Some(intro_video) = kit.intro_video.filter(|p| !p.is_empty()) {
+            let intro_path = PathBuf::from(&intro_video);
+            if !intro_path.exists() {
+                return Err(format!("片头视频不存在: {}", intro_video));
+            }
+            intro = Some((intro_path, kit.intro_trim));
+        }
+        extras.watermark = kit.watermark;
+        extras.bgm = kit.bgm;
+    }
+    extras.validate()?;
+
+    // 所有读写路径都必须在用户授权的范围内
+    check_input(&app, &video_path)?;
+    check_output(&app, &output_dir)?;
+    let intro_asset = intro.as_ref().map(|(path, _)| path.to_string_lossy().to_string());
+    let assets = [
+        new_ending_video.as_deref(),
+        intro_asset.as_deref(),
+        extras.watermark.as_ref().map(|w| w.image_path.as_str()),
+        extras.bgm.as_ref().map(|b| b.audio_path.as_str()),
+        extras.title.as_ref().and_then(|t| t.font_file.as_deref()),
+    ];
+    let references = ending_check.iter().flat_map(|c| c.reference_videos.iter().map(String::as_str));
+    for asset in assets.into_iter().flatten().chain(references).filter(|p| !p.is_empty()) {
+        check_input(&app, asset)?;
+    }
+    if let Some(watermark) = extras.watermark.as_mut() {
+        let asset = inspect_watermark(&app, watermark).await?;
+        if !asset.has_alpha {
+            let params = [asset.codec.clone(), asset.pix_fmt.clone()];
+            let _ = window.emit(
+                REMOVE_ENDING_PROGRESS,
+                progress(&app, "overlay.no_alpha", &params, 0),
+            );
+        }
+    }
+
+    // 解析算法
+    let algo = SimilarityAlgorithm::from_name(&algorithm, &frame_similarity::current(&app))?;
+
+    // 获取视频元数据
+    let metadata = get_video_metadata_internal(&app, &video_path).await?;
+
+    // 计算最小帧数
+    let min_frames = (min_duration * metadata.fps).round() as u32;
+
+    // 只关心结尾时从视频末尾往前找最后一个场景切换，不分析整个视频
+    let mut segments = match tail_window {
+        Some(tail) => {
+            let range = AnalyzeRange::new(Some((metadata.duration - tail).max(0.0)), None)?;
+            ending_segments_reverse(&app, &window, &video_path, algo, threshold, min_frames, range).await?
+        }
+        None => ending_segments_forward(&app, &window, &video_path, algo, threshold, min_frames).await?,
+    };
+
     if segments.is_empty() {
         return Err("未检测到场景切换（相似度始终高于阈值）".to_string());
     }
+    let original_count = segments.len();
+    let frame_timestamps = get_video_frame_timestamps(&app, &video_path).await?;
+    let total_frames_count = frame_timestamps.len();
+
+    // 先确认最后一个片段是片尾，没有片尾的视频不丢失正片内容
+    let mut kept_reason = None;
+    if let (Some(check), Some(last)) = (&ending_check, segments.last()) {
+        let start = frame_timestamps.get(last.start_frame as usize).copied().unwrap_or(0.0);
+        let end = frame_timestamps
+            .get(last.end_frame as usize + 1)
+            .copied()
+            .unwrap_or(metadata.duration);
+        let work_dir = std::env::temp_dir()
+            .join(format!("mp4handler_{}", calculate_hash(&video_path)))
+            .join("ending_check");
+        if let Some(reason) = check.verify(&app, Path::new(&video_path), start, end, algo, &work_dir).await? {
+            if check.skip_if_unsure {
+                return Err(format!("无法确认最后一个片段是片尾（{}），跳过该视频", reason));
+            }
+            let _ = window.emit(
+                REMOVE_ENDING_PROGRESS,
+                progress(&app, "ending.unsure_kept", std::slice::from_ref(&reason), 60),
+            );
+            kept_reason = Some(reason);
+        }
+    }
 
-    segments.pop();
+    if kept_reason.is_none() {
+        segments.pop();
+    }
 
     if segments.is_empty() {
         return Err(format!(
@@ -970,46 +2401,92 @@ pub async fn remove_ending_and_concat(
     }
 
     let _ = window.emit(
-        "remove_ending_progress",
-        serde_json::json!({
-            "message": format!("识别到 {} 个片段，移除最后一个后剩余 {} 个", original_count, segments.len()),
-            "percent": 60,
-        }),
+        REMOVE_ENDING_PROGRESS,
+        progress(
+            &app,
+            "ending.segments_found",
+            &[original_count.to_string(), segments.len().to_string()],
+            60,
+        ),
     );
 
-    // 如果需要随机打乱
+    // 如果需要随机打乱（指定种子时每次打乱结果相同）
     if shuffle_segments {
-        let mut rng = rand::thread_rng();
-        segments.shuffle(&mut rng);
+        match seed {
+            Some(seed) => segments.shuffle(&mut StdRng::seed_from_u64(seed)),
+            None => segments.shuffle(&mut rand::thread_rng()),
+        }
     }
 
-    // 生成临时片段文件
-    let _ = window.emit(
-        "remove_ending_progress",
-        serde_json::json!({
-            "message": "正在生成临时片段...",
-            "percent": 60,
-        }),
-    );
-
+    // 生成临时片段文件（直接裁剪时不需要）
+    let mut temp_segments = temp_segments;
+    if temp_segments.mode == TempSegmentMode::Direct && segments.len() > DIRECT_MAX_SEGMENTS {
+        let _ = window.emit(
+            REMOVE_ENDING_PROGRESS,
+            progress(
+                &app,
+                "ending.direct_fallback",
+                &[segments.len().to_string(), DIRECT_MAX_SEGMENTS.to_string()],
+                60,
+            ),
+        );
+        temp_segments.mode = TempSegmentMode::Reencode;
+    }
+    let direct = temp_segments.mode == TempSegmentMode::Direct;
     let video_hash = calculate_hash(&video_path);
     let temp_dir = std::env::temp_dir()
         .join(format!("mp4handler_{}", video_hash))
         .join("segments");
 
-    if temp_dir.exists() {
-        fs::remove_dir_all(&temp_dir).map_err(|e| format!("清理临时目录失败: {}", e))?;
+    if !direct {
+        let _ = window.emit(
+            REMOVE_ENDING_PROGRESS,
+            progress(&app, "ending.generating_temp", &[], 60),
+        );
+        if temp_dir.exists() {
+            fs::remove_dir_all(&temp_dir).map_err(|e| format!("清理临时目录失败: {}", e))?;
+        }
+        fs::create_dir_all(&temp_dir).map_err(|e| format!("创建临时目录失败: {}", e))?;
+        app.state::<ProcessRegistry>().track_temp_dir(&temp_dir);
     }
-    fs::create_dir_all(&temp_dir).map_err(|e| format!("创建临时目录失败: {}", e))?;
 
-    let frame_timestamps = get_video_frame_timestamps(&app, &video_path).await?;
-    let total_frames_count = frame_timestamps.len();
+    // 按接缝两侧的音频波形微调最后一个片段的出点，避免新结尾重复播放同一拍或出现爆音
+    let ending_path = new_ending_video.as_deref().filter(|p| !p.is_empty()).map(PathBuf::from);
+    if let (true, Some(ending_path), Some(last)) = (audio_join, ending_path.filter(|p| p.exists()), segments.last_mut()) {
+        let end_idx = last.end_frame as usize;
+        if end_idx < total_frames_count {
+            let max_frames = MAX_TRIM_FRAMES.min((last.end_frame - last.start_frame) as usize);
+            let cuts: Vec<f64> = (0..=max_frames)
+                .map(|k| {
+                    let idx = end_idx + 1 - k;
+                    if idx < total_frames_count {
+                        frame_timestamps[idx]
+                    } else {
+                        metadata.duration.max(frame_timestamps[end_idx])
+                    }
+                })
+                .collect();
+            let next_start = new_ending_trim.and_then(|t| t.start).unwrap_or(0.0);
+            let trim = align_junction(&app, Path::new(&video_path), &cuts, &ending_path, next_start).await?;
+            if trim.frames > 0 {
+                last.end_frame -= trim.frames as u32;
+                let key = if trim.repeat { "ending.audio_join_repeat" } else { "ending.audio_join_click" };
+                let removed_ms = ((cuts[0] - cuts[trim.frames]) * 1000.0).round();
+                let _ = window.emit(
+                    REMOVE_ENDING_PROGRESS,
+                    progress(&app, key, &[trim.frames.to_string(), removed_ms.to_string()], 60),
+                );
+            }
+        }
+    }
 
     let mut temp_segment_paths = Vec::new();
+    let mut trims: Vec<Option<ClipTrim>> = Vec::new();
+    let throttle = throttle::current(&app);
 
     for (idx, segment) in segments.iter().enumerate() {
         let segment_num = idx + 1;
-        let temp_file = temp_dir.join(format!("segment_{}.mp4", segment_num));
+        let temp_file = temp_dir.join(format!("segment_{}.{}", segment_num, temp_segments.extension()));
 
         let start_idx = segment.start_frame as usize;
         let end_idx = segment.end_frame as usize;
@@ -1025,56 +2502,57 @@ pub async fn remove_ending_and_concat(
         };
         let duration = (end_time_exclusive - start_time).max(0.0);
 
+        // 直接裁剪：原视频按片段时间作为输入，由合成时的 trim 滤镜截取
+        if direct {
+            temp_segment_paths.push(PathBuf::from(&video_path));
+            trims.push(Some(ClipTrim {
+                start: Some(start_time),
+                end: (end_idx + 1 < total_frames_count).then_some(end_time_exclusive),
+            }));
+            continue;
+        }
+
         let percent = 60 + ((segment_num as f64 / segments.len() as f64) * 20.0) as u32;
         let _ = window.emit(
-            "remove_ending_progress",
-            serde_json::json!({
-                "message": format!("正在生成临时片段 {}/{}", segment_num, segments.len()),
-                "percent": percent,
-            }),
+            REMOVE_ENDING_PROGRESS,
+            progress(
+                &app,
+                "ending.generating_temp_progress",
+                &[segment_num.to_string(), segments.len().to_string()],
+                percent,
+            ),
         );
 
-        let sidecar = app
-            .shell()
-            .sidecar("ffmpeg")
-            .map_err(|e| format!("FFmpeg 启动失败: {}", e))?;
+        let mut args: Vec<String> = [
+            "-i",
+            &video_path,
+            "-ss",
+            &start_time.to_string(),
+            "-t",
+            &duration.to_string(),
+            "-vf",
+            "setpts=PTS-STARTPTS",
+            "-vsync",
+            "vfr",
+            "-af",
+            "aresample=async=1:first_pts=0,asetpts=PTS-STARTPTS",
+            "-fflags",
+            "+genpts",
+            "-avoid_negative_ts",
+            "make_zero",
+            "-y",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        args.extend(temp_segments.encode_args());
+        args.extend(throttle.output_args());
+        args.push(temp_file.to_string_lossy().to_string());
 
-        let output = sidecar
-            .args(&[
-                "-i",
-                &video_path,
-                "-ss",
-                &start_time.to_string(),
-                "-t",
-                &duration.to_string(),
-                "-vf",
-                "setpts=PTS-STARTPTS",
-                "-vsync",
-                "vfr",
-                "-c:v",
-                "libx264",
-                "-preset",
-                "fast",
-                "-crf",
-                "18",
-                "-c:a",
-                "aac",
-                "-b:a",
-                "192k",
-                "-af",
-                "aresample=async=1:first_pts=0,asetpts=PTS-STARTPTS",
-                "-fflags",
-                "+genpts",
-                "-avoid_negative_ts",
-                "make_zero",
-                "-y",
-                temp_file.to_str().unwrap(),
-            ])
-            .output()
-            .await
-            .map_err(|e| format!("FFmpeg 执行失败: {}", e))?;
+        let label = t(&app, "ending.generating_temp_progress", &[segment_num.to_string(), segments.len().to_string()]);
+        let output = run_ffmpeg_with_progress(&app, args, "remove_ending", label, duration).await?;
 
-        if !output.status.success() {
+        if !output.success() {
             return Err(format!(
                 "生成临时片段 {} 失败: {}",
                 segment_num,
@@ -1082,7 +2560,16 @@ pub async fn remove_ending_and_concat(
             ));
         }
 
+        // 临时片段不裁剪
         temp_segment_paths.push(temp_file);
+        trims.push(None);
+    }
+
+    // 片头/新结尾视频按入点/出点裁剪
+
+    if let Some((intro_path, intro_trim)) = intro {
+        temp_segment_paths.insert(0, intro_path);
+        trims.insert(0, intro_trim);
     }
 
     // 如果有新结尾视频，添加到列表
@@ -1093,26 +2580,43 @@ pub async fn remove_ending_and_concat(
                 return Err(format!("新结尾视频不存在: {}", ending));
             }
             temp_segment_paths.push(ending_path);
+            trims.push(new_ending_trim);
         }
     }
 
     // 检测视频兼容性
     let _ = window.emit(
-        "remove_ending_progress",
-        serde_json::json!({
-            "message": "正在检测视频兼容性...",
-            "percent": 80,
-        }),
+        REMOVE_ENDING_PROGRESS,
+        progress(&app, "ending.checking", &[], 80),
     );
 
     let videos_info = check_video_compatibility_for_paths(&app, &temp_segment_paths).await?;
 
+    for ((name, info), trim) in videos_info.iter().zip(&trims) {
+        if let Some(trim) = trim {
+            trim.validate(name, info.duration)?;
+        }
+    }
+
     let (target_width, target_height) = videos_info
         .first()
         .map(|(_, info)| (info.width, info.height))
         .ok_or("无法获取目标分辨率")?;
 
-    let filter = build_concat_filter(&videos_info, target_width, target_height)?;
+    let filter = build_concat_filter(
+        &videos_info,
+        &trims,
+        &[Canvas::fit(target_width, target_height)],
+        &[],
+        &extras,
+    )?;
+
+    // 按顺序裁剪原视频时只解码一次；打乱顺序后 split 需要缓存大量帧，仍按片段分别读取
+    let (inputs, filter) = if direct && !shuffle_segments {
+        share_inputs(&temp_segment_paths, &videos_info, &filter)
+    } else {
+        (temp_segment_paths.clone(), filter)
+    };
 
     // 生成输出文件名
     let video_name = Path::new(&video_path)
@@ -1123,57 +2627,33 @@ pub async fn remove_ending_and_concat(
 
     // 合成视频
     let _ = window.emit(
-        "remove_ending_progress",
-        serde_json::json!({
-            "message": "正在合成视频...",
-            "percent": 80,
-        }),
+        REMOVE_ENDING_PROGRESS,
+        progress(&app, "ending.composing", &[], 80),
     );
 
-    let sidecar = app
-        .shell()
-        .sidecar("ffmpeg")
-        .map_err(|e| format!("FFmpeg 启动失败: {}", e))?;
-
-    let mut args: Vec<String> = Vec::new();
-    for video in &temp_segment_paths {
-        args.push("-i".to_string());
-        args.push(video.to_string_lossy().to_string());
-    }
-    args.push("-filter_complex".to_string());
-    args.push(filter);
-    args.push("-map".to_string());
-    args.push("[outv]".to_string());
-    args.push("-map".to_string());
-    args.push("[outa]".to_string());
-    args.push("-vsync".to_string());
-    args.push("vfr".to_string());
-    args.push("-c:v".to_string());
-    args.push("libx264".to_string());
-    args.push("-preset".to_string());
-    args.push("fast".to_string());
-    args.push("-crf".to_string());
-    args.push("23".to_string());
-    args.push("-pix_fmt".to_string());
-    args.push("yuv420p".to_string());
-    args.push("-c:a".to_string());
-    args.push("aac".to_string());
-    args.push("-b:a".to_string());
-    args.push("192k".to_string());
-    args.push("-fflags".to_string());
-    args.push("+genpts".to_string());
-    args.push("-avoid_negative_ts".to_string());
-    args.push("make_zero".to_string());
-    args.push("-shortest".to_string());
-    args.push(output_path.to_string_lossy().to_string());
+    let args = build_concat_command_args(
+        &inputs,
+        filter,
+        &extras,
+        &[EncodeOutput {
+            keyframes: gop::current(&app).encoder_args(
+                VideoCodec::H264,
+                videos_info.first().and_then(|(_, info)| parse_rational(&info.fps)),
+            ),
+            ..EncodeOutput::new(output_path.clone()).with_settings(&encode_settings)
+        }],
+        &throttle::current(&app),
+    );
 
-    let output = sidecar
-        .args(args)
-        .output()
-        .await
-        .map_err(|e| format!("FFmpeg 执行失败: {}", e))?;
+    let output_duration = videos_info
+        .iter()
+        .zip(&trims)
+        .map(|((_, info), trim)| trim.map(|t| t.trimmed_duration(info.duration)).unwrap_or(info.duration))
+        .sum();
+    let label = t(&app, "ending.composing", &[]);
+    let output = run_ffmpeg_with_progress(&app, args, "remove_ending", label, output_duration).await?;
 
-    if !output.status.success() {
+    if !output.success() {
         return Err(format!(
             "FFmpeg 执行失败: {}",
             String::from_utf8_lossy(&output.stderr)
@@ -1182,17 +2662,271 @@ pub async fn remove_ending_and_concat(
 
     // 清理临时文件
     let _ = fs::remove_dir_all(&temp_dir);
+    app.state::<ProcessRegistry>().release_temp_dir(&temp_dir);
+
+    record_output(&app, 1, 1, output_duration, false);
 
     let _ = window.emit(
-        "remove_ending_progress",
-        serde_json::json!({
-            "message": "完成",
-            "percent": 100,
-        }),
+        REMOVE_ENDING_PROGRESS,
+        progress(&app, "common.done", &[], 100),
     );
 
     Ok(format!(
-        "成功处理视频，输出文件: {}",
-        output_path.display()
+        "成功处理视频，输出文件: {}{}",
+        output_path.display(),
+        kept_reason
+            .map(|reason| format!("（{}，已保留最后一个片段）", reason))
+            .unwrap_or_default()
     ))
 }
+
+// 批量为文件夹内的视频换结尾：按固定时长去掉原结尾（不做场景检测）后追加新结尾视频
+#[tauri::command]
+pub async fn append_ending_to_folder(
+    app: AppHandle,
+    input_dir: String,
+    output_dir: String,
+    new_ending_video: String,
+    trim_end: Option<f64>,
+    new_ending_trim: Option<ClipTrim>,
+) -> Result<String, String> {
+    let result = append_ending_to_folder_internal(
+        &app,
+        input_dir,
+        output_dir,
+        new_ending_video,
+        trim_end.unwrap_or(0.0),
+        new_ending_trim,
+    )
+    .await;
+    record_operation(&app, "append_ending", &result);
+    result.map_err(|e| localize_error(&app, e))
+}
+
+async fn append_ending_to_folder_internal(
+    app: &AppHandle,
+    input_dir: String,
+    output_dir: String,
+    new_ending_video: String,
+    trim_end: f64,
+    new_ending_trim: Option<ClipTrim>,
+) -> Result<String, String> {
+    let window = app
+        .get_webview_window("main")
+        .ok_or("无法获取窗口")?;
+
+    if trim_end < 0.0 {
+        return Err("去掉的结尾时长不能为负数".to_string());
+    }
+    check_output(app, &output_dir)?;
+    check_input(app, &new_ending_video)?;
+    let ending_path = PathBuf::from(&new_ending_video);
+    if !ending_path.exists() {
+        return Err(format!("新结尾视频不存在: {}", new_ending_video));
+    }
+
+    let videos = list_mp4_files(app.clone(), input_dir)?;
+    if videos.is_empty() {
+        return Err("文件夹中没有 MP4 文件".to_string());
+    }
+
+    let total = videos.len();
+    let mut failures = Vec::new();
+    for (idx, video) in videos.iter().enumerate() {
+        let name = Path::new(video)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let _ = window.emit(
+            REMOVE_ENDING_PROGRESS,
+            progress(
+                app,
+                "ending.appending",
+                &[(idx + 1).to_string(), total.to_string(), name.clone()],
+                (idx * 100 / total) as u32,
+            ),
+        );
+        let ending = Some((ending_path.as_path(), new_ending_trim));
+        if let Err(e) = append_ending(app, video, &output_dir, trim_end, ending).await {
+            failures.push(format!("{}: {}", name, e));
+        }
+    }
+
+    let _ = window.emit(
+        REMOVE_ENDING_PROGRESS,
+        progress(app, "common.done", &[], 100),
+    );
+
+    if failures.is_empty() {
+        Ok(format!("已处理 {} 个视频，输出目录: {}", total, output_dir))
+    } else {
+        Ok(format!(
+            "已处理 {} 个视频，失败 {} 个：\n{}",
+            total - failures.len(),
+            failures.len(),
+            failures.join("\n")
+        ))
+    }
+}
+
+/// 去掉单个视频结尾的固定时长并追加新结尾（为空时只去结尾），输出 `_processed` 文件
+pub async fn append_ending(
+    app: &AppHandle,
+    video_path: &str,
+    output_dir: &str,
+    trim_end: f64,
+    ending: Option<(&Path, Option<ClipTrim>)>,
+) -> Result<(), String> {
+    let mut paths = vec![PathBuf::from(video_path)];
+    paths.extend(ending.map(|(path, _)| path.to_path_buf()));
+    let videos_info = check_video_compatibility_for_paths(app, &paths).await?;
+    let source = &videos_info[0].1;
+    if trim_end >= source.duration {
+        return Err(format!(
+            "视频时长 {:.1} 秒，不足以去掉 {:.1} 秒结尾",
+            source.duration, trim_end
+        ));
+    }
+
+    let mut trims = vec![(trim_end > 0.0).then_some(ClipTrim {
+        start: None,
+        end: Some(source.duration - trim_end),
+    })];
+    trims.extend(ending.map(|(_, trim)| trim));
+    for ((name, info), trim) in videos_info.iter().zip(&trims) {
+        if let Some(trim) = trim {
+            trim.validate(name, info.duration)?;
+        }
+    }
+
+    let extras = ConcatExtras::default();
+    let filter = build_concat_filter(
+        &videos_info,
+        &trims,
+        &[Canvas::fit(source.width, source.height)],
+        &[],
+        &extras,
+    )?;
+
+    let video_name = Path::new(video_path)
+        .file_stem()
+        .ok_or("无法获取视频文件名")?
+        .to_string_lossy();
+    let output_path = PathBuf::from(output_dir).join(format!("{}_processed.mp4", video_name));
+
+    let args = build_concat_command_args(
+        &paths,
+        filter,
+        &extras,
+        &[EncodeOutput {
+            keyframes: gop::current(app).encoder_args(VideoCodec::H264, parse_rational(&source.fps)),
+            ..EncodeOutput::new(output_path)
+        }],
+        &throttle::current(app),
+    );
+
+    let output = run_ffmpeg(app, args).await?;
+    if !output.success() {
+        return Err(format!(
+            "FFmpeg 执行失败: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let output_duration = videos_info
+        .iter()
+        .zip(&trims)
+        .map(|((_, info), trim)| trim.map(|t| t.trimmed_duration(info.duration)).unwrap_or(info.duration))
+        .sum();
+    record_output(app, 1, 1, output_duration, false);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(segments: &[SegmentRange]) -> Vec<(u32, u32)> {
+        segments.iter().map(|s| (s.start_frame, s.end_frame)).collect()
+    }
+
+    #[test]
+    fn split_points_respect_min_frames() {
+        let similarities = [1.0, 0.2, 1.0, 1.0, 0.1, 1.0];
+        let (points, counts) = scene_split_points(&similarities, 0, 0.5, 1, None);
+        assert_eq!(points, vec![0, 2, 5]);
+        assert_eq!(counts.candidates, 2);
+
+        let (points, counts) = scene_split_points(&similarities, 0, 0.5, 4, None);
+        assert_eq!(points, vec![0, 5]);
+        assert_eq!(counts.too_short, 1);
+    }
+
+    #[test]
+    fn hysteresis_waits_for_stable_scene() {
+        let hysteresis = Hysteresis {
+            recovered: 0.9,
+            stable_frames: 2,
+        };
+        let (points, counts) = scene_split_points(&[0.2, 0.95, 0.95, 1.0], 10, 0.5, 1, Some(hysteresis));
+        assert_eq!(points, vec![10, 11]);
+        assert_eq!(counts.unconfirmed, 0);
+
+        let (points, counts) = scene_split_points(&[0.2, 0.95], 10, 0.5, 1, Some(hysteresis));
+        assert_eq!(points, vec![10]);
+        assert_eq!(counts.unconfirmed, 1);
+    }
+
+    #[test]
+    fn long_segments_split_on_frame_starts() {
+        let timestamps = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0];
+        let segments = vec![SegmentRange {
+            start_frame: 0,
+            end_frame: 5,
+        }];
+        assert_eq!(ranges(&split_long_segments(segments, &timestamps, 2.0)), vec![(0, 2), (3, 5)]);
+
+        let invalid = vec![SegmentRange {
+            start_frame: 0,
+            end_frame: 10,
+        }];
+        assert_eq!(ranges(&split_long_segments(invalid, &timestamps, 2.0)), vec![(0, 10)]);
+    }
+
+    fn flash_filter() -> FlashFilter {
+        FlashFilter {
+            median_window: 5,
+            min_persist: 3,
+        }
+    }
+
+    #[test]
+    fn flash_filter_ignores_short_flashes() {
+        let flash = [1.0, 1.0, 0.1, 0.1, 1.0, 1.0, 1.0];
+        assert!(flash_filter().apply(&flash, 0.5).iter().all(|s| *s == 1.0));
+
+        let cut = [1.0, 1.0, 0.1, 1.0, 1.0, 1.0];
+        assert_eq!(flash_filter().apply(&cut, 0.5), cut.to_vec());
+    }
+
+    #[test]
+    fn flash_filter_keeps_gradual_transitions() {
+        let fade = [1.0, 0.2, 0.2, 0.2, 1.0];
+        assert_eq!(flash_filter().apply(&fade, 0.5), fade.to_vec());
+
+        let dissolve = [1.0, 1.0, 0.3, 0.3, 0.3, 0.3, 1.0, 1.0];
+        assert_eq!(flash_filter().apply(&dissolve, 0.5), dissolve.to_vec());
+    }
+
+    #[test]
+    fn flash_filter_defaults_to_median_window() {
+        let filter: FlashFilter = serde_json::from_str(r#"{"min_persist":3}"#).unwrap();
+        assert_eq!(filter, flash_filter());
+        assert!(FlashFilter { median_window: 0, ..filter }.validate().is_err());
+    }
+
+    #[test]
+    fn median_filter_truncates_at_edges() {
+        assert_eq!(median_filter(&[1.0, 5.0, 2.0, 8.0, 3.0], 3), vec![5.0, 2.0, 5.0, 3.0, 8.0]);
+    }
+}
@@ -1,29 +1,399 @@
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::Rng;
+use rand::{Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
+use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Mutex;
 use std::collections::HashMap;
 use tauri::{AppHandle, Emitter, Manager, State};
 use tauri_plugin_shell::ShellExt;
 use walkdir::WalkDir;
+use futures_util::StreamExt;
+use ts_rs::TS;
+use crate::batch::{BatchItemResult, BatchSummary};
+use crate::beat_sync::{align_trims, detect_beats, BeatGrid};
+use crate::channel_layout::ChannelLayout;
+use crate::clip_order::{order_clips, ClipOrder};
+use crate::hw_encoder::{self, EncoderChoice, EncoderSelection, HwEncoder};
+use crate::mezzanine::{self, MezzanineProfile};
+use crate::overlay::{inspect_watermark, OverlayAsset};
+use crate::effects::{ClipEffects, Variation};
+use crate::i18n::{localize_error, t};
+use crate::path_scope::{check_input, check_output};
+use crate::preanalysis;
+use crate::reframe::{analyze_focus, AspectPreset, FocusProfile, PadFill, ReframeMode, SafeArea, TargetResolution};
+use crate::report::{write_report, ProcessingReport, ReportEntry, ReportOutput};
+use crate::settings::{BrandKit, SettingsManager};
+use crate::source_date::{metadata_value, recorded_at, SourceDateOptions};
+use crate::tags::{DrawQuota, TagFilter, TagManager};
+use crate::collections::CollectionManager;
+use crate::transition::{Transition, TransitionStyle};
+use crate::join_check::{verify_joins, JoinIssue};
+use crate::events::{ConcatJobFinished, ConcatProgress, ProbeProgress, CONCAT_JOB_FINISHED, CONCAT_PROGRESS, PROBE_PROGRESS};
+use crate::gop::{self, GopSettings};
+use crate::export_preset::{bitrate_args, loudnorm_filter, DurationPolicy, ExportPreset};
+use crate::video_frame_extractor::parse_rational;
+use crate::stats::{record_operation, record_output};
+use crate::throttle::{run_ffmpeg, run_ffmpeg_with_progress, run_in_job, ThrottleSettings, JOB_CANCELLED};
 
 /// 视频池状态
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VideoPoolState {
     pub all_videos: Vec<PathBuf>,      // 完整视频列表
     pub remaining_videos: Vec<PathBuf>, // 剩余可用视频
+    pub excluded: Vec<ExcludedVideo>,   // 未通过质量筛选的视频
+    /// 每个视频累计被抽取的次数（跨轮次累计）
+    #[serde(default)]
+    pub use_counts: HashMap<String, u32>,
+    /// 黑名单（完整路径或通配符），命中的视频不进入池子
+    #[serde(default)]
+    pub blacklist: Vec<String>,
+}
+
+/// 被筛选排除的视频及原因
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExcludedVideo {
+    pub path: PathBuf,
+    pub reasons: Vec<String>,
+}
+
+/// 视频池概况（池子列表使用）
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolSummary {
+    /// 输入目录（合集为合集的池子标识）
+    pub input_dir: String,
+    pub max_depth: usize,
+    pub total: usize,
+    pub remaining: usize,
+    pub excluded: usize,
+    /// 累计抽取次数
+    pub draws: u32,
+}
+
+/// 视频池详情：本轮已抽过与尚未抽到的片段
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolStatus {
+    pub input_dir: String,
+    pub max_depth: usize,
+    pub used_videos: Vec<PathBuf>,
+    pub remaining_videos: Vec<PathBuf>,
+    pub excluded: Vec<ExcludedVideo>,
+    pub use_counts: HashMap<String, u32>,
+    pub blacklist: Vec<String>,
+}
+
+impl VideoPoolState {
+    fn status(&self, input_dir: &str, max_depth: usize) -> PoolStatus {
+        PoolStatus {
+            input_dir: input_dir.to_string(),
+            max_depth,
+            used_videos: self
+                .all_videos
+                .iter()
+                .filter(|v| !self.remaining_videos.contains(v))
+                .cloned()
+                .collect(),
+            remaining_videos: self.remaining_videos.clone(),
+            excluded: self.excluded.clone(),
+            use_counts: self.use_counts.clone(),
+            blacklist: self.blacklist.clone(),
+        }
+    }
+
+    /// 把命中黑名单的视频移出池子，记入排除列表
+    fn apply_blacklist(&mut self, input_dir: &str) {
+        let blacklist = &self.blacklist;
+        let mut blocked = Vec::new();
+        self.all_videos.retain(|video| match blacklisted_by(blacklist, input_dir, video) {
+            Some(pattern) => {
+                blocked.push(ExcludedVideo {
+                    path: video.clone(),
+                    reasons: vec![format!("{}{}", BLACKLIST_REASON, pattern)],
+                });
+                false
+            }
+            None => true,
+        });
+        let all_videos = &self.all_videos;
+        self.remaining_videos.retain(|video| all_videos.contains(video));
+        self.excluded.extend(blocked);
+    }
+}
+
+/// 排除列表中黑名单原因的前缀（更新黑名单时据此恢复不再命中的视频）
+const BLACKLIST_REASON: &str = "命中黑名单: ";
+
+/// 通配符匹配：`*` 匹配目录内的任意字符，`**/` 匹配任意层目录，`?` 匹配单个字符
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    // matched[j]：已处理的模式能否匹配文本的前 j 个字符
+    let mut matched = vec![false; t.len() + 1];
+    matched[0] = true;
+    let mut i = 0;
+    while i < p.len() {
+        let mut next = vec![false; t.len() + 1];
+        if p[i] == '*' && p.get(i + 1) == Some(&'*') {
+            // `**/` 也可以匹配零层目录
+            let dirs = p.get(i + 2) == Some(&'/');
+            let mut any = false;
+            for j in 0..=t.len() {
+                next[j] = if dirs {
+                    matched[j] || (j > 0 && t[j - 1] == '/' && any)
+                } else {
+                    matched[j] || any
+                };
+                any |= matched[j];
+            }
+            i += if dirs { 3 } else { 2 };
+        } else {
+            for j in 0..=t.len() {
+                next[j] = match p[i] {
+                    '*' => matched[j] || (j > 0 && next[j - 1] && t[j - 1] != '/'),
+                    '?' => j > 0 && matched[j - 1] && t[j - 1] != '/',
+                    c => j > 0 && matched[j - 1] && t[j - 1] == c,
+                };
+            }
+            i += 1;
+        }
+        matched = next;
+    }
+    matched[t.len()]
+}
+
+/// 返回视频命中的黑名单规则
+///
+/// 不含 `/` 的规则匹配文件名，绝对路径规则匹配完整路径，其余按相对输入目录的路径匹配；
+/// 路径分隔符统一为 `/`，Windows 下不区分大小写
+fn blacklisted_by<'a>(blacklist: &'a [String], input_dir: &str, video: &Path) -> Option<&'a String> {
+    let normalize = |s: &str| {
+        let s = s.replace('\\', "/");
+        if cfg!(windows) {
+            s.to_lowercase()
+        } else {
+            s
+        }
+    };
+    let full = normalize(&video.to_string_lossy());
+    let relative = video
+        .strip_prefix(input_dir)
+        .map(|p| normalize(&p.to_string_lossy()))
+        .unwrap_or_else(|_| full.clone());
+    let name = normalize(&video.file_name().unwrap_or_default().to_string_lossy());
+    blacklist.iter().find(|pattern| {
+        let pattern = normalize(pattern);
+        if !pattern.contains('/') {
+            wildcard_match(&pattern, &name)
+        } else if Path::new(&pattern).is_absolute() || pattern.starts_with('/') {
+            wildcard_match(&pattern, &full)
+        } else {
+            wildcard_match(&pattern, &relative)
+        }
+    })
+}
+
+/// 单个输入的流选择（带解说音轨、多机位等素材），序号从 0 开始
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct StreamSelection {
+    /// 输入视频路径
+    pub path: String,
+    /// 使用的视频流序号，为空则自动选择（跳过封面图片）
+    #[serde(default)]
+    pub video_stream: Option<u32>,
+    /// 使用的音轨序号，为空则使用第一个音轨
+    #[serde(default)]
+    pub audio_stream: Option<u32>,
+    /// 丢弃音轨，以静音代替
+    #[serde(default)]
+    pub drop_audio: bool,
+}
+
+/// 视频池质量筛选条件（均为可选）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PoolFilters {
+    pub min_width: Option<u32>,
+    pub min_height: Option<u32>,
+    /// 最低总码率（kbps）
+    pub min_bitrate_kbps: Option<u64>,
+    pub min_duration: Option<f64>,
+    pub max_duration: Option<f64>,
+    #[serde(default)]
+    pub require_audio: bool,
+}
+
+impl PoolFilters {
+    pub fn is_empty(&self) -> bool {
+        self.min_width.is_none()
+            && self.min_height.is_none()
+            && self.min_bitrate_kbps.is_none()
+            && self.min_duration.is_none()
+            && self.max_duration.is_none()
+            && !self.require_audio
+    }
+
+    /// 返回不满足的条件，空列表表示通过
+    pub fn check(&self, info: &VideoInfo) -> Vec<String> {
+        let mut reasons = Vec::new();
+        if let Some(min) = self.min_width {
+            if info.width < min {
+                reasons.push(format!("宽度 {} 小于 {}", info.width, min));
+            }
+        }
+        if let Some(min) = self.min_height {
+            if info.height < min {
+                reasons.push(format!("高度 {} 小于 {}", info.height, min));
+            }
+        }
+        if let Some(min) = self.min_bitrate_kbps {
+            match info.bit_rate {
+                Some(rate) if rate / 1000 < min => {
+                    reasons.push(format!("码率 {}kbps 低于 {}kbps", rate / 1000, min))
+                }
+                None => reasons.push("无法获取码率".to_string()),
+                _ => {}
+            }
+        }
+        if let Some(min) = self.min_duration {
+            if info.duration < min {
+                reasons.push(format!("时长 {:.1}s 短于 {:.1}s", info.duration, min));
+            }
+        }
+        if let Some(max) = self.max_duration {
+            if info.duration > max {
+                reasons.push(format!("时长 {:.1}s 超过 {:.1}s", info.duration, max));
+            }
+        }
+        if self.require_audio && !info.has_audio {
+            reasons.push("没有音轨".to_string());
+        }
+        reasons
+    }
+}
+
+/// 按时长抽取时的目标范围（秒），只计池子片段，不含片头、结尾与插播片段
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TargetDuration {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl TargetDuration {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.min <= 0.0 || self.max < self.min {
+            return Err("目标时长范围不合法".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// 随机抽取时各片段的权重，避免少数很长的素材占满成片
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum DrawWeighting {
+    /// 时长越长越不容易抽到：权重为 1 / 时长^strength
+    InverseDuration {
+        #[serde(default = "default_weight_strength")]
+        strength: f64,
+    },
+    /// 按路径指定权重，未指定的片段权重为 1
+    Custom { weights: HashMap<String, f64> },
+}
+
+fn default_weight_strength() -> f64 {
+    1.0
+}
+
+impl DrawWeighting {
+    pub fn validate(&self) -> Result<(), String> {
+        let valid = match self {
+            Self::InverseDuration { strength } => strength.is_finite() && *strength > 0.0,
+            Self::Custom { weights } => weights.values().all(|w| w.is_finite() && *w > 0.0),
+        };
+        if !valid {
+            return Err("抽取权重必须大于 0".to_string());
+        }
+        Ok(())
+    }
+
+    fn needs_durations(&self) -> bool {
+        matches!(self, Self::InverseDuration { .. })
+    }
+
+    /// 计算池子中每个视频的权重（时长未知的按 1 处理）
+    fn weights(&self, videos: &[PathBuf], durations: &HashMap<PathBuf, f64>) -> HashMap<PathBuf, f64> {
+        videos
+            .iter()
+            .map(|video| {
+                let weight = match self {
+                    Self::InverseDuration { strength } => durations
+                        .get(video)
+                        .filter(|d| **d > 0.0)
+                        .map_or(1.0, |d| 1.0 / d.powf(*strength)),
+                    Self::Custom { weights } => weights.get(video.to_string_lossy().as_ref()).copied().unwrap_or(1.0),
+                };
+                (video.clone(), weight)
+            })
+            .collect()
+    }
 }
 
-/// 全局视频池管理器
+/// 每次从池子抽取多少片段
+pub enum DrawLimit<'a> {
+    /// 固定数量
+    Count(usize),
+    /// 累计时长进入目标范围为止，放不下的片段留在池子里
+    Duration {
+        target: TargetDuration,
+        durations: &'a HashMap<PathBuf, f64>,
+    },
+}
+
+/// 一次抽取的方式
+pub struct DrawOptions<'a> {
+    pub limit: DrawLimit<'a>,
+    /// 标签配额：先为每个配额抽取带该标签的片段
+    pub quotas: &'a [DrawQuota],
+    /// 按权重随机排列剩余视频，权重越大越先被抽到
+    pub weights: Option<&'a HashMap<PathBuf, f64>>,
+    /// 按池子原有顺序依次抽取（有序合集），返回的片段也保持该顺序
+    pub ordered: bool,
+    /// 池子状态相同时按同一种子得到相同的抽取结果
+    pub seed: Option<u64>,
+}
+
+/// 全局视频池管理器，池子状态保存在数据目录的 video_pools.json，
+/// 崩溃或重启后继续按不放回的顺序抽取
 pub struct VideoPoolManager {
+    path: PathBuf,
     pools: Mutex<HashMap<String, VideoPoolState>>,
 }
 
 impl VideoPoolManager {
-    pub fn new() -> Self {
+    pub fn load(path: PathBuf) -> Self {
+        let pools = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
         Self {
-            pools: Mutex::new(HashMap::new()),
+            path,
+            pools: Mutex::new(pools),
+        }
+    }
+
+    /// 保存所有池子（在持有锁时调用），写入失败不影响抽取；
+    /// 先写临时文件再改名，写到一半退出时不会丢掉原有的抽取进度
+    fn save(&self, pools: &HashMap<String, VideoPoolState>) {
+        if let Ok(content) = serde_json::to_string(pools) {
+            if let Some(parent) = self.path.parent() {
+                let _ = fs::create_dir_all(parent);
+            }
+            let temp = self.path.with_extension("tmp");
+            if fs::write(&temp, content).is_ok() {
+                let _ = fs::rename(&temp, &self.path);
+            }
         }
     }
 
@@ -38,34 +408,94 @@ impl VideoPoolManager {
         input_dir: &str,
         max_depth: usize,
         all_videos: Vec<PathBuf>,
+        excluded: Vec<ExcludedVideo>,
     ) -> VideoPoolState {
         let key = Self::make_key(input_dir, max_depth);
         let mut pools = self.pools.lock().unwrap();
 
-        if let Some(pool) = pools.get(&key) {
+        // 先按已保存的黑名单去掉命中的视频，再与现有池子比较
+        let mut scanned = VideoPoolState {
+            all_videos,
+            remaining_videos: Vec::new(),
+            excluded,
+            use_counts: HashMap::new(),
+            blacklist: pools.get(&key).map(|p| p.blacklist.clone()).unwrap_or_default(),
+        };
+        scanned.apply_blacklist(input_dir);
+        let VideoPoolState { all_videos, excluded, blacklist, .. } = scanned;
+
+        if let Some(pool) = pools.get_mut(&key) {
             // 检查池子是否需要刷新（目录内容可能变化）
             if pool.all_videos.len() == all_videos.len() {
-                return pool.clone();
+                pool.excluded = excluded;
+                let pool = pool.clone();
+                self.save(&pools);
+                return pool;
             }
         }
 
-        // 创建新池子
+        // 创建新池子（保留仍在池子中的视频的抽取次数）
+        let mut use_counts = pools.remove(&key).map(|p| p.use_counts).unwrap_or_default();
+        use_counts.retain(|video, _| all_videos.iter().any(|v| v.to_string_lossy() == video.as_str()));
         let pool = VideoPoolState {
             all_videos: all_videos.clone(),
             remaining_videos: all_videos.clone(),
+            excluded,
+            use_counts,
+            blacklist,
         };
 
         pools.insert(key, pool.clone());
+        self.save(&pools);
+        pool
+    }
+
+    /// 向池子追加新视频（池子不存在时创建），新视频同时加入剩余列表
+    pub fn add_videos(
+        &self,
+        input_dir: &str,
+        max_depth: usize,
+        videos: Vec<PathBuf>,
+    ) -> VideoPoolState {
+        let key = Self::make_key(input_dir, max_depth);
+        let mut pools = self.pools.lock().unwrap();
+        let pool = pools.entry(key).or_insert_with(|| VideoPoolState {
+            all_videos: Vec::new(),
+            remaining_videos: Vec::new(),
+            excluded: Vec::new(),
+            use_counts: HashMap::new(),
+            blacklist: Vec::new(),
+        });
+
+        for video in videos {
+            if blacklisted_by(&pool.blacklist, input_dir, &video).is_some() {
+                continue;
+            }
+            if !pool.all_videos.contains(&video) {
+                pool.all_videos.push(video.clone());
+                pool.remaining_videos.push(video);
+            }
+        }
+        pool.all_videos.sort();
+        let pool = pool.clone();
+        self.save(&pools);
         pool
     }
 
     /// 从池子中抽取视频（不放回）
+    ///
+    /// 先为每个标签配额抽取带该标签的片段，剩余视频中不够时从本轮已抽过的片段中补足
     pub fn draw_videos(
         &self,
         input_dir: &str,
         max_depth: usize,
-        count: usize,
+        options: DrawOptions,
     ) -> Result<Vec<PathBuf>, String> {
+        let DrawOptions { limit, quotas, weights, ordered, seed } = options;
+        let count = match limit {
+            DrawLimit::Count(count) => count,
+            DrawLimit::Duration { .. } => usize::MAX,
+        };
         let key = Self::make_key(input_dir, max_depth);
         let mut pools = self.pools.lock().unwrap();
 
@@ -77,28 +507,250 @@ impl VideoPoolManager {
             pool.remaining_videos = pool.all_videos.clone();
         }
 
-        // 随机打乱剩余视频
-        let mut rng = rand::thread_rng();
-        pool.remaining_videos.shuffle(&mut rng);
+        // 随机打乱剩余视频（加权时每个视频的排序键为 u^(1/权重)，按键从大到小排列）
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        if !ordered {
+            match weights {
+                Some(weights) => {
+                    let mut keyed: Vec<(f64, PathBuf)> = pool
+                        .remaining_videos
+                        .drain(..)
+                        .map(|video| {
+                            let weight = weights.get(&video).copied().unwrap_or(1.0);
+                            (rng.gen::<f64>().powf(1.0 / weight), video)
+                        })
+                        .collect();
+                    keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+                    pool.remaining_videos = keyed.into_iter().map(|(_, video)| video).collect();
+                }
+                None => pool.remaining_videos.shuffle(&mut rng),
+            }
+        }
 
-        // 抽取指定数量
-        let actual_count = count.min(pool.remaining_videos.len());
-        let selected: Vec<PathBuf> = pool.remaining_videos
-            .drain(0..actual_count)
-            .collect();
+        let mut selected: Vec<PathBuf> = Vec::new();
+        for quota in quotas {
+            let mut have = selected.iter().filter(|v| quota.candidates.contains(*v)).count();
+            while have < quota.min && selected.len() < count {
+                let pick = match pool.remaining_videos.iter().position(|v| quota.candidates.contains(v)) {
+                    Some(pos) => pool.remaining_videos.remove(pos),
+                    None => {
+                        let mut reused: Vec<&PathBuf> = pool
+                            .all_videos
+                            .iter()
+                            .filter(|v| quota.candidates.contains(*v) && !selected.contains(v))
+                            .collect();
+                        reused.shuffle(&mut rng);
+                        match reused.first() {
+                            Some(video) => (*video).clone(),
+                            None => break,
+                        }
+                    }
+                };
+                selected.push(pick);
+                have += 1;
+            }
+        }
+
+        match limit {
+            DrawLimit::Count(_) => {
+                // 抽取剩余数量
+                let actual_count = count.saturating_sub(selected.len()).min(pool.remaining_videos.len());
+                selected.extend(pool.remaining_videos.drain(0..actual_count));
+            }
+            DrawLimit::Duration { target, durations } => {
+                // 依次抽取，超出上限的片段跳过，累计时长达到下限即停止
+                let duration_of = |video: &PathBuf| durations.get(video).copied().unwrap_or(0.0);
+                let mut total: f64 = selected.iter().map(duration_of).sum();
+                let mut index = 0;
+                while total < target.min && index < pool.remaining_videos.len() {
+                    let duration = duration_of(&pool.remaining_videos[index]);
+                    if total + duration <= target.max {
+                        total += duration;
+                        selected.push(pool.remaining_videos.remove(index));
+                    } else {
+                        index += 1;
+                    }
+                }
+            }
+        }
+        for video in &selected {
+            *pool.use_counts.entry(video.to_string_lossy().to_string()).or_insert(0) += 1;
+        }
+        // 配额片段不固定排在开头
+        if ordered {
+            selected.sort_by_key(|v| pool.all_videos.iter().position(|p| p == v));
+        } else {
+            selected.shuffle(&mut rng);
+        }
 
+        self.save(&pools);
         Ok(selected)
     }
 
+    /// 把抽出但没有用上的视频放回池子，下次优先抽到
+    pub fn return_videos(&self, input_dir: &str, max_depth: usize, videos: &[PathBuf]) {
+        let key = Self::make_key(input_dir, max_depth);
+        let mut pools = self.pools.lock().unwrap();
+        if let Some(pool) = pools.get_mut(&key) {
+            for video in videos.iter().rev() {
+                if pool.all_videos.contains(video) && !pool.remaining_videos.contains(video) {
+                    pool.remaining_videos.insert(0, video.clone());
+                    // 没有用上的不计入抽取次数
+                    if let Some(count) = pool.use_counts.get_mut(video.to_string_lossy().as_ref()) {
+                        *count = count.saturating_sub(1);
+                    }
+                }
+            }
+            self.save(&pools);
+        }
+    }
+
     /// 获取池子剩余视频数量
     pub fn get_remaining_count(&self, input_dir: &str, max_depth: usize) -> usize {
         let key = Self::make_key(input_dir, max_depth);
         let pools = self.pools.lock().unwrap();
         pools.get(&key).map(|p| p.remaining_videos.len()).unwrap_or(0)
     }
+
+    /// 获取池子详情
+    pub fn status(&self, input_dir: &str, max_depth: usize) -> Result<PoolStatus, String> {
+        let key = Self::make_key(input_dir, max_depth);
+        let pools = self.pools.lock().unwrap();
+        pools
+            .get(&key)
+            .map(|pool| pool.status(input_dir, max_depth))
+            .ok_or_else(|| "视频池不存在，请先初始化".to_string())
+    }
+
+    /// 手动重新填充池子：所有视频重新变为可抽取
+    pub fn reset(&self, input_dir: &str, max_depth: usize) -> Result<PoolStatus, String> {
+        let key = Self::make_key(input_dir, max_depth);
+        let mut pools = self.pools.lock().unwrap();
+        let pool = pools.get_mut(&key).ok_or("视频池不存在，请先初始化")?;
+        pool.remaining_videos = pool.all_videos.clone();
+        let status = pool.status(input_dir, max_depth);
+        self.save(&pools);
+        Ok(status)
+    }
+
+    /// 更新池子黑名单：命中的视频移出池子，之前因黑名单排除、现在不再命中的视频重新加入
+    pub fn set_blacklist(&self, input_dir: &str, max_depth: usize, blacklist: Vec<String>) -> Result<PoolStatus, String> {
+        let key = Self::make_key(input_dir, max_depth);
+        let mut pools = self.pools.lock().unwrap();
+        let pool = pools.get_mut(&key).ok_or("视频池不存在，请先初始化")?;
+
+        let mut restored = Vec::new();
+        pool.excluded.retain_mut(|entry| {
+            entry.reasons.retain(|reason| !reason.starts_with(BLACKLIST_REASON));
+            if entry.reasons.is_empty() {
+                restored.push(entry.path.clone());
+                return false;
+            }
+            true
+        });
+        for video in restored {
+            if !pool.all_videos.contains(&video) {
+                pool.all_videos.push(video.clone());
+                pool.remaining_videos.push(video);
+            }
+        }
+        pool.all_videos.sort();
+
+        pool.blacklist = blacklist;
+        pool.apply_blacklist(input_dir);
+        let status = pool.status(input_dir, max_depth);
+        self.save(&pools);
+        Ok(status)
+    }
+
+    /// 列出所有池子，按输入目录排序
+    pub fn list(&self) -> Vec<PoolSummary> {
+        let pools = self.pools.lock().unwrap();
+        let mut summaries: Vec<PoolSummary> = pools
+            .iter()
+            .filter_map(|(key, pool)| {
+                let (input_dir, max_depth) = key.rsplit_once("::")?;
+                Some(PoolSummary {
+                    input_dir: input_dir.to_string(),
+                    max_depth: max_depth.parse().ok()?,
+                    total: pool.all_videos.len(),
+                    remaining: pool.remaining_videos.len(),
+                    excluded: pool.excluded.len(),
+                    draws: pool.use_counts.values().sum(),
+                })
+            })
+            .collect();
+        summaries.sort_by(|a, b| a.input_dir.cmp(&b.input_dir).then(a.max_depth.cmp(&b.max_depth)));
+        summaries
+    }
+}
+
+// 扫描目录并创建视频池（已存在且视频数量未变化时保留抽取进度）
+#[tauri::command]
+pub async fn pool_create(
+    app: AppHandle,
+    pool_manager: State<'_, VideoPoolManager>,
+    input_dir: String,
+    max_depth: usize,
+    pool_filters: Option<PoolFilters>,
+) -> Result<PoolStatus, String> {
+    check_input(&app, &input_dir)?;
+    let videos = collect_videos(&input_dir, max_depth)?;
+    let (videos, excluded) = match pool_filters.filter(|f| !f.is_empty()) {
+        Some(filters) => filter_pool_videos(&app, videos, &filters).await,
+        None => (videos, Vec::new()),
+    };
+    let pool = pool_manager.get_or_create_pool(&input_dir, max_depth, videos, excluded);
+    Ok(pool.status(&input_dir, max_depth))
+}
+
+// 重置视频池，已抽过的片段重新可用
+#[tauri::command]
+pub fn pool_reset(
+    pool_manager: State<'_, VideoPoolManager>,
+    input_dir: String,
+    max_depth: usize,
+) -> Result<PoolStatus, String> {
+    pool_manager.reset(&input_dir, max_depth)
+}
+
+// 查看视频池已抽过与剩余的片段
+#[tauri::command]
+pub fn pool_status(
+    pool_manager: State<'_, VideoPoolManager>,
+    input_dir: String,
+    max_depth: usize,
+) -> Result<PoolStatus, String> {
+    pool_manager.status(&input_dir, max_depth)
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+// 设置视频池黑名单（完整路径或通配符，如已发布的片段），命中的视频不再被抽取
+#[tauri::command]
+pub fn pool_set_blacklist(
+    pool_manager: State<'_, VideoPoolManager>,
+    input_dir: String,
+    max_depth: usize,
+    blacklist: Vec<String>,
+) -> Result<PoolStatus, String> {
+    let mut patterns: Vec<String> = Vec::new();
+    for pattern in blacklist.iter().map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        if !patterns.iter().any(|p| p == pattern) {
+            patterns.push(pattern.to_string());
+        }
+    }
+    pool_manager.set_blacklist(&input_dir, max_depth, patterns)
+}
+
+// 列出所有视频池
+#[tauri::command]
+pub fn pool_list(pool_manager: State<'_, VideoPoolManager>) -> Vec<PoolSummary> {
+    pool_manager.list()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VideoInfo {
     pub codec: String,
     pub width: u32,
@@ -106,6 +758,33 @@ pub struct VideoInfo {
     pub fps: String,
     pub duration: f64,
     pub has_audio: bool,
+    /// 总码率（bit/s）
+    #[serde(default)]
+    pub bit_rate: Option<u64>,
+    /// 主视频流在所有视频流中的序号（跳过封面图片），对应 FFmpeg 的 `v:序号`
+    #[serde(default)]
+    pub video_stream_index: usize,
+    /// 使用的音轨在所有音轨中的序号，对应 FFmpeg 的 `a:序号`
+    #[serde(default)]
+    pub audio_stream_index: usize,
+    /// 音轨声道数（没有音轨或未报告时为 0）
+    #[serde(default)]
+    pub audio_channels: u32,
+    /// 音轨声道布局名（如 stereo、5.1(side)）
+    #[serde(default)]
+    pub audio_layout: Option<String>,
+    /// 容器的 creation_time 标签
+    #[serde(default)]
+    pub creation_time: Option<String>,
+}
+
+impl VideoInfo {
+    /// 音轨的声道布局，没有音轨返回 None
+    pub fn channel_layout(&self) -> Option<ChannelLayout> {
+        self.has_audio.then(|| {
+            ChannelLayout::from_probe(self.audio_channels, self.audio_layout.as_deref().unwrap_or(""))
+        })
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -113,10 +792,12 @@ pub struct CompatibilityResult {
     pub compatible: bool,
     pub message: String,
     pub videos_info: Vec<(String, VideoInfo)>,
+    /// 需要转为立体声的音轨（文件名: 原声道布局）
+    pub channel_conversions: Vec<String>,
 }
 
 /// 收集目录中的 MP4 视频（支持最大递归层数）
-fn collect_videos(dir: &str, max_depth: usize) -> Result<Vec<PathBuf>, String> {
+pub(crate) fn collect_videos(dir: &str, max_depth: usize) -> Result<Vec<PathBuf>, String> {
     let path = Path::new(dir);
     if !path.exists() {
         return Err(format!("目录不存在: {}", dir));
@@ -148,21 +829,86 @@ fn collect_videos(dir: &str, max_depth: usize) -> Result<Vec<PathBuf>, String> {
     Ok(videos)
 }
 
+/// 发送探测进度（第 `index` 个，从 0 开始）
+fn emit_probe_progress(app: &AppHandle, phase: &str, index: usize, total: usize, video: &Path) {
+    let _ = app.emit(
+        PROBE_PROGRESS,
+        ProbeProgress {
+            phase: phase.to_string(),
+            current: index as u32 + 1,
+            total: total as u32,
+            file_name: video.file_name().unwrap_or_default().to_string_lossy().to_string(),
+        },
+    );
+}
+
+/// 发送拼接进度：按当前语言生成消息，并附带消息目录中的 key 与参数
+fn emit_concat_progress(window: &tauri::WebviewWindow, key: &str, params: &[String]) -> Result<(), String> {
+    let progress = ConcatProgress {
+        message: t(window.app_handle(), key, params),
+        message_key: key.to_string(),
+        params: params.to_vec(),
+    };
+    window
+        .emit(CONCAT_PROGRESS, progress)
+        .map_err(|e| format!("发送进度事件失败: {}", e))
+}
+
+/// 探测每个视频并按条件筛选，返回（保留列表，排除列表）
+async fn filter_pool_videos(
+    app: &AppHandle,
+    videos: Vec<PathBuf>,
+    filters: &PoolFilters,
+) -> (Vec<PathBuf>, Vec<ExcludedVideo>) {
+    let mut kept = Vec::new();
+    let mut excluded = Vec::new();
+    let total = videos.len();
+    for (idx, video) in videos.into_iter().enumerate() {
+        emit_probe_progress(app, "pool_filter", idx, total, &video);
+        let reasons = match preanalysis::video_info(app, &video).await {
+            Ok(info) => filters.check(&info),
+            Err(e) => vec![e],
+        };
+        if reasons.is_empty() {
+            kept.push(video);
+        } else {
+            excluded.push(ExcludedVideo {
+                path: video,
+                reasons,
+            });
+        }
+    }
+    (kept, excluded)
+}
+
 /// 使用 FFprobe 检测视频信息
-async fn get_video_info(app: &AppHandle, video_path: &Path) -> Result<VideoInfo, String> {
+pub(crate) async fn get_video_info(app: &AppHandle, video_path: &Path) -> Result<VideoInfo, String> {
+    probe_video_info(app, video_path, None).await
+}
+
+/// 使用 FFprobe 检测视频信息，按流选择指定使用的视频流与音轨
+async fn probe_video_info(
+    app: &AppHandle,
+    video_path: &Path,
+    selection: Option<&StreamSelection>,
+) -> Result<VideoInfo, String> {
     let sidecar = app
         .shell()
         .sidecar("ffprobe")
         .map_err(|e| format!("FFprobe 启动失败: {}", e))?;
 
     let output = sidecar
-        .args(&[
+        .args([
             "-v",
             "error",
             "-show_entries",
-            "stream=codec_type,codec_name,width,height,r_frame_rate,avg_frame_rate",
+            "stream=codec_type,codec_name,width,height,r_frame_rate,avg_frame_rate,channels,channel_layout",
+            "-show_entries",
+            "format=duration,bit_rate",
+            "-show_entries",
+            "stream_disposition=attached_pic",
             "-show_entries",
-            "format=duration",
+            "format_tags=creation_time",
             "-of",
             "json",
             video_path.to_str().unwrap(),
@@ -186,18 +932,44 @@ async fn get_video_info(app: &AppHandle, video_path: &Path) -> Result<VideoInfo,
         .as_array()
         .ok_or("未找到流信息")?;
 
-    let mut video_stream = None;
-    let mut audio_stream = None;
-    for stream in streams {
-        let codec_type = stream["codec_type"].as_str().unwrap_or("");
-        if codec_type == "video" && video_stream.is_none() {
-            video_stream = Some(stream);
-        } else if codec_type == "audio" && audio_stream.is_none() {
-            audio_stream = Some(stream);
+    let of_type = |codec_type: &str| -> Vec<&serde_json::Value> {
+        streams
+            .iter()
+            .filter(|stream| stream["codec_type"].as_str() == Some(codec_type))
+            .collect()
+    };
+    let video_streams = of_type("video");
+    let audio_streams = of_type("audio");
+
+    // 封面图片（attached_pic）也是视频流，默认选择主视频流时跳过；序号按视频流计数，对应 FFmpeg 的 v:序号
+    let video_stream_index = match selection.and_then(|s| s.video_stream) {
+        Some(index) if index as usize >= video_streams.len() => {
+            return Err(format!("没有序号为 {} 的视频流（共 {} 个）", index, video_streams.len()))
         }
-    }
+        Some(index) => index as usize,
+        None => match video_streams
+            .iter()
+            .position(|stream| stream["disposition"]["attached_pic"].as_u64().unwrap_or(0) != 1)
+        {
+            Some(index) => index,
+            None if !video_streams.is_empty() => return Err("只有封面图片，没有视频画面".to_string()),
+            None => return Err("不包含视频流（纯音频文件）".to_string()),
+        },
+    };
+    let stream = video_streams[video_stream_index];
 
-    let stream = video_stream.ok_or("未找到视频流信息")?;
+    let audio_stream_index = selection.and_then(|s| s.audio_stream).unwrap_or(0) as usize;
+    let audio_stream = if selection.is_some_and(|s| s.drop_audio) {
+        None
+    } else if selection.and_then(|s| s.audio_stream).is_some() {
+        Some(*audio_streams.get(audio_stream_index).ok_or(format!(
+            "没有序号为 {} 的音轨（共 {} 个）",
+            audio_stream_index,
+            audio_streams.len()
+        ))?)
+    } else {
+        audio_streams.first().copied()
+    };
     let width = stream["width"]
         .as_u64()
         .ok_or("无法获取宽度")? as u32;
@@ -228,6 +1000,9 @@ async fn get_video_info(app: &AppHandle, video_path: &Path) -> Result<VideoInfo,
                 .and_then(|s| s.parse::<f64>().ok())
         })
         .unwrap_or(0.0);
+    let bit_rate = json["format"]["bit_rate"]
+        .as_str()
+        .and_then(|s| s.parse::<u64>().ok());
 
     Ok(VideoInfo {
         codec,
@@ -236,6 +1011,18 @@ async fn get_video_info(app: &AppHandle, video_path: &Path) -> Result<VideoInfo,
         fps,
         duration,
         has_audio: audio_stream.is_some(),
+        bit_rate,
+        video_stream_index,
+        audio_stream_index,
+        audio_channels: audio_stream
+            .and_then(|a| a["channels"].as_u64())
+            .unwrap_or(0) as u32,
+        audio_layout: audio_stream
+            .and_then(|a| a["channel_layout"].as_str())
+            .map(|l| l.to_string()),
+        creation_time: json["format"]["tags"]["creation_time"]
+            .as_str()
+            .map(|t| t.to_string()),
     })
 }
 
@@ -243,27 +1030,33 @@ async fn get_video_info(app: &AppHandle, video_path: &Path) -> Result<VideoInfo,
 async fn check_video_compatibility(
     app: &AppHandle,
     videos: &[PathBuf],
+    stream_overrides: &[StreamSelection],
 ) -> Result<CompatibilityResult, String> {
     let mut videos_info = Vec::new();
+    // 纯音频、只有封面图片等无法作为视频片段的文件逐个列出原因
+    let mut probe_issues = Vec::new();
 
-    for video in videos {
-        let info = get_video_info(app, video).await?;
-        videos_info.push((
-            video.file_name().unwrap().to_string_lossy().to_string(),
-            info,
-        ));
+    for (idx, video) in videos.iter().enumerate() {
+        emit_probe_progress(app, "compatibility", idx, videos.len(), video);
+        let name = video.file_name().unwrap().to_string_lossy().to_string();
+        let selection = stream_overrides.iter().find(|s| Path::new(&s.path) == video.as_path());
+        match probe_video_info(app, video, selection).await {
+            Ok(info) => videos_info.push((name, info)),
+            Err(e) => probe_issues.push(format!("{}: {}", name, e)),
+        }
     }
 
-    if videos_info.is_empty() {
+    if videos_info.is_empty() && probe_issues.is_empty() {
         return Ok(CompatibilityResult {
             compatible: true,
             message: "没有视频需要检测".to_string(),
             videos_info,
+            channel_conversions: Vec::new(),
         });
     }
 
-    let mut compatible = true;
-    let mut issues = Vec::new();
+    let mut compatible = probe_issues.is_empty();
+    let mut issues = probe_issues;
 
     for (name, info) in &videos_info {
         if info.width == 0 || info.height == 0 {
@@ -276,8 +1069,23 @@ async fn check_video_compatibility(
         }
     }
 
-    let message = if compatible {
+    // 非立体声音轨会在拼接时转为立体声，一并列出
+    let channel_conversions: Vec<String> = videos_info
+        .iter()
+        .filter_map(|(name, info)| {
+            info.channel_layout()
+                .filter(|l| *l != ChannelLayout::Stereo)
+                .map(|l| format!("{}: {}，将转为立体声", name, l.label()))
+        })
+        .collect();
+
+    let message = if compatible && channel_conversions.is_empty() {
         "视频信息解析完成，将统一重编码以保证音画同步".to_string()
+    } else if compatible {
+        format!(
+            "视频信息解析完成，将统一重编码以保证音画同步\n声道转换:\n{}",
+            channel_conversions.join("\n")
+        )
     } else {
         format!("检测到兼容性问题:\n{}", issues.join("\n"))
     };
@@ -286,6 +1094,7 @@ async fn check_video_compatibility(
         compatible,
         message,
         videos_info,
+        channel_conversions,
     })
 }
 
@@ -294,14 +1103,21 @@ pub async fn check_video_compatibility_for_paths(
     app: &AppHandle,
     paths: &[PathBuf],
 ) -> Result<Vec<(String, VideoInfo)>, String> {
-    let mut videos_info = Vec::new();
+    let mut videos_info: Vec<(String, VideoInfo)> = Vec::new();
 
-    for video in paths {
-        let info = get_video_info(app, video).await?;
-        videos_info.push((
-            video.file_name().unwrap().to_string_lossy().to_string(),
-            info,
-        ));
+    for (idx, video) in paths.iter().enumerate() {
+        emit_probe_progress(app, "compatibility", idx, paths.len(), video);
+        // 同一文件多次出现（如按时间裁剪的多个片段）只探测一次
+        if let Some(prev) = paths[..idx].iter().position(|p| p == video) {
+            let probed = videos_info[prev].clone();
+            videos_info.push(probed);
+            continue;
+        }
+        let name = video.file_name().unwrap().to_string_lossy().to_string();
+        let info = get_video_info(app, video)
+            .await
+            .map_err(|e| format!("{}: {}", name, e))?;
+        videos_info.push((name, info));
     }
 
     // 检查兼容性
@@ -317,470 +1133,3137 @@ pub async fn check_video_compatibility_for_paths(
     Ok(videos_info)
 }
 
-pub fn build_concat_filter(
-    videos_info: &[(String, VideoInfo)],
-    target_width: u32,
-    target_height: u32,
-) -> Result<String, String> {
-    let mut parts = Vec::new();
-    for (idx, (_, info)) in videos_info.iter().enumerate() {
-        parts.push(format!(
-            "[{idx}:v]scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2,setsar=1,format=yuv420p,setpts=PTS-STARTPTS[v{idx}]",
-            w = target_width,
-            h = target_height
-        ));
+/// 片段裁剪入点/出点（秒），用于片头、片尾视频去掉多余的开头或结尾
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ClipTrim {
+    /// 入点（秒），为空表示从头开始
+    pub start: Option<f64>,
+    /// 出点（秒），为空表示到视频结尾
+    pub end: Option<f64>,
+}
 
-        if info.has_audio {
-            parts.push(format!(
-                "[{idx}:a]aresample=async=1:first_pts=0,aformat=sample_rates=48000:channel_layouts=stereo,asetpts=PTS-STARTPTS[a{idx}]"
-            ));
-        } else {
-            let duration = if info.duration > 0.0 {
-                info.duration
-            } else {
-                return Err(format!("无法获取第 {} 个视频时长，无法补齐静音音轨", idx + 1));
-            };
-            parts.push(format!(
-                "anullsrc=channel_layout=stereo:sample_rate=48000,atrim=duration={:.6},asetpts=PTS-STARTPTS[a{idx}]",
-                duration
-            ));
+impl ClipTrim {
+    /// 校验裁剪点是否落在视频时长之内
+    pub fn validate(&self, name: &str, duration: f64) -> Result<(), String> {
+        let start = self.start.unwrap_or(0.0);
+        let end = self.end.unwrap_or(duration);
+        if start < 0.0 || end < 0.0 {
+            return Err(format!("{}: 裁剪点不能为负数", name));
+        }
+        if start >= end {
+            return Err(format!("{}: 裁剪入点 {:.3}s 必须早于出点 {:.3}s", name, start, end));
         }
+        if duration > 0.0 && start >= duration {
+            return Err(format!("{}: 裁剪入点 {:.3}s 超出视频时长 {:.3}s", name, start, duration));
+        }
+        Ok(())
     }
 
-    let mut concat_inputs = String::new();
-    for idx in 0..videos_info.len() {
-        concat_inputs.push_str(&format!("[v{idx}][a{idx}]"));
+    /// 裁剪后的实际时长
+    pub fn trimmed_duration(&self, duration: f64) -> f64 {
+        let start = self.start.unwrap_or(0.0).max(0.0);
+        let end = self.end.map(|e| e.min(duration)).unwrap_or(duration);
+        (end - start).max(0.0)
     }
-    parts.push(format!(
-        "{}concat=n={}:v=1:a=1[outv][outa]",
-        concat_inputs,
-        videos_info.len()
-    ));
 
-    Ok(parts.join(";"))
+    /// 生成 trim/atrim 的参数部分
+    fn filter_args(&self) -> String {
+        let mut args = Vec::new();
+        if let Some(start) = self.start {
+            args.push(format!("start={:.6}", start));
+        }
+        if let Some(end) = self.end {
+            args.push(format!("end={:.6}", end));
+        }
+        args.join(":")
+    }
+
+    fn is_noop(&self) -> bool {
+        self.start.is_none() && self.end.is_none()
+    }
 }
 
-/// 主命令：拼接视频（快速模式，使用 -c copy）
-#[tauri::command]
-pub async fn concat_videos(
-    app: AppHandle,
-    pool_manager: State<'_, VideoPoolManager>,  // 新增
-    input_dir: String,
-    ending_video: Option<String>,
-    random_count_min: usize,
-    random_count_max: usize,
-    max_depth: usize,
-    run_times: usize,
-    output_dir: String,
-) -> Result<String, String> {
-    let window = app.get_webview_window("main").unwrap();
+/// 拼接画布：输出尺寸与各输入的画面适配方式
+#[derive(Debug, Clone)]
+pub struct Canvas {
+    pub width: u32,
+    pub height: u32,
+    pub reframe: ReframeMode,
+    /// 每个输入的取景中心（0.0-1.0），仅智能裁剪时使用
+    pub focus: Vec<Option<(f64, f64)>>,
+    /// 补边区域的填充方式
+    pub fill: PadFill,
+    /// 画面四周保留的安全边距
+    pub safe_area: SafeArea,
+}
 
-    // 验证输入
-    if input_dir.is_empty() {
-        return Err("输入目录不能为空".to_string());
+impl Canvas {
+    /// 等比缩放补边的画布（默认行为）
+    pub fn fit(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            reframe: ReframeMode::Fit,
+            focus: Vec::new(),
+            fill: PadFill::Black,
+            safe_area: SafeArea::default(),
+        }
     }
-    if output_dir.is_empty() {
-        return Err("输出目录不能为空".to_string());
-    }
-    if random_count_min == 0 || random_count_max == 0 {
-        return Err("随机数量必须大于 0".to_string());
+
+    /// 内容区域尺寸（扣除安全边距），边距超出画布时不留边距
+    fn content_size(&self) -> (u32, u32) {
+        self.safe_area
+            .content_size(self.width, self.height)
+            .unwrap_or((self.width, self.height))
     }
-    if random_count_min > random_count_max {
-        return Err("随机数量范围不合法".to_string());
+
+    /// 裁剪窗口占输入画面的比例（宽、高）
+    pub fn crop_fraction(&self, info: &VideoInfo) -> (f64, f64) {
+        let (width, height) = self.content_size();
+        if info.width == 0 || info.height == 0 || height == 0 {
+            return (1.0, 1.0);
+        }
+        let canvas_ratio = width as f64 / height as f64;
+        let input_ratio = info.width as f64 / info.height as f64;
+        (
+            (canvas_ratio / input_ratio).min(1.0),
+            (input_ratio / canvas_ratio).min(1.0),
+        )
     }
-    if run_times == 0 {
-        return Err("执行次数必须大于 0".to_string());
+
+    /// 单个输入的缩放/补边/裁剪滤镜
+    ///
+    /// 画面先适配到内容区域，再放到画布的安全区内；模糊填充时拆成前景与背景两路，
+    /// 以带序号与画布后缀的标签区分，最后一段链的输出可以继续接滤镜
+    fn fit_filter(&self, idx: usize, suffix: &str) -> String {
+        let (w, h) = self.content_size();
+        let foreground = match self.reframe {
+            ReframeMode::Fit => format!("scale={w}:{h}:force_original_aspect_ratio=decrease"),
+            ReframeMode::CenterCrop => {
+                format!("scale={w}:{h}:force_original_aspect_ratio=increase,crop={w}:{h}")
+            }
+            ReframeMode::Smart => {
+                let (fx, fy) = self.focus.get(idx).copied().flatten().unwrap_or((0.5, 0.5));
+                format!(
+                    "scale={w}:{h}:force_original_aspect_ratio=increase,crop={w}:{h}:'max(0,min(iw-ow,{fx:.4}*iw-ow/2))':'max(0,min(ih-oh,{fy:.4}*ih-oh/2))'"
+                )
+            }
+        };
+        // 裁剪模式的画面已经铺满内容区域，没有安全边距时无需补边
+        if self.reframe != ReframeMode::Fit && self.safe_area.is_empty() {
+            return foreground;
+        }
+
+        let (cw, ch) = (self.width, self.height);
+        let (left, top) = if self.safe_area.is_empty() {
+            (0, 0)
+        } else {
+            (self.safe_area.left, self.safe_area.top)
+        };
+        match &self.fill {
+            PadFill::Blur => format!(
+                "split[fg{idx}{suffix}][bg{idx}{suffix}];[bg{idx}{suffix}]scale={cw}:{ch}:force_original_aspect_ratio=increase,crop={cw}:{ch},boxblur=20:2[bgb{idx}{suffix}];[fg{idx}{suffix}]{foreground}[fgs{idx}{suffix}];[bgb{idx}{suffix}][fgs{idx}{suffix}]overlay={left}+({w}-w)/2:{top}+({h}-h)/2"
+            ),
+            fill => {
+                let color = match fill {
+                    PadFill::Color(color) => format!(":color={}", color),
+                    _ => String::new(),
+                };
+                let position = if self.safe_area.is_empty() {
+                    "(ow-iw)/2:(oh-ih)/2".to_string()
+                } else {
+                    format!("{left}+({w}-iw)/2:{top}+({h}-ih)/2")
+                };
+                format!("{foreground},pad={cw}:{ch}:{position}{color}")
+            }
+        }
     }
+}
 
-    // 发送进度
-    window
-        .emit("progress", "正在扫描视频文件...")
-        .map_err(|e| format!("发送进度事件失败: {}", e))?;
+/// 片段在成片中的时长（裁剪、变速之后）
+pub fn clip_output_duration(info: &VideoInfo, trim: Option<ClipTrim>, effects: Option<&ClipEffects>) -> f64 {
+    let duration = trim.map(|t| t.trimmed_duration(info.duration)).unwrap_or(info.duration);
+    effects.map(|e| e.output_duration(duration)).unwrap_or(duration)
+}
 
-    // 收集视频列表
-    let all_videos = collect_videos(&input_dir, max_depth)?;
-    let available_count = all_videos.len();
+/// 滤镜链前加逗号，空链返回空字符串
+fn chain_suffix(filters: Vec<String>) -> String {
+    filters.iter().map(|f| format!(",{}", f)).collect()
+}
 
-    if available_count == 0 {
-        return Err(format!("在目录中未找到 MP4 文件: {}", input_dir));
+/// 同一组片段渲染多个画布时第 k 个画布的标签后缀，第一个画布不加后缀
+fn canvas_suffix(k: usize) -> String {
+    if k == 0 {
+        String::new()
+    } else {
+        format!("_{}", k)
     }
+}
 
-    let mut output_paths = Vec::new();
-    let base_timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S").to_string();
+/// 构建拼接滤镜，`trims`、`effects` 与 `videos_info` 一一对应（缺省视为不裁剪、无效果），
+/// `extras` 中的整体效果与变体变换作用于拼接后的整个成片
+///
+/// 每个画布输出一路画面 `[outv{后缀}]`：各输入只解码一次，裁剪后 split 到各画布分别适配；
+/// 声音与画布无关，只输出一路 `[outa]`
+pub fn build_concat_filter(
+    videos_info: &[(String, VideoInfo)],
+    trims: &[Option<ClipTrim>],
+    canvases: &[Canvas],
+    effects: &[Option<ClipEffects>],
+    extras: &ConcatExtras,
+) -> Result<String, String> {
+    // 转场要求各片段帧率一致，统一为第一个片段的帧率
+    let transition = extras.transition.filter(|_| videos_info.len() > 1);
+    let fps = match (&transition, videos_info.first()) {
+        (Some(_), Some((_, info))) if !info.fps.is_empty() && !info.fps.starts_with('0') => format!(",fps={}", info.fps),
+        (Some(_), _) => ",fps=30".to_string(),
+        _ => String::new(),
+    };
+    let mut parts = Vec::new();
+    let mut durations = Vec::with_capacity(videos_info.len());
+    for (idx, (_, info)) in videos_info.iter().enumerate() {
+        let trim = trims
+            .get(idx)
+            .copied()
+            .flatten()
+            .filter(|t| !t.is_noop());
+        let (vtrim, atrim) = match &trim {
+            Some(t) => (
+                format!("trim={},", t.filter_args()),
+                format!("atrim={},", t.filter_args()),
+            ),
+            None => (String::new(), String::new()),
+        };
+        let duration = trim.map(|t| t.trimmed_duration(info.duration)).unwrap_or(info.duration);
+        let clip_effects = effects.get(idx).and_then(|e| e.as_ref());
+        let (veffects, aeffects) = match clip_effects {
+            Some(e) => (
+                chain_suffix(e.video_filters(duration)),
+                chain_suffix(e.audio_filters(duration)),
+            ),
+            None => (String::new(), String::new()),
+        };
+        durations.push(clip_output_duration(info, trim, clip_effects));
+        // 逐片段响度标准化放在片段效果之前，淡入淡出等效果按标准化后的音量处理
+        let normalize = extras
+            .clip_loudness
+            .map(|target| format!(",{}", loudnorm_filter(target)))
+            .unwrap_or_default();
 
-    // 初始化视频池
-    pool_manager.get_or_create_pool(&input_dir, max_depth, all_videos.clone());
+        let video_tail = format!("setsar=1,format=yuv420p,setpts=PTS-STARTPTS{veffects}{fps}");
+        if let [canvas] = canvases {
+            parts.push(format!(
+                "[{idx}:v:{vs}]{vtrim}{fit},{video_tail}[v{idx}]",
+                vs = info.video_stream_index,
+                fit = canvas.fit_filter(idx, "")
+            ));
+        } else {
+            let branches: String = (0..canvases.len()).map(|k| format!("[c{idx}_{k}]")).collect();
+            parts.push(format!(
+                "[{idx}:v:{vs}]{vtrim}split={n}{branches}",
+                vs = info.video_stream_index,
+                n = canvases.len()
+            ));
+            for (k, canvas) in canvases.iter().enumerate() {
+                let suffix = canvas_suffix(k);
+                parts.push(format!(
+                    "[c{idx}_{k}]{fit},{video_tail}[v{idx}{suffix}]",
+                    fit = canvas.fit_filter(idx, &suffix)
+                ));
+            }
+        }
 
-    for run_index in 1..=run_times {
-        let desired_count = if random_count_min == random_count_max {
-            random_count_min
+        if info.has_audio {
+            parts.push(format!(
+                "[{idx}:a:{audio}]{atrim}aresample=async=1:first_pts=0,{downmix}aformat=sample_rates=48000:channel_layouts=stereo,asetpts=PTS-STARTPTS{normalize}{aeffects}[a{idx}]",
+                audio = info.audio_stream_index,
+                downmix = info.channel_layout().map(|l| l.stereo_filter()).unwrap_or_default()
+            ));
         } else {
-            rand::thread_rng().gen_range(random_count_min..=random_count_max)
+            if info.duration <= 0.0 {
+                return Err(format!("无法获取第 {} 个视频时长，无法补齐静音音轨", idx + 1));
+            }
+            parts.push(format!(
+                "anullsrc=channel_layout=stereo:sample_rate=48000,atrim=duration={:.6},asetpts=PTS-STARTPTS{aeffects}[a{idx}]",
+                duration
+            ));
+        }
+    }
+
+    let total_duration = durations.iter().sum::<f64>() - transition.map(|t| t.overlap(durations.len())).unwrap_or(0.0);
+    let whole_effects = extras.effects.as_ref().filter(|e| !e.is_empty());
+    let mut achain = Vec::new();
+    if let Some(e) = whole_effects {
+        achain.extend(e.audio_filters(total_duration));
+    }
+    if let Some(v) = &extras.variation {
+        achain.extend(v.audio_filters());
+    }
+    // 整体效果接在拼接之后，输出标签保持不变
+    let join_a = if achain.is_empty() { "[outa]" } else { "[cata]" };
+    for (k, canvas) in canvases.iter().enumerate() {
+        let suffix = canvas_suffix(k);
+        let mut vchain = Vec::new();
+        if let Some(e) = whole_effects {
+            vchain.extend(e.video_filters(total_duration));
+        }
+        if let Some(v) = &extras.variation {
+            vchain.extend(v.video_filters(canvas.width, canvas.height));
+        }
+        let out_v = format!("[outv{}]", suffix);
+        let join_v = if vchain.is_empty() { out_v.clone() } else { format!("[catv{}]", suffix) };
+        // 声音随第一个画布一起连接，其余画布只连接画面
+        match (transition, k) {
+            (Some(transition), 0) => parts.extend(transition.join_filters(&durations, &join_v, join_a)?),
+            (Some(transition), _) => parts.extend(transition.video_join_filters(&durations, &suffix, &join_v)),
+            (None, 0) => {
+                let concat_inputs: String = (0..videos_info.len()).map(|idx| format!("[v{idx}][a{idx}]")).collect();
+                parts.push(format!("{}concat=n={}:v=1:a=1{}{}", concat_inputs, videos_info.len(), join_v, join_a));
+            }
+            (None, _) => {
+                let concat_inputs: String = (0..videos_info.len()).map(|idx| format!("[v{idx}{suffix}]")).collect();
+                parts.push(format!("{}concat=n={}:v=1:a=0{}", concat_inputs, videos_info.len(), join_v));
+            }
+        }
+        if !vchain.is_empty() {
+            parts.push(format!("{}{}{}", join_v, vchain.join(","), out_v));
+        }
+    }
+    if !achain.is_empty() {
+        parts.push(format!("[cata]{}[outa]", achain.join(",")));
+    }
+
+    Ok(parts.join(";"))
+}
+
+/// 水印位置
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatermarkPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+    Center,
+}
+
+impl WatermarkPosition {
+    /// overlay 滤镜的 x:y 表达式
+    fn overlay_expr(&self, margin: u32) -> String {
+        match self {
+            Self::TopLeft => format!("{m}:{m}", m = margin),
+            Self::TopRight => format!("W-w-{m}:{m}", m = margin),
+            Self::BottomLeft => format!("{m}:H-h-{m}", m = margin),
+            Self::BottomRight => format!("W-w-{m}:H-h-{m}", m = margin),
+            Self::Center => "(W-w)/2:(H-h)/2".to_string(),
+        }
+    }
+}
+
+/// 水印配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatermarkConfig {
+    pub image_path: String,
+    #[serde(default)]
+    pub position: WatermarkPosition,
+    /// 距离画面边缘的像素
+    #[serde(default = "default_watermark_margin")]
+    pub margin: u32,
+    /// 素材颜色已按透明度预乘（premultiplied alpha）
+    #[serde(default)]
+    pub premultiplied: bool,
+    /// 不透明度（0-1）
+    #[serde(default = "default_watermark_opacity")]
+    pub opacity: f64,
+    /// 水印宽度占成片宽度的比例（0-1，等比缩放），为空则保持素材原尺寸
+    #[serde(default)]
+    pub scale: Option<f64>,
+    /// 素材探测结果（运行时填充，不保存）
+    #[serde(skip)]
+    pub asset: Option<OverlayAsset>,
+}
+
+fn default_watermark_margin() -> u32 {
+    20
+}
+
+fn default_watermark_opacity() -> f64 {
+    1.0
+}
+
+impl WatermarkConfig {
+    /// 水印素材的预处理滤镜：转为 rgba 保留透明通道，再按设置调整透明度与尺寸
+    ///
+    /// 按比例缩放时用 scale2ref 以主画面为参照，输出 `[wm{后缀}]` 与主画面 `[wmbase{后缀}]`
+    fn prepare_filter(&self, input: usize, video_label: &str, suffix: &str) -> (String, String) {
+        let mut chain = format!("[{}:v]format=rgba", input);
+        if self.opacity < 1.0 {
+            // 预乘素材的颜色也要随透明度缩放
+            if self.premultiplied {
+                chain.push_str(&format!(
+                    ",colorchannelmixer=rr={o:.3}:gg={o:.3}:bb={o:.3}:aa={o:.3}",
+                    o = self.opacity
+                ));
+            } else {
+                chain.push_str(&format!(",colorchannelmixer=aa={:.3}", self.opacity));
+            }
+        }
+        match self.scale {
+            Some(scale) => (
+                format!(
+                    "{chain}[wmraw{suffix}];[wmraw{suffix}]{video_label}scale2ref=w=main_w*{scale:.4}:h=ow/a[wm{suffix}][wmbase{suffix}]"
+                ),
+                format!("[wmbase{}]", suffix),
+            ),
+            None => (format!("{}[wm{}]", chain, suffix), video_label.to_string()),
+        }
+    }
+}
+
+/// 标题文字：成片开头的一段时间内居中显示
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TitleConfig {
+    pub text: String,
+    /// 字体文件，为空时使用品牌包的标题字体，都没有时使用 FFmpeg 默认字体
+    #[serde(default)]
+    pub font_file: Option<String>,
+    #[serde(default = "default_title_font_size")]
+    pub font_size: u32,
+    /// 显示时长（秒）
+    #[serde(default = "default_title_duration")]
+    pub duration: f64,
+}
+
+fn default_title_font_size() -> u32 {
+    48
+}
+
+fn default_title_duration() -> f64 {
+    3.0
+}
+
+impl TitleConfig {
+    /// 没有单独指定字体时使用品牌包的标题字体
+    pub fn apply_brand_font(&mut self, kit: &BrandKit) {
+        if self.font_file.as_deref().unwrap_or("").is_empty() {
+            self.font_file = kit.title_font.clone();
+        }
+    }
+
+    /// drawtext 滤镜：白字半透明底框，位于画面上方三分之一处
+    fn drawtext_filter(&self) -> String {
+        let font = self
+            .font_file
+            .as_deref()
+            .filter(|f| !f.is_empty())
+            .map(|f| format!("fontfile={}:", escape_drawtext(&f.replace('\\', "/"))))
+            .unwrap_or_default();
+        format!(
+            "drawtext={font}text={text}:expansion=none:fontsize={size}:fontcolor=white:\
+             box=1:boxcolor=black@0.4:boxborderw=16:x=(w-text_w)/2:y=h/3-text_h/2:enable='lt(t,{duration:.3})'",
+            font = font,
+            text = escape_drawtext(&self.text),
+            size = self.font_size,
+            duration = self.duration,
+        )
+    }
+}
+
+/// drawtext 参数值的转义：先按滤镜选项转义，再按滤镜图转义
+fn escape_drawtext(value: &str) -> String {
+    escape_chars(&escape_chars(value, &['\\', '\'', ':']), &['\\', '\'', '[', ']', ',', ';'])
+}
+
+fn escape_chars(value: &str, special: &[char]) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if special.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// 背景音乐配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BgmConfig {
+    pub audio_path: String,
+    /// 背景音乐音量（1.0 为原始音量）
+    #[serde(default = "default_bgm_volume")]
+    pub volume: f64,
+    /// 按背景音乐节拍调整片段出点（卡点剪辑）
+    #[serde(default)]
+    pub beat_sync: bool,
+    /// 用背景音乐替换片段原声，否则与原声混合
+    #[serde(default)]
+    pub replace_original: bool,
+}
+
+fn default_bgm_volume() -> f64 {
+    0.3
+}
+
+/// 插播片段：在池子片段之间插入品牌过场等固定素材
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterstitialConfig {
+    /// 插播视频或目录（目录展开为其中的视频），有多个时每次插入随机选择一个
+    pub videos: Vec<String>,
+    /// 每隔多少个池子片段插入一次，1 表示每两个片段之间都插入
+    #[serde(default = "default_interstitial_every")]
+    pub every: usize,
+    #[serde(default)]
+    pub trim: Option<ClipTrim>,
+}
+
+fn default_interstitial_every() -> usize {
+    1
+}
+
+impl InterstitialConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.every == 0 {
+            return Err("插播间隔必须大于 0".to_string());
+        }
+        Ok(())
+    }
+
+    /// 使用 `pool_count` 个池子片段时的插入次数（最后一个片段之后不插入）
+    fn insert_count(&self, pool_count: usize) -> usize {
+        pool_count.saturating_sub(1) / self.every
+    }
+}
+
+/// 拼接输出的附加素材（水印、背景音乐），作为额外输入追加在视频之后；
+/// 以及标题文字、作用于整个成片的画面效果与变体变换
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConcatExtras {
+    pub watermark: Option<WatermarkConfig>,
+    pub bgm: Option<BgmConfig>,
+    pub effects: Option<ClipEffects>,
+    pub variation: Option<Variation>,
+    /// 片段之间的转场（为空时直接拼接）
+    pub transition: Option<Transition>,
+    /// 响度标准化目标（LUFS）
+    pub loudness_target: Option<f64>,
+    /// 逐片段响度标准化目标（LUFS），拼接前把各来源的片段统一到相同响度
+    #[serde(default)]
+    pub clip_loudness: Option<f64>,
+    #[serde(default)]
+    pub title: Option<TitleConfig>,
+}
+
+impl ConcatExtras {
+    /// 检查素材文件是否存在
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(watermark) = &self.watermark {
+            if !Path::new(&watermark.image_path).exists() {
+                return Err(format!("水印图片不存在: {}", watermark.image_path));
+            }
+            if !(0.0..=1.0).contains(&watermark.opacity) {
+                return Err("水印不透明度必须在 0 到 1 之间".to_string());
+            }
+            if watermark.scale.is_some_and(|s| s <= 0.0 || s > 1.0) {
+                return Err("水印缩放比例必须在 0 到 1 之间".to_string());
+            }
+        }
+        if let Some(bgm) = &self.bgm {
+            if !Path::new(&bgm.audio_path).exists() {
+                return Err(format!("背景音乐不存在: {}", bgm.audio_path));
+            }
+            if bgm.volume < 0.0 {
+                return Err("背景音乐音量不能为负数".to_string());
+            }
+        }
+        if let Some(title) = &self.title {
+            if let Some(font) = title.font_file.as_deref().filter(|f| !f.is_empty()) {
+                if !Path::new(font).exists() {
+                    return Err(format!("标题字体不存在: {}", font));
+                }
+            }
+            if title.font_size == 0 || title.duration <= 0.0 || !title.duration.is_finite() {
+                return Err("标题字号与显示时长必须大于 0".to_string());
+            }
+        }
+        if let Some(effects) = &self.effects {
+            effects.validate()?;
+        }
+        if let Some(transition) = &self.transition {
+            transition.validate()?;
+        }
+        if self.clip_loudness.is_some_and(|l| !(-70.0..=-5.0).contains(&l)) {
+            return Err("片段响度目标必须在 -70 到 -5 LUFS 之间".to_string());
+        }
+        Ok(())
+    }
+
+    /// `clips` 个片段总时长为 `duration` 时，扣除转场重叠、经过整体效果与变体变速后的成片时长
+    pub fn output_duration(&self, duration: f64, clips: usize) -> f64 {
+        let duration = duration - self.transition.map(|t| t.overlap(clips)).unwrap_or(0.0);
+        let duration = self.effects.as_ref().map(|e| e.output_duration(duration)).unwrap_or(duration);
+        self.variation.map(|v| v.output_duration(duration)).unwrap_or(duration)
+    }
+}
+
+/// 视频编码格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum VideoCodec {
+    #[default]
+    H264,
+    Hevc,
+}
+
+impl VideoCodec {
+    pub fn encoder(&self) -> &'static str {
+        match self {
+            Self::H264 => "libx264",
+            Self::Hevc => "libx265",
+        }
+    }
+
+    pub fn default_crf(&self) -> u32 {
+        match self {
+            Self::H264 => 23,
+            Self::Hevc => 28,
+        }
+    }
+}
+
+/// 输出规格：同一组视频可按多个规格输出（如 1080p H.264 + 720p HEVC + 竖屏）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputVariant {
+    /// 输出文件名后缀
+    pub name: String,
+    #[serde(default)]
+    pub aspect: AspectPreset,
+    /// 输出高度（等比缩放），为空则保持画布尺寸
+    #[serde(default)]
+    pub height: Option<u32>,
+    #[serde(default)]
+    pub codec: VideoCodec,
+    #[serde(default)]
+    pub crf: Option<u32>,
+    /// 该规格单独的关键帧设置，为空时使用全局设置
+    #[serde(default)]
+    pub gop: Option<GopSettings>,
+    /// 视频码率上限（kbps）
+    #[serde(default)]
+    pub max_bitrate: Option<u32>,
+    /// 把 moov 移到文件开头
+    #[serde(default)]
+    pub faststart: bool,
+}
+
+impl OutputVariant {
+    /// 仅指定画面比例的规格（对应旧的 aspect_presets 参数）
+    pub fn from_aspect(aspect: AspectPreset) -> Self {
+        Self {
+            name: aspect.suffix().to_string(),
+            aspect,
+            height: None,
+            codec: VideoCodec::default(),
+            crf: None,
+            gop: None,
+            max_bitrate: None,
+            faststart: false,
+        }
+    }
+}
+
+/// 重编码参数，未设置的项使用各处理流程原有的默认值
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct EncodeSettings {
+    /// 恒定质量（0-51），输出规格或导出预设单独指定时以其为准
+    #[serde(default)]
+    pub crf: Option<u32>,
+    /// 编码器预设（如 libx264 的 fast、slow，NVENC 的 p1-p7）
+    #[serde(default)]
+    pub preset: Option<String>,
+    /// 视频平均码率（kbps），设置后按码率编码而不是恒定质量
+    #[serde(default)]
+    pub video_bitrate: Option<u32>,
+    /// 音频码率（kbps），默认 192
+    #[serde(default)]
+    pub audio_bitrate: Option<u32>,
+    /// 输出像素格式（如 yuv420p、yuv420p10le）
+    #[serde(default)]
+    pub pix_fmt: Option<String>,
+}
+
+impl EncodeSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.crf.is_some_and(|crf| crf > 51) {
+            return Err("CRF 必须在 0 到 51 之间".to_string());
+        }
+        if self.video_bitrate == Some(0) || self.audio_bitrate == Some(0) {
+            return Err("码率必须大于 0".to_string());
+        }
+        // 预设与像素格式直接作为 FFmpeg 参数，只允许简单的名称
+        for (label, value) in [("编码预设", &self.preset), ("像素格式", &self.pix_fmt)] {
+            if let Some(value) = value {
+                if value.is_empty() || !value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                    return Err(format!("{}无效: {}", label, value));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 音频码率参数
+    pub fn audio_bitrate_arg(&self) -> String {
+        format!("{}k", self.audio_bitrate.unwrap_or(192))
+    }
+}
+
+/// 单个输出文件的编码参数
+#[derive(Debug, Clone)]
+pub struct EncodeOutput {
+    pub path: PathBuf,
+    /// 输出高度（等比缩放），为空则保持画布尺寸
+    pub height: Option<u32>,
+    pub codec: VideoCodec,
+    pub crf: u32,
+    /// 写入输出文件的 creation_time，为空则由 FFmpeg 决定
+    pub creation_time: Option<String>,
+    /// 关键帧参数（由 GopSettings 按输出帧率生成），为空则使用编码器默认值
+    pub keyframes: Vec<String>,
+    /// 视频码率上限（kbps）
+    pub max_bitrate: Option<u32>,
+    pub faststart: bool,
+    /// 硬件编码器，默认 CPU 编码
+    pub encoder: HwEncoder,
+    /// 编码预设、码率与像素格式（CRF 已计入 `crf`）
+    pub settings: EncodeSettings,
+    /// 使用拼接滤镜中的第几个画布
+    pub canvas: usize,
+}
+
+impl EncodeOutput {
+    /// 默认编码参数（H.264，CRF 23）
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            height: None,
+            codec: VideoCodec::H264,
+            crf: VideoCodec::H264.default_crf(),
+            creation_time: None,
+            keyframes: Vec::new(),
+            max_bitrate: None,
+            faststart: false,
+            encoder: HwEncoder::Software,
+            settings: EncodeSettings::default(),
+            canvas: 0,
+        }
+    }
+
+    /// 套用重编码参数
+    pub fn with_settings(mut self, settings: &EncodeSettings) -> Self {
+        if let Some(crf) = settings.crf {
+            self.crf = crf;
+        }
+        self.settings = settings.clone();
+        self
+    }
+
+    pub fn from_variant(path: PathBuf, variant: &OutputVariant) -> Self {
+        Self {
+            path,
+            height: variant.height,
+            codec: variant.codec,
+            crf: variant.crf.unwrap_or_else(|| variant.codec.default_crf()),
+            creation_time: None,
+            keyframes: Vec::new(),
+            max_bitrate: variant.max_bitrate,
+            faststart: variant.faststart,
+            encoder: HwEncoder::Software,
+            settings: EncodeSettings::default(),
+            canvas: 0,
+        }
+    }
+
+    fn push_args(&self, args: &mut Vec<String>, video_label: &str, audio_label: &str, throttle: &ThrottleSettings) {
+        args.push("-map".to_string());
+        args.push(video_label.to_string());
+        args.push("-map".to_string());
+        args.push(audio_label.to_string());
+        args.push("-vsync".to_string());
+        args.push("vfr".to_string());
+        args.extend(self.encoder.video_args(self.codec, self.crf, &self.settings));
+        args.extend(self.keyframes.iter().cloned());
+        if let Some(kbps) = self.max_bitrate {
+            args.extend(bitrate_args(kbps));
+        }
+        if self.codec == VideoCodec::Hevc {
+            args.push("-tag:v".to_string());
+            args.push("hvc1".to_string());
+        }
+        args.push("-pix_fmt".to_string());
+        args.push(self.settings.pix_fmt.as_deref().unwrap_or(self.encoder.pix_fmt()).to_string());
+        args.push("-c:a".to_string());
+        args.push("aac".to_string());
+        args.push("-b:a".to_string());
+        args.push(self.settings.audio_bitrate_arg());
+        args.push("-fflags".to_string());
+        args.push("+genpts".to_string());
+        args.push("-avoid_negative_ts".to_string());
+        args.push("make_zero".to_string());
+        args.push("-shortest".to_string());
+        if self.faststart {
+            args.push("-movflags".to_string());
+            args.push("+faststart".to_string());
+        }
+        if let Some(time) = &self.creation_time {
+            args.push("-metadata".to_string());
+            args.push(format!("creation_time={}", time));
+        }
+        args.extend(throttle.output_args());
+        args.push(self.path.to_string_lossy().to_string());
+    }
+}
+
+/// 同一文件作为多个片段的输入时合并为一个输入，解码一次后用 split/asplit 分给各片段
+///
+/// `filter` 为 `build_concat_filter` 按片段顺序生成的滤镜；返回去重后的输入与改写后的滤镜。
+/// 同一文件的片段须按时间先后排列，否则 split 要缓存大量尚未用到的帧
+pub fn share_inputs(
+    videos: &[PathBuf],
+    videos_info: &[(String, VideoInfo)],
+    filter: &str,
+) -> (Vec<PathBuf>, String) {
+    let mut inputs: Vec<PathBuf> = Vec::new();
+    let mut owners = Vec::with_capacity(videos.len());
+    for video in videos {
+        let owner = match inputs.iter().position(|p| p == video) {
+            Some(owner) => owner,
+            None => {
+                inputs.push(video.clone());
+                inputs.len() - 1
+            }
         };
+        owners.push(owner);
+    }
+    if inputs.len() == videos.len() {
+        return (inputs, filter.to_string());
+    }
 
-        let actual_count = desired_count.min(available_count);
+    // 先换成占位标签，避免新旧输入序号互相覆盖
+    let mut filter = filter.to_string();
+    for (idx, (_, info)) in videos_info.iter().enumerate() {
+        filter = filter.replace(&format!("[{}:v:{}]", idx, info.video_stream_index), &format!("[in{}v]", idx));
+        if info.has_audio {
+            filter = filter.replace(&format!("[{}:a:{}]", idx, info.audio_stream_index), &format!("[in{}a]", idx));
+        }
+    }
 
-        // 从池子中抽取视频（不放回）
-        let mut videos = pool_manager.draw_videos(&input_dir, max_depth, actual_count)?;
-
-        if desired_count > available_count {
-            window
-                .emit(
-                    "progress",
-                    format!(
-                        "第 {}/{} 次：请求 {} 个视频，但只找到 {} 个，将使用全部 {} 个视频",
-                        run_index, run_times, desired_count, available_count, available_count
-                    ),
-                )
-                .map_err(|e| format!("发送进度事件失败: {}", e))?;
+    let mut parts = Vec::new();
+    for input in 0..inputs.len() {
+        let clips: Vec<usize> = (0..videos.len()).filter(|&idx| owners[idx] == input).collect();
+        let info = &videos_info[clips[0]].1;
+        if let [idx] = clips[..] {
+            filter = filter.replace(&format!("[in{}v]", idx), &format!("[{}:v:{}]", input, info.video_stream_index));
+            filter = filter.replace(&format!("[in{}a]", idx), &format!("[{}:a:{}]", input, info.audio_stream_index));
+            continue;
+        }
+        let labels = |kind: char| clips.iter().map(|idx| format!("[in{}{}]", idx, kind)).collect::<String>();
+        parts.push(format!(
+            "[{}:v:{}]split={}{}",
+            input,
+            info.video_stream_index,
+            clips.len(),
+            labels('v')
+        ));
+        if info.has_audio {
+            parts.push(format!(
+                "[{}:a:{}]asplit={}{}",
+                input,
+                info.audio_stream_index,
+                clips.len(),
+                labels('a')
+            ));
+        }
+    }
+    parts.push(filter);
+    (inputs, parts.join(";"))
+}
+
+/// 生成拼接输出的完整 FFmpeg 参数（输入、滤镜、编码参数）
+///
+/// 多个输出共用同一次解码和滤镜计算（split 分流后分别缩放、编码）；
+/// 每个输出按 `canvas` 使用 `filter` 中对应画布的画面
+pub fn build_concat_command_args(
+    videos: &[PathBuf],
+    filter: String,
+    extras: &ConcatExtras,
+    outputs: &[EncodeOutput],
+    throttle: &ThrottleSettings,
+) -> Vec<String> {
+    let mut args: Vec<String> = Vec::new();
+    for video in videos {
+        args.push("-i".to_string());
+        args.push(video.to_string_lossy().to_string());
+    }
+
+    let mut filter = filter;
+    let canvas_count = outputs.iter().map(|o| o.canvas + 1).max().unwrap_or(1);
+    let mut video_labels: Vec<String> = (0..canvas_count).map(|k| format!("[outv{}]", canvas_suffix(k))).collect();
+    let mut audio_label = "[outa]".to_string();
+    let mut next_input = videos.len();
+
+    if let Some(watermark) = &extras.watermark {
+        if let Some(asset) = &watermark.asset {
+            args.extend(asset.input_args());
+        }
+        args.push("-i".to_string());
+        args.push(watermark.image_path.clone());
+        // 预乘素材按预乘方式混合，循环的动态素材随主画面结束
+        let mut overlay_opts = String::new();
+        if watermark.premultiplied {
+            overlay_opts.push_str(":alpha=premultiplied");
+        }
+        if watermark.asset.as_ref().is_some_and(|a| a.animated) {
+            overlay_opts.push_str(":shortest=1");
+        }
+        for (k, video_label) in video_labels.iter_mut().enumerate() {
+            let suffix = canvas_suffix(k);
+            let (prepare, base_label) = watermark.prepare_filter(next_input, video_label, &suffix);
+            filter.push_str(&format!(
+                ";{prepare};{v}[wm{suffix}]overlay={pos}{opts}[wmv{suffix}]",
+                prepare = prepare,
+                v = base_label,
+                suffix = suffix,
+                pos = watermark.position.overlay_expr(watermark.margin),
+                opts = overlay_opts,
+            ));
+            *video_label = format!("[wmv{}]", suffix);
+        }
+        next_input += 1;
+    }
+
+    // 标题叠加在水印之上
+    if let Some(title) = &extras.title {
+        for (k, video_label) in video_labels.iter_mut().enumerate() {
+            let suffix = canvas_suffix(k);
+            filter.push_str(&format!(";{}{}[ttv{}]", video_label, title.drawtext_filter(), suffix));
+            *video_label = format!("[ttv{}]", suffix);
+        }
+    }
+
+    if let Some(bgm) = &extras.bgm {
+        args.push("-stream_loop".to_string());
+        args.push("-1".to_string());
+        args.push("-i".to_string());
+        args.push(bgm.audio_path.clone());
+        let bgm_chain = format!(
+            "[{idx}:a]aresample=48000,aformat=sample_rates=48000:channel_layouts=stereo,volume={vol:.3}",
+            idx = next_input,
+            vol = bgm.volume,
+        );
+        if bgm.replace_original {
+            // 原声不再使用，接到 anullsink 避免滤镜图有未连接的输出；循环的背景音乐由 -shortest 截到成片长度
+            filter.push_str(&format!(";{a}anullsink;{chain}[bgma]", a = audio_label, chain = bgm_chain));
         } else {
-            // 检查是否触发了池子重填
-            let remaining = pool_manager.get_remaining_count(&input_dir, max_depth);
+            filter.push_str(&format!(
+                ";{chain}[bgm];{a}[bgm]amix=inputs=2:duration=first:dropout_transition=0[bgma]",
+                chain = bgm_chain,
+                a = audio_label,
+            ));
+        }
+        audio_label = "[bgma]".to_string();
+    }
 
-            let msg = if remaining + videos.len() == available_count {
-                format!("第 {}/{} 次：池子已抽完，重新填充。本次选择 {} 个视频", run_index, run_times, videos.len())
-            } else {
-                format!("第 {}/{} 次：已选择 {} 个视频（池子剩余 {}）", run_index, run_times, videos.len(), remaining)
+    // 响度标准化放在混入背景音乐之后，按最终成片的声音计算
+    if let Some(target) = extras.loudness_target {
+        filter.push_str(&format!(";{}{}[lna]", audio_label, loudnorm_filter(target)));
+        audio_label = "[lna]".to_string();
+    }
+
+    // 多个输出时分流：声音分到所有输出，画面按各输出使用的画布分流，每路按需缩放
+    let mut output_labels = Vec::new();
+    if outputs.len() == 1 && outputs[0].height.is_none() {
+        output_labels.push((video_labels[outputs[0].canvas].clone(), audio_label));
+    } else {
+        let n = outputs.len();
+        let split_a: String = (0..n).map(|i| format!("[sa{}]", i)).collect();
+        for (k, video_label) in video_labels.iter().enumerate() {
+            let split_v: String = (0..n)
+                .filter(|i| outputs[*i].canvas == k)
+                .map(|i| format!("[sv{}]", i))
+                .collect();
+            let count = outputs.iter().filter(|o| o.canvas == k).count();
+            filter.push_str(&format!(";{}split={}{}", video_label, count, split_v));
+        }
+        filter.push_str(&format!(";{}asplit={}{}", audio_label, n, split_a));
+        for (i, output) in outputs.iter().enumerate() {
+            let v = match output.height {
+                Some(height) => {
+                    filter.push_str(&format!(";[sv{i}]scale=-2:{height},setsar=1[ov{i}]"));
+                    format!("[ov{}]", i)
+                }
+                None => format!("[sv{}]", i),
             };
+            output_labels.push((v, format!("[sa{}]", i)));
+        }
+    }
+
+    args.push("-filter_complex".to_string());
+    args.push(filter);
+    for (output, (v, a)) in outputs.iter().zip(&output_labels) {
+        output.push_args(&mut args, v, a, throttle);
+    }
+    args
+}
 
-            window.emit("progress", msg)
-                .map_err(|e| format!("发送进度事件失败: {}", e))?;
+/// 拼接任务参数（两个拼接命令共用）
+struct ConcatJob {
+    input_dir: String,
+    intro_video: Option<String>,
+    intro_trim: Option<ClipTrim>,
+    ending_video: Option<String>,
+    ending_trim: Option<ClipTrim>,
+    /// 结尾候选（视频或目录），每次输出选用其中一个；与 `ending_video` 合并
+    ending_videos: Vec<String>,
+    /// 池子片段之间的插播片段
+    interstitial: Option<InterstitialConfig>,
+    extras: ConcatExtras,
+    /// 作用于每个池子片段的画面效果
+    clip_effects: Option<ClipEffects>,
+    /// 变体模式的基础种子，第 N 次输出使用 种子 + N - 1
+    variation_seed: Option<u64>,
+    variants: Vec<OutputVariant>,
+    reframe_mode: ReframeMode,
+    /// 补边填充方式与安全边距
+    pad_fill: PadFill,
+    safe_area: SafeArea,
+    target_resolution: TargetResolution,
+    max_part_duration: Option<f64>,
+    /// 成片时长上限：超出时从末尾去掉池子片段
+    max_total_duration: Option<f64>,
+    pool_filters: Option<PoolFilters>,
+    clip_order: ClipOrder,
+    /// 池子片段预先转成统一规格的中间文件，之后流复制拼接
+    mezzanine: Option<MezzanineProfile>,
+    /// 按输入路径指定使用的视频流与音轨
+    stream_overrides: Vec<StreamSelection>,
+    /// 输出的 creation_time 设为所用片段中最早的拍摄时间
+    keep_creation_time: bool,
+    /// 按片段标签筛选池子并设置每次抽取的配额
+    tag_filter: Option<TagFilter>,
+    /// 使用合集中的片段代替输入目录
+    collection: Option<String>,
+    /// 渲染后检查每个输出接缝处的帧
+    verify_joins: bool,
+    /// 套用的导出预设名称（仅用于报告）
+    export_preset: Option<String>,
+    random_count_min: usize,
+    random_count_max: usize,
+    /// 按时长抽取（代替随机数量）
+    target_duration: Option<TargetDuration>,
+    /// 随机抽取的权重
+    weighting: Option<DrawWeighting>,
+    /// 随机种子：池子状态相同时按同一种子得到相同的抽取、数量与排列
+    seed: Option<u64>,
+    max_depth: usize,
+    run_times: usize,
+    output_dir: String,
+    /// 完成后在输出目录生成 CSV/HTML 处理报告
+    export_report: bool,
+    /// 视频编码器（硬件编码不可用时退回 CPU 编码）
+    encoder: EncoderChoice,
+    encode_settings: EncodeSettings,
+}
+
+impl ConcatJob {
+    /// 套用品牌包：只填充任务中没有单独指定的项
+    fn apply_brand_kit(&mut self, kit: &BrandKit) {
+        if self.intro_video.as_deref().unwrap_or("").is_empty() && kit.intro_video.is_some() {
+            self.intro_video = kit.intro_video.clone();
+            self.intro_trim = kit.intro_trim;
+        }
+        if self.ending_video.as_deref().unwrap_or("").is_empty()
+            && self.ending_videos.is_empty()
+            && kit.outro_video.is_some()
+        {
+            self.ending_video = kit.outro_video.clone();
+            self.ending_trim = kit.outro_trim;
+        }
+        if self.extras.watermark.is_none() {
+            self.extras.watermark = kit.watermark.clone();
+        }
+        if self.extras.bgm.is_none() {
+            self.extras.bgm = kit.bgm.clone();
+        }
+        if let Some(title) = self.extras.title.as_mut() {
+            title.apply_brand_font(kit);
         }
+    }
 
-        // 添加结尾视频
-        if let Some(ending) = &ending_video {
-            if !ending.is_empty() {
-                let ending_path = PathBuf::from(ending);
-                if !ending_path.exists() {
-                    return Err(format!("结尾视频不存在: {}", ending));
+    /// 套用导出预设：输出规格与响度由预设决定；时长上限按预设的方式分段或限制成片时长，
+    /// 分段时单独指定的分段时长优先
+    fn apply_export_preset(&mut self, preset: &ExportPreset) {
+        self.variants = vec![preset.variant()];
+        match preset.duration_policy {
+            DurationPolicy::Split => self.max_part_duration = self.max_part_duration.or(preset.max_duration),
+            DurationPolicy::Limit => self.max_total_duration = preset.max_duration,
+        }
+        self.extras.loudness_target = preset.loudness_target;
+        self.export_preset = Some(preset.name.clone());
+    }
+}
+
+/// 合并比例预设与输出规格；都未指定时输出一个跟随目标分辨率的版本
+fn resolve_variants(
+    aspect_presets: Option<Vec<AspectPreset>>,
+    variants: Option<Vec<OutputVariant>>,
+) -> Vec<OutputVariant> {
+    let mut resolved = variants.unwrap_or_default();
+    for preset in aspect_presets.unwrap_or_default() {
+        if !resolved.iter().any(|v| v.aspect == preset && v.height.is_none()) {
+            resolved.push(OutputVariant::from_aspect(preset));
+        }
+    }
+    if resolved.is_empty() {
+        resolved.push(OutputVariant {
+            name: String::new(),
+            ..OutputVariant::from_aspect(AspectPreset::Source)
+        });
+    }
+    resolved
+}
+
+/// 开启变体模式时确定基础种子：未指定则随机生成（限制在 u32 范围，前端数字不丢精度）
+fn resolve_variation_seed(variation: Option<bool>, seed: Option<u64>) -> Option<u64> {
+    variation
+        .unwrap_or(false)
+        .then(|| seed.unwrap_or_else(|| rand::random::<u32>() as u64))
+}
+
+/// 变体清单中的一项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VariationRecord {
+    outputs: Vec<String>,
+    variation: Variation,
+}
+
+/// 按顺序把片段划分为若干段，每段总时长不超过 `max_duration`（只在片段边界处切分）
+///
+/// 单个片段本身超过上限时单独成段
+fn split_into_parts(durations: &[f64], max_duration: f64) -> Vec<std::ops::Range<usize>> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut total = 0.0;
+    for (idx, &duration) in durations.iter().enumerate() {
+        if idx > start && total + duration > max_duration {
+            parts.push(start..idx);
+            start = idx;
+            total = 0.0;
+        }
+        total += duration;
+    }
+    if start < durations.len() {
+        parts.push(start..durations.len());
+    }
+    parts
+}
+
+/// 一次渲染使用的片段及其裁剪与效果（裁剪、效果可以比片段少，缺少的视为不处理）
+#[derive(Clone, Copy)]
+struct ConcatClips<'a> {
+    videos: &'a [PathBuf],
+    videos_info: &'a [(String, VideoInfo)],
+    trims: &'a [Option<ClipTrim>],
+    effects: &'a [Option<ClipEffects>],
+}
+
+/// 构建滤镜并调用 FFmpeg 渲染一组视频到一个或多个画布的输出（只解码一次）；指定 `progress_label` 时发送编码进度
+async fn render_concat_output(
+    app: &AppHandle,
+    clips: ConcatClips<'_>,
+    canvases: &[Canvas],
+    extras: &ConcatExtras,
+    outputs: &[EncodeOutput],
+    progress_label: Option<String>,
+) -> Result<(), String> {
+    let ConcatClips { videos, videos_info, trims, effects } = clips;
+    let filter = build_concat_filter(videos_info, trims, canvases, effects, extras)?;
+
+    let throttle = crate::throttle::current(app);
+    let args = build_concat_command_args(videos, filter, extras, outputs, &throttle);
+
+    let result = match progress_label {
+        Some(label) => {
+            let clip_durations: f64 = videos_info
+                .iter()
+                .enumerate()
+                .map(|(idx, (_, info))| {
+                    let trim = trims.get(idx).copied().flatten();
+                    clip_output_duration(info, trim, effects.get(idx).and_then(|e| e.as_ref()))
+                })
+                .sum();
+            let duration = extras.output_duration(clip_durations, videos_info.len());
+            run_ffmpeg_with_progress(app, args, "concat", label, duration).await
+        }
+        None => run_ffmpeg(app, args).await,
+    };
+    let output = match result {
+        Ok(output) => output,
+        // 取消时删除写了一半的输出文件
+        Err(e) if e == JOB_CANCELLED => {
+            for output in outputs {
+                let _ = fs::remove_file(&output.path);
+            }
+            return Err(e);
+        }
+        Err(e) => return Err(e),
+    };
+
+    if !output.success() {
+        return Err(format!(
+            "FFmpeg 执行失败: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(())
+}
+
+/// 选片预览中每个片段截取的时长（秒）
+const PREVIEW_CLIP_SECONDS: f64 = 1.0;
+/// 选片预览的输出高度
+const PREVIEW_HEIGHT: u32 = 360;
+/// 选片预览的编码质量（画质够看出顺序即可）
+const PREVIEW_CRF: u32 = 32;
+
+// 选片预览：截取每个选中片段的开头一小段，低分辨率拼成一个预览文件，正式渲染前快速确认顺序和节奏
+#[tauri::command]
+pub async fn render_selection_preview(
+    app: AppHandle,
+    clips: Vec<String>,
+    clip_seconds: Option<f64>,
+    height: Option<u32>,
+) -> Result<String, String> {
+    render_selection_preview_internal(&app, clips, clip_seconds, height)
+        .await
+        .map_err(|e| localize_error(&app, e))
+}
+
+async fn render_selection_preview_internal(
+    app: &AppHandle,
+    clips: Vec<String>,
+    clip_seconds: Option<f64>,
+    height: Option<u32>,
+) -> Result<String, String> {
+    if clips.is_empty() {
+        return Err("没有选中的片段".to_string());
+    }
+    let clip_seconds = clip_seconds.unwrap_or(PREVIEW_CLIP_SECONDS);
+    if clip_seconds <= 0.0 {
+        return Err("预览片段时长必须大于 0".to_string());
+    }
+    let height = height.unwrap_or(PREVIEW_HEIGHT);
+    if height == 0 {
+        return Err("预览高度必须大于 0".to_string());
+    }
+    for clip in &clips {
+        check_input(app, clip)?;
+    }
+
+    let videos: Vec<PathBuf> = clips.iter().map(PathBuf::from).collect();
+    let videos_info = check_video_compatibility_for_paths(app, &videos).await?;
+    let trims: Vec<Option<ClipTrim>> = videos_info
+        .iter()
+        .map(|(_, info)| {
+            Some(ClipTrim {
+                start: None,
+                end: Some(clip_seconds.min(info.duration)),
+            })
+        })
+        .collect();
+
+    // 画布按第一个片段的比例缩小到预览高度（宽高取偶数以满足 yuv420p）
+    let (width, source_height) = videos_info
+        .first()
+        .map(|(_, info)| (info.width, info.height))
+        .ok_or("无法获取目标分辨率")?;
+    let height = (height.min(source_height) / 2 * 2).max(2);
+    let width = ((width as f64 * height as f64 / source_height as f64).round() as u32 / 2 * 2).max(2);
+    let canvas = Canvas::fit(width, height);
+
+    // 只保留最新的预览，文件名带时间戳避免前端播放器缓存旧文件
+    let preview_dir = std::env::temp_dir().join("mp4handler_preview");
+    if preview_dir.exists() {
+        fs::remove_dir_all(&preview_dir).map_err(|e| format!("清理临时目录失败: {}", e))?;
+    }
+    fs::create_dir_all(&preview_dir).map_err(|e| format!("创建临时目录失败: {}", e))?;
+    let output = EncodeOutput {
+        path: preview_dir.join(format!(
+            "selection_preview_{}.mp4",
+            chrono::Local::now().format("%Y%m%d_%H%M%S")
+        )),
+        height: None,
+        codec: VideoCodec::H264,
+        crf: PREVIEW_CRF,
+        creation_time: None,
+        keyframes: Vec::new(),
+        max_bitrate: None,
+        faststart: false,
+        encoder: HwEncoder::Software,
+        settings: EncodeSettings::default(),
+        canvas: 0,
+    };
+
+    render_concat_output(
+        app,
+        ConcatClips {
+            videos: &videos,
+            videos_info: &videos_info,
+            trims: &trims,
+            effects: &[],
+        },
+        std::slice::from_ref(&canvas),
+        &ConcatExtras::default(),
+        std::slice::from_ref(&output),
+        None,
+    )
+    .await?;
+
+    Ok(output.path.to_string_lossy().to_string())
+}
+
+/// 展开结尾、插播等素材：目录展开为其中的视频，结果去重排序
+fn expand_asset_videos<'a>(entries: impl IntoIterator<Item = &'a String>, label: &str) -> Result<Vec<PathBuf>, String> {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for entry in entries.into_iter().filter(|e| !e.is_empty()) {
+        let path = PathBuf::from(entry);
+        if path.is_dir() {
+            let videos = collect_videos(entry, 0)?;
+            if videos.is_empty() {
+                return Err(format!("{}目录中没有视频: {}", label, entry));
+            }
+            paths.extend(videos);
+        } else if path.exists() {
+            paths.push(path);
+        } else {
+            return Err(format!("{}视频不存在: {}", label, entry));
+        }
+    }
+    paths.sort();
+    paths.dedup();
+    Ok(paths)
+}
+
+/// 拼接命令的参数（前端以一个对象传入，除输入输出目录与抽取次数外均可省略）
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ConcatOptions {
+    pub input_dir: String,
+    pub ending_video: Option<String>,
+    pub ending_trim: Option<ClipTrim>,
+    /// 套用的品牌包名称：只填充没有单独指定的项
+    pub brand_kit: Option<String>,
+    pub clip_effects: Option<ClipEffects>,
+    pub output_effects: Option<ClipEffects>,
+    pub variation: Option<bool>,
+    pub variation_seed: Option<u64>,
+    pub aspect_presets: Option<Vec<AspectPreset>>,
+    pub variants: Option<Vec<OutputVariant>>,
+    pub reframe_mode: Option<ReframeMode>,
+    pub pad_fill: Option<PadFill>,
+    pub safe_area: Option<SafeArea>,
+    pub target_resolution: Option<TargetResolution>,
+    pub max_part_duration: Option<f64>,
+    pub pool_filters: Option<PoolFilters>,
+    pub clip_order: Option<ClipOrder>,
+    pub mezzanine: Option<MezzanineProfile>,
+    pub stream_overrides: Option<Vec<StreamSelection>>,
+    pub source_date: Option<SourceDateOptions>,
+    pub tag_filter: Option<TagFilter>,
+    pub collection: Option<String>,
+    pub transition: Option<Transition>,
+    pub verify_joins: Option<bool>,
+    /// 套用的导出预设名称（不能与画面比例或输出规格同时指定）
+    pub export_preset: Option<String>,
+    pub random_count_min: usize,
+    pub random_count_max: usize,
+    pub max_depth: usize,
+    pub run_times: usize,
+    pub output_dir: String,
+    pub export_report: Option<bool>,
+    /// 在后台执行并立即返回任务 ID
+    pub background: Option<bool>,
+    pub encoder: Option<EncoderChoice>,
+    pub encode_settings: Option<EncodeSettings>,
+    pub watermark: Option<WatermarkConfig>,
+    pub bgm: Option<BgmConfig>,
+    /// 成片开头的标题文字
+    pub title: Option<TitleConfig>,
+    pub clip_loudness: Option<f64>,
+    pub intro_video: Option<String>,
+    pub intro_trim: Option<ClipTrim>,
+    pub ending_videos: Option<Vec<String>>,
+    pub interstitial: Option<InterstitialConfig>,
+    pub target_duration: Option<TargetDuration>,
+    pub weighting: Option<DrawWeighting>,
+    pub seed: Option<u64>,
+}
+
+impl ConcatOptions {
+    /// 生成拼接任务：过滤空值、套用品牌包与导出预设
+    fn into_job(self, settings: &SettingsManager) -> Result<ConcatJob, String> {
+        let has_output_specs = self.aspect_presets.as_ref().is_some_and(|p| !p.is_empty())
+            || self.variants.as_ref().is_some_and(|v| !v.is_empty());
+        let mut job = ConcatJob {
+            input_dir: self.input_dir,
+            intro_video: self.intro_video,
+            intro_trim: self.intro_trim,
+            ending_video: self.ending_video,
+            ending_trim: self.ending_trim,
+            ending_videos: self.ending_videos.unwrap_or_default(),
+            interstitial: self.interstitial.filter(|i| !i.videos.is_empty()),
+            extras: ConcatExtras {
+                // 单独指定的水印与背景音乐优先于品牌包
+                watermark: self.watermark.filter(|w| !w.image_path.is_empty()),
+                bgm: self.bgm.filter(|b| !b.audio_path.is_empty()),
+                title: self.title.filter(|t| !t.text.is_empty()),
+                effects: self.output_effects.filter(|e| !e.is_empty()),
+                transition: self.transition,
+                clip_loudness: self.clip_loudness,
+                ..ConcatExtras::default()
+            },
+            clip_effects: self.clip_effects.filter(|e| !e.is_empty()),
+            variation_seed: resolve_variation_seed(self.variation, self.variation_seed),
+            variants: resolve_variants(self.aspect_presets, self.variants),
+            reframe_mode: self.reframe_mode.unwrap_or_default(),
+            pad_fill: self.pad_fill.unwrap_or_default(),
+            safe_area: self.safe_area.unwrap_or_default(),
+            target_resolution: self.target_resolution.unwrap_or_default(),
+            max_part_duration: self.max_part_duration,
+            max_total_duration: None,
+            pool_filters: self.pool_filters,
+            clip_order: self.clip_order.unwrap_or_default(),
+            mezzanine: self.mezzanine,
+            stream_overrides: self.stream_overrides.unwrap_or_default(),
+            keep_creation_time: self.source_date.unwrap_or_default().keep_creation_time,
+            tag_filter: self.tag_filter.filter(|f| !f.is_empty()),
+            collection: self.collection.filter(|n| !n.is_empty()),
+            verify_joins: self.verify_joins.unwrap_or(false),
+            export_preset: None,
+            random_count_min: self.random_count_min,
+            random_count_max: self.random_count_max,
+            target_duration: self.target_duration,
+            weighting: self.weighting,
+            seed: self.seed,
+            max_depth: self.max_depth,
+            run_times: self.run_times,
+            output_dir: self.output_dir,
+            export_report: self.export_report.unwrap_or(false),
+            encoder: self.encoder.unwrap_or_default(),
+            encode_settings: self.encode_settings.unwrap_or_default(),
+        };
+        if let Some(name) = self.brand_kit.filter(|n| !n.is_empty()) {
+            job.apply_brand_kit(&settings.find_brand_kit(&name)?);
+        }
+        if let Some(name) = self.export_preset.filter(|n| !n.is_empty()) {
+            if has_output_specs {
+                return Err("导出预设已包含输出规格，不能同时指定画面比例或输出规格".to_string());
+            }
+            job.apply_export_preset(&settings.find_export_preset(&name)?);
+        }
+        Ok(job)
+    }
+}
+
+/// 按参数执行拼接（命令与自检共用）；指定后台执行时立即返回任务 ID
+pub(crate) async fn run_concat(app: &AppHandle, options: ConcatOptions) -> Result<String, String> {
+    let background = options.background.unwrap_or(false);
+    let job = options.into_job(&app.state::<SettingsManager>())?;
+    if background {
+        return Ok(spawn_concat_job(app, job));
+    }
+    run_concat_command(app, &app.state::<VideoPoolManager>(), job).await
+}
+
+/// 主命令：拼接视频（从池子随机抽取片段，统一重新编码后拼接）
+#[tauri::command]
+pub async fn concat_videos(app: AppHandle, options: ConcatOptions) -> Result<String, String> {
+    run_concat(&app, options).await
+}
+
+/// 备选命令：与 concat_videos 相同，保留给前端“重新编码拼接”按钮使用
+#[tauri::command]
+pub async fn concat_videos_with_reencode(app: AppHandle, options: ConcatOptions) -> Result<String, String> {
+    concat_videos(app, options).await
+}
+
+/// 后台拼接任务 ID 序号（同一秒内启动多个任务时区分）
+static NEXT_CONCAT_JOB: AtomicU64 = AtomicU64::new(1);
+
+/// 在后台执行拼接任务并立即返回任务 ID，可用 `cancel_job` 取消；结束时发送 concat_job_finished 事件
+fn spawn_concat_job(app: &AppHandle, job: ConcatJob) -> String {
+    let job_id = format!(
+        "concat-{}-{}",
+        chrono::Local::now().format("%Y%m%d%H%M%S"),
+        NEXT_CONCAT_JOB.fetch_add(1, Ordering::Relaxed)
+    );
+    let app = app.clone();
+    let id = job_id.clone();
+    tauri::async_runtime::spawn(async move {
+        let pool_manager = app.state::<VideoPoolManager>();
+        let result = run_in_job(&app, &id, None, run_concat_command(&app, &pool_manager, job)).await;
+        let cancelled = result.as_ref().is_err_and(|e| e == &localize_error(&app, JOB_CANCELLED.to_string()));
+        let (result, error) = match result {
+            Ok(message) => (Some(message), None),
+            Err(e) => (None, Some(e)),
+        };
+        let _ = app.emit(CONCAT_JOB_FINISHED, ConcatJobFinished {
+            job_id: id,
+            result,
+            error,
+            cancelled,
+        });
+    });
+    job_id
+}
+
+/// 执行拼接任务：按次数从池子抽取视频、追加结尾并统一重编码输出；记录统计并按需导出处理报告（失败时报告中标记出错的那一次）
+async fn run_concat_command(
+    app: &AppHandle,
+    pool_manager: &VideoPoolManager,
+    job: ConcatJob,
+) -> Result<String, String> {
+    let output_dir = PathBuf::from(&job.output_dir);
+    let export_report = job.export_report;
+    let mut report = ProcessingReport::new("视频拼接报告");
+
+    let mut result = run_concat_job(app, pool_manager, job, &mut report).await;
+    record_operation(app, "concat", &result);
+
+    if export_report {
+        if let Err(e) = &result {
+            if let Some(entry) = report.entries.last_mut().filter(|entry| entry.status == "running") {
+                entry.status = "failed".to_string();
+                entry.error = Some(e.clone());
+            }
+        }
+        let stem = format!("concat_report_{}", chrono::Local::now().format("%Y%m%d_%H%M%S"));
+        let written = write_report(app, &report, &output_dir, &stem).await;
+        match (&mut result, written) {
+            (Ok(message), Ok(paths)) => {
+                message.push_str(&format!("\n处理报告: {}", paths[1].display()))
+            }
+            (Ok(message), Err(e)) => message.push_str(&format!("\n{}", e)),
+            _ => {}
+        }
+    }
+    result.map_err(|e| localize_error(app, e))
+}
+
+/// 一次输出的渲染计划：抽取、检测与对齐都已完成，只剩 FFmpeg 编码
+#[derive(Serialize, Deserialize)]
+struct RunPlan {
+    run_index: usize,
+    videos: Vec<PathBuf>,
+    videos_info: Vec<(String, VideoInfo)>,
+    trims: Vec<Option<ClipTrim>>,
+    effects: Vec<Option<ClipEffects>>,
+    parts: Vec<std::ops::Range<usize>>,
+    extras: ConcatExtras,
+    /// 目标分辨率（按目标分辨率策略计算）
+    target_size: (u32, u32),
+}
+
+/// 各次输出共用的渲染参数
+struct RenderContext<'a> {
+    app: &'a AppHandle,
+    window: &'a tauri::WebviewWindow,
+    run_times: usize,
+    aspect_groups: &'a [(AspectPreset, Vec<&'a OutputVariant>)],
+    /// 只有一个未命名的输出规格时文件名不加后缀
+    single_output: bool,
+    reframe_mode: ReframeMode,
+    pad_fill: &'a PadFill,
+    safe_area: SafeArea,
+    focus_profiles: &'a HashMap<PathBuf, FocusProfile>,
+    output_dir: &'a str,
+    base_timestamp: &'a str,
+    mezzanine: Option<&'a MezzanineProfile>,
+    keep_creation_time: bool,
+    verify_joins: bool,
+    /// 各编码格式实际使用的编码器
+    encoders: EncoderSelection,
+    encode_settings: &'a EncodeSettings,
+}
+
+/// 一次输出的渲染结果
+struct RenderedRun {
+    paths: Vec<PathBuf>,
+    outputs: Vec<ReportOutput>,
+    /// 所有输出文件的总时长
+    duration: f64,
+    /// 接缝检查发现的问题
+    join_issues: Vec<JoinIssue>,
+}
+
+/// 一段输出的 creation_time：所用片段中最早的拍摄时间
+fn part_creation_time(context: &RenderContext<'_>, videos: &[PathBuf], infos: &[(String, VideoInfo)]) -> Option<String> {
+    if !context.keep_creation_time {
+        return None;
+    }
+    videos
+        .iter()
+        .zip(infos)
+        .filter_map(|(video, (_, info))| recorded_at(info, video))
+        .min()
+        .map(metadata_value)
+}
+
+/// 检查一段输出的接缝（未开启检查或只有一个片段时跳过），发现的问题同时发送到进度
+///
+/// `durations` 为各片段在拼接前的时长；有转场时接缝取重叠区间的中点，整体变速按比例换算
+async fn check_part_joins(
+    context: &RenderContext<'_>,
+    run_index: usize,
+    output: &Path,
+    durations: &[f64],
+    extras: &ConcatExtras,
+) -> Result<Vec<JoinIssue>, String> {
+    if !context.verify_joins || durations.len() < 2 {
+        return Ok(Vec::new());
+    }
+    emit_concat_progress(
+        context.window,
+        "concat.checking_joins",
+        &[
+            run_index.to_string(),
+            context.run_times.to_string(),
+            (durations.len() - 1).to_string(),
+        ],
+    )?;
+
+    let overlap = extras.transition.map(|t| t.duration).unwrap_or(0.0);
+    let joined = durations.iter().sum::<f64>() - overlap * (durations.len() - 1) as f64;
+    let scale = if joined > 0.0 {
+        extras.output_duration(durations.iter().sum(), durations.len()) / joined
+    } else {
+        1.0
+    };
+    let mut joins = Vec::with_capacity(durations.len() - 1);
+    let mut start = 0.0;
+    for duration in &durations[..durations.len() - 1] {
+        start += duration - overlap;
+        joins.push((start + overlap / 2.0) * scale);
+    }
+    let check_black = extras.transition.is_none_or(|t| t.style != TransitionStyle::FadeBlack);
+    let issues = verify_joins(context.app, output, &joins, check_black).await?;
+    for issue in &issues {
+        emit_concat_progress(
+            context.window,
+            "concat.run_note",
+            &[run_index.to_string(), context.run_times.to_string(), issue.describe()],
+        )?;
+    }
+    Ok(issues)
+}
+
+/// 按计划渲染一次输出的所有分段与规格
+async fn render_run(context: &RenderContext<'_>, plan: &RunPlan) -> Result<RenderedRun, String> {
+    let RunPlan {
+        run_index,
+        videos,
+        videos_info,
+        trims,
+        effects,
+        parts,
+        extras,
+        target_size: (target_width, target_height),
+    } = plan;
+    let run_times = context.run_times;
+    let output_stem = if run_times == 1 {
+        format!("output_{}", context.base_timestamp)
+    } else {
+        format!("output_{}_{}", context.base_timestamp, run_index)
+    };
+
+    if let Some(profile) = context.mezzanine {
+        match copy_concat_blocker(context, plan, profile) {
+            None => return render_run_with_mezzanine(context, plan, profile, &output_stem).await,
+            Some(reason) => emit_concat_progress(
+                context.window,
+                "concat.fallback_reencode",
+                &[run_index.to_string(), run_times.to_string(), reason.to_string()],
+            )?,
+        }
+    }
+
+    let mut rendered = RenderedRun {
+        paths: Vec::new(),
+        outputs: Vec::new(),
+        duration: 0.0,
+        join_issues: Vec::new(),
+    };
+    for (part_index, part) in parts.iter().enumerate() {
+        let part_videos = &videos[part.clone()];
+        let part_infos = &videos_info[part.clone()];
+        let part_trims = &trims[part.clone()];
+        let part_effects = &effects[part.clone()];
+        let part_stem = if parts.len() > 1 {
+            format!("{}_part{:02}", output_stem, part_index + 1)
+        } else {
+            output_stem.clone()
+        };
+
+        // 同一组视频只解码一次：每个比例一个画布，同比例的规格从该画布分流输出
+        // 关键帧间隔按第一个片段的帧率换算
+        let global_gop = gop::current(context.app);
+        let fps = part_infos.first().and_then(|(_, info)| parse_rational(&info.fps));
+        let creation_time = part_creation_time(context, part_videos, part_infos);
+        let mut canvases = Vec::with_capacity(context.aspect_groups.len());
+        let mut outputs: Vec<EncodeOutput> = Vec::new();
+        for (canvas_index, (preset, group)) in context.aspect_groups.iter().enumerate() {
+            let (canvas_width, canvas_height) = preset.canvas_size(*target_width, *target_height);
+            if context.safe_area.content_size(canvas_width, canvas_height).is_none() {
+                return Err(format!("安全边距超出了 {}x{} 的画布", canvas_width, canvas_height));
+            }
+            let mut canvas = Canvas {
+                width: canvas_width,
+                height: canvas_height,
+                reframe: context.reframe_mode,
+                focus: Vec::new(),
+                fill: context.pad_fill.clone(),
+                safe_area: context.safe_area,
+            };
+            canvas.focus = part_videos
+                .iter()
+                .zip(part_infos)
+                .map(|(video, (_, info))| {
+                    let (wf, hf) = canvas.crop_fraction(info);
+                    context.focus_profiles.get(video).map(|p| p.center(wf, hf))
+                })
+                .collect();
+            canvases.push(canvas);
+
+            outputs.extend(group.iter().map(|variant| {
+                let file_name = if context.single_output {
+                    format!("{}.mp4", part_stem)
+                } else {
+                    format!("{}_{}.mp4", part_stem, variant.name)
+                };
+                EncodeOutput {
+                    creation_time: creation_time.clone(),
+                    keyframes: variant.gop.unwrap_or(global_gop).encoder_args(variant.codec, fps),
+                    encoder: context.encoders.for_codec(variant.codec),
+                    crf: variant
+                        .crf
+                        .or(context.encode_settings.crf)
+                        .unwrap_or_else(|| variant.codec.default_crf()),
+                    settings: context.encode_settings.clone(),
+                    canvas: canvas_index,
+                    ..EncodeOutput::from_variant(PathBuf::from(context.output_dir).join(file_name), variant)
                 }
-                videos.push(ending_path);
-                window
-                    .emit("progress", "已添加结尾视频")
-                    .map_err(|e| format!("发送进度事件失败: {}", e))?;
+            }));
+        }
+
+        // 调用 FFmpeg 拼接（统一重编码）
+        let sizes: Vec<String> = canvases.iter().map(|c| format!("{}x{}", c.width, c.height)).collect();
+        let mut params = vec![run_index.to_string(), run_times.to_string()];
+        let (progress_key, label_key) = if parts.len() > 1 {
+            params.extend([(part_index + 1).to_string(), parts.len().to_string()]);
+            ("concat.encoding_part", "concat.encode_label_part")
+        } else {
+            ("concat.encoding", "concat.encode_label")
+        };
+        let label = t(context.app, label_key, &params);
+        params.push(sizes.join(", "));
+        emit_concat_progress(context.window, progress_key, &params)?;
+
+        render_concat_output(
+            context.app,
+            ConcatClips {
+                videos: part_videos,
+                videos_info: part_infos,
+                trims: part_trims,
+                effects: part_effects,
+            },
+            &canvases,
+            extras,
+            &outputs,
+            Some(label),
+        )
+        .await?;
+
+        let clip_durations: Vec<f64> = part_infos
+            .iter()
+            .zip(part_trims.iter().zip(part_effects))
+            .map(|((_, info), (trim, effects))| clip_output_duration(info, *trim, effects.as_ref()))
+            .collect();
+        // 同一画布的规格共用一次滤镜计算，每个画布检查其中一个即可
+        for canvas_index in 0..canvases.len() {
+            if let Some(output) = outputs.iter().find(|o| o.canvas == canvas_index) {
+                rendered
+                    .join_issues
+                    .extend(check_part_joins(context, *run_index, &output.path, &clip_durations, extras).await?);
             }
         }
+        let part_duration = extras.output_duration(clip_durations.iter().sum(), part_infos.len());
+        rendered.duration += part_duration * outputs.len() as f64;
+        rendered.outputs.extend(outputs.iter().map(|o| ReportOutput {
+            path: o.path.to_string_lossy().to_string(),
+            duration: part_duration,
+        }));
+        rendered.paths.extend(outputs.into_iter().map(|o| o.path));
+    }
+
+    emit_concat_progress(context.window, "concat.run_done", &[run_index.to_string(), run_times.to_string()])?;
+    Ok(rendered)
+}
+
+/// 中间文件只能整段流复制，需要滤镜处理的任务返回原因
+fn copy_concat_blocker(
+    context: &RenderContext<'_>,
+    plan: &RunPlan,
+    profile: &MezzanineProfile,
+) -> Option<&'static str> {
+    if plan.trims.iter().flatten().any(|t| !t.is_noop()) {
+        return Some("片段有裁剪");
+    }
+    if plan.effects.iter().any(|e| e.is_some()) || plan.extras.effects.is_some() || plan.extras.variation.is_some() {
+        return Some("启用了画面效果或变体");
+    }
+    if plan.extras.watermark.is_some() || plan.extras.bgm.is_some() || plan.extras.title.is_some() {
+        return Some("启用了水印、标题或背景音乐");
+    }
+    if plan.extras.transition.is_some() {
+        return Some("启用了转场");
+    }
+    if plan.extras.loudness_target.is_some() || plan.extras.clip_loudness.is_some() {
+        return Some("启用了响度标准化");
+    }
+    if context.reframe_mode != ReframeMode::Fit {
+        return Some("画面适配方式不是等比补边");
+    }
+    if *context.pad_fill != PadFill::Black || !context.safe_area.is_empty() {
+        return Some("设置了补边填充或安全边距");
+    }
+    // 中间文件按自身规格缩放并统一帧率，与重编码拼接的输出不同时不能使用
+    if plan.target_size != (profile.width, profile.height) {
+        return Some("目标分辨率与中间文件规格不一致");
+    }
+    let fps_differs = |fps: &str| parse_rational(fps).is_none_or(|fps| (fps - profile.fps as f64).abs() > 0.01);
+    if plan.videos_info.iter().any(|(_, info)| fps_differs(&info.fps)) {
+        return Some("片段帧率与中间文件规格不一致");
+    }
+    if let Some(reason) = profile.encode_mismatch(context.encode_settings) {
+        return Some(reason);
+    }
+    if context.encoders.for_codec(profile.codec) != HwEncoder::Software {
+        return Some("选择了硬件编码器");
+    }
+    match context.aspect_groups {
+        [(AspectPreset::Source, group)]
+            if group.len() == 1
+                && group[0].height.is_none()
+                && group[0].codec == profile.codec
+                && group[0].crf.is_none_or(|crf| crf == profile.crf())
+                && group[0].gop.is_none()
+                && group[0].max_bitrate.is_none() =>
+        {
+            None
+        }
+        _ => Some("输出规格与中间文件规格不一致"),
+    }
+}
+
+/// 使用中间文件缓存渲染一次输出：片段只在第一次使用时编码，之后直接流复制拼接
+async fn render_run_with_mezzanine(
+    context: &RenderContext<'_>,
+    plan: &RunPlan,
+    profile: &MezzanineProfile,
+    output_stem: &str,
+) -> Result<RenderedRun, String> {
+    let run_index = plan.run_index;
+    let run_times = context.run_times;
+    let mut rendered = RenderedRun {
+        paths: Vec::new(),
+        outputs: Vec::new(),
+        duration: 0.0,
+        join_issues: Vec::new(),
+    };
+    for (part_index, part) in plan.parts.iter().enumerate() {
+        let part_videos = &plan.videos[part.clone()];
+        let part_infos = &plan.videos_info[part.clone()];
+
+        let mut clips = Vec::with_capacity(part_videos.len());
+        for (idx, (video, (name, info))) in part_videos.iter().zip(part_infos).enumerate() {
+            emit_concat_progress(
+                context.window,
+                "concat.preparing_mezzanine",
+                &[
+                    run_index.to_string(),
+                    run_times.to_string(),
+                    (idx + 1).to_string(),
+                    part_videos.len().to_string(),
+                    name.clone(),
+                ],
+            )?;
+            clips.push(mezzanine::ensure_cached(context.app, video, info, profile).await?);
+        }
+
+        let file_name = if plan.parts.len() > 1 {
+            format!("{}_part{:02}", output_stem, part_index + 1)
+        } else {
+            output_stem.to_string()
+        };
+        let file_name = if context.single_output {
+            format!("{}.mp4", file_name)
+        } else {
+            format!("{}_{}.mp4", file_name, context.aspect_groups[0].1[0].name)
+        };
+        let output_path = PathBuf::from(context.output_dir).join(file_name);
+        emit_concat_progress(context.window, "concat.copy_concat", &[run_index.to_string(), run_times.to_string()])?;
+        let creation_time = part_creation_time(context, part_videos, part_infos);
+        mezzanine::concat_copy(context.app, &clips, &output_path, creation_time.as_deref()).await?;
+
+        let clip_durations: Vec<f64> = part_infos.iter().map(|(_, info)| info.duration).collect();
+        rendered
+            .join_issues
+            .extend(check_part_joins(context, run_index, &output_path, &clip_durations, &plan.extras).await?);
+        let part_duration: f64 = clip_durations.iter().sum();
+        rendered.duration += part_duration;
+        rendered.outputs.push(ReportOutput {
+            path: output_path.to_string_lossy().to_string(),
+            duration: part_duration,
+        });
+        rendered.paths.push(output_path);
+    }
+
+    emit_concat_progress(context.window, "concat.run_done", &[run_index.to_string(), run_times.to_string()])?;
+    Ok(rendered)
+}
+
+/// 按画面比例分组：同一比例的多个规格共用一次 FFmpeg 解码
+fn group_by_aspect(variants: &[OutputVariant]) -> Vec<(AspectPreset, Vec<&OutputVariant>)> {
+    let mut aspect_groups: Vec<(AspectPreset, Vec<&OutputVariant>)> = Vec::new();
+    for variant in variants {
+        match aspect_groups.iter_mut().find(|(aspect, _)| *aspect == variant.aspect) {
+            Some((_, group)) => group.push(variant),
+            None => aspect_groups.push((variant.aspect, vec![variant])),
+        }
+    }
+    aspect_groups
+}
+
+/// 拼接批次的渲染计划，编码前保存到数据目录，失败或中断的输出可按原片段组合重试
+#[derive(Serialize, Deserialize)]
+struct BatchPlan {
+    job_id: String,
+    output_dir: String,
+    base_timestamp: String,
+    run_times: usize,
+    variants: Vec<OutputVariant>,
+    reframe_mode: ReframeMode,
+    pad_fill: PadFill,
+    safe_area: SafeArea,
+    mezzanine: Option<MezzanineProfile>,
+    #[serde(default)]
+    keep_creation_time: bool,
+    #[serde(default)]
+    verify_joins: bool,
+    #[serde(default)]
+    encoder: EncoderChoice,
+    #[serde(default)]
+    encode_settings: EncodeSettings,
+    runs: Vec<RunPlan>,
+    /// 已完成的次数（run_index）
+    completed: Vec<usize>,
+}
+
+impl BatchPlan {
+    fn file(app: &AppHandle, job_id: &str) -> Result<PathBuf, String> {
+        Ok(app
+            .path()
+            .app_data_dir()
+            .map_err(|e| format!("获取数据目录失败: {}", e))?
+            .join("concat_plans")
+            .join(format!("{}.json", job_id)))
+    }
+
+    fn load(app: &AppHandle, job_id: &str) -> Result<Self, String> {
+        let path = Self::file(app, job_id)?;
+        if !path.exists() {
+            return Err(format!("未找到拼接任务的计划（可能已全部完成）: {}", job_id));
+        }
+        let content = fs::read_to_string(&path).map_err(|e| format!("读取拼接计划失败: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("解析拼接计划失败: {}", e))
+    }
 
-        // 检测兼容性
-        window
-            .emit(
-                "progress",
-                format!("第 {}/{} 次：正在检测视频兼容性...", run_index, run_times),
-            )
-            .map_err(|e| format!("发送进度事件失败: {}", e))?;
-
-        let compatibility = check_video_compatibility(&app, &videos).await?;
-
-        if !compatibility.compatible {
-            return Err(format!(
-                "INCOMPATIBLE_VIDEOS:第 {} 次生成：\n{}",
-                run_index,
-                compatibility.message.clone()
-            ));
+    /// 保存计划；全部完成后删除
+    fn save(&self, app: &AppHandle) -> Result<(), String> {
+        let path = Self::file(app, &self.job_id)?;
+        if self.runs.iter().all(|run| self.completed.contains(&run.run_index)) {
+            let _ = fs::remove_file(&path);
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("创建数据目录失败: {}", e))?;
         }
+        let content = serde_json::to_string(self).map_err(|e| format!("序列化拼接计划失败: {}", e))?;
+        fs::write(&path, content).map_err(|e| format!("保存拼接计划失败: {}", e))
+    }
+}
 
-        // 生成输出文件名
-        let output_file_name = if run_times == 1 {
-            format!("output_{}.mp4", base_timestamp)
-        } else {
-            format!("output_{}_{}.mp4", base_timestamp, run_index)
-        };
-        let output_path = PathBuf::from(&output_dir).join(output_file_name);
+/// 把一次变体输出追加到输出目录的变体清单
+fn append_variation_record(output_dir: &str, base_timestamp: &str, record: VariationRecord) -> Result<(), String> {
+    let manifest_path = PathBuf::from(output_dir).join(format!("variations_{}.json", base_timestamp));
+    let mut records: Vec<VariationRecord> = fs::read_to_string(&manifest_path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    records.push(record);
+    let manifest = serde_json::to_string_pretty(&records).map_err(|e| format!("序列化变体清单失败: {}", e))?;
+    fs::write(&manifest_path, manifest).map_err(|e| format!("写入变体清单失败: {}", e))
+}
 
-        let (target_width, target_height) = compatibility
-            .videos_info
-            .first()
-            .map(|(_, info)| (info.width, info.height))
-            .ok_or("无法获取目标分辨率")?;
+// 按保存的计划重新编码拼接任务中失败或未完成的输出（片段组合与原来相同）
+#[tauri::command]
+pub async fn retry_failed_runs(app: AppHandle, job_id: String) -> Result<String, String> {
+    let result = retry_failed_runs_internal(&app, &job_id).await;
+    record_operation(&app, "concat_retry", &result);
+    result.map_err(|e| localize_error(&app, e))
+}
 
-        let filter = build_concat_filter(&compatibility.videos_info, target_width, target_height)?;
+async fn retry_failed_runs_internal(app: &AppHandle, job_id: &str) -> Result<String, String> {
+    let window = app.get_webview_window("main").unwrap();
+    let mut batch = BatchPlan::load(app, job_id)?;
+    check_output(app, &batch.output_dir)?;
 
-        // 调用 FFmpeg 拼接（统一重编码）
-        window
-            .emit(
-                "progress",
-                format!("第 {}/{} 次：正在拼接视频（统一重编码以保证同步）...", run_index, run_times),
-            )
-            .map_err(|e| format!("发送进度事件失败: {}", e))?;
-
-        let sidecar = app
-            .shell()
-            .sidecar("ffmpeg")
-            .map_err(|e| format!("FFmpeg 启动失败: {}", e))?;
-
-        let mut args: Vec<String> = Vec::new();
-        for video in &videos {
-            args.push("-i".to_string());
-            args.push(video.to_string_lossy().to_string());
-        }
-        args.push("-filter_complex".to_string());
-        args.push(filter);
-        args.push("-map".to_string());
-        args.push("[outv]".to_string());
-        args.push("-map".to_string());
-        args.push("[outa]".to_string());
-        args.push("-vsync".to_string());
-        args.push("vfr".to_string());
-        args.push("-c:v".to_string());
-        args.push("libx264".to_string());
-        args.push("-preset".to_string());
-        args.push("fast".to_string());
-        args.push("-crf".to_string());
-        args.push("23".to_string());
-        args.push("-pix_fmt".to_string());
-        args.push("yuv420p".to_string());
-        args.push("-c:a".to_string());
-        args.push("aac".to_string());
-        args.push("-b:a".to_string());
-        args.push("192k".to_string());
-        args.push("-fflags".to_string());
-        args.push("+genpts".to_string());
-        args.push("-avoid_negative_ts".to_string());
-        args.push("make_zero".to_string());
-        args.push("-shortest".to_string());
-        args.push(output_path.to_string_lossy().to_string());
+    let pending: Vec<usize> = batch
+        .runs
+        .iter()
+        .map(|run| run.run_index)
+        .filter(|run_index| !batch.completed.contains(run_index))
+        .collect();
+    if pending.is_empty() {
+        batch.save(app)?;
+        return Ok("没有需要重试的输出".to_string());
+    }
 
-        let output = sidecar
-            .args(args)
-            .output()
-            .await
-            .map_err(|e| format!("FFmpeg 执行失败: {}", e))?;
+    // 水印探测结果与智能裁剪的画面分析不保存在计划中，重新获取
+    let mut focus_profiles: HashMap<PathBuf, FocusProfile> = HashMap::new();
+    for run in batch.runs.iter_mut().filter(|run| pending.contains(&run.run_index)) {
+        if let Some(watermark) = run.extras.watermark.as_mut() {
+            inspect_watermark(app, watermark).await?;
+        }
+        if batch.reframe_mode == ReframeMode::Smart {
+            for (video, (_, info)) in run.videos.iter().zip(&run.videos_info) {
+                if !focus_profiles.contains_key(video) {
+                    let profile = analyze_focus(app, video, info.duration).await?;
+                    focus_profiles.insert(video.clone(), profile);
+                }
+            }
+        }
+    }
 
-        if !output.status.success() {
-            return Err(format!(
-                "FFmpeg 执行失败: {}",
-                String::from_utf8_lossy(&output.stderr)
-            ));
+    let aspect_groups = group_by_aspect(&batch.variants);
+    let context = RenderContext {
+        app,
+        window: &window,
+        run_times: batch.run_times,
+        aspect_groups: &aspect_groups,
+        single_output: batch.variants.len() == 1 && batch.variants[0].name.is_empty(),
+        reframe_mode: batch.reframe_mode,
+        pad_fill: &batch.pad_fill,
+        safe_area: batch.safe_area,
+        focus_profiles: &focus_profiles,
+        output_dir: &batch.output_dir,
+        base_timestamp: &batch.base_timestamp,
+        mezzanine: batch.mezzanine.as_ref(),
+        keep_creation_time: batch.keep_creation_time,
+        verify_joins: batch.verify_joins,
+        encoders: hw_encoder::select(app, batch.encoder).await,
+        encode_settings: &batch.encode_settings,
+    };
+
+    let mut completed = Vec::new();
+    let mut output_paths: Vec<PathBuf> = Vec::new();
+    let mut output_duration = 0.0;
+    let mut source_count = 0;
+    let mut first_error: Option<String> = None;
+    for plan in batch.runs.iter().filter(|run| pending.contains(&run.run_index)) {
+        emit_concat_progress(&window, "concat.retrying", &[plan.run_index.to_string(), batch.run_times.to_string()])?;
+        match render_run(&context, plan).await {
+            Ok(rendered) => {
+                if let Some(variation) = plan.extras.variation {
+                    append_variation_record(
+                        &batch.output_dir,
+                        &batch.base_timestamp,
+                        VariationRecord {
+                            outputs: rendered.paths.iter().map(|p| p.to_string_lossy().to_string()).collect(),
+                            variation,
+                        },
+                    )?;
+                }
+                completed.push(plan.run_index);
+                source_count += plan.videos.len();
+                output_duration += rendered.duration;
+                output_paths.extend(rendered.paths);
+            }
+            Err(e) => {
+                if first_error.is_none() {
+                    first_error = Some(format!("第 {} 次: {}", plan.run_index, e));
+                }
+            }
         }
+    }
 
-        output_paths.push(output_path);
+    batch.completed.extend(completed);
+    batch.save(app)?;
+    record_output(app, source_count, output_paths.len(), output_duration, false);
+    if let Some(e) = first_error {
+        return Err(e);
     }
 
-    window
-        .emit("progress", "完成！")
-        .map_err(|e| format!("发送进度事件失败: {}", e))?;
+    emit_concat_progress(&window, "concat.done", &[])?;
+    let list = output_paths
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(format!("重试完成！共生成 {} 个视频：\n{}", output_paths.len(), list))
+}
 
-    if output_paths.len() == 1 {
-        Ok(format!(
-            "视频拼接完成！输出文件: {}",
-            output_paths[0].display()
-        ))
-    } else {
-        let list = output_paths
-            .iter()
-            .map(|p| p.display().to_string())
-            .collect::<Vec<_>>()
-            .join("\n");
-        Ok(format!("视频拼接完成！共生成 {} 个视频：\n{}", output_paths.len(), list))
+/// 结尾的规格说明，以及与成片分辨率不一致时的处理方式
+///
+/// 成片分辨率为指定值，或按默认策略取第一个输入（片头）；其余情况要等抽取池子片段后才确定
+fn bookend_note(info: &VideoInfo, target: Option<(u32, u32)>, reframe_mode: ReframeMode) -> String {
+    let spec = format!("{}x{} {} {}fps", info.width, info.height, info.codec, info.fps);
+    let handling = match reframe_mode {
+        ReframeMode::Fit => "等比缩放后补边",
+        ReframeMode::CenterCrop => "等比放大后居中裁剪",
+        ReframeMode::Smart => "等比放大后按画面重点裁剪",
+    };
+    match target {
+        Some((w, h)) if (w, h) == (info.width, info.height) => format!("{}，与成片分辨率一致", spec),
+        Some((w, h)) => format!("{}，与成片分辨率 {}x{} 不同，将{}", spec, w, h, handling),
+        None => format!("{}，分辨率与成片不同时将{}，并统一重编码", spec, handling),
     }
 }
 
-/// 备选命令：重新编码拼接视频
-#[tauri::command]
-pub async fn concat_videos_with_reencode(
-    app: AppHandle,
-    pool_manager: State<'_, VideoPoolManager>,  // 新增
-    input_dir: String,
-    ending_video: Option<String>,
-    random_count_min: usize,
-    random_count_max: usize,
-    max_depth: usize,
+/// 多次输出时某一次在编码前失败（个别片段无法读取、不兼容等）：只把该次记为失败，其余输出照常生成
+fn fail_planned_run(
+    window: &tauri::WebviewWindow,
+    report: &mut ProcessingReport,
+    run_index: usize,
     run_times: usize,
-    output_dir: String,
+    error: &str,
+) -> Result<(), String> {
+    let error = error.trim_start_matches("INCOMPATIBLE_VIDEOS:");
+    if let Some(entry) = report.entries.last_mut() {
+        entry.status = "failed".to_string();
+        entry.error = Some(error.to_string());
+    }
+    emit_concat_progress(
+        window,
+        "concat.run_failed",
+        &[run_index.to_string(), run_times.to_string(), error.to_string()],
+    )
+}
+
+/// 多次输出的逐次汇总
+fn emit_run_summary(app: &AppHandle, report: &ProcessingReport) -> BatchSummary {
+    let items = report
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let name = format!("第 {} 次", i + 1);
+            match (entry.status.as_str(), &entry.error) {
+                ("completed", _) => BatchItemResult::succeeded(name),
+                (_, Some(error)) => BatchItemResult::failed(name, error.clone()),
+                _ => BatchItemResult::skipped(name, "未生成"),
+            }
+        })
+        .collect();
+    let summary = BatchSummary::new("concat", items);
+    summary.emit(app);
+    summary
+}
+
+async fn run_concat_job(
+    app: &AppHandle,
+    pool_manager: &VideoPoolManager,
+    job: ConcatJob,
+    report: &mut ProcessingReport,
 ) -> Result<String, String> {
     let window = app.get_webview_window("main").unwrap();
+    let ConcatJob {
+        input_dir,
+        intro_video,
+        intro_trim,
+        ending_video,
+        ending_trim,
+        ending_videos,
+        interstitial,
+        mut extras,
+        clip_effects,
+        variation_seed,
+        variants,
+        reframe_mode,
+        pad_fill,
+        safe_area,
+        target_resolution,
+        max_part_duration,
+        max_total_duration,
+        pool_filters,
+        clip_order,
+        mezzanine,
+        stream_overrides,
+        keep_creation_time,
+        tag_filter,
+        collection,
+        verify_joins,
+        export_preset,
+        random_count_min,
+        random_count_max,
+        target_duration,
+        weighting,
+        seed,
+        max_depth,
+        run_times,
+        output_dir,
+        export_report: _,
+        encoder,
+        encode_settings,
+    } = job;
+    encode_settings.validate()?;
+
+    // 拼接来源：合集或输入目录
+    let collection = match collection.as_deref() {
+        Some(name) => Some(app.state::<CollectionManager>().get(name)?),
+        None => None,
+    };
+    // 有序合集按列表顺序抽取，不再重新排列
+    let ordered = collection.as_ref().is_some_and(|c| c.ordered);
+    match &collection {
+        Some(collection) => report.setting(
+            "合集",
+            format!("{}（{} 个片段，{}）", collection.name, collection.clips.len(), if ordered { "有序" } else { "无序" }),
+        ),
+        None => report.setting("输入目录", &input_dir),
+    }
+    report.setting("输出目录", &output_dir);
+    match target_duration {
+        Some(target) => report.setting("目标时长", format!("{:.1}-{:.1}s", target.min, target.max)),
+        None => report.setting("随机数量", format!("{}-{}", random_count_min, random_count_max)),
+    }
+    if let Some(weighting) = &weighting {
+        report.setting("抽取权重", format!("{:?}", weighting));
+    }
+    if let Some(seed) = seed {
+        report.setting("随机种子", seed);
+    }
+    report.setting("执行次数", run_times);
+    report.setting("片头", intro_video.as_deref().unwrap_or("无"));
+    report.setting("结尾", ending_video.as_deref().unwrap_or("无"));
+    if !ending_videos.is_empty() {
+        report.setting("结尾池", ending_videos.join("、"));
+    }
+    if let Some(config) = &interstitial {
+        report.setting("插播片段", format!("每 {} 个片段插入：{}", config.every, config.videos.join("、")));
+    }
+    report.setting("画面适配", format!("{:?}", reframe_mode));
+    report.setting("目标分辨率", format!("{:?}", target_resolution));
+    report.setting("补边填充", format!("{:?}", pad_fill));
+    if !safe_area.is_empty() {
+        report.setting(
+            "安全边距",
+            format!("上 {} 右 {} 下 {} 左 {}", safe_area.top, safe_area.right, safe_area.bottom, safe_area.left),
+        );
+    }
+    if ordered {
+        report.setting("片段顺序", "按合集顺序");
+    } else {
+        report.setting("片段顺序", format!("{:?}", clip_order));
+    }
+    if let Some(transition) = &extras.transition {
+        report.setting(
+            "转场",
+            format!(
+                "{:?} 画面 {}s 声音 {}s",
+                transition.style,
+                transition.duration,
+                transition.audio_duration()
+            ),
+        );
+    }
+    if let Some(name) = &export_preset {
+        report.setting("导出预设", name);
+    }
+    if let Some(max) = max_total_duration {
+        report.setting("时长上限", format!("{}s（超出时减少片段）", max));
+    }
+    if let Some(target) = extras.loudness_target {
+        report.setting("响度标准化", format!("{} LUFS", target));
+    }
+    if let Some(target) = extras.clip_loudness {
+        report.setting("片段响度标准化", format!("{} LUFS", target));
+    }
+    if keep_creation_time {
+        report.setting("拍摄时间", "输出沿用最早片段的拍摄时间");
+    }
+    if verify_joins {
+        report.setting("接缝检查", "检查每处接缝的重复帧、黑帧与丢帧");
+    }
+    if let Some(filter) = &tag_filter {
+        let quotas: Vec<String> = filter.quotas.iter().map(|q| format!("{} ≥ {}", q.tag, q.min)).collect();
+        report.setting(
+            "标签筛选",
+            format!(
+                "包含 [{}] 排除 [{}] 配额 [{}]",
+                filter.include.join(", "),
+                filter.exclude.join(", "),
+                quotas.join(", ")
+            ),
+        );
+    }
+    for selection in &stream_overrides {
+        let video = selection.video_stream.map(|i| format!("视频流 {}", i)).unwrap_or_else(|| "默认视频流".to_string());
+        let audio = match (selection.drop_audio, selection.audio_stream) {
+            (true, _) => "丢弃音轨".to_string(),
+            (false, Some(i)) => format!("音轨 {}", i),
+            (false, None) => "默认音轨".to_string(),
+        };
+        report.setting("流选择", format!("{} {} {}", selection.path, video, audio));
+    }
+    if let Some(profile) = &mezzanine {
+        report.setting(
+            "中间文件",
+            format!("{}x{} {}fps {}", profile.width, profile.height, profile.fps, profile.codec.encoder()),
+        );
+    }
+    if let Some(seed) = variation_seed {
+        report.setting("变体种子", seed);
+    }
+    if let Some(max) = max_part_duration {
+        report.setting("分段最大时长", format!("{}s", max));
+    }
+    for variant in &variants {
+        let height = variant.height.map(|h| format!("{}p", h)).unwrap_or_else(|| "原始高度".to_string());
+        report.setting(
+            "输出规格",
+            format!("{} {} {} {}", variant.name, variant.aspect.suffix(), height, variant.codec.encoder()),
+        );
+    }
 
     // 验证输入
-    if input_dir.is_empty() {
+    if input_dir.is_empty() && collection.is_none() {
         return Err("输入目录不能为空".to_string());
     }
     if output_dir.is_empty() {
         return Err("输出目录不能为空".to_string());
     }
-    if random_count_min == 0 || random_count_max == 0 {
-        return Err("随机数量必须大于 0".to_string());
+    if let Some(weighting) = &weighting {
+        weighting.validate()?;
     }
-    if random_count_min > random_count_max {
-        return Err("随机数量范围不合法".to_string());
+    match target_duration {
+        Some(target) => target.validate()?,
+        None => {
+            if random_count_min == 0 || random_count_max == 0 {
+                return Err("随机数量必须大于 0".to_string());
+            }
+            if random_count_min > random_count_max {
+                return Err("随机数量范围不合法".to_string());
+            }
+            if let Some(filter) = &tag_filter {
+                filter.validate(random_count_min)?;
+            }
+        }
     }
     if run_times == 0 {
         return Err("执行次数必须大于 0".to_string());
     }
+    let intro_path = match intro_video.as_deref() {
+        Some(intro) if !intro.is_empty() => {
+            let path = PathBuf::from(intro);
+            if !path.exists() {
+                return Err(format!("片头视频不存在: {}", intro));
+            }
+            Some(path)
+        }
+        _ => None,
+    };
+    extras.validate()?;
+    if let Some(config) = &interstitial {
+        config.validate()?;
+    }
+    if let Some(profile) = &mezzanine {
+        profile.validate()?;
+    }
+    target_resolution.validate()?;
+    pad_fill.validate()?;
+    if let Some(effects) = &clip_effects {
+        effects.validate()?;
+    }
+
+    // 所有读写路径都必须在用户授权的范围内
+    match &collection {
+        Some(collection) => {
+            for clip in &collection.clips {
+                check_input(app, clip)?;
+            }
+        }
+        None => {
+            check_input(app, &input_dir)?;
+        }
+    }
+    check_output(app, &output_dir)?;
+    let assets = [
+        intro_video.as_deref(),
+        ending_video.as_deref(),
+        extras.watermark.as_ref().map(|w| w.image_path.as_str()),
+        extras.bgm.as_ref().map(|b| b.audio_path.as_str()),
+        extras.title.as_ref().and_then(|t| t.font_file.as_deref()),
+        extras.effects.as_ref().and_then(|e| e.lut_path.as_deref()),
+        clip_effects.as_ref().and_then(|e| e.lut_path.as_deref()),
+    ];
+    let asset_lists = ending_videos.iter().chain(interstitial.iter().flat_map(|c| c.videos.iter()));
+    for asset in assets.into_iter().flatten().chain(asset_lists.map(String::as_str)) {
+        if !asset.is_empty() {
+            check_input(app, asset)?;
+        }
+    }
+
+    // 水印素材的透明通道决定解码与叠加方式
+    if let Some(watermark) = extras.watermark.as_mut() {
+        let asset = inspect_watermark(app, watermark).await?;
+        if !asset.has_alpha {
+            let params = [asset.codec.clone(), asset.pix_fmt.clone()];
+            emit_concat_progress(&window, "overlay.no_alpha", &params)?;
+            report.setting("水印", t(app, "overlay.no_alpha", &params));
+        }
+    }
+
+    // 片头、结尾每次都会用到，先检测，避免抽取和探测池子后才发现无法使用
+    // 结尾候选：单独指定的结尾视频加上结尾池（目录展开为其中的视频）
+    let ending_paths = expand_asset_videos(ending_video.iter().chain(&ending_videos), "结尾")?;
+    let bookends: Vec<(&str, &PathBuf, Option<ClipTrim>)> = intro_path
+        .iter()
+        .map(|path| ("片头", path, intro_trim))
+        .chain(ending_paths.iter().map(|path| ("结尾", path, ending_trim)))
+        .collect();
+    let mut intro_size = None;
+    for (label, path, trim) in bookends {
+        let is_intro = label == "片头";
+        let probing = if is_intro { "concat.probing_intro" } else { "concat.probing_ending" };
+        emit_concat_progress(&window, probing, &[])?;
+        let selection = stream_overrides.iter().find(|s| Path::new(&s.path) == path.as_path());
+        let info = probe_video_info(app, path, selection)
+            .await
+            .map_err(|e| format!("{}视频无法使用: {}", label, e))?;
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        if let Some(trim) = trim {
+            trim.validate(&name, info.duration)?;
+        }
+        let note = match (label, target_resolution) {
+            ("片头", TargetResolution::First) => {
+                intro_size = Some((info.width, info.height));
+                format!("{}x{} {} {}fps，成片分辨率以片头为准", info.width, info.height, info.codec, info.fps)
+            }
+            (_, TargetResolution::Explicit { width, height }) => {
+                bookend_note(&info, Some((width, height)), reframe_mode)
+            }
+            _ => bookend_note(&info, intro_size, reframe_mode),
+        };
+        let note = if label == "结尾" && ending_paths.len() > 1 {
+            format!("{} {}", name, note)
+        } else {
+            note
+        };
+        let noted = if is_intro { "concat.intro_note" } else { "concat.ending_note" };
+        emit_concat_progress(&window, noted, std::slice::from_ref(&note))?;
+        report.setting(&format!("{}检测", label), note);
+    }
+
+    // 插播片段同样先检测，每次插入时从中随机选择
+    let mut interstitial_clips: Vec<(PathBuf, String, VideoInfo)> = Vec::new();
+    if let Some(config) = &interstitial {
+        emit_concat_progress(&window, "concat.probing_interstitials", &[])?;
+        for path in expand_asset_videos(&config.videos, "插播")? {
+            let name = path.file_name().unwrap().to_string_lossy().to_string();
+            let selection = stream_overrides.iter().find(|s| Path::new(&s.path) == path.as_path());
+            let info = probe_video_info(app, &path, selection)
+                .await
+                .map_err(|e| format!("插播视频无法使用: {}: {}", name, e))?;
+            if let Some(trim) = config.trim {
+                trim.validate(&name, info.duration)?;
+            }
+            interstitial_clips.push((path, name, info));
+        }
+        if interstitial_clips.is_empty() {
+            return Err("没有可用的插播视频".to_string());
+        }
+    }
+
+    if max_part_duration.is_some_and(|max| max <= 0.0) {
+        return Err("分段最大时长必须大于 0".to_string());
+    }
+    if max_total_duration.is_some_and(|max| max <= 0.0) {
+        return Err("成片时长上限必须大于 0".to_string());
+    }
+    if variants.iter().any(|v| v.height == Some(0)) {
+        return Err("输出规格的高度必须大于 0".to_string());
+    }
+    for gop in variants.iter().filter_map(|v| v.gop.as_ref()) {
+        gop.validate()?;
+    }
+    if variants.len() > 1 {
+        let mut names: Vec<&str> = variants.iter().map(|v| v.name.as_str()).collect();
+        names.sort();
+        names.dedup();
+        if names.len() != variants.len() || names.contains(&"") {
+            return Err("多个输出规格的名称不能为空且不能重复".to_string());
+        }
+    }
+    let aspect_groups = group_by_aspect(&variants);
+    let mut focus_profiles: HashMap<PathBuf, FocusProfile> = HashMap::new();
+    // 卡点剪辑的节拍位置（每个任务只检测一次）
+    let mut beat_grid: Option<BeatGrid> = None;
 
     // 发送进度
-    window
-        .emit("progress", "正在扫描视频文件...")
-        .map_err(|e| format!("发送进度事件失败: {}", e))?;
+    emit_concat_progress(&window, "concat.scanning", &[])?;
+
+    // 收集视频列表（合集中已不存在的片段记为排除）
+    let (all_videos, missing) = match &collection {
+        Some(collection) => {
+            let (videos, missing): (Vec<PathBuf>, Vec<PathBuf>) =
+                collection.clips.iter().map(PathBuf::from).partition(|p| p.is_file());
+            if videos.is_empty() {
+                return Err(format!("合集中没有可用的片段: {}", collection.name));
+            }
+            let missing = missing
+                .into_iter()
+                .map(|path| ExcludedVideo {
+                    path,
+                    reasons: vec!["文件不存在".to_string()],
+                })
+                .collect();
+            (videos, missing)
+        }
+        None => (collect_videos(&input_dir, max_depth)?, Vec::new()),
+    };
 
-    // 收集视频列表
-    let all_videos = collect_videos(&input_dir, max_depth)?;
+    // 按质量条件筛选池子视频
+    let (all_videos, mut excluded) = match pool_filters.filter(|f| !f.is_empty()) {
+        Some(filters) => {
+            emit_concat_progress(&window, "concat.filtering", &[])?;
+            filter_pool_videos(app, all_videos, &filters).await
+        }
+        None => (all_videos, Vec::new()),
+    };
+    excluded.extend(missing);
+    // 按标签筛选，并计算各配额在池子中的候选片段
+    let tag_manager = app.state::<TagManager>();
+    let (all_videos, excluded, quotas) = match &tag_filter {
+        Some(filter) => {
+            let (kept, mut tag_excluded) = filter.apply(&tag_manager, all_videos);
+            tag_excluded.extend(excluded);
+            let quotas = filter.draw_quotas(&tag_manager, &kept);
+            for quota in &quotas {
+                if quota.candidates.len() < quota.min {
+                    emit_concat_progress(
+                        &window,
+                        "concat.tag_quota_short",
+                        &[
+                            quota.candidates.len().to_string(),
+                            quota.tag.clone(),
+                            quota.min.to_string(),
+                        ],
+                    )?;
+                }
+            }
+            (kept, tag_excluded, quotas)
+        }
+        None => (all_videos, excluded, Vec::new()),
+    };
     let available_count = all_videos.len();
 
+    if !excluded.is_empty() {
+        emit_concat_progress(&window, "concat.excluded", &[excluded.len().to_string()])?;
+    }
     if available_count == 0 {
-        return Err(format!("在目录中未找到 MP4 文件: {}", input_dir));
+        return Err(match &collection {
+            Some(collection) => format!("合集中没有符合条件的片段: {}", collection.name),
+            None => format!("在目录中未找到符合条件的 MP4 文件: {}", input_dir),
+        });
     }
 
-    let mut output_paths = Vec::new();
+    let mut plans: Vec<RunPlan> = Vec::new();
+    // 编码前失败的第一次输出的错误（所有输出都失败时返回）
+    let mut plan_error: Option<String> = None;
+    let mut source_count = 0;
     let base_timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S").to_string();
 
-    // 初始化视频池
-    pool_manager.get_or_create_pool(&input_dir, max_depth, all_videos.clone());
+    // 初始化视频池（合集使用独立的池子，与递归深度无关）
+    let (pool_key, pool_depth) = match &collection {
+        Some(collection) => (collection.pool_key(), 0),
+        None => (input_dir.clone(), max_depth),
+    };
+    pool_manager.get_or_create_pool(&pool_key, pool_depth, all_videos.clone(), excluded);
+
+    // 按时长抽取与按时长加权需要每个池子片段的输出时长（含片段效果的变速）
+    let mut clip_durations: HashMap<PathBuf, f64> = HashMap::new();
+    if target_duration.is_some() || weighting.as_ref().is_some_and(|w| w.needs_durations()) {
+        for (idx, video) in all_videos.iter().enumerate() {
+            emit_probe_progress(app, "duration", idx, all_videos.len(), video);
+            let info = preanalysis::video_info(app, video).await?;
+            clip_durations.insert(video.clone(), clip_output_duration(&info, None, clip_effects.as_ref()));
+        }
+    }
 
+    let draw_weights = weighting.as_ref().map(|w| w.weights(&all_videos, &clip_durations));
+
+    // 结尾池打乱后轮流使用，各次输出尽量不用同一个结尾
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let mut ending_order = ending_paths.clone();
+    ending_order.shuffle(&mut rng);
+
+    // 先按顺序完成每次输出的抽取、检测与对齐（池子抽取有先后依赖），再并行编码
     for run_index in 1..=run_times {
-        let desired_count = if random_count_min == random_count_max {
+        let desired_count = if target_duration.is_some() {
+            available_count
+        } else if random_count_min == random_count_max {
             random_count_min
         } else {
-            rand::thread_rng().gen_range(random_count_min..=random_count_max)
+            rng.gen_range(random_count_min..=random_count_max)
         };
 
         let actual_count = desired_count.min(available_count);
 
         // 从池子中抽取视频（不放回）
-        let mut videos = pool_manager.draw_videos(&input_dir, max_depth, actual_count)?;
-
-        if desired_count > available_count {
-            window
-                .emit(
-                    "progress",
-                    format!(
-                        "第 {}/{} 次：请求 {} 个视频，但只找到 {} 个，将使用全部 {} 个视频",
-                        run_index, run_times, desired_count, available_count, available_count
-                    ),
-                )
-                .map_err(|e| format!("发送进度事件失败: {}", e))?;
+        let limit = match target_duration {
+            Some(target) => DrawLimit::Duration {
+                target,
+                durations: &clip_durations,
+            },
+            None => DrawLimit::Count(actual_count),
+        };
+        // 第 N 次抽取使用 种子 + N - 1
+        let draw_seed = seed.map(|seed| seed.wrapping_add(run_index as u64 - 1));
+        let mut videos = pool_manager.draw_videos(
+            &pool_key,
+            pool_depth,
+            DrawOptions {
+                limit,
+                quotas: &quotas,
+                weights: draw_weights.as_ref(),
+                ordered,
+                seed: draw_seed,
+            },
+        )?;
+        source_count += videos.len();
+
+        if let Some(target) = target_duration {
+            let total: f64 = videos.iter().filter_map(|v| clip_durations.get(v)).sum();
+            if videos.is_empty() {
+                return Err(format!(
+                    "第 {} 次：池子中没有能放进目标时长 {:.1}-{:.1}s 的片段",
+                    run_index, target.min, target.max
+                ));
+            }
+            let mut params = vec![
+                run_index.to_string(),
+                run_times.to_string(),
+                videos.len().to_string(),
+                format!("{:.1}", total),
+            ];
+            if total < target.min {
+                params.push(format!("{:.1}", target.min));
+                emit_concat_progress(&window, "concat.target_short", &params)?;
+            } else {
+                emit_concat_progress(&window, "concat.selected_duration", &params)?;
+            }
+        } else if desired_count > available_count {
+            emit_concat_progress(
+                &window,
+                "concat.not_enough",
+                &[
+                    run_index.to_string(),
+                    run_times.to_string(),
+                    desired_count.to_string(),
+                    available_count.to_string(),
+                    available_count.to_string(),
+                ],
+            )?;
         } else {
             // 检查是否触发了池子重填
-            let remaining = pool_manager.get_remaining_count(&input_dir, max_depth);
+            let remaining = pool_manager.get_remaining_count(&pool_key, pool_depth);
 
-            let msg = if remaining + videos.len() == available_count {
-                format!("第 {}/{} 次：池子已抽完，重新填充。本次选择 {} 个视频", run_index, run_times, videos.len())
+            let mut params = vec![run_index.to_string(), run_times.to_string(), videos.len().to_string()];
+            if remaining + videos.len() == available_count {
+                emit_concat_progress(&window, "concat.pool_refilled", &params)?;
             } else {
-                format!("第 {}/{} 次：已选择 {} 个视频（池子剩余 {}）", run_index, run_times, videos.len(), remaining)
-            };
+                params.push(remaining.to_string());
+                emit_concat_progress(&window, "concat.selected", &params)?;
+            }
+        }
 
-            window.emit("progress", msg)
-                .map_err(|e| format!("发送进度事件失败: {}", e))?;
+        // 按选定的排列方式调整片段顺序（片头、结尾位置不变）
+        if clip_order != ClipOrder::Shuffle && !ordered {
+            emit_concat_progress(
+                &window,
+                "concat.ordering",
+                &[
+                    run_index.to_string(),
+                    run_times.to_string(),
+                    format!("{:?}", clip_order),
+                ],
+            )?;
+            videos = order_clips(app, videos, clip_order).await?;
+        }
+        // 池子片段在成片中的位置（片头插在前面后整体后移）
+        let drawn_count = videos.len();
+        let mut pool_range = if intro_path.is_some() {
+            1..drawn_count + 1
+        } else {
+            0..drawn_count
+        };
+
+        // 每个输入的裁剪点（池子视频不裁剪）
+        let mut trims: Vec<Option<ClipTrim>> = vec![None; videos.len()];
+        // 每个输入的画面效果（只作用于池子片段）
+        let mut effects: Vec<Option<ClipEffects>> = vec![clip_effects.clone(); videos.len()];
+
+        // 添加片头视频
+        if let Some(intro) = &intro_path {
+            videos.insert(0, intro.clone());
+            trims.insert(0, intro_trim);
+            effects.insert(0, None);
         }
 
         // 添加结尾视频
-        if let Some(ending) = &ending_video {
-            if !ending.is_empty() {
-                let ending_path = PathBuf::from(ending);
-                if !ending_path.exists() {
-                    return Err(format!("结尾视频不存在: {}", ending));
+        if let Some(ending) = ending_order.get((run_index - 1) % ending_order.len().max(1)) {
+            videos.push(ending.clone());
+            trims.push(ending_trim);
+            effects.push(None);
+            if ending_order.len() > 1 {
+                let name = ending.file_name().unwrap_or_default().to_string_lossy().to_string();
+                emit_concat_progress(&window, "concat.ending_added_named", &[name])?;
+            } else {
+                emit_concat_progress(&window, "concat.ending_added", &[])?;
+            }
+        }
+
+        report.entries.push(ReportEntry {
+            inputs: videos.iter().map(|v| v.to_string_lossy().to_string()).collect(),
+            status: "running".to_string(),
+            outputs: Vec::new(),
+            error: None,
+            warnings: Vec::new(),
+        });
+
+        // 检测兼容性
+        emit_concat_progress(&window, "concat.checking", &[run_index.to_string(), run_times.to_string()])?;
+
+        let checked = check_video_compatibility(app, &videos, &stream_overrides)
+            .await
+            .and_then(|compatibility| {
+                if !compatibility.compatible {
+                    return Err(format!(
+                        "INCOMPATIBLE_VIDEOS:第 {} 次生成：\n{}",
+                        run_index,
+                        compatibility.message.clone()
+                    ));
+                }
+                for ((name, info), trim) in compatibility.videos_info.iter().zip(&trims) {
+                    if let Some(trim) = trim {
+                        trim.validate(name, info.duration)?;
+                    }
+                }
+                Ok(compatibility)
+            });
+        let mut compatibility = match checked {
+            Ok(compatibility) => compatibility,
+            Err(e) => {
+                // 本次抽到的片段都没有用上，放回池子（包括其中兼容的片段）
+                pool_manager.return_videos(&pool_key, pool_depth, &videos[pool_range.clone()]);
+                if run_times == 1 {
+                    return Err(e);
                 }
-                videos.push(ending_path);
-                window
-                    .emit("progress", "已添加结尾视频")
-                    .map_err(|e| format!("发送进度事件失败: {}", e))?;
+                fail_planned_run(&window, report, run_index, run_times, &e)?;
+                source_count -= pool_range.len();
+                plan_error.get_or_insert(e);
+                continue;
             }
+        };
+        if !compatibility.channel_conversions.is_empty() {
+            emit_concat_progress(
+                &window,
+                "concat.run_note",
+                &[
+                    run_index.to_string(),
+                    run_times.to_string(),
+                    compatibility.message.clone(),
+                ],
+            )?;
         }
 
-        // 生成输出文件名
-        let output_file_name = if run_times == 1 {
-            format!("output_{}.mp4", base_timestamp)
-        } else {
-            format!("output_{}_{}.mp4", base_timestamp, run_index)
+        // 卡点剪辑：池子片段的出点对齐到背景音乐节拍
+        if let Some(bgm) = extras.bgm.as_ref().filter(|b| b.beat_sync) {
+            if beat_grid.is_none() {
+                emit_concat_progress(&window, "concat.detecting_beats", &[])?;
+                let grid = detect_beats(app, Path::new(&bgm.audio_path)).await?;
+                report.setting("节拍对齐", format!("检测到 {} 个节拍", grid.beats.len()));
+                beat_grid = Some(grid);
+            }
+            if let Some(grid) = &beat_grid {
+                let aligned = align_trims(
+                    grid,
+                    &compatibility.videos_info,
+                    &mut trims,
+                    &effects,
+                    pool_range.clone(),
+                );
+                emit_concat_progress(
+                    &window,
+                    "concat.beats_aligned",
+                    &[run_index.to_string(), run_times.to_string(), aligned.to_string()],
+                )?;
+            }
+        }
+
+        // 变体模式：每次输出使用各自的种子，同一次的分段与各规格共用一组变换
+        let mut run_extras = extras.clone();
+        run_extras.variation = variation_seed.map(|seed| Variation::from_seed(seed.wrapping_add(run_index as u64 - 1)));
+
+        // 预先为每个可能的插入位置选好插播片段，时长上限按插入后的成片计算
+        let interstitial_picks: Vec<usize> = match &interstitial {
+            Some(config) => (0..config.insert_count(pool_range.len()))
+                .map(|_| rng.gen_range(0..interstitial_clips.len()))
+                .collect(),
+            None => Vec::new(),
         };
-        let output_path = PathBuf::from(&output_dir).join(output_file_name);
+        let interstitial_durations: Vec<f64> = interstitial_picks
+            .iter()
+            .map(|&pick| clip_output_duration(&interstitial_clips[pick].2, interstitial.as_ref().and_then(|c| c.trim), None))
+            .collect();
 
-        let compatibility = check_video_compatibility(&app, &videos).await?;
+        // 成片超过时长上限时从末尾去掉池子片段（至少保留一个），去掉的片段放回池子
+        if let Some(max) = max_total_duration {
+            let durations: Vec<f64> = compatibility
+                .videos_info
+                .iter()
+                .zip(trims.iter().zip(&effects))
+                .map(|((_, info), (trim, effects))| clip_output_duration(info, *trim, effects.as_ref()))
+                .collect();
+            let total_without = |dropped: std::ops::Range<usize>| {
+                let inserted = interstitial
+                    .as_ref()
+                    .map_or(0, |c| c.insert_count(pool_range.len() - dropped.len()));
+                let total = durations.iter().sum::<f64>() - durations[dropped.clone()].iter().sum::<f64>()
+                    + interstitial_durations[..inserted].iter().sum::<f64>();
+                run_extras.output_duration(total, durations.len() - dropped.len() + inserted)
+            };
+            let mut keep_end = pool_range.end;
+            while keep_end > pool_range.start + 1 && total_without(keep_end..pool_range.end) > max {
+                keep_end -= 1;
+            }
+            let total = total_without(keep_end..pool_range.end);
+            if total > max {
+                let e = format!(
+                    "第 {} 次：只保留一个片段时成片仍有 {:.1}s，超过时长上限 {:.1}s",
+                    run_index, total, max
+                );
+                pool_manager.return_videos(&pool_key, pool_depth, &videos[pool_range.clone()]);
+                if run_times == 1 {
+                    return Err(e);
+                }
+                fail_planned_run(&window, report, run_index, run_times, &e)?;
+                source_count -= pool_range.len();
+                plan_error.get_or_insert(e);
+                continue;
+            }
+            if keep_end < pool_range.end {
+                let dropped: Vec<PathBuf> = videos.drain(keep_end..pool_range.end).collect();
+                trims.drain(keep_end..pool_range.end);
+                effects.drain(keep_end..pool_range.end);
+                compatibility.videos_info.drain(keep_end..pool_range.end);
+                pool_manager.return_videos(&pool_key, pool_depth, &dropped);
+                source_count -= dropped.len();
+                if let Some(entry) = report.entries.last_mut() {
+                    entry.inputs = videos.iter().map(|v| v.to_string_lossy().to_string()).collect();
+                }
+                emit_concat_progress(
+                    &window,
+                    "concat.duration_capped",
+                    &[
+                        run_index.to_string(),
+                        run_times.to_string(),
+                        format!("{:.1}", max),
+                        dropped.len().to_string(),
+                        format!("{:.1}", total),
+                    ],
+                )?;
+                pool_range.end = keep_end;
+            }
+        }
 
-        if !compatibility.compatible {
-            return Err(format!(
-                "INCOMPATIBLE_VIDEOS:第 {} 次生成：\n{}",
-                run_index,
-                compatibility.message.clone()
-            ));
+        // 插入插播片段（从后往前插入，前面的位置不受影响）
+        if let Some(config) = &interstitial {
+            let inserted = config.insert_count(pool_range.len());
+            for (n, &pick) in interstitial_picks[..inserted].iter().enumerate().rev() {
+                let at = pool_range.start + (n + 1) * config.every;
+                let (path, name, info) = &interstitial_clips[pick];
+                videos.insert(at, path.clone());
+                trims.insert(at, config.trim);
+                effects.insert(at, None);
+                compatibility.videos_info.insert(at, (name.clone(), info.clone()));
+            }
+            if inserted > 0 {
+                if let Some(entry) = report.entries.last_mut() {
+                    entry.inputs = videos.iter().map(|v| v.to_string_lossy().to_string()).collect();
+                }
+                emit_concat_progress(
+                    &window,
+                    "concat.interstitials_inserted",
+                    &[run_index.to_string(), run_times.to_string(), inserted.to_string()],
+                )?;
+            }
         }
 
-        let (target_width, target_height) = compatibility
+        let sizes: Vec<(u32, u32)> = compatibility
             .videos_info
-            .first()
+            .iter()
             .map(|(_, info)| (info.width, info.height))
-            .ok_or("无法获取目标分辨率")?;
+            .collect();
+        let target_size = target_resolution.resolve(&sizes).ok_or("无法获取目标分辨率")?;
 
-        let filter = build_concat_filter(&compatibility.videos_info, target_width, target_height)?;
+        // 智能裁剪需要每个输入的画面能量分布（同一任务内缓存）
+        if reframe_mode == ReframeMode::Smart {
+            for (video, (_, info)) in videos.iter().zip(&compatibility.videos_info) {
+                if !focus_profiles.contains_key(video) {
+                    let profile = analyze_focus(app, video, info.duration).await?;
+                    focus_profiles.insert(video.clone(), profile);
+                }
+            }
+        }
 
-        // 调用 FFmpeg 拼接（统一重编码）
-        window
-            .emit(
-                "progress",
-                format!(
-                    "第 {}/{} 次：正在拼接视频（统一重编码以保证同步）...",
-                    run_index, run_times
-                ),
-            )
-            .map_err(|e| format!("发送进度事件失败: {}", e))?;
+        // 按最大时长在片段边界处分段（未设置时整体输出）
+        let parts = match max_part_duration {
+            Some(max) => {
+                let durations: Vec<f64> = compatibility
+                    .videos_info
+                    .iter()
+                    .zip(trims.iter().zip(&effects))
+                    .map(|((_, info), (trim, effects))| clip_output_duration(info, *trim, effects.as_ref()))
+                    .collect();
+                let parts = split_into_parts(&durations, max);
+                for part in &parts {
+                    let total: f64 = durations[part.clone()].iter().sum();
+                    if total > max {
+                        emit_concat_progress(
+                            &window,
+                            "concat.long_clip_part",
+                            &[
+                                run_index.to_string(),
+                                run_times.to_string(),
+                                compatibility.videos_info[part.start].0.clone(),
+                                format!("{:.1}", total),
+                                format!("{:.1}", max),
+                            ],
+                        )?;
+                    }
+                }
+                parts
+            }
+            None => std::iter::once(0..videos.len()).collect(),
+        };
 
-        let sidecar = app
-            .shell()
-            .sidecar("ffmpeg")
-            .map_err(|e| format!("FFmpeg 启动失败: {}", e))?;
+        plans.push(RunPlan {
+            run_index,
+            videos,
+            videos_info: compatibility.videos_info,
+            trims,
+            effects,
+            parts,
+            extras: run_extras,
+            target_size,
+        });
+    }
 
-        let mut args: Vec<String> = Vec::new();
-        for video in &videos {
-            args.push("-i".to_string());
-            args.push(video.to_string_lossy().to_string());
+    if plans.is_empty() {
+        if run_times > 1 {
+            emit_run_summary(app, report);
         }
-        args.push("-filter_complex".to_string());
-        args.push(filter);
-        args.push("-map".to_string());
-        args.push("[outv]".to_string());
-        args.push("-map".to_string());
-        args.push("[outa]".to_string());
-        args.push("-vsync".to_string());
-        args.push("vfr".to_string());
-        args.push("-c:v".to_string());
-        args.push("libx264".to_string());
-        args.push("-preset".to_string());
-        args.push("fast".to_string());
-        args.push("-crf".to_string());
-        args.push("23".to_string());
-        args.push("-pix_fmt".to_string());
-        args.push("yuv420p".to_string());
-        args.push("-c:a".to_string());
-        args.push("aac".to_string());
-        args.push("-b:a".to_string());
-        args.push("192k".to_string());
-        args.push("-fflags".to_string());
-        args.push("+genpts".to_string());
-        args.push("-avoid_negative_ts".to_string());
-        args.push("make_zero".to_string());
-        args.push("-shortest".to_string());
-        args.push(output_path.to_string_lossy().to_string());
+        return Err(plan_error.unwrap_or_else(|| "没有可生成的输出".to_string()));
+    }
 
-        let output = sidecar
-            .args(args)
-            .output()
-            .await
-            .map_err(|e| format!("FFmpeg 执行失败: {}", e))?;
+    // 编码前保存计划，失败或中断的输出之后可以按相同的片段组合重试
+    let mut batch = BatchPlan {
+        job_id: format!("concat_{}", base_timestamp),
+        output_dir: output_dir.clone(),
+        base_timestamp: base_timestamp.clone(),
+        run_times,
+        variants: variants.clone(),
+        reframe_mode,
+        pad_fill: pad_fill.clone(),
+        safe_area,
+        mezzanine: mezzanine.clone(),
+        keep_creation_time,
+        verify_joins,
+        encoder,
+        encode_settings: encode_settings.clone(),
+        runs: plans,
+        completed: Vec::new(),
+    };
+    batch.save(app)?;
+    report.setting("任务 ID", &batch.job_id);
 
-        if !output.status.success() {
-            return Err(format!(
-                "FFmpeg 执行失败: {}",
-                String::from_utf8_lossy(&output.stderr)
-            ));
+    // 各次输出互不依赖，按资源限制并行编码；文件编号与报告顺序仍按次数
+    let parallel = crate::throttle::current(app).parallel_runs().min(batch.runs.len()).max(1);
+    if parallel > 1 {
+        emit_concat_progress(&window, "concat.parallel", &[batch.runs.len().to_string(), parallel.to_string()])?;
+    }
+    let context = RenderContext {
+        app,
+        window: &window,
+        run_times,
+        aspect_groups: &aspect_groups,
+        single_output: variants.len() == 1 && variants[0].name.is_empty(),
+        reframe_mode,
+        pad_fill: &pad_fill,
+        safe_area,
+        focus_profiles: &focus_profiles,
+        output_dir: &output_dir,
+        base_timestamp: &base_timestamp,
+        mezzanine: mezzanine.as_ref(),
+        keep_creation_time,
+        verify_joins,
+        encoders: hw_encoder::select(app, encoder).await,
+        encode_settings: &encode_settings,
+    };
+    let mut renders = Vec::with_capacity(batch.runs.len());
+    for plan in &batch.runs {
+        let context = &context;
+        renders.push(async move { (plan, render_run(context, plan).await) });
+    }
+    let mut renders = futures_util::stream::iter(renders).buffer_unordered(parallel);
+
+    let mut run_paths: Vec<Vec<PathBuf>> = vec![Vec::new(); run_times];
+    let mut variation_records: Vec<(usize, VariationRecord)> = Vec::new();
+    let mut output_duration = 0.0;
+    let mut join_issue_count = 0;
+    let mut first_error: Option<(usize, String)> = None;
+    while let Some((plan, result)) = renders.next().await {
+        let entry = &mut report.entries[plan.run_index - 1];
+        match result {
+            Ok(rendered) => {
+                entry.status = "completed".to_string();
+                output_duration += rendered.duration;
+                entry.outputs = rendered.outputs;
+                join_issue_count += rendered.join_issues.len();
+                entry.warnings = rendered.join_issues.iter().map(|issue| issue.describe()).collect();
+
+                // 变体清单随每次输出更新，中途失败时已完成的输出也能复现
+                if let Some(variation) = plan.extras.variation {
+                    variation_records.push((
+                        plan.run_index,
+                        VariationRecord {
+                            outputs: rendered.paths.iter().map(|p| p.to_string_lossy().to_string()).collect(),
+                            variation,
+                        },
+                    ));
+                    variation_records.sort_by_key(|(run_index, _)| *run_index);
+                    let records: Vec<&VariationRecord> = variation_records.iter().map(|(_, r)| r).collect();
+                    let manifest = serde_json::to_string_pretty(&records)
+                        .map_err(|e| format!("序列化变体清单失败: {}", e))?;
+                    let manifest_path = PathBuf::from(&output_dir).join(format!("variations_{}.json", base_timestamp));
+                    fs::write(&manifest_path, manifest).map_err(|e| format!("写入变体清单失败: {}", e))?;
+                }
+                run_paths[plan.run_index - 1] = rendered.paths;
+                // 计划只用于重试，保存失败不影响本次输出
+                batch.completed.push(plan.run_index);
+                let _ = batch.save(app);
+            }
+            Err(e) => {
+                // 其余输出继续编码完成，最后返回次数最小的错误
+                entry.status = "failed".to_string();
+                entry.error = Some(e.clone());
+                if first_error.as_ref().is_none_or(|(run_index, _)| plan.run_index < *run_index) {
+                    first_error = Some((plan.run_index, e));
+                }
+            }
         }
+    }
 
-        output_paths.push(output_path);
+    let output_paths: Vec<PathBuf> = run_paths.into_iter().flatten().collect();
+    record_output(app, source_count, output_paths.len(), output_duration, false);
+    let summary = (run_times > 1).then(|| emit_run_summary(app, report));
+    // 多次输出时只要有成功的输出就正常返回，失败的次数附在消息之后
+    if let Some((_, e)) = first_error.filter(|_| output_paths.is_empty()) {
+        return Err(format!("{}\n失败的输出可以按原片段组合重试，任务 ID: {}", e, batch.job_id));
     }
 
-    window
-        .emit("progress", "完成！")
-        .map_err(|e| format!("发送进度事件失败: {}", e))?;
+    emit_concat_progress(&window, "concat.done", &[])?;
 
-    if output_paths.len() == 1 {
-        Ok(format!(
+    let message = if output_paths.len() == 1 {
+        format!(
             "视频拼接完成！输出文件: {}",
             output_paths[0].display()
-        ))
+        )
     } else {
         let list = output_paths
             .iter()
             .map(|p| p.display().to_string())
             .collect::<Vec<_>>()
             .join("\n");
-        Ok(format!("视频拼接完成！共生成 {} 个视频：\n{}", output_paths.len(), list))
+        format!("视频拼接完成！共生成 {} 个视频：\n{}", output_paths.len(), list)
+    };
+    let message = match summary.filter(|s| s.failed > 0) {
+        Some(summary) => format!(
+            "{}\n{} 次输出失败，可以按原片段组合重试，任务 ID: {}\n{}",
+            message,
+            summary.failed,
+            batch.job_id,
+            summary.problems()
+        ),
+        None => message,
+    };
+    if join_issue_count > 0 {
+        Ok(format!("{}\n有 {} 处接缝需要检查，详见进度记录或处理报告", message, join_issue_count))
+    } else {
+        Ok(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcard_stays_within_directory() {
+        assert!(wildcard_match("*.mp4", "a.mp4"));
+        assert!(!wildcard_match("*.mp4", "dir/a.mp4"));
+        assert!(wildcard_match("clip?.mp4", "clip1.mp4"));
+        assert!(!wildcard_match("clip?.mp4", "clip12.mp4"));
+    }
+
+    #[test]
+    fn double_star_matches_any_depth() {
+        assert!(wildcard_match("**/*.mp4", "dir/sub/a.mp4"));
+        assert!(wildcard_match("**/*.mp4", "a.mp4"));
+        assert!(wildcard_match("ads/**", "ads/x/y.mp4"));
+        assert!(!wildcard_match("ads/**", "other/y.mp4"));
+    }
+
+    #[test]
+    fn drawtext_is_escaped_twice() {
+        assert_eq!(escape_drawtext("a:b"), "a\\\\:b");
+        assert_eq!(escape_drawtext("it's"), "it\\\\\\'s");
+        assert_eq!(escape_drawtext("[x],y"), "\\[x\\]\\,y");
     }
 }
@@ -0,0 +1,34 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 自动拆解的检测诊断，随每次拆解结果一起发送，便于调整阈值与最短时长
+ */
+export type AutoSplitDiagnostics = { video_path: string, 
+/**
+ * 逐帧分析结果来自缓存
+ */
+cache_hit: boolean, 
+/**
+ * 相似度低于阈值的候选切换点
+ */
+candidate_cuts: number, 
+/**
+ * 因最短时长忽略的候选切换点
+ */
+rejected_min_duration: number, 
+/**
+ * 滞回检测未确认的候选切换点
+ */
+rejected_unconfirmed: number, 
+/**
+ * 被闪光过滤忽略的候选切换点
+ */
+flash_suppressed: number, 
+/**
+ * 相似度分布：[0, 0.1)、[0.1, 0.2) … [0.9, 1.0] 十个区间的帧数
+ */
+similarity_histogram: Array<number>, 
+/**
+ * 各阶段耗时（秒）：逐帧分析、切分、生成片段
+ */
+analysis_secs: number, split_secs: number, generate_secs: number, };
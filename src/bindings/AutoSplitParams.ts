@@ -0,0 +1,30 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { EncodeSettings } from "./EncodeSettings";
+import type { FlashFilter } from "./FlashFilter";
+import type { Hysteresis } from "./Hysteresis";
+import type { SourceDateOptions } from "./SourceDateOptions";
+
+/**
+ * 自动拆解参数（auto_split_video 命令、批量处理、任务队列与自检共用）
+ */
+export type AutoSplitParams = { output_dir: string, algorithm: string, threshold: number, min_duration: number, skip_first: boolean, skip_last: boolean, source_date: SourceDateOptions, 
+/**
+ * 片段按导出预设编码
+ */
+export_preset: string | null, encode_settings: EncodeSettings | null, 
+/**
+ * 只分析源视频的这段时间（秒）
+ */
+analyze_start: number | null, analyze_end: number | null, 
+/**
+ * 滞回检测（切分后需确认新场景稳定）
+ */
+hysteresis: Hysteresis | null, 
+/**
+ * 忽略闪光、频闪造成的单帧异常
+ */
+flash_filter: FlashFilter | null, 
+/**
+ * 检测预设名称，套用后覆盖算法、阈值与最短时长
+ */
+detection_profile: string | null, };
@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HwEncoder } from "./HwEncoder";
+import type { VideoCodec } from "./VideoCodec";
+
+/**
+ * 某个编码格式下可用的编码器
+ */
+export type AvailableEncoder = { encoder: HwEncoder, codec: VideoCodec, 
+/**
+ * FFmpeg 编码器名称
+ */
+name: string, };
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 整批下载的汇总进度
+ */
+export type BatchDownloadProgress = { files_done: number, files_failed: number, files_total: number, bytes_done: number, 
+/**
+ * 已知大小文件的总字节数（尚未开始或未返回大小的文件不计入）
+ */
+bytes_total: number, 
+/**
+ * 是否所有文件的大小都已知
+ */
+bytes_total_exact: boolean, speed: string, eta_seconds: number | null, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DownloadItemResult } from "./DownloadItemResult";
+
+/**
+ * 整批下载结束后的汇总
+ */
+export type BatchDownloadSummary = { files_total: number, succeeded: number, failed: number, bytes_downloaded: number, elapsed_seconds: number, average_speed: string, failures: Array<[string, string]>, results: Array<DownloadItemResult>, };
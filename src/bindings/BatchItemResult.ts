@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 批量操作中单个条目的结果
+ */
+export type BatchItemResult = { 
+/**
+ * 文件名、下载地址或第几次输出
+ */
+name: string, 
+/**
+ * succeeded / failed / skipped
+ */
+status: string, 
+/**
+ * 失败或跳过的原因
+ */
+reason: string | null, };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { BatchItemResult } from "./BatchItemResult";
+
+/**
+ * 批量操作结束后的逐项汇总（批量拆解、批量下载与多次拼接共用同一结构）
+ */
+export type BatchSummary = { 
+/**
+ * auto_split / download / concat
+ */
+operation: string, succeeded: number, failed: number, skipped: number, items: Array<BatchItemResult>, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { VideoCodec } from "./VideoCodec";
+
+/**
+ * 单个配置的测试结果
+ */
+export type BenchmarkEntry = { encode_seconds: number, 
+/**
+ * 编码速度（相对实时的倍数）
+ */
+speed: number, 
+/**
+ * 与无损源的 SSIM（0-1），无法计算时为空
+ */
+ssim: number | null, file_size: number, codec: VideoCodec, preset: string, };
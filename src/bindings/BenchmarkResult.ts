@@ -0,0 +1,20 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { BenchmarkEntry } from "./BenchmarkEntry";
+import type { EncodingProfile } from "./EncodingProfile";
+
+/**
+ * 本机编码性能测试结果（保存在应用配置目录的 benchmark.json）
+ */
+export type BenchmarkResult = { created_at: string, 
+/**
+ * 测试片段描述
+ */
+clip: string, entries: Array<BenchmarkEntry>, 
+/**
+ * 失败的配置（如 FFmpeg 未编译对应编码器）
+ */
+failures: Array<string>, 
+/**
+ * 推荐的默认编码配置：达到实时速度的配置中画质最好的；都达不到时选最快的
+ */
+recommended: EncodingProfile | null, };
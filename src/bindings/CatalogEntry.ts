@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CatalogThumbnail } from "./CatalogThumbnail";
+
+/**
+ * 一个视频的缩略图目录
+ */
+export type CatalogEntry = { path: string, 
+/**
+ * 缩略图所在子目录（相对输出目录）
+ */
+folder: string, duration: number, width: number, height: number, thumbnails: Array<CatalogThumbnail>, 
+/**
+ * 处理失败的原因（其余视频照常生成）
+ */
+error: string | null, };
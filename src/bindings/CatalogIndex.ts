@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CatalogEntry } from "./CatalogEntry";
+
+/**
+ * 目录索引（写入输出目录的 catalog.json）
+ */
+export type CatalogIndex = { source_dir: string, interval: number, generated_at: string, videos: Array<CatalogEntry>, };
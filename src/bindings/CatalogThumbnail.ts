@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 一张缩略图
+ */
+export type CatalogThumbnail = { 
+/**
+ * 相对输出目录的路径
+ */
+file: string, 
+/**
+ * 在视频中的大致时间（秒）
+ */
+timestamp: number, };
@@ -0,0 +1,28 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ColorStyle } from "./ColorStyle";
+import type { SpeedRamp } from "./SpeedRamp";
+
+/**
+ * 画面效果，可用于单个片段或整个成片
+ */
+export type ClipEffects = { 
+/**
+ * 3D LUT 调色文件（.cube）
+ */
+lut_path: string | null, style: ColorStyle | null, 
+/**
+ * 水平镜像
+ */
+mirror: boolean, 
+/**
+ * 亮度（-1.0 ~ 1.0，0 为不变）
+ */
+brightness: number | null, 
+/**
+ * 对比度（0.0 ~ 2.0，1 为不变）
+ */
+contrast: number | null, 
+/**
+ * 饱和度（0.0 ~ 3.0，1 为不变）
+ */
+saturation: number | null, speed: SpeedRamp | null, };
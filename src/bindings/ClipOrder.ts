@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 抽取出的片段在成片中的排列方式
+ */
+export type ClipOrder = "shuffle" | "shortest_first" | "alternate_long_short" | "color_temperature" | "motion";
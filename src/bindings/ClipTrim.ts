@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 片段裁剪入点/出点（秒），用于片头、片尾视频去掉多余的开头或结尾
+ */
+export type ClipTrim = { 
+/**
+ * 入点（秒），为空表示从头开始
+ */
+start: number | null, 
+/**
+ * 出点（秒），为空表示到视频结尾
+ */
+end: number | null, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 片段合集：手动整理的一组片段，可代替目录作为拼接的来源
+ */
+export type Collection = { name: string, 
+/**
+ * 有序合集按列表顺序依次抽取片段，无序合集随机抽取
+ */
+ordered: boolean, clips: Array<string>, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 后台拼接任务结束（完成、失败或被取消）
+ */
+export type ConcatJobFinished = { job_id: string, result: string | null, error: string | null, cancelled: boolean, };
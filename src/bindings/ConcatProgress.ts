@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 拼接进度（选取片段、兼容性检测、逐次输出），没有百分比
+ */
+export type ConcatProgress = { 
+/**
+ * 按当前语言生成的消息
+ */
+message: string, 
+/**
+ * 消息目录中的 key，前端可以自行翻译
+ */
+message_key: string, params: Array<string>, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 单个下载条目：可以是单个 URL，也可以是按优先级排列的多个镜像地址
+ */
+export type DownloadItem = string | { urls: Array<string>, };
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 单个条目的下载结果
+ */
+export type DownloadItemResult = { url: string, 
+/**
+ * 实际成功的地址
+ */
+succeeded_url: string | null, 
+/**
+ * 是否由备用镜像下载成功
+ */
+used_mirror: boolean, attempts: number, error: string | null, };
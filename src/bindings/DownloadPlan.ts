@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PlannedDownload } from "./PlannedDownload";
+
+/**
+ * 下载计划（不下载任何数据）
+ */
+export type DownloadPlan = { items: Array<PlannedDownload>, 
+/**
+ * 已知大小的总字节数
+ */
+total_bytes: number, 
+/**
+ * 无法获取大小的条目数
+ */
+unknown_size_count: number, warnings: Array<string>, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 边缘检测方式
+ */
+export type EdgeDetector = "sobel" | "canny";
@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { EdgeDetector } from "./EdgeDetector";
+
+/**
+ * 边缘差异参数
+ */
+export type EdgeParams = { detector: EdgeDetector, 
+/**
+ * 边缘阈值：Sobel 为梯度幅值阈值，Canny 为低阈值
+ */
+low_threshold: number, 
+/**
+ * Canny 的高阈值
+ */
+high_threshold: number, 
+/**
+ * 边缘位置允许的偏移（像素），吸收轻微抖动和编码噪点
+ */
+tolerance: number, };
@@ -0,0 +1,26 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 单次 FFmpeg 编码的实时进度（由 -progress 输出计算）
+ */
+export type EncodeProgress = { 
+/**
+ * concat、segments 或 remove_ending
+ */
+task: string, 
+/**
+ * 当前编码的说明（如第几次拼接、第几个片段）
+ */
+label: string, 
+/**
+ * 0-100
+ */
+percent: number, 
+/**
+ * 预计剩余时间（秒），尚无编码速度时为空
+ */
+eta_seconds: number | null, 
+/**
+ * 编码速度（相对实时播放的倍数）
+ */
+speed: number | null, };
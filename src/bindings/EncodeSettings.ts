@@ -0,0 +1,26 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 重编码参数，未设置的项使用各处理流程原有的默认值
+ */
+export type EncodeSettings = { 
+/**
+ * 恒定质量（0-51），输出规格或导出预设单独指定时以其为准
+ */
+crf: number | null, 
+/**
+ * 编码器预设（如 libx264 的 fast、slow，NVENC 的 p1-p7）
+ */
+preset: string | null, 
+/**
+ * 视频平均码率（kbps），设置后按码率编码而不是恒定质量
+ */
+video_bitrate: number | null, 
+/**
+ * 音频码率（kbps），默认 192
+ */
+audio_bitrate: number | null, 
+/**
+ * 输出像素格式（如 yuv420p、yuv420p10le）
+ */
+pix_fmt: string | null, };
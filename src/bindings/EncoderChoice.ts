@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 视频编码器选择
+ */
+export type EncoderChoice = "software" | "auto" | "nvenc" | "qsv" | "video_toolbox";
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { VideoCodec } from "./VideoCodec";
+
+/**
+ * 单个编码配置
+ */
+export type EncodingProfile = { codec: VideoCodec, preset: string, };
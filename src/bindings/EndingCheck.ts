@@ -0,0 +1,26 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 移除最后一个片段前确认它是片尾的条件，均未设置时照常移除
+ */
+export type EndingCheck = { 
+/**
+ * 片尾的最短时长（秒）
+ */
+min_duration: number | null, 
+/**
+ * 片尾的最长时长（秒），过长的片段多半是正片内容
+ */
+max_duration: number | null, 
+/**
+ * 已知的片尾样本（以往移除的片尾视频），最后一个片段需与其中之一相似
+ */
+reference_videos: Array<string>, 
+/**
+ * 与片尾样本的最低相似度（0-1）
+ */
+reference_similarity: number, 
+/**
+ * 无法确认是片尾时跳过该文件；否则保留最后一个片段照常合成
+ */
+skip_if_unsure: boolean, };
@@ -0,0 +1,22 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 多机渲染状态
+ */
+export type FarmStatus = { 
+/**
+ * 作为协调端监听的端口
+ */
+coordinator_port: number | null, 
+/**
+ * 作为协调端监听的地址
+ */
+coordinator_address: string | null, 
+/**
+ * 协调端最近一次接受连接失败的原因
+ */
+coordinator_error: string | null, 
+/**
+ * 作为执行节点连接的协调端地址
+ */
+worker_coordinator: string | null, };
@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 闪光、频闪过滤：闪光帧与前后帧都不相似，会在相似度序列中留下相邻的两个低谷，
+ * 切出只有一两帧的片段。切换后的新画面需要持续若干帧不再变化才算切换，
+ * 期间再次出现的低谷（画面恢复）一并忽略；中值滤波后仍低于阈值的连续低谷视为渐变转场，不受影响
+ *
+ * 中值滤波窗口决定多长的连续低谷算渐变转场：窗口为 5 时闪光留下的两个低谷被滤掉，
+ * 三帧及以上的溶解、淡入淡出保留
+ */
+export type FlashFilter = { 
+/**
+ * 中值滤波窗口（帧数，不小于 3 的奇数）
+ */
+median_window: number, 
+/**
+ * 切换后新画面至少持续的帧数
+ */
+min_persist: number, };
@@ -0,0 +1,33 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 元数据帧数与实际提取帧数的核对结果
+ *
+ * 元数据的帧数来自 ffprobe 统计，提取使用 `-vsync 0` 按原始时间戳输出，
+ * 可变帧率或有损坏帧的文件两者可能不同；界面的帧滑块应以实际提取的帧为准
+ */
+export type FrameCountReport = { video_path: string, 
+/**
+ * 元数据中的帧数
+ */
+metadata_frames: number, 
+/**
+ * 元数据帧数是否为估算值
+ */
+metadata_estimated: boolean, 
+/**
+ * 探测到的帧时间戳数量
+ */
+probed_frames: number, 
+/**
+ * 实际提取的帧数，即以此为准的帧数
+ */
+frame_count: number, 
+/**
+ * 每个提取帧按元数据帧率换算的帧号（恒定帧率时与序号相同，可变帧率或丢帧时出现跳跃）
+ */
+nominal_frames: Array<number>, 
+/**
+ * 差异超过容差
+ */
+flagged: boolean, message: string | null, };
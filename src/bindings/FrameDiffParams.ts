@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 帧差异参数
+ */
+export type FrameDiffParams = { 
+/**
+ * 比较前把画面缩小的倍数，倍数越大越能忽略噪点和细小运动
+ */
+downscale: number, };
@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 关键帧（GOP）设置，各项为空时使用编码器默认值
+ */
+export type GopSettings = { 
+/**
+ * 关键帧间隔（秒），对应 `-g`；如 YouTube 建议 2 秒
+ */
+keyframe_interval: number | null, 
+/**
+ * 最小关键帧间隔（秒），对应 `keyint_min`
+ */
+min_keyframe_interval: number | null, 
+/**
+ * 关闭场景切换时自动插入关键帧，使 GOP 严格固定
+ */
+disable_scene_cut: boolean, };
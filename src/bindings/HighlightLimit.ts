@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 集锦的选取上限
+ */
+export type HighlightLimit = { "top_count": { count: number, } } | { "top_minutes": { minutes: number, } };
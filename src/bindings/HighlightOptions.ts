@@ -0,0 +1,27 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HighlightLimit } from "./HighlightLimit";
+
+/**
+ * 精彩集锦设置
+ */
+export type HighlightOptions = { limit: HighlightLimit, 
+/**
+ * 理想的片段时长（秒），越接近得分越高
+ */
+target_duration: number, 
+/**
+ * 画面运动强度的权重
+ */
+motion_weight: number, 
+/**
+ * 响度的权重
+ */
+loudness_weight: number, 
+/**
+ * 时长匹配度的权重
+ */
+duration_weight: number, 
+/**
+ * 直接拼接成一个集锦视频（不保留单独的片段）
+ */
+concat: boolean, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 直方图的颜色空间
+ */
+export type HistogramColorSpace = "gray" | "rgb" | "hsv";
@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HistogramColorSpace } from "./HistogramColorSpace";
+import type { HistogramCompare } from "./HistogramCompare";
+
+/**
+ * 直方图参数
+ */
+export type HistogramParams = { 
+/**
+ * 直方图的分组数（2-256），分组越少对轻微的亮度变化越不敏感
+ */
+bins: number, 
+/**
+ * 颜色空间，RGB/HSV 需要彩色缩略图
+ */
+color_space: HistogramColorSpace, compare: HistogramCompare, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 实际使用的编码器
+ */
+export type HwEncoder = "software" | "nvenc" | "qsv" | "video_toolbox";
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 滞回检测：相似度低于切分阈值只是候选切换点，之后连续若干对相邻帧的相似度
+ * 都回到恢复阈值以上（新场景已稳定）才确认切分，避免噪点较多的画面反复误切
+ */
+export type Hysteresis = { 
+/**
+ * 恢复阈值，不低于切分阈值
+ */
+recovered: number, 
+/**
+ * 需要连续稳定的帧数
+ */
+stable_frames: number, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 待比较的图片：文件路径或 base64 编码的图片数据（可带 data URI 前缀）
+ */
+export type ImageSource = { "path": string } | { "base64": string };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 接口版本信息
+ */
+export type IpcInfo = { version: number, app_version: string, };
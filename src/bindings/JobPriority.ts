@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 任务优先级：高优先级任务排在队列前面（正在执行的任务不会被打断）
+ */
+export type JobPriority = "low" | "normal" | "high";
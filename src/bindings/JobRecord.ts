@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { JobPriority } from "./JobPriority";
+import type { JobStatus } from "./JobStatus";
+
+/**
+ * 任务记录（发送给前端的 job_updated 事件也使用该结构）
+ */
+export type JobRecord = { id: string, kind: string, source: string, status: JobStatus, priority: JobPriority, 
+/**
+ * 等待中任务在队列中的位置（从 0 开始）
+ */
+queue_position: number | null, created_at: string, started_at: string | null, finished_at: string | null, result: string | null, error: string | null, 
+/**
+ * 由其他机器执行时的执行节点名称
+ */
+worker: string | null, };
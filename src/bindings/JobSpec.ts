@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AutoSplitParams } from "./AutoSplitParams";
+import type { RemoveEndingParams } from "./RemoveEndingParams";
+
+/**
+ * 队列任务内容
+ */
+export type JobSpec = { "type": "auto_split", video_path: string, params: AutoSplitParams, } | { "type": "remove_ending", video_path: string, params: RemoveEndingParams, };
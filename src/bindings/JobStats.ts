@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * job_stats 事件
+ */
+export type JobStats = { job_id: string, 
+/**
+ * 整机 CPU 占用（0-100）
+ */
+cpu_percent: number, threads: number | null, low_priority: boolean, };
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 需要注意的接缝
+ */
+export type JoinIssue = { output: string, 
+/**
+ * 第几处接缝（从 1 开始）
+ */
+index: number, 
+/**
+ * 接缝在成片中的时间（秒）
+ */
+time: number, problems: Array<string>, };
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 比较画面时忽略的区域（水印、台标），对所有算法生效
+ */
+export type MaskSettings = { 
+/**
+ * 四个角各忽略的宽、高比例（0-0.5），0 表示不忽略
+ */
+corner_fraction: number, 
+/**
+ * 自动检测整个视频中始终不变的区域（如固定水印）并忽略
+ */
+auto_static: boolean, };
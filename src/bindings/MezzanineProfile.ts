@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { VideoCodec } from "./VideoCodec";
+
+/**
+ * 中间文件规格：池子片段统一转成该规格后可直接流复制拼接
+ */
+export type MezzanineProfile = { width: number, height: number, fps: number, codec: VideoCodec, crf: number | null, };
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 标注了片尾区间的示例视频
+ */
+export type OutroExample = { video_path: string, 
+/**
+ * 片尾开始时间（秒）
+ */
+start: number, 
+/**
+ * 片尾结束时间（秒），为空表示到视频结尾
+ */
+end: number | null, };
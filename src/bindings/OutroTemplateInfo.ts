@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 片尾模板概要（不含指纹数据）
+ */
+export type OutroTemplateInfo = { name: string, duration: number, examples: number, has_audio: boolean, };
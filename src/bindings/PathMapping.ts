@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 路径映射：任务路径以 `from` 开头时替换为 `to`（如协调端的 `D:\share` 对应本机的 `/mnt/share`）
+ */
+export type PathMapping = { from: string, to: string, };
@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 下载计划中的单个条目
+ */
+export type PlannedDownload = { 
+/**
+ * 主地址
+ */
+url: string, 
+/**
+ * 主地址不可用、由备用镜像探测成功时的镜像地址
+ */
+mirror: string | null, filename: string, output_path: string, status: number | null, content_length: number | null, content_type: string | null, 
+/**
+ * 目标文件已存在（将被覆盖）
+ */
+exists: boolean, warnings: Array<string>, error: string | null, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 视频池更新事件
+ */
+export type PoolUpdated = { input_dir: string, max_depth: number, added: Array<string>, rejected: Array<[string, string]>, total: number, remaining: number, };
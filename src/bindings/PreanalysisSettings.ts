@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 素材池目录的后台预分析：空闲时为新文件预先计算元数据、逐帧相似度与画面特征，
+ * 之后的自动拆解、视频池筛选直接使用缓存
+ */
+export type PreanalysisSettings = { enabled: boolean, 
+/**
+ * 需要预分析的素材池目录
+ */
+directories: Array<string>, 
+/**
+ * 递归深度，与视频池一致
+ */
+max_depth: number, 
+/**
+ * 无键鼠输入超过该分钟数才开始分析；为空时只要任务队列空闲即可
+ */
+idle_minutes: number | null, 
+/**
+ * 预先计算逐帧相似度的算法（名称与自动拆解一致）
+ */
+algorithms: Array<string>, };
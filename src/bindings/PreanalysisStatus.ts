@@ -0,0 +1,26 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 后台预分析进度
+ */
+export type PreanalysisStatus = { 
+/**
+ * 正在分析的文件
+ */
+current: string | null, 
+/**
+ * 已完成预分析的文件数
+ */
+analyzed: number, 
+/**
+ * 上次扫描时等待分析的文件数
+ */
+pending: number, 
+/**
+ * 最近一次分析失败的文件与原因
+ */
+last_error: string | null, 
+/**
+ * 已启用但暂不分析的原因
+ */
+waiting: string | null, };
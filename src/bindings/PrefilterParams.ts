@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 逐帧比较前的 dHash 预筛选：哈希差异明确的相邻帧直接判定，只有难以判断的才用所选算法比较
+ */
+export type PrefilterParams = { enabled: boolean, 
+/**
+ * 哈希距离（0-64）不超过该值视为同一画面，相似度记为 1
+ */
+same_distance: number, 
+/**
+ * 哈希距离不小于该值视为场景切换，相似度记为 0
+ */
+cut_distance: number, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 拼接前逐个探测视频的进度（兼容性检测、质量筛选、按时长抽取）
+ */
+export type ProbeProgress = { 
+/**
+ * compatibility、pool_filter 或 duration
+ */
+phase: string, current: number, total: number, file_name: string, };
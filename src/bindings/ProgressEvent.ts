@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 通用进度事件（帧提取、自动拆解、去结尾、音频分析、缩略图目录、性能测试）
+ */
+export type ProgressEvent = { 
+/**
+ * 按当前语言生成的消息
+ */
+message: string, 
+/**
+ * 消息目录中的 key，前端可以自行翻译
+ */
+message_key: string, params: Array<string>, percent: number, };
@@ -0,0 +1,27 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ClipTrim } from "./ClipTrim";
+import type { EncodeSettings } from "./EncodeSettings";
+import type { EndingCheck } from "./EndingCheck";
+import type { TempSegmentOptions } from "./TempSegmentOptions";
+import type { TitleConfig } from "./TitleConfig";
+
+/**
+ * 去结尾参数（remove_ending_and_concat 命令与任务队列共用）
+ */
+export type RemoveEndingParams = { output_dir: string, algorithm: string, threshold: number, min_duration: number, new_ending_video: string | null, new_ending_trim: ClipTrim | null, brand_kit: string | null, shuffle_segments: boolean, 
+/**
+ * 按音频波形微调新结尾的接缝
+ */
+audio_join: boolean, temp_segments: TempSegmentOptions, ending_check: EndingCheck | null, encode_settings: EncodeSettings | null, 
+/**
+ * 只在视频末尾这么多秒内反向查找片尾，为空时分析整个视频
+ */
+tail_window: number | null, 
+/**
+ * 打乱片段的随机种子，指定后每次打乱结果相同
+ */
+seed: number | null, 
+/**
+ * 成片开头的标题文字
+ */
+title: TitleConfig | null, };
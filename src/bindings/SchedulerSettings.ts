@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 任务调度设置
+ */
+export type SchedulerSettings = { 
+/**
+ * 只在空闲时执行队列任务（任务之间暂停，不会打断正在执行的任务）
+ */
+idle_only: boolean, 
+/**
+ * 无键鼠输入超过该分钟数视为空闲
+ */
+idle_minutes: number | null, 
+/**
+ * 允许执行的时间段，格式 "HH:MM"，可以跨零点（如 01:00–07:00 或 22:00–06:00）
+ */
+window_start: string | null, window_end: string | null, };
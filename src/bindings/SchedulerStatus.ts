@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 调度暂停状态事件
+ */
+export type SchedulerStatus = { paused: boolean, reason: string | null, };
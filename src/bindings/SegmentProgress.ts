@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 片段生成进度
+ */
+export type SegmentProgress = { current: number, total: number, segmentName: string, percent: number, };
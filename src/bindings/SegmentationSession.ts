@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SegmentationSnapshot } from "./SegmentationSnapshot";
+
+/**
+ * 单个视频的手动分段会话（按内容指纹保存，视频改名或移动后仍可恢复）
+ */
+export type SegmentationSession = { content_hash: string, 
+/**
+ * 最近一次保存时的视频路径
+ */
+video_path: string, current: SegmentationSnapshot, 
+/**
+ * 之前的状态，最新的在最后
+ */
+history: Array<SegmentationSnapshot>, };
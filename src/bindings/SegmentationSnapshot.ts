@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SegmentRange } from "./SegmentRange";
+
+/**
+ * 分段编辑的一个状态
+ */
+export type SegmentationSnapshot = { segments: Array<SegmentRange>, 
+/**
+ * 与 segments 一一对应的片段标签
+ */
+labels: Array<string>, notes: string, updated_at: string, };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SelfTestStep } from "./SelfTestStep";
+
+/**
+ * 自检结果
+ */
+export type SelfTestReport = { passed: boolean, steps: Array<SelfTestStep>, 
+/**
+ * 自检失败时保留的工作目录，便于排查
+ */
+work_dir: string | null, };
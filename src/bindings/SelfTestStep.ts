@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 自检中的一步
+ */
+export type SelfTestStep = { name: string, passed: boolean, detail: string, };
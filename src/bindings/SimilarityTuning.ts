@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { EdgeParams } from "./EdgeParams";
+import type { FrameDiffParams } from "./FrameDiffParams";
+import type { HistogramParams } from "./HistogramParams";
+import type { MaskSettings } from "./MaskSettings";
+import type { PrefilterParams } from "./PrefilterParams";
+import type { SsimParams } from "./SsimParams";
+
+/**
+ * 各相似度算法的参数（全局设置）
+ */
+export type SimilarityTuning = { histogram: HistogramParams, ssim: SsimParams, frame_diff: FrameDiffParams, edge: EdgeParams, mask: MaskSettings, prefilter: PrefilterParams, };
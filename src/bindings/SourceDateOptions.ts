@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 按源视频拍摄时间命名与标记输出
+ */
+export type SourceDateOptions = { 
+/**
+ * 拆出的片段按拍摄时间命名（源视频拍摄时间 + 片段起点）
+ */
+name_by_recording_time: boolean, 
+/**
+ * 输出文件的 creation_time 设为源视频的拍摄时间（拼接取最早的一个）
+ */
+keep_creation_time: boolean, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 变速：起止速度相同为匀速，不同则在片段内线性渐变
+ */
+export type SpeedRamp = { start: number, end: number, };
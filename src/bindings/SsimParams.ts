@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * SSIM 参数
+ */
+export type SsimParams = { 
+/**
+ * 分块计算的窗口边长（像素），各块取平均；为空时整幅画面作为一个窗口
+ */
+window: number | null, };
@@ -0,0 +1,22 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 单个输入的流选择（带解说音轨、多机位等素材），序号从 0 开始
+ */
+export type StreamSelection = { 
+/**
+ * 输入视频路径
+ */
+path: string, 
+/**
+ * 使用的视频流序号，为空则自动选择（跳过封面图片）
+ */
+video_stream: number | null, 
+/**
+ * 使用的音轨序号，为空则使用第一个音轨
+ */
+audio_stream: number | null, 
+/**
+ * 丢弃音轨，以静音代替
+ */
+drop_audio: boolean, };
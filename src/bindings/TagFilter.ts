@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TagQuota } from "./TagQuota";
+
+/**
+ * 拼接时的标签条件
+ */
+export type TagFilter = { 
+/**
+ * 只使用带有其中任一标签的片段（为空则不限制）
+ */
+include: Array<string>, 
+/**
+ * 带有其中任一标签的片段不进入池子
+ */
+exclude: Array<string>, quotas: Array<TagQuota>, };
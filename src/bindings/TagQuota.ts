@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 标签配额：每次抽取至少包含 `min` 个带该标签的片段
+ */
+export type TagQuota = { tag: string, min: number, };
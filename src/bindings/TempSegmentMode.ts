@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 去结尾时临时片段的生成方式
+ */
+export type TempSegmentMode = "reencode" | "intra" | "direct";
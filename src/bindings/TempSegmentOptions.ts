@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TempSegmentMode } from "./TempSegmentMode";
+
+/**
+ * 去结尾的临时片段设置
+ */
+export type TempSegmentOptions = { mode: TempSegmentMode, 
+/**
+ * 临时片段的 CRF，为空时重新编码用 18、全关键帧用 12
+ */
+crf: number | null, };
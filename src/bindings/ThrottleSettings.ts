@@ -0,0 +1,22 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 编码资源限制：让后台编码给其他程序留出余量
+ */
+export type ThrottleSettings = { 
+/**
+ * FFmpeg 编码/滤镜线程数（libx264 的 threads 参数同样由 -threads 设置），为空则由 FFmpeg 自动决定
+ */
+threads: number | null, 
+/**
+ * 以较低的进程优先级运行 FFmpeg
+ */
+low_priority: boolean, 
+/**
+ * 只允许 FFmpeg 使用这些 CPU 核心（从 0 开始的序号），为空则不限制；macOS 不支持
+ */
+cpu_affinity: Array<number> | null, 
+/**
+ * 多次拼接输出时同时运行的 FFmpeg 数量，为空则按 CPU 核数自动决定
+ */
+max_parallel_runs: number | null, };
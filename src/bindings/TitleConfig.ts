@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 标题文字：成片开头的一段时间内居中显示
+ */
+export type TitleConfig = { text: string, 
+/**
+ * 字体文件，为空时使用品牌包的标题字体，都没有时使用 FFmpeg 默认字体
+ */
+font_file: string | null, font_size: number, 
+/**
+ * 显示时长（秒）
+ */
+duration: number, };
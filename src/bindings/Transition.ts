@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TransitionStyle } from "./TransitionStyle";
+
+/**
+ * 片段之间的转场：画面用 xfade 重叠过渡，声音单独设置交叉淡化时长
+ */
+export type Transition = { style: TransitionStyle, 
+/**
+ * 画面转场时长（秒），相邻片段在这段时间内重叠
+ */
+duration: number, 
+/**
+ * 声音交叉淡化时长（秒），为空时与画面转场相同；声音突然切换比画面更明显，通常设得更长
+ */
+audio_duration: number | null, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 转场样式，对应 FFmpeg xfade 的 transition 参数
+ */
+export type TransitionStyle = "fade" | "fade_black" | "dissolve" | "wipe_left" | "wipe_right" | "slide_left" | "slide_right";
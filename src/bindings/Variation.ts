@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 输出变体：由种子确定的轻微放大、变速与色彩偏移，相同种子生成相同的变换
+ */
+export type Variation = { seed: number, 
+/**
+ * 放大倍数（放大后裁回原画布）
+ */
+zoom: number, speed: number, 
+/**
+ * 色相偏移（度）
+ */
+hue: number, saturation: number, brightness: number, };